@@ -3,6 +3,7 @@
 use divan::{AllocProfiler, Bencher, black_box_drop};
 use gql_parser::ast::{BinaryOp, Expr};
 use gql_parser::span::Spanned;
+use minigu::common::value::{F32, ScalarValue, VectorValue};
 
 #[global_allocator]
 static ALLOC: AllocProfiler = AllocProfiler::system();
@@ -37,3 +38,30 @@ fn drop_ast(bencher: Bencher, depth: usize) {
         .with_inputs(|| build_expr(depth))
         .bench_local_values(black_box_drop);
 }
+
+const VECTOR_DIMS: &[usize] = &[128, 1024];
+
+fn query_vector_arg(dimension: usize) -> ScalarValue {
+    let data: Vec<F32> = (0..dimension).map(|i| F32::from(i as f32)).collect();
+    ScalarValue::new_vector(dimension, Some(VectorValue::new(data, dimension).unwrap()))
+}
+
+/// Query vector extraction as the vector_search procedure used to do it: clone, then convert
+#[divan::bench(args = VECTOR_DIMS)]
+fn query_vector_cloned(bencher: Bencher, dimension: usize) {
+    let arg = query_vector_arg(dimension);
+    bencher.bench_local(|| {
+        let vector = black_box(&arg).get_vector().unwrap();
+        black_box(vector.to_f32_vec())
+    });
+}
+
+/// Query vector extraction by borrowing; allocates nothing
+#[divan::bench(args = VECTOR_DIMS)]
+fn query_vector_borrowed(bencher: Bencher, dimension: usize) {
+    let arg = query_vector_arg(dimension);
+    bencher.bench_local(|| {
+        let vector = black_box(&arg).as_vector().unwrap();
+        black_box(vector.as_f32_slice().len())
+    });
+}
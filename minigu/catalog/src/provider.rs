@@ -5,6 +5,7 @@
 use downcast_rs::{DowncastSync, impl_downcast};
 use minigu_common::data_type::{DataSchemaRef, LogicalType};
 use minigu_common::types::{LabelId, PropertyId};
+use minigu_common::value::ScalarValue;
 
 use crate::error::CatalogResult;
 use crate::label_set::LabelSet;
@@ -133,9 +134,22 @@ pub trait ProcedureProvider: Debug + Send + Sync + Any {
     /// Returns the parameters of the procedure.
     fn parameters(&self) -> &[LogicalType];
 
+    /// Returns the number of leading parameters a call must supply; the remaining trailing
+    /// parameters are optional. Defaults to all parameters being required.
+    fn required_parameters(&self) -> usize {
+        self.parameters().len()
+    }
+
     /// Returns the data schema of the procedure.
     fn schema(&self) -> Option<DataSchemaRef>;
 
+    /// Returns the data schema of a call with the given arguments, for procedures whose output
+    /// columns depend on them. Defaults to [`schema`](Self::schema).
+    fn schema_for_args(&self, args: &[ScalarValue]) -> Option<DataSchemaRef> {
+        let _ = args;
+        self.schema()
+    }
+
     /// Returns a reference to the underlying procedure.
     fn as_any(&self) -> &dyn Any;
 }
@@ -866,6 +866,13 @@ pub fn dimension(&self) -> usize {
         self.data.len()
     }
 
+    /// Borrows the vector data as plain `f32`s, without copying
+    pub fn as_f32_slice(&self) -> &[f32] {
+        // SAFETY: `OrderedFloat<f32>` is `#[repr(transparent)]` over `f32`, so `[F32]` and
+        // `[f32]` have the same layout and every bit pattern is valid for both.
+        unsafe { std::slice::from_raw_parts(self.data.as_ptr().cast::<f32>(), self.data.len()) }
+    }
+
     /// Converts to a `Vec<f32>`
     pub fn to_f32_vec(&self) -> Vec<f32> {
         self.data.iter().map(|f| f.into_inner()).collect()
@@ -1063,10 +1070,15 @@ pub fn get_string(&self) -> Result<String, String> {
     }
 
     pub fn get_vector(&self) -> Result<VectorValue, String> {
+        self.as_vector().cloned()
+    }
+
+    /// Borrows the vector value, avoiding the clone made by [`get_vector`](Self::get_vector)
+    pub fn as_vector(&self) -> Result<&VectorValue, String> {
         match self {
             ScalarValue::Vector {
                 value: Some(val), ..
-            } => Ok(val.clone()),
+            } => Ok(val),
             ScalarValue::Vector { value: None, .. } => Err("Null value".to_string()),
             _ => Err("Not a Vector value".to_string()),
         }
@@ -1074,10 +1086,8 @@ pub fn get_vector(&self) -> Result<VectorValue, String> {
 
     /// Returns the vector data as `Vec<F32>`
     pub fn get_vector_data(&self) -> Result<Vec<F32>, String> {
-        match self.get_vector() {
-            Ok(vector_value) => Ok(vector_value.data().to_vec()),
-            Err(e) => Err(e),
-        }
+        self.as_vector()
+            .map(|vector_value| vector_value.data().to_vec())
     }
 
     pub fn get_vertex(&self) -> Result<VertexValue, String> {
@@ -1620,6 +1630,20 @@ fn test_get_vector() {
         assert_eq!(scalar.get_vector().unwrap_err(), "Not a Vector value");
     }
 
+    #[test]
+    fn test_as_vector_borrows() {
+        let vector_value =
+            VectorValue::new(vec![OrderedFloat(1.5), OrderedFloat(-2.0)], 2).unwrap();
+        let scalar = ScalarValue::new_vector(2, Some(vector_value));
+        let borrowed = scalar.as_vector().unwrap();
+        assert_eq!(borrowed.as_f32_slice(), &[1.5, -2.0]);
+        assert_eq!(
+            borrowed.as_f32_slice().as_ptr(),
+            borrowed.data().as_ptr().cast::<f32>()
+        );
+        assert!(ScalarValue::new_vector(2, None).as_vector().is_err());
+    }
+
     #[test]
     fn test_vector_to_scalar_array() {
         // Test vector to Arrow array conversion
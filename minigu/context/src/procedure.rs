@@ -18,9 +18,13 @@
         + Sync,
 >;
 
+pub type SchemaFn = Box<dyn Fn(&[ScalarValue]) -> Option<DataSchemaRef> + Send + Sync>;
+
 pub struct Procedure {
     parameters: Vec<LogicalType>,
+    required_parameters: usize,
     schema: Option<DataSchemaRef>,
+    schema_fn: Option<SchemaFn>,
     inner: ProcedureImpl,
 }
 
@@ -36,12 +40,35 @@ pub fn new<F>(parameters: Vec<LogicalType>, schema: Option<DataSchemaRef>, inner
             + 'static,
     {
         Self {
+            required_parameters: parameters.len(),
             parameters,
             schema,
+            schema_fn: None,
             inner: Box::new(inner),
         }
     }
 
+    /// Marks the last `count` parameters as optional. Calls may omit them from the end, and the
+    /// procedure then receives only the arguments actually supplied.
+    pub fn with_optional_parameters(mut self, count: usize) -> Self {
+        assert!(
+            count <= self.parameters.len(),
+            "more optional parameters than parameters"
+        );
+        self.required_parameters = self.parameters.len() - count;
+        self
+    }
+
+    /// Derives the output schema of each call from its arguments, for procedures whose columns
+    /// depend on them. The static schema remains the one reported without arguments.
+    pub fn with_schema_fn<F>(mut self, schema_fn: F) -> Self
+    where
+        F: Fn(&[ScalarValue]) -> Option<DataSchemaRef> + Send + Sync + 'static,
+    {
+        self.schema_fn = Some(Box::new(schema_fn));
+        self
+    }
+
     pub fn call(
         &self,
         session_context: SessionContext,
@@ -55,6 +82,7 @@ impl Debug for Procedure {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Procedure")
             .field("parameters", &self.parameters)
+            .field("required_parameters", &self.required_parameters)
             .field("schema", &self.schema)
             .finish()
     }
@@ -66,11 +94,24 @@ fn parameters(&self) -> &[LogicalType] {
         &self.parameters
     }
 
+    #[inline]
+    fn required_parameters(&self) -> usize {
+        self.required_parameters
+    }
+
     #[inline]
     fn schema(&self) -> Option<DataSchemaRef> {
         self.schema.clone()
     }
 
+    #[inline]
+    fn schema_for_args(&self, args: &[ScalarValue]) -> Option<DataSchemaRef> {
+        match &self.schema_fn {
+            Some(schema_fn) => schema_fn(args),
+            None => self.schema.clone(),
+        }
+    }
+
     #[inline]
     fn as_any(&self) -> &dyn Any {
         self
@@ -41,7 +41,7 @@ pub fn session(&self) -> Result<Session> {
         Session::new(self.context.clone(), self.default_schema().clone())
     }
 
-    fn default_schema(&self) -> &Arc<MemorySchemaCatalog> {
+    pub(crate) fn default_schema(&self) -> &Arc<MemorySchemaCatalog> {
         &self.default_schema
     }
 }
@@ -9,3 +9,4 @@
 pub mod session;
 
 pub use minigu_common as common;
+pub use procedures::filter_condition;
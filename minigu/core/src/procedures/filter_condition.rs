@@ -0,0 +1,768 @@
+//! Predicate language for the `filter_condition` argument of `vector_search`.
+//!
+//! ```text
+//! condition  := or_expr
+//! or_expr    := and_expr (OR and_expr)*
+//! and_expr   := primary (AND primary)*
+//! primary    := '(' or_expr ')' | comparison
+//! comparison := property op literal
+//! property   := identifier | identifier ':' identifier
+//! op         := '=' | '!=' | '<' | '<=' | '>' | '>='
+//! literal    := integer | float | 'string' | TRUE | FALSE
+//! ```
+//!
+//! Keywords are case-insensitive; string literals use single quotes, with `''` as an escaped
+//! quote. A property may be qualified with a label, as in `PERSON:age`, to pick one when vertex
+//! types define the name with different IDs. Properties are resolved against the graph type, and
+//! every comparison is type-checked before any data is touched. Error spans are byte offsets into
+//! the condition string.
+//!
+//! Evaluation follows SQL: a comparison against a null or missing property is false, for `!=`
+//! as well. A property is missing on vertices whose type does not define it.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use minigu_catalog::label_set::LabelSet;
+use minigu_catalog::provider::GraphTypeProvider;
+use minigu_common::data_type::LogicalType;
+use minigu_common::types::{LabelId, PropertyId};
+use minigu_common::value::ScalarValue;
+use thiserror::Error;
+
+use super::vector_search::resolve_property;
+
+/// Byte range `[start, end)` of the condition string an error refers to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum FilterParseError {
+    #[error("unexpected character '{found}' at offset {}", span.start)]
+    UnexpectedChar { found: char, span: Span },
+    #[error("unterminated string literal starting at offset {}", span.start)]
+    UnterminatedString { span: Span },
+    #[error("invalid number '{text}' at offset {}", span.start)]
+    InvalidNumber { text: String, span: Span },
+    #[error("expected {expected} at offset {}, found {found}", span.start)]
+    Unexpected {
+        expected: &'static str,
+        found: String,
+        span: Span,
+    },
+    #[error("unknown property '{name}' at offset {}", span.start)]
+    UnknownProperty { name: String, span: Span },
+    #[error(
+        "cannot compare property '{property}' of type {property_type} using '{op}' with {literal} at offset {}",
+        span.start
+    )]
+    TypeMismatch {
+        property: String,
+        property_type: LogicalType,
+        op: CompareOp,
+        literal: String,
+        span: Span,
+    },
+    #[error("failed to resolve properties: {0}")]
+    Catalog(String),
+}
+
+impl FilterParseError {
+    /// Location of the error in the condition string, if it refers to one
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::UnexpectedChar { span, .. }
+            | Self::UnterminatedString { span }
+            | Self::InvalidNumber { span, .. }
+            | Self::Unexpected { span, .. }
+            | Self::UnknownProperty { span, .. }
+            | Self::TypeMismatch { span, .. } => Some(*span),
+            Self::Catalog(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl fmt::Display for CompareOp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            CompareOp::Eq => "=",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        };
+        f.write_str(symbol)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Boolean(bool),
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::Integer(value) => write!(f, "integer {value}"),
+            Literal::Float(value) => write!(f, "float {value}"),
+            Literal::String(value) => write!(f, "string '{value}'"),
+            Literal::Boolean(value) => write!(f, "boolean {value}"),
+        }
+    }
+}
+
+/// Type-checked predicate with properties resolved to IDs
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpr {
+    And(Box<FilterExpr>, Box<FilterExpr>),
+    Or(Box<FilterExpr>, Box<FilterExpr>),
+    Compare {
+        property_id: PropertyId,
+        /// Label sets of the vertex types defining the property; `property_id` is a position
+        /// within those types only, so vertices with other labels never match
+        labels: Vec<LabelSet>,
+        op: CompareOp,
+        literal: Literal,
+    },
+}
+
+impl FilterExpr {
+    /// Evaluate the predicate over the property values of a vertex labelled `label_id`, indexed
+    /// by property ID
+    pub fn matches(&self, label_id: LabelId, properties: &[ScalarValue]) -> bool {
+        match self {
+            FilterExpr::And(left, right) => {
+                left.matches(label_id, properties) && right.matches(label_id, properties)
+            }
+            FilterExpr::Or(left, right) => {
+                left.matches(label_id, properties) || right.matches(label_id, properties)
+            }
+            FilterExpr::Compare {
+                property_id,
+                labels,
+                op,
+                literal,
+            } => labels
+                .iter()
+                .any(|label_set| label_set.contains(label_id))
+                .then(|| properties.get(*property_id as usize))
+                .flatten()
+                .and_then(|value| compare_value(value, literal))
+                .is_some_and(|ordering| match op {
+                    CompareOp::Eq => ordering == Ordering::Equal,
+                    CompareOp::Ne => ordering != Ordering::Equal,
+                    CompareOp::Lt => ordering == Ordering::Less,
+                    CompareOp::Le => ordering != Ordering::Greater,
+                    CompareOp::Gt => ordering == Ordering::Greater,
+                    CompareOp::Ge => ordering != Ordering::Less,
+                }),
+        }
+    }
+}
+
+/// Order of `value` relative to `literal`; `None` for nulls, NaN and values of another type
+fn compare_value(value: &ScalarValue, literal: &Literal) -> Option<Ordering> {
+    use ScalarValue as V;
+    match literal {
+        // Integers compare exactly against integer properties of any width
+        Literal::Integer(literal) => {
+            let value: i128 = match value {
+                V::Int8(v) => (*v)?.into(),
+                V::Int16(v) => (*v)?.into(),
+                V::Int32(v) => (*v)?.into(),
+                V::Int64(v) => (*v)?.into(),
+                V::UInt8(v) => (*v)?.into(),
+                V::UInt16(v) => (*v)?.into(),
+                V::UInt32(v) => (*v)?.into(),
+                V::UInt64(v) => (*v)?.into(),
+                V::Float32(_) | V::Float64(_) => {
+                    return value.to_f64().ok()?.partial_cmp(&(*literal as f64));
+                }
+                _ => return None,
+            };
+            Some(value.cmp(&(*literal).into()))
+        }
+        Literal::Float(literal) => match value {
+            V::Int8(_)
+            | V::Int16(_)
+            | V::Int32(_)
+            | V::Int64(_)
+            | V::UInt8(_)
+            | V::UInt16(_)
+            | V::UInt32(_)
+            | V::UInt64(_)
+            | V::Float32(_)
+            | V::Float64(_) => value.to_f64().ok()?.partial_cmp(literal),
+            _ => None,
+        },
+        Literal::String(literal) => match value {
+            V::String(Some(value)) => Some(value.as_str().cmp(literal)),
+            _ => None,
+        },
+        Literal::Boolean(literal) => match value {
+            V::Boolean(Some(value)) => Some(value.cmp(literal)),
+            _ => None,
+        },
+    }
+}
+
+/// Check that `condition` parses and type-checks against `graph_type` without evaluating it.
+/// An empty (or all-whitespace) condition means "no filter" and is valid.
+pub fn validate_filter_condition(
+    condition: &str,
+    graph_type: &dyn GraphTypeProvider,
+) -> Result<(), FilterParseError> {
+    parse_filter_condition(condition, graph_type).map(|_| ())
+}
+
+/// Parse and type-check `condition`; `None` for an empty condition
+pub fn parse_filter_condition(
+    condition: &str,
+    graph_type: &dyn GraphTypeProvider,
+) -> Result<Option<FilterExpr>, FilterParseError> {
+    let tokens = tokenize(condition)?;
+    if tokens.is_empty() {
+        return Ok(None);
+    }
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        end: condition.len(),
+        graph_type,
+    };
+    let expr = parser.parse_or()?;
+    if let Some(token) = parser.peek() {
+        return Err(FilterParseError::Unexpected {
+            expected: "AND, OR or end of condition",
+            found: token.kind.to_string(),
+            span: token.span,
+        });
+    }
+    Ok(Some(expr))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Identifier(String),
+    Literal(Literal),
+    Op(CompareOp),
+    And,
+    Or,
+    LParen,
+    RParen,
+}
+
+impl fmt::Display for TokenKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenKind::Identifier(name) => write!(f, "'{name}'"),
+            TokenKind::Literal(literal) => write!(f, "{literal}"),
+            TokenKind::Op(op) => write!(f, "'{op}'"),
+            TokenKind::And => f.write_str("AND"),
+            TokenKind::Or => f.write_str("OR"),
+            TokenKind::LParen => f.write_str("'('"),
+            TokenKind::RParen => f.write_str("')'"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    span: Span,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, FilterParseError> {
+    let bytes = input.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let c = bytes[i];
+        let start = i;
+        let kind = match c {
+            b' ' | b'\t' | b'\r' | b'\n' => {
+                i += 1;
+                continue;
+            }
+            b'(' => {
+                i += 1;
+                TokenKind::LParen
+            }
+            b')' => {
+                i += 1;
+                TokenKind::RParen
+            }
+            b'=' => {
+                i += 1;
+                TokenKind::Op(CompareOp::Eq)
+            }
+            b'!' if bytes.get(i + 1) == Some(&b'=') => {
+                i += 2;
+                TokenKind::Op(CompareOp::Ne)
+            }
+            b'<' | b'>' => {
+                let or_equal = bytes.get(i + 1) == Some(&b'=');
+                i += if or_equal { 2 } else { 1 };
+                TokenKind::Op(match (c, or_equal) {
+                    (b'<', false) => CompareOp::Lt,
+                    (b'<', true) => CompareOp::Le,
+                    (_, false) => CompareOp::Gt,
+                    (_, true) => CompareOp::Ge,
+                })
+            }
+            b'\'' => {
+                let mut value = String::new();
+                i += 1;
+                loop {
+                    match input[i..].find('\'') {
+                        Some(offset) => {
+                            value.push_str(&input[i..i + offset]);
+                            i += offset + 1;
+                            // '' is an escaped quote inside the literal
+                            if bytes.get(i) == Some(&b'\'') {
+                                value.push('\'');
+                                i += 1;
+                            } else {
+                                break;
+                            }
+                        }
+                        None => {
+                            return Err(FilterParseError::UnterminatedString {
+                                span: Span {
+                                    start,
+                                    end: input.len(),
+                                },
+                            });
+                        }
+                    }
+                }
+                TokenKind::Literal(Literal::String(value))
+            }
+            b'0'..=b'9' | b'-' | b'.' => {
+                i += 1;
+                while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') {
+                    i += 1;
+                }
+                let text = &input[start..i];
+                let span = Span { start, end: i };
+                let literal = if text.contains('.') {
+                    text.parse().map(Literal::Float).ok()
+                } else {
+                    text.parse().map(Literal::Integer).ok()
+                };
+                TokenKind::Literal(literal.ok_or_else(|| FilterParseError::InvalidNumber {
+                    text: text.to_string(),
+                    span,
+                })?)
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                let is_word_start = |b: &u8| b.is_ascii_alphabetic() || *b == b'_';
+                let is_word = |b: &u8| b.is_ascii_alphanumeric() || *b == b'_';
+                while bytes.get(i).is_some_and(is_word) {
+                    i += 1;
+                }
+                // `label:property` is one qualified property name, never a keyword
+                let qualified =
+                    bytes.get(i) == Some(&b':') && bytes.get(i + 1).is_some_and(is_word_start);
+                if qualified {
+                    i += 1;
+                    while bytes.get(i).is_some_and(is_word) {
+                        i += 1;
+                    }
+                }
+                let word = &input[start..i];
+                match word.to_ascii_uppercase().as_str() {
+                    _ if qualified => TokenKind::Identifier(word.to_string()),
+                    "AND" => TokenKind::And,
+                    "OR" => TokenKind::Or,
+                    "TRUE" => TokenKind::Literal(Literal::Boolean(true)),
+                    "FALSE" => TokenKind::Literal(Literal::Boolean(false)),
+                    _ => TokenKind::Identifier(word.to_string()),
+                }
+            }
+            _ => {
+                let found = input[i..].chars().next().unwrap_or_default();
+                return Err(FilterParseError::UnexpectedChar {
+                    found,
+                    span: Span {
+                        start,
+                        end: start + found.len_utf8(),
+                    },
+                });
+            }
+        };
+        tokens.push(Token {
+            kind,
+            span: Span { start, end: i },
+        });
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    end: usize,
+    graph_type: &'a dyn GraphTypeProvider,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self, expected: &'static str) -> Result<Token, FilterParseError> {
+        let token =
+            self.tokens
+                .get(self.position)
+                .cloned()
+                .ok_or(FilterParseError::Unexpected {
+                    expected,
+                    found: "end of condition".to_string(),
+                    span: Span {
+                        start: self.end,
+                        end: self.end,
+                    },
+                })?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn parse_or(&mut self) -> Result<FilterExpr, FilterParseError> {
+        let mut expr = self.parse_and()?;
+        while self.peek().is_some_and(|token| token.kind == TokenKind::Or) {
+            self.position += 1;
+            expr = FilterExpr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<FilterExpr, FilterParseError> {
+        let mut expr = self.parse_primary()?;
+        while self
+            .peek()
+            .is_some_and(|token| token.kind == TokenKind::And)
+        {
+            self.position += 1;
+            expr = FilterExpr::And(Box::new(expr), Box::new(self.parse_primary()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_primary(&mut self) -> Result<FilterExpr, FilterParseError> {
+        let token = self.next("a property name or '('")?;
+        match token.kind {
+            TokenKind::LParen => {
+                let expr = self.parse_or()?;
+                let close = self.next("')'")?;
+                if close.kind != TokenKind::RParen {
+                    return Err(FilterParseError::Unexpected {
+                        expected: "')'",
+                        found: close.kind.to_string(),
+                        span: close.span,
+                    });
+                }
+                Ok(expr)
+            }
+            TokenKind::Identifier(name) => self.parse_comparison(name, token.span),
+            other => Err(FilterParseError::Unexpected {
+                expected: "a property name or '('",
+                found: other.to_string(),
+                span: token.span,
+            }),
+        }
+    }
+
+    fn parse_comparison(
+        &mut self,
+        name: String,
+        name_span: Span,
+    ) -> Result<FilterExpr, FilterParseError> {
+        let op_token = self.next("a comparison operator")?;
+        let TokenKind::Op(op) = op_token.kind else {
+            return Err(FilterParseError::Unexpected {
+                expected: "a comparison operator",
+                found: op_token.kind.to_string(),
+                span: op_token.span,
+            });
+        };
+        let literal_token = self.next("a literal")?;
+        let TokenKind::Literal(literal) = literal_token.kind else {
+            return Err(FilterParseError::Unexpected {
+                expected: "a literal",
+                found: literal_token.kind.to_string(),
+                span: literal_token.span,
+            });
+        };
+
+        let (property_id, property_type, labels) = resolve_property(self.graph_type, &name)
+            .map_err(|e| FilterParseError::Catalog(e.to_string()))?
+            .ok_or_else(|| FilterParseError::UnknownProperty {
+                name: name.clone(),
+                span: name_span,
+            })?;
+        if !comparable(&property_type, op, &literal) {
+            return Err(FilterParseError::TypeMismatch {
+                property: name,
+                property_type,
+                op,
+                literal: literal.to_string(),
+                span: Span {
+                    start: name_span.start,
+                    end: literal_token.span.end,
+                },
+            });
+        }
+        Ok(FilterExpr::Compare {
+            property_id,
+            labels,
+            op,
+            literal,
+        })
+    }
+}
+
+/// Whether a property of type `property_type` can be compared with `literal` using `op`
+fn comparable(property_type: &LogicalType, op: CompareOp, literal: &Literal) -> bool {
+    use LogicalType as T;
+    match literal {
+        Literal::Integer(_) | Literal::Float(_) => matches!(
+            property_type,
+            T::Int8
+                | T::Int16
+                | T::Int32
+                | T::Int64
+                | T::UInt8
+                | T::UInt16
+                | T::UInt32
+                | T::UInt64
+                | T::Float32
+                | T::Float64
+        ),
+        Literal::String(_) => *property_type == T::String,
+        // Booleans have no order
+        Literal::Boolean(_) => {
+            *property_type == T::Boolean && matches!(op, CompareOp::Eq | CompareOp::Ne)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use minigu_catalog::memory::graph_type::{MemoryGraphTypeCatalog, MemoryVertexTypeCatalog};
+    use minigu_catalog::property::Property;
+
+    use super::*;
+
+    fn person() -> LabelId {
+        LabelId::new(1).unwrap()
+    }
+
+    fn person_labels() -> Vec<LabelSet> {
+        vec![LabelSet::from_iter([person()])]
+    }
+
+    fn graph_type() -> MemoryGraphTypeCatalog {
+        let mut graph_type = MemoryGraphTypeCatalog::new();
+        let person = person();
+        graph_type.add_label("PERSON".to_string()).unwrap();
+        graph_type.add_vertex_type(
+            LabelSet::from_iter([person]),
+            Arc::new(MemoryVertexTypeCatalog::new(
+                LabelSet::from_iter([person]),
+                vec![
+                    Property::new("name".to_string(), LogicalType::String, false),
+                    Property::new("age".to_string(), LogicalType::Int32, false),
+                    Property::new("active".to_string(), LogicalType::Boolean, false),
+                ],
+            )),
+        );
+        graph_type
+    }
+
+    #[test]
+    fn test_parse_precedence_and_literals() {
+        let graph_type = graph_type();
+        let expr = parse_filter_condition(
+            "age >= 30 and name = 'O''Brien' OR (active != false)",
+            &graph_type,
+        )
+        .unwrap()
+        .unwrap();
+        // AND binds tighter than OR
+        let FilterExpr::Or(left, right) = expr else {
+            panic!("expected OR at the top level");
+        };
+        assert_eq!(
+            *left,
+            FilterExpr::And(
+                Box::new(FilterExpr::Compare {
+                    property_id: 1,
+                    labels: person_labels(),
+                    op: CompareOp::Ge,
+                    literal: Literal::Integer(30),
+                }),
+                Box::new(FilterExpr::Compare {
+                    property_id: 0,
+                    labels: person_labels(),
+                    op: CompareOp::Eq,
+                    literal: Literal::String("O'Brien".to_string()),
+                }),
+            )
+        );
+        assert_eq!(
+            *right,
+            FilterExpr::Compare {
+                property_id: 2,
+                labels: person_labels(),
+                op: CompareOp::Ne,
+                literal: Literal::Boolean(false),
+            }
+        );
+        assert_eq!(parse_filter_condition("  ", &graph_type).unwrap(), None);
+    }
+
+    #[test]
+    fn test_validate_reports_error_spans() {
+        let graph_type = graph_type();
+        assert!(validate_filter_condition("age > -1.5", &graph_type).is_ok());
+
+        let error = validate_filter_condition("age > 3 AND height < 2", &graph_type).unwrap_err();
+        assert!(
+            matches!(error, FilterParseError::UnknownProperty { ref name, .. } if name == "height")
+        );
+        assert_eq!(error.span(), Some(Span { start: 12, end: 18 }));
+
+        let error = validate_filter_condition("name < 5", &graph_type).unwrap_err();
+        assert!(matches!(error, FilterParseError::TypeMismatch { .. }));
+        assert_eq!(error.span(), Some(Span { start: 0, end: 8 }));
+
+        assert!(matches!(
+            validate_filter_condition("active < true", &graph_type),
+            Err(FilterParseError::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            validate_filter_condition("name = 'open", &graph_type),
+            Err(FilterParseError::UnterminatedString { .. })
+        ));
+        assert!(matches!(
+            validate_filter_condition("age >", &graph_type),
+            Err(FilterParseError::Unexpected {
+                span: Span { start: 5, end: 5 },
+                ..
+            })
+        ));
+        assert!(matches!(
+            validate_filter_condition("(age > 1", &graph_type),
+            Err(FilterParseError::Unexpected { .. })
+        ));
+        assert!(matches!(
+            validate_filter_condition("age ? 1", &graph_type),
+            Err(FilterParseError::UnexpectedChar { found: '?', .. })
+        ));
+    }
+
+    #[test]
+    fn test_qualified_property() {
+        let mut graph_type = graph_type();
+        let city = LabelId::new(2).unwrap();
+        graph_type.add_label("CITY".to_string()).unwrap();
+        graph_type.add_vertex_type(
+            LabelSet::from_iter([city]),
+            Arc::new(MemoryVertexTypeCatalog::new(
+                LabelSet::from_iter([city]),
+                vec![
+                    Property::new("population".to_string(), LogicalType::Int64, false),
+                    Property::new("name".to_string(), LogicalType::String, false),
+                ],
+            )),
+        );
+
+        // `name` has ID 0 on PERSON and 1 on CITY
+        let error = validate_filter_condition("name = 'x'", &graph_type).unwrap_err();
+        assert!(matches!(error, FilterParseError::Catalog(ref message)
+            if message.contains("ambiguous") && message.contains("'label:name'")));
+        assert_eq!(
+            parse_filter_condition("CITY:name = 'x' AND PERSON:age > 3", &graph_type),
+            Ok(Some(FilterExpr::And(
+                Box::new(FilterExpr::Compare {
+                    property_id: 1,
+                    labels: vec![LabelSet::from_iter([city])],
+                    op: CompareOp::Eq,
+                    literal: Literal::String("x".to_string()),
+                }),
+                Box::new(FilterExpr::Compare {
+                    property_id: 1,
+                    labels: person_labels(),
+                    op: CompareOp::Gt,
+                    literal: Literal::Integer(3),
+                }),
+            )))
+        );
+
+        let error = validate_filter_condition("CITY:age > 3", &graph_type).unwrap_err();
+        assert!(
+            matches!(error, FilterParseError::UnknownProperty { ref name, .. } if name == "CITY:age")
+        );
+        assert_eq!(error.span(), Some(Span { start: 0, end: 8 }));
+        assert!(matches!(
+            validate_filter_condition("PLANET:name = 'x'", &graph_type),
+            Err(FilterParseError::Catalog(message)) if message.contains("label 'PLANET' not found")
+        ));
+        assert!(matches!(
+            validate_filter_condition("CITY: name = 'x'", &graph_type),
+            Err(FilterParseError::UnexpectedChar { found: ':', .. })
+        ));
+    }
+
+    #[test]
+    fn test_matches() {
+        let graph_type = graph_type();
+        let expr =
+            parse_filter_condition("(age > 30 AND name != 'bob') OR active = true", &graph_type)
+                .unwrap()
+                .unwrap();
+        let vertex = |name: Option<&str>, age: Option<i32>, active: Option<bool>| {
+            vec![
+                ScalarValue::String(name.map(str::to_string)),
+                ScalarValue::Int32(age),
+                ScalarValue::Boolean(active),
+            ]
+        };
+        assert!(expr.matches(person(), &vertex(Some("alice"), Some(31), Some(false))));
+        assert!(!expr.matches(person(), &vertex(Some("bob"), Some(31), Some(false))));
+        assert!(!expr.matches(person(), &vertex(Some("alice"), Some(30), Some(false))));
+        assert!(expr.matches(person(), &vertex(Some("bob"), Some(20), Some(true))));
+        // Nulls and missing properties never match, not even through '!='
+        assert!(!expr.matches(person(), &vertex(None, Some(31), None)));
+        assert!(!expr.matches(person(), &[]));
+        // Vertices of a type not defining the properties never match
+        let other = LabelId::new(2).unwrap();
+        assert!(!expr.matches(other, &vertex(Some("bob"), Some(20), Some(true))));
+
+        let expr = parse_filter_condition("age <= 30.5", &graph_type)
+            .unwrap()
+            .unwrap();
+        assert!(expr.matches(person(), &vertex(None, Some(30), None)));
+        assert!(!expr.matches(person(), &vertex(None, Some(31), None)));
+    }
+}
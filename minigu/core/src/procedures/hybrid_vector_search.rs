@@ -0,0 +1,329 @@
+//! call hybrid_vector_search(<property_a>, <query_a>, <weight_a>,
+//!                           <property_b>, <query_b>, <weight_b>, <k>, <l_value>
+//!                           [, <per_property_k>]);
+//!
+//! Nearest neighbor search over two vector properties of the current graph, such as a text and
+//! an image embedding, fused into one ranking.
+//!
+//! ## Inputs
+//! * `<property_a>`, `<property_b>` – Names of two indexed vector properties, optionally qualified
+//!   as `label:property` and prefixed with `EDGE:` for edge properties (see
+//!   [`super::vector_search`]). Mixing vertex and edge properties is rejected.
+//! * `<query_a>`, `<query_b>` – Query vector for each property; dimensions must match its index.
+//! * `<weight_a>`, `<weight_b>` – Non-negative float weight of each property, not both zero.
+//! * `<k>` – Number of fused results to return.
+//! * `<l_value>` – DiskANN search list size of each per-property search.
+//! * `<per_property_k>` – Optional number of neighbors fetched per property, default `k`. Fetching
+//!   more than `k` lets nodes ranked moderately by both properties surface.
+//!
+//! Each property's Euclidean (or cosine) distances are min-max normalized to `[0, 1]`; a node
+//! found for only one property scores 1.0 for the other. Nodes are ranked by the weighted sum,
+//! see [`merge_weighted_results`].
+//!
+//! ## Output
+//! * `node_id` – IDs of the fused nearest vertices, or edges for `EDGE:` properties, best first.
+//! * `score` – Weighted sum of normalized distances; lower is closer.
+
+use std::sync::Arc;
+
+use arrow::array::{Float32Array, UInt64Array};
+use minigu_catalog::provider::GraphTypeProvider;
+use minigu_common::data_chunk::DataChunk;
+use minigu_common::data_type::{DataField, DataSchema, LogicalType};
+use minigu_common::value::ScalarValue;
+use minigu_context::procedure::Procedure;
+use minigu_storage::tp::MemoryGraph;
+use minigu_storage::tp::vector_index::merge_weighted_results;
+
+use super::common::Result;
+use super::vector_search::{
+    check_query_dimension, current_memory_graph, positive_arg, resolve_vector_property,
+};
+
+pub fn build_procedure() -> Procedure {
+    let parameters = vec![
+        LogicalType::String,
+        LogicalType::Vector(0),
+        LogicalType::Float64,
+        LogicalType::String,
+        LogicalType::Vector(0),
+        LogicalType::Float64,
+        LogicalType::Int64,
+        LogicalType::Int64,
+        // Optional per-property search depth
+        LogicalType::Int64,
+    ];
+    let schema = Arc::new(DataSchema::new(vec![
+        DataField::new("node_id".into(), LogicalType::UInt64, false),
+        DataField::new("score".into(), LogicalType::Float32, false),
+    ]));
+
+    Procedure::new(parameters, Some(schema), |context, args| {
+        if !(8..=9).contains(&args.len()) {
+            return Err(anyhow::anyhow!(
+                "hybrid_vector_search takes 8 or 9 arguments, got {}",
+                args.len()
+            )
+            .into());
+        }
+        let weights = [
+            weight_arg(&args[2], "weight_a")?,
+            weight_arg(&args[5], "weight_b")?,
+        ];
+        let k = positive_arg(&args[6], "k")?;
+        let l_value = u32::try_from(positive_arg(&args[7], "l_value")?)
+            .map_err(|_| anyhow::anyhow!("l_value must fit in u32"))?;
+        let per_property_k = match args.get(8) {
+            None | Some(ScalarValue::Null | ScalarValue::Int64(None)) => k,
+            Some(value) => positive_arg(value, "per_property_k")?,
+        };
+        let property_names = [
+            property_name_arg(&args[0], "property_a")?,
+            property_name_arg(&args[3], "property_b")?,
+        ];
+        let queries = [
+            args[1]
+                .as_vector()
+                .map_err(|e| anyhow::anyhow!("invalid query vector: {e}"))?,
+            args[4]
+                .as_vector()
+                .map_err(|e| anyhow::anyhow!("invalid query vector: {e}"))?,
+        ];
+
+        let (graph, graph_type) = current_memory_graph(&context)?;
+        let properties = [
+            (
+                property_names[0].as_str(),
+                queries[0].as_f32_slice(),
+                weights[0],
+            ),
+            (
+                property_names[1].as_str(),
+                queries[1].as_f32_slice(),
+                weights[1],
+            ),
+        ];
+        let (node_ids, scores): (Vec<u64>, Vec<f32>) = fused_search(
+            &graph,
+            graph_type.as_ref(),
+            properties,
+            k,
+            l_value,
+            per_property_k,
+        )?
+        .into_iter()
+        .unzip();
+        Ok(vec![DataChunk::new(vec![
+            Arc::new(UInt64Array::from(node_ids)),
+            Arc::new(Float32Array::from(scores)),
+        ])])
+    })
+    .with_optional_parameters(1)
+}
+
+/// Search each `(property name, query, weight)` for `per_property_k` neighbors and fuse the
+/// lists into the `k` best `(node_id, score)` pairs by [`merge_weighted_results`]
+fn fused_search(
+    graph: &MemoryGraph,
+    graph_type: &dyn GraphTypeProvider,
+    properties: [(&str, &[f32], f32); 2],
+    k: usize,
+    l_value: u32,
+    per_property_k: usize,
+) -> Result<Vec<(u64, f32)>> {
+    if properties.iter().all(|&(_, _, weight)| weight == 0.0) {
+        return Err(anyhow::anyhow!("weight_a and weight_b can't both be zero").into());
+    }
+    let mut per_property = Vec::with_capacity(2);
+    let mut previous_kind = None;
+    for (property_name, query, _) in properties {
+        let (kind, label_id, property_id) = resolve_vector_property(graph_type, property_name)?
+            .ok_or_else(|| anyhow::anyhow!("vector property '{property_name}' not found"))?;
+        // Vertex and edge IDs are separate ID spaces that can't be fused
+        if previous_kind
+            .replace(kind)
+            .is_some_and(|previous| previous != kind)
+        {
+            return Err(anyhow::anyhow!(
+                "property_a and property_b must both be vertex or both be edge properties"
+            )
+            .into());
+        }
+        check_query_dimension(
+            query.len(),
+            kind.index_dimension(graph, label_id, property_id, property_name)?,
+            property_name,
+        )?;
+        per_property.push(kind.search(
+            graph,
+            label_id,
+            property_id,
+            query,
+            per_property_k,
+            l_value,
+            None,
+            None,
+        )?);
+    }
+
+    let lists: Vec<(&[(u64, f32)], f32)> = per_property
+        .iter()
+        .map(Vec::as_slice)
+        .zip(properties.map(|(_, _, weight)| weight))
+        .collect();
+    Ok(merge_weighted_results(&lists, k))
+}
+
+/// A non-null string argument naming a property
+fn property_name_arg(value: &ScalarValue, name: &str) -> Result<String> {
+    match value {
+        ScalarValue::String(Some(property_name)) => Ok(property_name.clone()),
+        ScalarValue::String(None) | ScalarValue::Null => {
+            Err(anyhow::anyhow!("{name} can't be null").into())
+        }
+        other => Err(anyhow::anyhow!("{name} must be a string, got {other:?}").into()),
+    }
+}
+
+/// A finite, non-negative weight
+fn weight_arg(value: &ScalarValue, name: &str) -> Result<f32> {
+    let weight = value
+        .to_f64()
+        .map_err(|e| anyhow::anyhow!("{name} must be a number: {e:?}"))?;
+    if !weight.is_finite() || weight < 0.0 {
+        return Err(anyhow::anyhow!("{name} must be finite and non-negative, got {weight}").into());
+    }
+    Ok(weight as f32)
+}
+
+#[cfg(test)]
+mod tests {
+    use minigu_catalog::label_set::LabelSet;
+    use minigu_catalog::memory::graph_type::{MemoryGraphTypeCatalog, MemoryVertexTypeCatalog};
+    use minigu_catalog::property::Property;
+    use minigu_common::types::{LabelId, VectorIndexKey};
+    use minigu_common::value::{F32, F64, VectorValue};
+    use minigu_storage::common::{PropertyRecord, Vertex};
+    use minigu_transaction::{GraphTxnManager, IsolationLevel, Transaction};
+
+    use super::*;
+
+    /// DiskANN only supports a few dimensions
+    const DIMENSION: usize = 104;
+
+    /// A vector `x` units along the first axis
+    fn along_axis(x: f32) -> VectorValue {
+        let mut values = vec![F32::from(0.0); DIMENSION];
+        values[0] = F32::from(x);
+        VectorValue::new(values, DIMENSION).unwrap()
+    }
+
+    #[test]
+    fn test_fused_search_ranking() {
+        let person = LabelId::new(1).unwrap();
+        let mut graph_type = MemoryGraphTypeCatalog::new();
+        graph_type.add_label("PERSON".to_string()).unwrap();
+        graph_type.add_vertex_type(
+            LabelSet::from_iter([person]),
+            Arc::new(MemoryVertexTypeCatalog::new(
+                LabelSet::from_iter([person]),
+                vec![
+                    Property::new("name".to_string(), LogicalType::String, false),
+                    Property::new("text".to_string(), LogicalType::Vector(DIMENSION), false),
+                    Property::new("image".to_string(), LogicalType::Vector(DIMENSION), false),
+                ],
+            )),
+        );
+        // Distances to the origin by text and by image: vertex 1 is closest by text, 2 by image,
+        // and 3 moderately close by both. Far vertices connect the graph but miss both top 3.
+        let graph = MemoryGraph::in_memory();
+        let txn = graph
+            .txn_manager()
+            .begin_transaction(IsolationLevel::Serializable)
+            .unwrap();
+        let far = (10..30).map(|vid| (vid, vid as f32 + 10.0, vid as f32 + 10.0));
+        for (vid, text, image) in [(1, 0.0, 10.0), (2, 10.0, 0.0), (3, 3.0, 3.0)]
+            .into_iter()
+            .chain(far)
+        {
+            let properties = PropertyRecord::new(vec![
+                ScalarValue::String(Some(format!("v{vid}"))),
+                ScalarValue::new_vector(DIMENSION, Some(along_axis(text))),
+                ScalarValue::new_vector(DIMENSION, Some(along_axis(image))),
+            ]);
+            graph
+                .create_vertex(&txn, Vertex::new(vid, person, properties))
+                .unwrap();
+        }
+        for property_id in [1, 2] {
+            graph
+                .build_vector_index(&txn, VectorIndexKey::new(person, property_id))
+                .unwrap();
+        }
+        txn.commit().unwrap();
+
+        let origin = vec![0.0; DIMENSION];
+        let ranking = |text_weight, image_weight| {
+            let properties = [
+                ("text", origin.as_slice(), text_weight),
+                ("image", origin.as_slice(), image_weight),
+            ];
+            fused_search(&graph, &graph_type, properties, 3, 10, 3).unwrap()
+        };
+        let node_ids =
+            |results: &[(u64, f32)]| results.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+
+        // Normalized distances are 0, 1 and 0.3 by text and 1, 0 and 0.3 by image, so equal
+        // weights rank the compromise first
+        let balanced = ranking(0.5, 0.5);
+        assert_eq!(balanced[0].0, 3);
+        assert!((balanced[0].1 - 0.3).abs() < 1e-4);
+        assert!(
+            balanced[1..]
+                .iter()
+                .all(|(_, score)| (score - 0.5).abs() < 1e-4)
+        );
+        assert_eq!(node_ids(&ranking(0.9, 0.1)), vec![1, 3, 2]);
+        assert_eq!(node_ids(&ranking(0.1, 0.9)), vec![2, 3, 1]);
+
+        let properties = [
+            ("text", origin.as_slice(), 0.0),
+            ("image", origin.as_slice(), 0.0),
+        ];
+        assert!(fused_search(&graph, &graph_type, properties, 3, 10, 3).is_err());
+        let properties = [
+            ("text", origin.as_slice(), 1.0),
+            ("audio", origin.as_slice(), 1.0),
+        ];
+        let error = fused_search(&graph, &graph_type, properties, 3, 10, 3).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("vector property 'audio' not found")
+        );
+    }
+
+    #[test]
+    fn test_property_name_arg() {
+        assert_eq!(
+            property_name_arg(&ScalarValue::String(Some("text".into())), "property_a").unwrap(),
+            "text"
+        );
+        let error = property_name_arg(&ScalarValue::String(None), "property_a").unwrap_err();
+        assert!(error.to_string().contains("property_a can't be null"));
+        let error = property_name_arg(&ScalarValue::Int64(Some(1)), "property_b").unwrap_err();
+        assert!(error.to_string().contains("property_b must be a string"));
+    }
+
+    #[test]
+    fn test_weight_arg() {
+        assert_eq!(
+            weight_arg(&ScalarValue::Float64(Some(F64::from(0.25))), "w").unwrap(),
+            0.25
+        );
+        assert_eq!(weight_arg(&ScalarValue::Int64(Some(2)), "w").unwrap(), 2.0);
+        assert!(weight_arg(&ScalarValue::Float64(Some(F64::from(-1.0))), "w").is_err());
+        assert!(weight_arg(&ScalarValue::Float64(Some(F64::from(f64::NAN))), "w").is_err());
+        assert!(weight_arg(&ScalarValue::Float64(None), "w").is_err());
+    }
+}
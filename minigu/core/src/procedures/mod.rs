@@ -3,9 +3,12 @@
 mod create_test_graph_data;
 mod echo;
 mod export_graph;
+pub mod filter_condition;
+mod hybrid_vector_search;
 mod import_graph;
 mod show_graph;
 mod show_procedures;
+mod vector_search;
 
 pub(crate) use import_graph::import;
 use minigu_context::procedure::Procedure;
@@ -29,5 +32,13 @@ pub fn build_predefined_procedures() -> Vec<(String, Procedure)> {
         ("show_graph".to_string(), show_graph::build_procedure()),
         ("import_graph".to_string(), import_graph::build_procedure()),
         ("export_graph".to_string(), export_graph::build_procedure()),
+        (
+            "vector_search".to_string(),
+            vector_search::build_procedure(),
+        ),
+        (
+            "hybrid_vector_search".to_string(),
+            hybrid_vector_search::build_procedure(),
+        ),
     ]
 }
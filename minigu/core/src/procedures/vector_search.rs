@@ -0,0 +1,1028 @@
+//! call vector_search(<property_name>, <query_vector>, <k>, <l_value>, <filter_condition>
+//!                    [, <metric> [, <limit> [, <include_distance>]]]);
+//!
+//! Approximate nearest neighbor search over a vector index of the current graph.
+//!
+//! ## Inputs
+//! * `<property_name>` – Name of the indexed vector property. When vertex types with different
+//!   labels define properties of this name with different IDs, or the property is indexed under
+//!   several labels, qualify it with a label, as in `'PERSON:embedding'`; the label's index is then
+//!   searched. Prefix it with `EDGE:` to search an edge vector index over edge types instead, as in
+//!   `'EDGE:embedding'` or `'EDGE:FRIEND:embedding'`; `VERTEX:` names the default explicitly. The
+//!   prefixes take precedence over labels named `EDGE` or `VERTEX`.
+//! * `<query_vector>` – Query vector; its dimension must match the index.
+//! * `<k>` – Number of nearest neighbors to return.
+//! * `<l_value>` – DiskANN search list size (at least `k`).
+//! * `<filter_condition>` – Predicate over vertex properties restricting the candidates, such as
+//!   `age > 30 AND country = 'US'`; pass `''` for no filter. The grammar is documented in
+//!   [`super::filter_condition`]. Edge properties support no filter.
+//! * `<metric>` – Optional: `'l2'`, `'cosine'` or `'inner_product'`. When given, the index's
+//!   `l_value` candidates are re-ranked by an exact distance under this metric over the stored
+//!   vectors. This always takes the exact re-score path, adding one full distance computation per
+//!   candidate, so prefer an index built for the metric when latency matters. Pass `NULL` to keep
+//!   the index metric while setting `<limit>` or `<include_distance>`.
+//! * `<limit>` – Optional cap on the rows returned, applied after search and re-ranking; at most
+//!   `k`, defaulting to `k` (also when `NULL`). Lets `k` set the retrieval depth independently of
+//!   the output size.
+//! * `<include_distance>` – Optional boolean, default `false`. When `true`, a `distance` column is
+//!   added to the output; pass `NULL` for `<metric>` and `<limit>` to request it alone.
+//!
+//! ## Output
+//! * `node_id` – IDs of the nearest vertices, closest first; edge IDs for an `EDGE:` property.
+//! * `distance` – Only with `<include_distance>`: distance of each vertex to the query, under
+//!   `<metric>` when given and the index metric otherwise: Euclidean (not squared) for `l2`, `1 -
+//!   cos` for `cosine` and the negated dot product for `inner_product`.
+
+use std::sync::Arc;
+use std::time::Instant;
+
+use arrow::array::{BooleanArray, BooleanBufferBuilder, Float32Array, UInt64Array};
+use minigu_catalog::label_set::LabelSet;
+use minigu_catalog::provider::{GraphTypeProvider, PropertiesProvider};
+use minigu_common::data_chunk::DataChunk;
+use minigu_common::data_type::{DataField, DataSchema, DataSchemaRef, LogicalType};
+use minigu_common::types::{LabelId, PropertyId};
+use minigu_common::value::ScalarValue;
+use minigu_context::graph::{GraphContainer, GraphStorage};
+use minigu_context::procedure::Procedure;
+use minigu_context::session::SessionContext;
+use minigu_storage::error::{StorageError, VectorIndexError};
+use minigu_storage::tp::MemoryGraph;
+use minigu_storage::tp::vector_index::filter::{ArrowBooleanFilterMask, SearchFilter};
+use minigu_storage::tp::vector_index::{DistanceMetric, DistanceUnits, SearchStats};
+use minigu_transaction::{GraphTxnManager, IsolationLevel, Transaction};
+
+use super::common::Result;
+use super::filter_condition::{FilterExpr, parse_filter_condition};
+
+pub fn build_procedure() -> Procedure {
+    let parameters = vec![
+        LogicalType::String,
+        // Any dimension is accepted here; it is checked against the index at runtime
+        LogicalType::Vector(0),
+        LogicalType::Int64,
+        LogicalType::Int64,
+        LogicalType::String,
+        // Optional metric override
+        LogicalType::String,
+        // Optional row limit
+        LogicalType::Int64,
+        // Optional distance column toggle
+        LogicalType::Boolean,
+    ];
+
+    Procedure::new(parameters, Some(output_schema(false)), |context, args| {
+        assert!((5..=8).contains(&args.len()));
+        let property_name = args[0]
+            .try_as_string()
+            .expect("property name must be a string")
+            .clone()
+            .ok_or_else(|| anyhow::anyhow!("property name can't be null"))?;
+        // Borrowed, not cloned: the index copies the query at most once, into an aligned buffer
+        let query_vector = args[1]
+            .as_vector()
+            .map_err(|e| anyhow::anyhow!("invalid query vector: {e}"))?;
+        let k = positive_arg(&args[2], "k")?;
+        let l_value = u32::try_from(positive_arg(&args[3], "l_value")?)
+            .map_err(|_| anyhow::anyhow!("l_value must fit in u32"))?;
+        let filter_condition = args[4]
+            .try_as_string()
+            .expect("filter condition must be a string")
+            .clone()
+            .unwrap_or_default();
+        let metric = metric_arg(args.get(5))?;
+        let limit = limit_arg(args.get(6), k)?;
+        let include_distance = include_distance_arg(&args);
+
+        let (graph, graph_type) = current_memory_graph(&context)?;
+        let (results, _) = run_vector_search(
+            &graph,
+            graph_type.as_ref(),
+            &property_name,
+            query_vector.as_f32_slice(),
+            k,
+            l_value,
+            &filter_condition,
+            metric,
+        )?;
+        let (node_ids, distances): (Vec<u64>, Vec<f32>) = results.into_iter().take(limit).unzip();
+
+        let node_ids = Arc::new(UInt64Array::from(node_ids));
+        if include_distance {
+            let distances = Arc::new(Float32Array::from(distances));
+            Ok(vec![DataChunk::new(vec![node_ids, distances])])
+        } else {
+            Ok(vec![DataChunk::new(vec![node_ids])])
+        }
+    })
+    .with_optional_parameters(3)
+    .with_schema_fn(|args| Some(output_schema(include_distance_arg(args))))
+}
+
+/// Search the index of `property_name` as a `vector_search` call does, before its row limit,
+/// also reporting where the time went. Evaluating `filter_condition` over the vertices counts
+/// as filter construction in the stats, next to the search itself.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn run_vector_search(
+    graph: &MemoryGraph,
+    graph_type: &dyn GraphTypeProvider,
+    property_name: &str,
+    query: &[f32],
+    k: usize,
+    l_value: u32,
+    filter_condition: &str,
+    metric: Option<DistanceMetric>,
+) -> Result<(Vec<(u64, f32)>, SearchStats)> {
+    let (kind, label_id, property_id) = resolve_vector_property(graph_type, property_name)?
+        .ok_or_else(|| anyhow::anyhow!("vector property '{property_name}' not found"))?;
+    check_query_dimension(
+        query.len(),
+        kind.index_dimension(graph, label_id, property_id, property_name)?,
+        property_name,
+    )?;
+
+    if kind == ElementKind::Edge && !filter_condition.trim().is_empty() {
+        return Err(anyhow::anyhow!(
+            "filter conditions are not supported for edge property '{property_name}'"
+        )
+        .into());
+    }
+    let mut scan_stats = SearchStats::default();
+    let filter = parse_filter_condition(filter_condition, graph_type)
+        .map_err(|e| anyhow::anyhow!("invalid filter condition: {e}"))?
+        .map(|expr| generate_filter_mask(graph, &expr, &mut scan_stats))
+        .transpose()?;
+
+    let (results, mut stats) = kind.search_with_stats(
+        graph,
+        label_id,
+        property_id,
+        query,
+        k,
+        l_value,
+        filter.as_ref().map(|mask| mask as &dyn SearchFilter),
+        metric,
+    )?;
+    stats.filter_build_time += scan_stats.filter_build_time;
+    stats.vertices_scanned = scan_stats.vertices_scanned;
+    Ok((results, stats))
+}
+
+/// `node_id`, followed by `distance` when requested
+fn output_schema(include_distance: bool) -> DataSchemaRef {
+    let mut fields = vec![DataField::new("node_id".into(), LogicalType::UInt64, false)];
+    if include_distance {
+        fields.push(DataField::new(
+            "distance".into(),
+            LogicalType::Float32,
+            false,
+        ));
+    }
+    Arc::new(DataSchema::new(fields))
+}
+
+/// Whether the optional `include_distance` argument is present and true; null counts as false
+fn include_distance_arg(args: &[ScalarValue]) -> bool {
+    matches!(args.get(7), Some(ScalarValue::Boolean(Some(true))))
+}
+
+pub(crate) fn check_query_dimension(
+    actual: usize,
+    expected: usize,
+    property_name: &str,
+) -> Result<()> {
+    if actual != expected {
+        return Err(anyhow::anyhow!(
+            "query vector has dimension {actual} but property '{property_name}' expects {expected}"
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// Whether a vector property belongs to vertex or edge types
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ElementKind {
+    Vertex,
+    Edge,
+}
+
+impl ElementKind {
+    fn type_name(self) -> &'static str {
+        match self {
+            ElementKind::Vertex => "vertex type",
+            ElementKind::Edge => "edge type",
+        }
+    }
+
+    /// Dimension of the index built on `property_id` for this kind of element, under `label_id`
+    /// when the name was qualified with a label. Fails naming `property_name` when no index
+    /// exists, so searches stop before reaching the storage call.
+    pub(crate) fn index_dimension(
+        self,
+        graph: &MemoryGraph,
+        label_id: Option<LabelId>,
+        property_id: PropertyId,
+        property_name: &str,
+    ) -> Result<usize> {
+        let dimension = match self {
+            ElementKind::Vertex => graph.vector_index_dimension(label_id, property_id),
+            ElementKind::Edge => graph.edge_vector_index_dimension(label_id, property_id),
+        };
+        match dimension {
+            Err(StorageError::VectorIndex(VectorIndexError::IndexNotFound(_))) => {
+                let element = match self {
+                    ElementKind::Vertex => "vertex",
+                    ElementKind::Edge => "edge",
+                };
+                Err(anyhow::anyhow!(
+                    "no vector index exists on {element} property '{property_name}'"
+                )
+                .into())
+            }
+            dimension => Ok(dimension?),
+        }
+    }
+
+    /// Search the index found as for [`index_dimension`](Self::index_dimension), returning
+    /// element IDs with natural distances
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn search(
+        self,
+        graph: &MemoryGraph,
+        label_id: Option<LabelId>,
+        property_id: PropertyId,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter: Option<&dyn SearchFilter>,
+        metric: Option<DistanceMetric>,
+    ) -> Result<Vec<(u64, f32)>> {
+        let (results, _) = self.search_with_stats(
+            graph,
+            label_id,
+            property_id,
+            query,
+            k,
+            l_value,
+            filter,
+            metric,
+        )?;
+        Ok(results)
+    }
+
+    /// Like [`search`](Self::search), also returning the storage's cost breakdown
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn search_with_stats(
+        self,
+        graph: &MemoryGraph,
+        label_id: Option<LabelId>,
+        property_id: PropertyId,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter: Option<&dyn SearchFilter>,
+        metric: Option<DistanceMetric>,
+    ) -> Result<(Vec<(u64, f32)>, SearchStats)> {
+        let search = match self {
+            ElementKind::Vertex => MemoryGraph::vector_search_raw_with_stats,
+            ElementKind::Edge => MemoryGraph::edge_vector_search_raw_with_stats,
+        };
+        Ok(search(
+            graph,
+            label_id,
+            property_id,
+            query,
+            k,
+            l_value,
+            filter,
+            metric,
+            DistanceUnits::Natural,
+        )?)
+    }
+}
+
+/// Resolve a vector property name as accepted by `vector_search`: an optional `EDGE:` or
+/// `VERTEX:` prefix (vertex by default) followed by a name for [`resolve_element_property`].
+/// Also returns the label of a `label:property` name, which selects among indexes built on the
+/// property under different labels.
+pub(crate) fn resolve_vector_property(
+    graph_type: &dyn GraphTypeProvider,
+    name: &str,
+) -> Result<Option<(ElementKind, Option<LabelId>, PropertyId)>> {
+    let (kind, name) = if let Some(name) = name.strip_prefix("EDGE:") {
+        (ElementKind::Edge, name)
+    } else if let Some(name) = name.strip_prefix("VERTEX:") {
+        (ElementKind::Vertex, name)
+    } else {
+        (ElementKind::Vertex, name)
+    };
+    let (label_id, _) = split_label(graph_type, name)?;
+    Ok(resolve_element_property(graph_type, kind, name)?
+        .map(|(property_id, ..)| (kind, label_id, property_id)))
+}
+
+/// Find the ID of the vertex property named `name`, either a bare property name or
+/// `label:property` restricted to vertex types carrying that label. Fails if the name matches
+/// properties with different IDs.
+pub(crate) fn resolve_property_name(
+    graph_type: &dyn GraphTypeProvider,
+    name: &str,
+) -> Result<Option<PropertyId>> {
+    Ok(resolve_property(graph_type, name)?.map(|(property_id, ..)| property_id))
+}
+
+/// Like [`resolve_property_name`], also returning the property's type and the label sets of
+/// the vertex types defining it. Property IDs are positions within a vertex type, so the ID
+/// only identifies the property on vertices of those types.
+pub(crate) fn resolve_property(
+    graph_type: &dyn GraphTypeProvider,
+    name: &str,
+) -> Result<Option<(PropertyId, LogicalType, Vec<LabelSet>)>> {
+    resolve_element_property(graph_type, ElementKind::Vertex, name)
+}
+
+/// Like [`resolve_property`] over the vertex or edge types of `graph_type`
+pub(crate) fn resolve_element_property(
+    graph_type: &dyn GraphTypeProvider,
+    kind: ElementKind,
+    name: &str,
+) -> Result<Option<(PropertyId, LogicalType, Vec<LabelSet>)>> {
+    let (label, property_name) = split_label(graph_type, name)?;
+
+    let keys = match kind {
+        ElementKind::Vertex => graph_type.vertex_type_keys(),
+        ElementKind::Edge => graph_type.edge_type_keys(),
+    };
+    let mut resolved: Option<(PropertyId, LogicalType, Vec<LabelSet>)> = None;
+    for key in keys {
+        if label.is_some_and(|label| !key.contains(label)) {
+            continue;
+        }
+        let property = match kind {
+            ElementKind::Vertex => graph_type
+                .get_vertex_type(&key)?
+                .map(|vertex_type| owned_property(vertex_type.as_ref(), property_name))
+                .transpose()?,
+            ElementKind::Edge => graph_type
+                .get_edge_type(&key)?
+                .map(|edge_type| owned_property(edge_type.as_ref(), property_name))
+                .transpose()?,
+        };
+        let Some((property_id, logical_type)) = property.flatten() else {
+            continue;
+        };
+        match &mut resolved {
+            None => resolved = Some((property_id, logical_type, vec![key])),
+            Some((first_id, _, keys)) if *first_id == property_id => keys.push(key),
+            Some((first_id, _, keys)) => {
+                return Err(anyhow::anyhow!(
+                    "property '{name}' is ambiguous: {kind} {} defines it with ID \
+                     {first_id} and {kind} {} with ID {property_id}; qualify it as \
+                     'label:{property_name}'",
+                    label_names(graph_type, &keys[0]),
+                    label_names(graph_type, &key),
+                    kind = kind.type_name(),
+                )
+                .into());
+            }
+        }
+    }
+    Ok(resolved)
+}
+
+/// Split `label:property` into the label's ID and the property name; a bare name has no label
+fn split_label<'a>(
+    graph_type: &dyn GraphTypeProvider,
+    name: &'a str,
+) -> Result<(Option<LabelId>, &'a str)> {
+    match name.split_once(':') {
+        Some((label, property_name)) => {
+            let label_id = graph_type
+                .get_label_id(label)?
+                .ok_or_else(|| anyhow::anyhow!("label '{label}' not found"))?;
+            Ok((Some(label_id), property_name))
+        }
+        None => Ok((None, name)),
+    }
+}
+
+/// The ID and type of the property `name` of a vertex or edge type
+fn owned_property<P: PropertiesProvider + ?Sized>(
+    element_type: &P,
+    name: &str,
+) -> Result<Option<(PropertyId, LogicalType)>> {
+    Ok(element_type
+        .get_property(name)?
+        .map(|(property_id, property)| (property_id, property.logical_type().clone())))
+}
+
+/// Names of the labels in `key`, such as `PERSON&EMPLOYEE`
+fn label_names(graph_type: &dyn GraphTypeProvider, key: &LabelSet) -> String {
+    graph_type
+        .label_names()
+        .into_iter()
+        .filter(|name| {
+            matches!(graph_type.get_label_id(name), Ok(Some(label_id)) if key.contains(label_id))
+        })
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+pub(crate) fn current_memory_graph(
+    context: &SessionContext,
+) -> Result<(Arc<MemoryGraph>, Arc<dyn GraphTypeProvider>)> {
+    let graph_ref = context
+        .current_graph
+        .clone()
+        .ok_or_else(|| anyhow::anyhow!("current graph is not selected"))?;
+    let container = graph_ref
+        .object()
+        .downcast_ref::<GraphContainer>()
+        .ok_or_else(|| anyhow::anyhow!("only in-memory graphs support vector search"))?;
+    let graph_type: Arc<dyn GraphTypeProvider> = container.graph_type();
+    match container.graph_storage() {
+        GraphStorage::Memory(graph) => Ok((Arc::clone(graph), graph_type)),
+    }
+}
+
+/// Evaluate `expr` over the vertices visible to a fresh transaction: element `i` is true iff
+/// vertex `i` satisfies it, comparing only properties its vertex type defines. The array ends at
+/// the highest matching vertex ID, and is searched in place as a node-indexed filter. The scan's
+/// duration and vertex count are added to `stats`. Errors if a matching vertex ID does not fit the
+/// filter's `u32` candidate IDs.
+pub(crate) fn generate_filter_mask(
+    graph: &MemoryGraph,
+    expr: &FilterExpr,
+    stats: &mut SearchStats,
+) -> Result<ArrowBooleanFilterMask> {
+    let start = Instant::now();
+    let txn = graph
+        .txn_manager()
+        .begin_transaction(IsolationLevel::Serializable)?;
+    let mut bits = BooleanBufferBuilder::new(0);
+    for vertex in txn.iter_vertices() {
+        let vertex = vertex?;
+        stats.vertices_scanned += 1;
+        if expr.matches(vertex.label_id, vertex.properties.props()) {
+            if u32::try_from(vertex.vid).is_err() {
+                return Err(anyhow::anyhow!(
+                    "vertex {} is beyond the ID range a filter can select",
+                    vertex.vid
+                )
+                .into());
+            }
+            let vid = vertex.vid as usize;
+            if vid >= bits.len() {
+                bits.resize(vid + 1);
+            }
+            bits.set_bit(vid, true);
+        }
+    }
+    txn.commit()?;
+    let mask = ArrowBooleanFilterMask::new(BooleanArray::new(bits.finish(), None));
+    stats.filter_build_time += start.elapsed();
+    Ok(mask)
+}
+
+/// The optional metric override: none when absent or null
+fn metric_arg(value: Option<&ScalarValue>) -> Result<Option<DistanceMetric>> {
+    match value {
+        None | Some(ScalarValue::Null | ScalarValue::String(None)) => Ok(None),
+        Some(ScalarValue::String(Some(metric))) => Ok(Some(metric.parse()?)),
+        Some(other) => Err(anyhow::anyhow!("metric must be a string, got {other:?}").into()),
+    }
+}
+
+/// The optional row limit: `k` when absent or null, otherwise a positive value of at most `k`
+fn limit_arg(value: Option<&ScalarValue>, k: usize) -> Result<usize> {
+    let limit = match value {
+        None | Some(ScalarValue::Null | ScalarValue::Int64(None)) => return Ok(k),
+        Some(value) => positive_arg(value, "limit")?,
+    };
+    if limit > k {
+        return Err(anyhow::anyhow!("limit ({limit}) must not exceed k ({k})").into());
+    }
+    Ok(limit)
+}
+
+pub(crate) fn positive_arg(value: &ScalarValue, name: &str) -> Result<usize> {
+    let value = value
+        .to_i64()
+        .map_err(|e| anyhow::anyhow!("{name} must be an integer: {e:?}"))?;
+    if value <= 0 {
+        return Err(anyhow::anyhow!("{name} must be positive, got {value}").into());
+    }
+    Ok(value as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use minigu_catalog::memory::graph_type::{
+        MemoryEdgeTypeCatalog, MemoryGraphTypeCatalog, MemoryVertexTypeCatalog,
+    };
+    use minigu_catalog::property::Property;
+    use minigu_catalog::provider::ProcedureProvider;
+    use minigu_common::types::VectorIndexKey;
+    use minigu_common::value::{F32, VectorValue};
+    use minigu_planner::binder::error::BindError;
+    use minigu_planner::error::PlanError;
+    use minigu_storage::common::{PropertyRecord, Vertex};
+
+    use super::*;
+    use crate::database::Database;
+    use crate::procedures::filter_condition::parse_filter_condition;
+
+    #[test]
+    fn test_resolve_property_name() {
+        let graph_type = person_graph_type();
+        assert_eq!(
+            resolve_property_name(&graph_type, "embedding").unwrap(),
+            Some(1)
+        );
+        assert_eq!(resolve_property_name(&graph_type, "missing").unwrap(), None);
+        assert_eq!(
+            resolve_property_name(&graph_type, "PERSON:embedding").unwrap(),
+            Some(1)
+        );
+        assert!(resolve_property_name(&graph_type, "CITY:embedding").is_err());
+    }
+
+    #[test]
+    fn test_resolve_ambiguous_property_name() {
+        let mut graph_type = person_graph_type();
+        graph_type.add_label("PRODUCT".to_string()).unwrap();
+        let product = LabelId::new(2).unwrap();
+        graph_type.add_vertex_type(
+            LabelSet::from_iter([product]),
+            Arc::new(MemoryVertexTypeCatalog::new(
+                LabelSet::from_iter([product]),
+                vec![Property::new(
+                    "embedding".to_string(),
+                    LogicalType::Vector(8),
+                    false,
+                )],
+            )),
+        );
+
+        let error = resolve_property_name(&graph_type, "embedding").unwrap_err();
+        assert!(error.to_string().contains("ambiguous"), "{error}");
+        assert_eq!(
+            resolve_property_name(&graph_type, "PERSON:embedding").unwrap(),
+            Some(1)
+        );
+        assert_eq!(
+            resolve_property(&graph_type, "PRODUCT:embedding").unwrap(),
+            Some((
+                0,
+                LogicalType::Vector(8),
+                vec![LabelSet::from_iter([product])]
+            ))
+        );
+        // Names defined by only one type stay unqualified
+        assert_eq!(resolve_property_name(&graph_type, "name").unwrap(), Some(0));
+    }
+
+    #[test]
+    fn test_resolve_edge_vector_property() {
+        let mut graph_type = person_graph_type();
+        let person = LabelId::new(1).unwrap();
+        graph_type.add_label("FRIEND".to_string()).unwrap();
+        let friend = LabelId::new(2).unwrap();
+        let person_type = graph_type
+            .get_vertex_type(&LabelSet::from_iter([person]))
+            .unwrap()
+            .unwrap();
+        graph_type.add_edge_type(
+            LabelSet::from_iter([friend]),
+            Arc::new(MemoryEdgeTypeCatalog::new(
+                LabelSet::from_iter([friend]),
+                person_type.clone(),
+                person_type,
+                vec![
+                    Property::new("since".to_string(), LogicalType::Int64, false),
+                    Property::new("embedding".to_string(), LogicalType::Vector(4), false),
+                    Property::new("context".to_string(), LogicalType::Vector(4), false),
+                ],
+            )),
+        );
+
+        assert_eq!(
+            resolve_vector_property(&graph_type, "embedding").unwrap(),
+            Some((ElementKind::Vertex, None, 1))
+        );
+        assert_eq!(
+            resolve_vector_property(&graph_type, "VERTEX:PERSON:embedding").unwrap(),
+            Some((ElementKind::Vertex, Some(person), 1))
+        );
+        assert_eq!(
+            resolve_vector_property(&graph_type, "EDGE:embedding").unwrap(),
+            Some((ElementKind::Edge, None, 1))
+        );
+        assert_eq!(
+            resolve_vector_property(&graph_type, "EDGE:FRIEND:context").unwrap(),
+            Some((ElementKind::Edge, Some(friend), 2))
+        );
+        // Edge properties are not visible without the prefix, nor vertex ones with it
+        assert_eq!(
+            resolve_vector_property(&graph_type, "context").unwrap(),
+            None
+        );
+        assert_eq!(
+            resolve_vector_property(&graph_type, "EDGE:name").unwrap(),
+            None
+        );
+        assert_eq!(
+            resolve_vector_property(&graph_type, "EDGE:PERSON:embedding").unwrap(),
+            None
+        );
+    }
+
+    fn person_graph_type() -> MemoryGraphTypeCatalog {
+        let mut graph_type = MemoryGraphTypeCatalog::new();
+        let person = LabelId::new(1).unwrap();
+        graph_type.add_label("PERSON".to_string()).unwrap();
+        graph_type.add_vertex_type(
+            LabelSet::from_iter([person]),
+            Arc::new(MemoryVertexTypeCatalog::new(
+                LabelSet::from_iter([person]),
+                vec![
+                    Property::new("name".to_string(), LogicalType::String, false),
+                    Property::new("embedding".to_string(), LogicalType::Vector(4), false),
+                ],
+            )),
+        );
+        graph_type
+    }
+
+    #[test]
+    fn test_generate_filter_mask() {
+        let graph = MemoryGraph::in_memory();
+        let person = LabelId::new(1).unwrap();
+        let txn = graph
+            .txn_manager()
+            .begin_transaction(IsolationLevel::Serializable)
+            .unwrap();
+        for (vid, name) in [(1, "alice"), (2, "bob"), (5, "carol"), (6, "bob")] {
+            let properties = PropertyRecord::new(vec![
+                ScalarValue::String(Some(name.to_string())),
+                ScalarValue::Vector {
+                    dimension: 4,
+                    value: None,
+                },
+            ]);
+            graph
+                .create_vertex(&txn, Vertex::new(vid, person, properties))
+                .unwrap();
+        }
+        txn.commit().unwrap();
+
+        let graph_type = person_graph_type();
+        let expr = parse_filter_condition("name = 'bob' OR name = 'alice'", &graph_type)
+            .unwrap()
+            .unwrap();
+        let mut stats = SearchStats::default();
+        let mask = generate_filter_mask(&graph, &expr, &mut stats).unwrap();
+        assert_eq!(mask.iter_candidates().collect::<Vec<_>>(), vec![1, 2, 6]);
+        assert_eq!(stats.vertices_scanned, 4);
+        assert_eq!(mask.total_vector_num(), 7);
+
+        let expr = parse_filter_condition("name = 'dave'", &graph_type)
+            .unwrap()
+            .unwrap();
+        let mask = generate_filter_mask(&graph, &expr, &mut stats).unwrap();
+        assert_eq!(mask.candidate_count(), 0);
+        assert_eq!(stats.vertices_scanned, 8);
+    }
+
+    #[test]
+    fn test_filter_mask_compares_only_defining_types() {
+        let mut graph_type = MemoryGraphTypeCatalog::new();
+        let (person, city) = (LabelId::new(1).unwrap(), LabelId::new(2).unwrap());
+        for (name, label, property) in [("PERSON", person, "age"), ("CITY", city, "population")] {
+            graph_type.add_label(name.to_string()).unwrap();
+            graph_type.add_vertex_type(
+                LabelSet::from_iter([label]),
+                Arc::new(MemoryVertexTypeCatalog::new(
+                    LabelSet::from_iter([label]),
+                    vec![
+                        Property::new("name".to_string(), LogicalType::String, false),
+                        Property::new(property.to_string(), LogicalType::Int64, false),
+                    ],
+                )),
+            );
+        }
+        let graph = MemoryGraph::in_memory();
+        let txn = graph
+            .txn_manager()
+            .begin_transaction(IsolationLevel::Serializable)
+            .unwrap();
+        for (vid, label, value) in [(0, person, 40), (1, city, 3), (2, city, 9)] {
+            let properties = PropertyRecord::new(vec![
+                ScalarValue::String(Some(format!("v{vid}"))),
+                ScalarValue::Int64(Some(value)),
+            ]);
+            graph
+                .create_vertex(&txn, Vertex::new(vid, label, properties))
+                .unwrap();
+        }
+        txn.commit().unwrap();
+
+        // `population` shares its ID with PERSON's `age`, which must not be compared
+        let expr = parse_filter_condition("population > 5", &graph_type)
+            .unwrap()
+            .unwrap();
+        let mask = generate_filter_mask(&graph, &expr, &mut SearchStats::default()).unwrap();
+        assert_eq!(mask.iter_candidates().collect::<Vec<_>>(), vec![2]);
+
+        // A property both types define at one ID is compared on all of them
+        let expr = parse_filter_condition("name != 'v1'", &graph_type)
+            .unwrap()
+            .unwrap();
+        let mask = generate_filter_mask(&graph, &expr, &mut SearchStats::default()).unwrap();
+        assert_eq!(mask.iter_candidates().collect::<Vec<_>>(), vec![0, 2]);
+    }
+
+    #[test]
+    fn test_check_query_dimension() {
+        assert!(check_query_dimension(4, 4, "emb").is_ok());
+        let error = check_query_dimension(3, 4, "emb").unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("query vector has dimension 3 but property 'emb' expects 4")
+        );
+    }
+
+    #[test]
+    fn test_index_dimension_requires_index() {
+        let graph = MemoryGraph::in_memory();
+        for (kind, element) in [(ElementKind::Vertex, "vertex"), (ElementKind::Edge, "edge")] {
+            let error = kind.index_dimension(&graph, None, 1, "emb").unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!("no vector index exists on {element} property 'emb'")
+            );
+        }
+    }
+
+    /// One of DiskANN's supported dimensions
+    const DIMENSION: usize = 104;
+
+    /// PERSON vertices `0..12` and ROBOT vertices `100..112` share the `embedding` property, each
+    /// label with its own index; vertex `first + i` lies at `i` along the first axis
+    fn per_label_graph() -> (Arc<MemoryGraph>, MemoryGraphTypeCatalog) {
+        let mut graph_type = MemoryGraphTypeCatalog::new();
+        let mut labels = Vec::new();
+        for (i, name) in ["PERSON", "ROBOT"].into_iter().enumerate() {
+            graph_type.add_label(name.to_string()).unwrap();
+            let label = LabelId::new(i as u32 + 1).unwrap();
+            graph_type.add_vertex_type(
+                LabelSet::from_iter([label]),
+                Arc::new(MemoryVertexTypeCatalog::new(
+                    LabelSet::from_iter([label]),
+                    vec![
+                        Property::new("name".to_string(), LogicalType::String, false),
+                        Property::new(
+                            "embedding".to_string(),
+                            LogicalType::Vector(DIMENSION),
+                            false,
+                        ),
+                    ],
+                )),
+            );
+            labels.push(label);
+        }
+        let graph = MemoryGraph::in_memory();
+        let txn = graph
+            .txn_manager()
+            .begin_transaction(IsolationLevel::Serializable)
+            .unwrap();
+        for (label, first_vid) in labels.into_iter().zip([0, 100]) {
+            for i in 0..12u64 {
+                let mut embedding = vec![F32::from(0.0); DIMENSION];
+                embedding[0] = F32::from(i as f32);
+                let embedding = VectorValue::new(embedding, DIMENSION).unwrap();
+                let properties = PropertyRecord::new(vec![
+                    ScalarValue::String(Some(format!("v{i}"))),
+                    ScalarValue::new_vector(DIMENSION, Some(embedding)),
+                ]);
+                graph
+                    .create_vertex(&txn, Vertex::new(first_vid + i, label, properties))
+                    .unwrap();
+            }
+            graph
+                .build_vector_index(&txn, VectorIndexKey::new(label, 1))
+                .unwrap();
+        }
+        txn.commit().unwrap();
+        (graph, graph_type)
+    }
+
+    #[test]
+    fn test_search_per_label_index() {
+        let (graph, graph_type) = per_label_graph();
+        let mut query = [0.0; DIMENSION];
+        query[0] = 3.0;
+        for (name, first_vid) in [("PERSON:embedding", 0), ("ROBOT:embedding", 100)] {
+            let (kind, label_id, property_id) =
+                resolve_vector_property(&graph_type, name).unwrap().unwrap();
+            assert_eq!(
+                kind.index_dimension(&graph, label_id, property_id, name)
+                    .unwrap(),
+                DIMENSION
+            );
+            let results = kind
+                .search(&graph, label_id, property_id, &query, 3, 10, None, None)
+                .unwrap();
+            let node_ids: Vec<u64> = results.iter().map(|(node_id, _)| *node_id).collect();
+            assert_eq!(node_ids[0], first_vid + 3);
+            assert!(
+                node_ids
+                    .iter()
+                    .all(|vid| (first_vid..first_vid + 12).contains(vid))
+            );
+        }
+
+        // The bare name can't pick one of the two indexes
+        let (kind, label_id, property_id) = resolve_vector_property(&graph_type, "embedding")
+            .unwrap()
+            .unwrap();
+        assert_eq!(label_id, None);
+        let error = kind
+            .index_dimension(&graph, label_id, property_id, "embedding")
+            .unwrap_err();
+        assert!(error.to_string().contains("multiple labels"));
+    }
+
+    #[test]
+    fn test_run_vector_search_stats() {
+        let (graph, graph_type) = per_label_graph();
+        let mut query = [0.0; DIMENSION];
+        query[0] = 3.0;
+        let search = |filter_condition: &str| {
+            run_vector_search(
+                &graph,
+                &graph_type,
+                "PERSON:embedding",
+                &query,
+                3,
+                10,
+                filter_condition,
+                None,
+            )
+            .unwrap()
+        };
+
+        // Every vertex of the graph is checked against the predicate, ROBOT ones included, and
+        // the scan counts towards filter construction. The matching ROBOT vertices are counted
+        // as candidates, though not indexed by the PERSON index
+        let (results, stats) = search("name = 'v3' OR name = 'v5'");
+        assert_eq!(
+            results
+                .iter()
+                .map(|(node_id, _)| *node_id)
+                .collect::<Vec<_>>(),
+            [3, 5]
+        );
+        assert_eq!(stats.vertices_scanned, 24);
+        assert_eq!(stats.filter_candidates, 4);
+        assert!(stats.filter_build_time > Duration::ZERO);
+
+        let (results, stats) = search("");
+        assert_eq!(results.len(), 3);
+        assert_eq!(stats.vertices_scanned, 0);
+        assert_eq!(stats.filter_candidates, 0);
+    }
+
+    #[test]
+    fn test_call_with_null_metric() {
+        let (graph, graph_type) = per_label_graph();
+        let database = Database::open_in_memory(Default::default()).unwrap();
+        database.default_schema().add_graph(
+            "g".to_string(),
+            Arc::new(GraphContainer::new(
+                Arc::new(graph_type),
+                GraphStorage::Memory(graph),
+            )),
+        );
+        let mut session = database.session().unwrap();
+        session.query("SESSION SET GRAPH g").unwrap();
+        let mut query = vec!["0.0"; DIMENSION];
+        query[0] = "3.0";
+        let mut call = |args: &str| {
+            session.query(&format!(
+                "CALL vector_search('PERSON:embedding', VECTOR [{}], 3, 10, {args}) RETURN *",
+                query.join(", ")
+            ))
+        };
+
+        // A NULL metric keeps the index metric and reaches the limit
+        let result = call("'', NULL, 2").unwrap();
+        let node_ids = result.chunks[0].columns()[0]
+            .as_any()
+            .downcast_ref::<UInt64Array>()
+            .unwrap();
+        assert_eq!(node_ids.len(), 2);
+        assert_eq!(node_ids.value(0), 3);
+
+        // Distances come back under the index metric, without forcing exact re-scoring
+        let result = call("'', NULL, NULL, true").unwrap();
+        let fields: Vec<_> = result
+            .schema()
+            .unwrap()
+            .fields()
+            .iter()
+            .map(|field| field.name())
+            .collect();
+        assert_eq!(fields, ["node_id", "distance"]);
+        let distances = result.chunks[0].columns()[1]
+            .as_any()
+            .downcast_ref::<Float32Array>()
+            .unwrap();
+        assert_eq!(distances.values(), &[0.0, 1.0, 1.0]);
+
+        // NULL limit and distance toggle fall back to their defaults
+        let result = call("'', NULL, NULL, NULL").unwrap();
+        assert_eq!(result.schema().unwrap().fields().len(), 1);
+        assert_eq!(result.chunks[0].len(), 3);
+
+        // Required parameters still reject NULL, and optional ones other types
+        for args in ["NULL", "'', 5"] {
+            let error = call(args).unwrap_err();
+            assert!(
+                matches!(
+                    error,
+                    crate::error::Error::Plan(PlanError::Bind(
+                        BindError::IncorrectArguments { .. }
+                    ))
+                ),
+                "{error:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_positive_arg() {
+        assert_eq!(positive_arg(&ScalarValue::Int8(Some(5)), "k").unwrap(), 5);
+        assert!(positive_arg(&ScalarValue::Int64(Some(0)), "k").is_err());
+        assert!(positive_arg(&ScalarValue::String(Some("x".into())), "k").is_err());
+    }
+
+    #[test]
+    fn test_output_schema_follows_include_distance() {
+        let procedure = build_procedure();
+        let mut args = vec![
+            ScalarValue::String(Some("embedding".into())),
+            ScalarValue::Null,
+            ScalarValue::Int64(Some(5)),
+            ScalarValue::Int64(Some(10)),
+            ScalarValue::String(Some(String::new())),
+        ];
+        let names = |args: &[ScalarValue]| {
+            procedure
+                .schema_for_args(args)
+                .unwrap()
+                .fields()
+                .iter()
+                .map(|field| field.name().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&args), ["node_id"]);
+
+        args.extend([
+            ScalarValue::Null,
+            ScalarValue::Null,
+            ScalarValue::Boolean(Some(true)),
+        ]);
+        assert_eq!(names(&args), ["node_id", "distance"]);
+        args[7] = ScalarValue::Boolean(None);
+        assert_eq!(names(&args), ["node_id"]);
+    }
+
+    #[test]
+    fn test_metric_arg() {
+        assert_eq!(metric_arg(None).unwrap(), None);
+        assert_eq!(metric_arg(Some(&ScalarValue::Null)).unwrap(), None);
+        assert_eq!(
+            metric_arg(Some(&ScalarValue::String(Some("cosine".into())))).unwrap(),
+            Some(DistanceMetric::Cosine)
+        );
+        assert!(metric_arg(Some(&ScalarValue::String(Some("manhattan".into())))).is_err());
+        assert!(metric_arg(Some(&ScalarValue::Int64(Some(1)))).is_err());
+    }
+
+    #[test]
+    fn test_limit_arg() {
+        assert_eq!(limit_arg(None, 10).unwrap(), 10);
+        assert_eq!(limit_arg(Some(&ScalarValue::Int64(None)), 10).unwrap(), 10);
+        assert_eq!(
+            limit_arg(Some(&ScalarValue::Int64(Some(3))), 10).unwrap(),
+            3
+        );
+        assert!(limit_arg(Some(&ScalarValue::Int64(Some(11))), 10).is_err());
+        assert!(limit_arg(Some(&ScalarValue::Int64(Some(0))), 10).is_err());
+    }
+}
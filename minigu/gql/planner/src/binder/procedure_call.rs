@@ -2,7 +2,7 @@
 
 use gql_parser::ast::{CallProcedureStatement, NamedProcedureCall, ProcedureCall};
 use itertools::Itertools;
-use minigu_common::data_type::{DataField, DataSchema};
+use minigu_common::data_type::{DataField, DataSchema, LogicalType};
 use minigu_common::error::not_implemented;
 
 use super::Binder;
@@ -43,15 +43,34 @@ pub fn bind_named_procedure_call(
             .map(|arg| self.bind_value_expression(arg.value()))
             .try_collect()?;
         let args_types = args.iter().map(|a| a.logical_type.clone()).collect_vec();
-        if args_types != parameters {
+        let required = procedure_ref.required_parameters();
+        if args_types.len() < required
+            || args_types.len() > parameters.len()
+            || !parameters
+                .iter()
+                .zip(&args_types)
+                .enumerate()
+                .all(|(i, (parameter, argument))| {
+                    argument_matches(parameter, argument)
+                        || (i >= required && *argument == LogicalType::Null)
+                })
+        {
             return Err(BindError::IncorrectArguments {
                 procedure: procedure_ref.name().clone(),
                 expected: parameters.to_vec(),
                 actual: args_types,
             });
         }
+        // Arguments are literals, so procedures may shape their output by argument values
+        let original_schema = match args
+            .iter()
+            .map(|arg| arg.clone().evaluate_scalar())
+            .collect::<Option<Vec<_>>>()
+        {
+            Some(values) => procedure_ref.schema_for_args(&values),
+            None => procedure_ref.schema(),
+        };
         let schema = if let Some(yield_clause) = call.yield_clause.as_ref() {
-            let original_schema = procedure_ref.schema();
             let yield_clause = yield_clause.value();
             if let Some(original_schema) = original_schema {
                 if yield_clause.len() != original_schema.fields().len() {
@@ -81,7 +100,7 @@ pub fn bind_named_procedure_call(
                 ));
             }
         } else {
-            procedure_ref.schema().clone()
+            original_schema
         };
         Ok(BoundNamedProcedureCall {
             procedure_ref,
@@ -90,3 +109,30 @@ pub fn bind_named_procedure_call(
         })
     }
 }
+
+/// Whether an argument of type `argument` can be passed to a parameter of type `parameter`.
+/// Optional parameters additionally accept `NULL`, so a call can skip one to reach the next.
+///
+/// Besides exact matches, `Vector(0)` accepts vectors of any dimension (procedures validate the
+/// dimension at runtime), and signed integer parameters accept narrower signed integers since
+/// integer literals bind to the smallest type that fits.
+fn argument_matches(parameter: &LogicalType, argument: &LogicalType) -> bool {
+    fn signed_width(ty: &LogicalType) -> Option<u8> {
+        match ty {
+            LogicalType::Int8 => Some(8),
+            LogicalType::Int16 => Some(16),
+            LogicalType::Int32 => Some(32),
+            LogicalType::Int64 => Some(64),
+            _ => None,
+        }
+    }
+
+    match (parameter, argument) {
+        _ if parameter == argument => true,
+        (LogicalType::Vector(0), LogicalType::Vector(_)) => true,
+        _ => matches!(
+            (signed_width(parameter), signed_width(argument)),
+            (Some(param_width), Some(arg_width)) if arg_width <= param_width
+        ),
+    }
+}
@@ -0,0 +1,60 @@
+use std::hint::black_box;
+use std::sync::LazyLock;
+
+use divan::Bencher;
+use minigu_storage::tp::vector_index::filter::FilterMask;
+use minigu_storage::tp::vector_index::in_mem_diskann::create_vector_index_config;
+use minigu_storage::tp::vector_index::{AdapterConfig, InMemANNAdapter, SearchTuning, VectorIndex};
+use rayon::ThreadPoolBuilder;
+
+fn main() {
+    divan::main();
+}
+
+const DIM: usize = 128;
+const VECTORS: usize = 20_000;
+
+/// Index answering every filtered search by brute force, so a mask over all vectors scans all
+/// of them
+static ADAPTER: LazyLock<InMemANNAdapter> = LazyLock::new(|| {
+    let vectors: Vec<Vec<f32>> = (0..VECTORS)
+        .map(|i| {
+            (0..DIM)
+                .map(|d| ((i * 31 + d * 17) % 97) as f32 / 97.0)
+                .collect()
+        })
+        .collect();
+    let refs: Vec<(u64, &[f32])> = vectors
+        .iter()
+        .enumerate()
+        .map(|(i, vector)| (i as u64, vector.as_slice()))
+        .collect();
+    let tuning = SearchTuning {
+        brute_force_selectivity: 2.0,
+        ..Default::default()
+    };
+    let mut adapter = InMemANNAdapter::with_config(
+        create_vector_index_config(DIM, VECTORS),
+        AdapterConfig::new().with_tuning(tuning),
+    )
+    .unwrap();
+    adapter.build(&refs).unwrap();
+    adapter
+});
+
+/// Brute-force search over every vector, on a pool of the given size
+#[divan::bench(args = [1, 2, 4, 8])]
+fn brute_force_search(bencher: Bencher, threads: usize) {
+    let adapter = &*ADAPTER;
+    let mask = FilterMask::new((0..VECTORS as u32).collect(), adapter.size());
+    let query: Vec<f32> = (0..DIM).map(|d| d as f32 / DIM as f32).collect();
+    let pool = ThreadPoolBuilder::new()
+        .num_threads(threads)
+        .build()
+        .unwrap();
+    bencher.bench_local(|| {
+        pool.install(|| {
+            black_box(adapter.search(black_box(&query), 10, 20, Some(&mask), false)).unwrap()
+        })
+    });
+}
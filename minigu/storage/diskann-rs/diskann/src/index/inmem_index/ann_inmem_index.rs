@@ -6,9 +6,11 @@
 // Modifications:
 // - Added memory-based interface methods for miniGU integration: `build_from_memory`,
 //   `insert_from_memory`, and `get_aligned_vector_data`.
+// - Added `entry_point` for graph diagnostics.
+// - Added `export_graph` and `build_from_graph` for persisting the graph without relinking it.
 // - Extended the `search` method to include additional parameters: `distances: &mut [f32]`,
 //   `filter_mask: Option<&dyn FilterIndex>`, and `should_pre: bool`, allowing for more flexible
-//   search results with filtering and distance tracking.
+//   search results with filtering and distance tracking. It returns the number of results written.
 #![warn(missing_docs)]
 
 //! ANN in-memory index abstraction
@@ -27,7 +29,8 @@ pub trait ANNInmemIndex<T>: Sync + Send
     T: Default + Copy + Sync + Send + Into<f32>,
 {
     /// Search the index for K nearest neighbors of query using given L value, for benchmarking
-    /// purposes
+    /// purposes. Returns the number of results written, which is below K when fewer points
+    /// pass the filter
     #[allow(clippy::too_many_arguments)]
     fn search(
         &self,
@@ -72,6 +75,35 @@ fn get_aligned_vector_data(&self, _vector_id: u32) -> ANNResult<&[T]> {
             "get_aligned_vector_data not implemented".to_string(),
         ))
     }
+
+    /// Vector ID every search starts from, `None` before any point is indexed or when the
+    /// start is a frozen point rather than an indexed vector.
+    ///
+    /// Default implementation reports no entry point for backward compatibility.
+    fn entry_point(&self) -> Option<u32> {
+        None
+    }
+
+    /// Out-neighbors of every indexed vector by vector ID, and the start point of every search
+    /// Default implementation returns not supported error for backward compatibility
+    fn export_graph(&self) -> ANNResult<(Vec<Vec<u32>>, u32)> {
+        Err(ANNError::log_index_error(
+            "export_graph not implemented".to_string(),
+        ))
+    }
+
+    /// Load memory vectors together with a graph taken by `export_graph`, without linking anew
+    /// Default implementation returns not supported error for backward compatibility
+    fn build_from_graph(
+        &mut self,
+        _vectors: &[&[T]],
+        _adjacency: &[Vec<u32>],
+        _start: u32,
+    ) -> ANNResult<()> {
+        Err(ANNError::log_index_error(
+            "build_from_graph not implemented".to_string(),
+        ))
+    }
 }
 
 /// Create Index<T, N> based on configuration
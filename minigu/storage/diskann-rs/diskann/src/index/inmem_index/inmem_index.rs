@@ -8,7 +8,10 @@
 // - Enhanced search function: added distances output, pre-filter and post-filter support
 //   * Pre-filter: filter during graph traversal (via search_with_l_override)
 //   * Post-filter: filter in result candidates before returning top-K
+//   * Returns the number of results written rather than the comparison count
 // - Added get_aligned_vector_data for zero-copy vector access
+// - Added entry_point exposing the search start point for diagnostics
+// - Added export_graph and build_from_graph restoring a persisted graph without relinking
 
 use std::cmp;
 use std::sync::RwLock;
@@ -290,8 +293,7 @@ fn search(
             );
         }
 
-        let cmp =
-            self.search_with_l_override(query, scratch, l_value as usize, filter_mask, should_pre)?;
+        self.search_with_l_override(query, scratch, l_value as usize, filter_mask, should_pre)?;
         let mut pos = 0;
 
         for i in 0..scratch.best_candidates.size() {
@@ -326,7 +328,7 @@ fn search(
             eprintln!("Found fewer than K elements for query! Found: {pos} but K: {k_value}");
         }
 
-        Ok(cmp)
+        Ok(pos as u32)
     }
 
     fn cleanup_graph(&mut self, visit_order: &[u32]) -> ANNResult<()> {
@@ -771,6 +773,101 @@ fn get_aligned_vector_data(&self, vector_id: u32) -> ANNResult<&[T]> {
         // Return direct slice reference from aligned dataset (zero-copy!)
         Ok(&self.dataset.data[start..end])
     }
+
+    fn entry_point(&self) -> Option<u32> {
+        // Frozen points live at max_points and beyond, which is also the start before a build
+        (self.num_active_pts > 0 && (self.start as usize) < self.configuration.max_points)
+            .then_some(self.start)
+    }
+
+    fn export_graph(&self) -> ANNResult<(Vec<Vec<u32>>, u32)> {
+        if self.configuration.num_frozen_pts > 0 {
+            return Err(ANNError::log_index_error(
+                "ERROR: Cannot export a graph with frozen points.".to_string(),
+            ));
+        }
+        let mut adjacency = Vec::with_capacity(self.num_active_pts);
+        for vertex_id in 0..self.num_active_pts as u32 {
+            let vertex = self.final_graph.read_vertex_and_neighbors(vertex_id)?;
+            adjacency.push(vertex.get_neighbors().to_vec());
+        }
+        Ok((adjacency, self.start))
+    }
+
+    fn build_from_graph(
+        &mut self,
+        vectors: &[&[T]],
+        adjacency: &[Vec<u32>],
+        start: u32,
+    ) -> ANNResult<()> {
+        let num_points = vectors.len();
+        if num_points == 0 {
+            return Err(ANNError::log_index_error(
+                "ERROR: Cannot build index with 0 vectors.".to_string(),
+            ));
+        }
+        if num_points > self.configuration.max_points {
+            return Err(ANNError::log_index_error(format!(
+                "ERROR: Cannot load {} vectors, index can support only {} points as specified in configuration.",
+                num_points, self.configuration.max_points
+            )));
+        }
+        if self.configuration.num_frozen_pts > 0 {
+            return Err(ANNError::log_index_error(
+                "ERROR: Cannot import a graph into an index with frozen points.".to_string(),
+            ));
+        }
+        if adjacency.len() != num_points {
+            return Err(ANNError::log_index_error(format!(
+                "ERROR: Graph has {} adjacency lists for {} vectors.",
+                adjacency.len(),
+                num_points
+            )));
+        }
+        if start as usize >= num_points
+            || adjacency
+                .iter()
+                .flatten()
+                .any(|&neighbor| neighbor as usize >= num_points)
+        {
+            return Err(ANNError::log_index_error(format!(
+                "ERROR: Graph references a vertex beyond its {num_points} vectors."
+            )));
+        }
+        for (i, vector) in vectors.iter().enumerate() {
+            if vector.len() != self.configuration.dim {
+                return Err(ANNError::log_index_error(format!(
+                    "ERROR: Vector {} has dimension {} but configuration expects {} dimension.",
+                    i,
+                    vector.len(),
+                    self.configuration.dim
+                )));
+            }
+        }
+
+        self.dataset
+            .build_from_memory(vectors, num_points, self.configuration.dim)?;
+        let max_degree = self.configuration.index_write_parameter.max_degree as usize;
+        for (vertex_id, neighbors) in adjacency.iter().enumerate() {
+            let mut list = AdjacencyList::for_range(max_degree);
+            list.extend_from_slice(neighbors);
+            self.final_graph
+                .write_vertex_and_neighbors(vertex_id as u32)?
+                .set_neighbors(list);
+        }
+        self.num_active_pts = num_points;
+        self.start = start;
+
+        if self.query_scratch_queue.size()? == 0 {
+            self.initialize_query_scratch(
+                5 + self.configuration.index_write_parameter.num_threads,
+                self.configuration.index_write_parameter.search_list_size,
+            )?;
+        }
+        self.print_stats()?;
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -855,6 +952,85 @@ fn test_inmem_index_build_from_memory() {
         assert_eq!(indices[0], 0, "First result should be the query vector");
     }
 
+    #[test]
+    fn test_inmem_index_build_from_graph() {
+        let config = || {
+            let index_write_parameters = IndexWriteParametersBuilder::new(L, R)
+                .with_alpha(ALPHA)
+                .with_num_threads(1)
+                .build();
+            IndexConfiguration::new(
+                Metric::L2,
+                128,
+                round_up(128u64, 16u64) as usize,
+                100,
+                false,
+                0,
+                false,
+                0,
+                1.0f32,
+                index_write_parameters,
+            )
+        };
+        let test_vectors: Vec<Vec<f32>> = (0..20)
+            .map(|i| (0..128).map(|j| ((i * 7 + j) % 13) as f32).collect())
+            .collect();
+        let vector_refs: Vec<&[f32]> = test_vectors.iter().map(|v| v.as_slice()).collect();
+
+        let mut index: InmemIndex<f32, DIM_128> = InmemIndex::new(config()).unwrap();
+        index.build_from_memory(&vector_refs).unwrap();
+        let (adjacency, start) = index.export_graph().unwrap();
+        assert_eq!(adjacency.len(), 20);
+        assert_eq!(start, index.start);
+
+        let mut restored: InmemIndex<f32, DIM_128> = InmemIndex::new(config()).unwrap();
+        restored
+            .build_from_graph(&vector_refs, &adjacency, start)
+            .unwrap();
+        assert_eq!(restored.num_active_pts, 20);
+        assert_eq!(restored.export_graph().unwrap(), (adjacency.clone(), start));
+
+        let search = |index: &InmemIndex<f32, DIM_128>, query: &[f32]| {
+            let mut indices = vec![0u32; 5];
+            let mut distances = vec![0.0f32; 5];
+            ANNInmemIndex::search(
+                index,
+                query,
+                5,
+                20,
+                &mut indices,
+                &mut distances,
+                None,
+                false,
+            )
+            .unwrap();
+            (indices, distances)
+        };
+        for query in &vector_refs {
+            assert_eq!(search(&restored, query), search(&index, query));
+        }
+
+        // Graphs that don't fit the vectors are rejected
+        let mut target: InmemIndex<f32, DIM_128> = InmemIndex::new(config()).unwrap();
+        assert!(
+            target
+                .build_from_graph(&vector_refs, &adjacency[..19], start)
+                .is_err()
+        );
+        let mut dangling = adjacency.clone();
+        dangling[0].push(20);
+        assert!(
+            target
+                .build_from_graph(&vector_refs, &dangling, start)
+                .is_err()
+        );
+        assert!(
+            target
+                .build_from_graph(&vector_refs, &adjacency, 20)
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_inmem_index_insert_from_memory() {
         let index_write_parameters = IndexWriteParametersBuilder::new(L, R)
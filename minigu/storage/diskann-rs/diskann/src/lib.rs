@@ -15,3 +15,6 @@
 pub mod common;
 
 pub mod index;
+
+/// Version of this crate, as recorded in index provenance
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -169,7 +169,180 @@ pub enum VectorIndexError {
         label_id: NonZeroU32,
         property_id: u32,
     },
+    #[error("Index persistence error: {0}")]
+    Persistence(String),
+    #[error("Search needs about {required} bytes of candidate buffers, budget is {budget}")]
+    SearchBudgetExceeded { required: usize, budget: usize },
+    #[error("Index is being rebuilt, retry the search later")]
+    IndexRebuilding,
+    #[error("Index build was cancelled")]
+    BuildCancelled,
+    #[error("{count} ID mapping inconsistencies, first: {}", .examples.join("; "))]
+    InconsistentMappings {
+        /// Total number of inconsistencies found
+        count: usize,
+        /// Descriptions of the first few
+        examples: Vec<String>,
+    },
+}
+
+/// Stable numeric codes for [`VectorIndexError`], for callers that cannot match on Rust enums
+/// (e.g. across a C ABI). Values are part of the public contract: never renumber or reuse them,
+/// only append new codes.
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum VectorIndexErrorCode {
+    IndexNotBuilt = 1,
+    InvalidDimension = 2,
+    DiskANN = 3,
+    IndexNotFound = 4,
+    DataConversion = 5,
+    UnsupportedOperation = 6,
+    InvalidIndexType = 7,
+    Configuration = 8,
+    BuildError = 9,
+    SearchError = 10,
+    IdMappingError = 11,
+    VectorIdNotFound = 12,
+    NodeIdNotFound = 13,
+    DuplicateNodeId = 14,
+    EmptyDataset = 15,
+    TempFileError = 16,
+    InvalidSearchParams = 17,
+    InvalidBuildParams = 18,
+    InvalidInput = 19,
+    VectorIdOverflow = 20,
+    CapacityExceeded = 21,
+    NotSupported = 22,
+    InvalidBitmapLength = 23,
+    FilterError = 24,
+    IndexAlreadyExists = 25,
+    Persistence = 26,
+    SearchBudgetExceeded = 27,
+    IndexRebuilding = 28,
+    BuildCancelled = 29,
+    InconsistentMappings = 30,
+}
+
+impl VectorIndexErrorCode {
+    pub fn as_u32(self) -> u32 {
+        self as u32
+    }
+}
+
+impl VectorIndexError {
+    /// Stable numeric code identifying the error kind
+    pub fn code(&self) -> VectorIndexErrorCode {
+        use VectorIndexErrorCode as Code;
+        match self {
+            Self::DiskANN(_) => Code::DiskANN,
+            Self::IndexNotFound(_) => Code::IndexNotFound,
+            Self::InvalidDimension { .. } => Code::InvalidDimension,
+            Self::DataConversion(_) => Code::DataConversion,
+            Self::UnsupportedOperation(_) => Code::UnsupportedOperation,
+            Self::InvalidIndexType(_) => Code::InvalidIndexType,
+            Self::Configuration(_) => Code::Configuration,
+            Self::BuildError(_) => Code::BuildError,
+            Self::SearchError(_) => Code::SearchError,
+            Self::IdMappingError(_) => Code::IdMappingError,
+            Self::VectorIdNotFound { .. } => Code::VectorIdNotFound,
+            Self::NodeIdNotFound { .. } => Code::NodeIdNotFound,
+            Self::DuplicateNodeId { .. } => Code::DuplicateNodeId,
+            Self::EmptyDataset => Code::EmptyDataset,
+            Self::TempFileError(_) => Code::TempFileError,
+            Self::IndexNotBuilt => Code::IndexNotBuilt,
+            Self::InvalidSearchParams(_) => Code::InvalidSearchParams,
+            Self::InvalidBuildParams(_) => Code::InvalidBuildParams,
+            Self::InvalidInput(_) => Code::InvalidInput,
+            Self::VectorIdOverflow { .. } => Code::VectorIdOverflow,
+            Self::CapacityExceeded { .. } => Code::CapacityExceeded,
+            Self::NotSupported(_) => Code::NotSupported,
+            Self::InvalidBitmapLength { .. } => Code::InvalidBitmapLength,
+            Self::FilterError(_) => Code::FilterError,
+            Self::IndexAlreadyExists { .. } => Code::IndexAlreadyExists,
+            Self::Persistence(_) => Code::Persistence,
+            Self::SearchBudgetExceeded { .. } => Code::SearchBudgetExceeded,
+            Self::IndexRebuilding => Code::IndexRebuilding,
+            Self::BuildCancelled => Code::BuildCancelled,
+            Self::InconsistentMappings { .. } => Code::InconsistentMappings,
+        }
+    }
 }
 
 // Re-export DbFileError from db_file module for convenience
 pub use crate::db_file::DbFileError;
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_vector_index_error_codes_are_distinct() {
+        let errors = vec![
+            VectorIndexError::DiskANN(diskann::common::ANNError::log_index_error(String::new())),
+            VectorIndexError::IndexNotFound(String::new()),
+            VectorIndexError::InvalidDimension {
+                expected: 0,
+                actual: 0,
+            },
+            VectorIndexError::DataConversion(String::new()),
+            VectorIndexError::UnsupportedOperation(String::new()),
+            VectorIndexError::InvalidIndexType(String::new()),
+            VectorIndexError::Configuration(String::new()),
+            VectorIndexError::BuildError(String::new()),
+            VectorIndexError::SearchError(String::new()),
+            VectorIndexError::IdMappingError(String::new()),
+            VectorIndexError::VectorIdNotFound { vector_id: 0 },
+            VectorIndexError::NodeIdNotFound { node_id: 0 },
+            VectorIndexError::DuplicateNodeId { node_id: 0 },
+            VectorIndexError::EmptyDataset,
+            VectorIndexError::TempFileError(String::new()),
+            VectorIndexError::IndexNotBuilt,
+            VectorIndexError::InvalidSearchParams(String::new()),
+            VectorIndexError::InvalidBuildParams(String::new()),
+            VectorIndexError::InvalidInput(String::new()),
+            VectorIndexError::VectorIdOverflow { vector_id: 0 },
+            VectorIndexError::CapacityExceeded {
+                current: 0,
+                max_capacity: 0,
+            },
+            VectorIndexError::NotSupported(String::new()),
+            VectorIndexError::InvalidBitmapLength {
+                expected: 0,
+                got: 0,
+            },
+            VectorIndexError::FilterError(String::new()),
+            VectorIndexError::IndexAlreadyExists {
+                label_id: NonZeroU32::new(1).unwrap(),
+                property_id: 0,
+            },
+            VectorIndexError::Persistence(String::new()),
+            VectorIndexError::SearchBudgetExceeded {
+                required: 0,
+                budget: 0,
+            },
+            VectorIndexError::IndexRebuilding,
+            VectorIndexError::BuildCancelled,
+            VectorIndexError::InconsistentMappings {
+                count: 0,
+                examples: Vec::new(),
+            },
+        ];
+
+        let codes: HashSet<u32> = errors.iter().map(|e| e.code().as_u32()).collect();
+        assert_eq!(codes.len(), errors.len());
+        assert_eq!(VectorIndexError::IndexNotBuilt.code().as_u32(), 1);
+        assert_eq!(
+            VectorIndexError::InvalidDimension {
+                expected: 1,
+                actual: 2
+            }
+            .code()
+            .as_u32(),
+            2
+        );
+    }
+}
@@ -1,11 +1,14 @@
 use std::path::Path;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, RwLock, Weak};
+use std::time::Instant;
 
 use arrow::array::BooleanArray;
 use crossbeam_skiplist::SkipSet;
 use dashmap::DashMap;
-use minigu_common::types::{EdgeId, VectorIndexKey, VertexId};
+use diskann::common::FilterIndex as DiskANNFilterMask;
+use diskann::model::IndexConfiguration;
+use minigu_common::types::{EdgeId, LabelId, VectorIndexKey, VertexId};
 use minigu_common::value::{ScalarValue, VectorValue};
 use minigu_transaction::{IsolationLevel, Timestamp, Transaction};
 
@@ -14,9 +17,12 @@
 use super::persistence::PersistenceProvider;
 use super::transaction::{MemTransaction, UndoEntry, UndoPtr};
 use super::txn_manager::MemTxnManager;
-use super::vector_index::filter::create_filter_mask;
+use super::vector_index::filter::{FilterMask, SearchFilter, create_filter_mask};
 use super::vector_index::in_mem_diskann::create_vector_index_config;
-use super::vector_index::{InMemANNAdapter, VectorIndex};
+use super::vector_index::{
+    AdapterConfig, DistanceMetric, DistanceStats, DistanceUnits, InMemANNAdapter, IndexBuildReport,
+    SearchStats, VectorIndex,
+};
 use crate::common::model::edge::{Edge, Neighbor};
 use crate::common::model::vertex::Vertex;
 use crate::common::wal::graph_wal::{Operation, RedoEntry};
@@ -364,6 +370,74 @@ fn default() -> Self {
     }
 }
 
+/// Vector search hits together with their graph neighborhood, from
+/// [`MemoryGraph::vector_search_subgraph`]
+#[derive(Debug, Clone)]
+pub struct SearchSubgraph {
+    /// Search hits as `(node_id, distance)`, closest first
+    pub results: Vec<(u64, f32)>,
+    /// The hits plus every vertex reached within the requested hops, in ascending ID order
+    pub vertices: Vec<VertexId>,
+    /// Visible edges with both endpoints in `vertices`, in ascending ID order
+    pub edges: Vec<Edge>,
+    /// Whether expansion stopped early at the vertex limit
+    pub truncated: bool,
+}
+
+/// A registered vector index, as listed by [`MemoryGraph::vector_indexes`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct VectorIndexInfo {
+    pub key: VectorIndexKey,
+    pub dimension: usize,
+    pub metric: DistanceMetric,
+    /// Number of live vectors
+    pub size: usize,
+}
+
+/// A filter indexed by node ID, searched as a filter over the vector IDs of `index` by
+/// translating IDs as they are read rather than copying the candidates up front.
+///
+/// The candidate count and selectivity are those of the node filter, an overestimate when some
+/// candidate nodes are not indexed, which only steers strategy selection.
+struct NodeIdFilter<'a> {
+    nodes: &'a dyn SearchFilter,
+    index: &'a dyn VectorIndex,
+}
+
+impl DiskANNFilterMask for NodeIdFilter<'_> {
+    fn contains_vector(&self, vector_id: u32) -> bool {
+        self.index
+            .vector_to_node_id(vector_id)
+            .and_then(|node_id| u32::try_from(node_id).ok())
+            .is_some_and(|node_id| self.nodes.contains_vector(node_id))
+    }
+}
+
+impl SearchFilter for NodeIdFilter<'_> {
+    fn selectivity(&self) -> f32 {
+        match self.index.size() {
+            0 => 0.0,
+            size => (self.candidate_count() as f32 / size as f32).min(1.0),
+        }
+    }
+
+    fn candidate_count(&self) -> usize {
+        self.nodes.candidate_count()
+    }
+
+    fn total_vector_num(&self) -> usize {
+        self.index.size()
+    }
+
+    fn iter_candidates(&self) -> Box<dyn Iterator<Item = u32> + '_> {
+        Box::new(
+            self.nodes
+                .iter_candidates()
+                .filter_map(|node_id| self.index.node_to_vector_id(node_id as u64)),
+        )
+    }
+}
+
 pub struct MemoryGraph {
     // ---- Versioned data storage ----
     pub(super) vertices: DashMap<VertexId, VersionedVertex>, // Stores versioned vertices
@@ -389,6 +463,8 @@ pub struct MemoryGraph {
 
     // ---- Vector indices ----
     pub(super) vector_indices: DashMap<VectorIndexKey, Arc<RwLock<Box<dyn VectorIndex>>>>,
+    // Indexes over edge properties, keyed by edge label; their "node" IDs are edge IDs
+    pub(super) edge_vector_indices: DashMap<VectorIndexKey, Arc<RwLock<Box<dyn VectorIndex>>>>,
 }
 
 impl MemoryGraph {
@@ -449,6 +525,7 @@ fn with_persistence_and_config(
             checkpoint_config,
             wal_entries_since_checkpoint: AtomicUsize::new(0),
             vector_indices: DashMap::new(),
+            edge_vector_indices: DashMap::new(),
         });
 
         // Set the graph reference in the transaction manager
@@ -1015,12 +1092,21 @@ fn extract_vector_from_vertex(
         vertex: &Vertex,
         index_key: VectorIndexKey,
     ) -> Option<VectorValue> {
-        if vertex.label_id != index_key.label_id {
+        Self::extract_vector(vertex.label_id, vertex.properties(), index_key)
+    }
+
+    /// Extract the indexed vector from the properties of a vertex or edge with `label_id`
+    fn extract_vector(
+        label_id: LabelId,
+        properties: &[ScalarValue],
+        index_key: VectorIndexKey,
+    ) -> Option<VectorValue> {
+        if label_id != index_key.label_id {
             return None;
         }
 
         if let Ok(property_idx) = usize::try_from(index_key.property_id)
-            && let Some(property_value) = vertex.properties().get(property_idx)
+            && let Some(property_value) = properties.get(property_idx)
         {
             match property_value {
                 ScalarValue::Vector {
@@ -1065,13 +1151,15 @@ fn collect_vectors_from_nodes(
         Ok(vectors)
     }
 
-    /// Collect vectors from graph nodes for the specified vector index
+    /// Collect vectors from graph nodes for the specified vector index, along with the number
+    /// of vertices carrying the index label but no vector value
     fn collect_vectors_for_index(
         &self,
         txn: &Arc<MemTransaction>,
         index_key: VectorIndexKey,
-    ) -> StorageResult<Vec<(u64, VectorValue)>> {
+    ) -> StorageResult<(Vec<(u64, VectorValue)>, usize)> {
         let mut vectors = Vec::new();
+        let mut skipped = 0;
 
         // Iterate through all vertices in the graph
         let vertex_iter = self.iter_vertices(txn)?;
@@ -1082,10 +1170,35 @@ fn collect_vectors_for_index(
             // Use helper function to extract vector from vertex
             if let Some(vector_value) = Self::extract_vector_from_vertex(&vertex, index_key) {
                 vectors.push((node_id, vector_value));
+            } else if vertex.label_id == index_key.label_id {
+                skipped += 1;
             }
         }
 
-        Ok(vectors)
+        Ok((vectors, skipped))
+    }
+
+    /// Like [`collect_vectors_for_index`](Self::collect_vectors_for_index), scanning edges
+    fn collect_vectors_for_edge_index(
+        &self,
+        txn: &Arc<MemTransaction>,
+        index_key: VectorIndexKey,
+    ) -> StorageResult<(Vec<(u64, VectorValue)>, usize)> {
+        let mut vectors = Vec::new();
+        let mut skipped = 0;
+
+        for edge_result in self.iter_edges(txn)? {
+            let edge = edge_result?;
+            if let Some(vector_value) =
+                Self::extract_vector(edge.label_id(), edge.properties(), index_key)
+            {
+                vectors.push((edge.eid(), vector_value));
+            } else if edge.label_id() == index_key.label_id {
+                skipped += 1;
+            }
+        }
+
+        Ok((vectors, skipped))
     }
 
     /// Build a vector index for the specified property within a specific label
@@ -1094,7 +1207,135 @@ pub fn build_vector_index(
         txn: &Arc<MemTransaction>,
         index_key: VectorIndexKey,
     ) -> StorageResult<()> {
-        let vectors = self.collect_vectors_for_index(txn, index_key)?;
+        self.build_vector_index_with_config(txn, index_key, AdapterConfig::default())
+            .map(|_| ())
+    }
+
+    /// Build and register a vector index from a full vertex scan with explicit adapter options.
+    ///
+    /// Vertices with the index label whose property is null or not a vector are skipped; the
+    /// dimension is inferred from the first vector and every other vector must match it.
+    /// Returns how many vertices were indexed and skipped.
+    pub fn build_vector_index_with_config(
+        &self,
+        txn: &Arc<MemTransaction>,
+        index_key: VectorIndexKey,
+        adapter_config: AdapterConfig,
+    ) -> StorageResult<IndexBuildReport> {
+        let (vectors, skipped) = self.collect_vectors_for_index(txn, index_key)?;
+        let (index, report) = Self::build_index_from_vectors(vectors, skipped, adapter_config)?;
+        self.vector_indices.insert(index_key, index);
+        Ok(report)
+    }
+
+    /// Create a vector index over `index_key` with an explicit DiskANN configuration, from a
+    /// full vertex scan. Unlike the `build_*` methods, which replace an existing index, this
+    /// fails with `IndexAlreadyExists` if one is registered, and every vector must have
+    /// `index_config.dim`. With no vectors to index yet, the index is created empty and filled
+    /// by later inserts.
+    pub fn create_vector_index(
+        &self,
+        txn: &Arc<MemTransaction>,
+        index_key: VectorIndexKey,
+        index_config: IndexConfiguration,
+        adapter_config: AdapterConfig,
+    ) -> StorageResult<IndexBuildReport> {
+        let already_exists = || {
+            StorageError::VectorIndex(VectorIndexError::IndexAlreadyExists {
+                label_id: index_key.label_id,
+                property_id: index_key.property_id,
+            })
+        };
+        if self.vector_indices.contains_key(&index_key) {
+            return Err(already_exists());
+        }
+        let (vectors, skipped) = self.collect_vectors_for_index(txn, index_key)?;
+        let (index, report) =
+            Self::build_configured_index(vectors, skipped, index_config, adapter_config)?;
+        match self.vector_indices.entry(index_key) {
+            dashmap::Entry::Occupied(_) => Err(already_exists()),
+            dashmap::Entry::Vacant(entry) => {
+                entry.insert(index);
+                Ok(report)
+            }
+        }
+    }
+
+    /// Registered vertex vector indexes, ordered by label and property
+    pub fn vector_indexes(&self) -> Vec<VectorIndexInfo> {
+        Self::index_infos(&self.vector_indices)
+    }
+
+    /// Registered edge vector indexes, ordered by edge label and property
+    pub fn edge_vector_indexes(&self) -> Vec<VectorIndexInfo> {
+        Self::index_infos(&self.edge_vector_indices)
+    }
+
+    fn index_infos(
+        indices: &DashMap<VectorIndexKey, Arc<RwLock<Box<dyn VectorIndex>>>>,
+    ) -> Vec<VectorIndexInfo> {
+        let mut infos: Vec<VectorIndexInfo> = indices
+            .iter()
+            .map(|entry| {
+                let index = entry.value().read().unwrap();
+                VectorIndexInfo {
+                    key: *entry.key(),
+                    dimension: index.get_dimension(),
+                    metric: index.metric(),
+                    size: index.size(),
+                }
+            })
+            .collect();
+        infos.sort_by_key(|info| (info.key.label_id, info.key.property_id));
+        infos
+    }
+
+    /// Unregister the vertex vector index on `index_key`. Searches already holding the index
+    /// finish against it; it is freed once they do.
+    pub fn drop_vector_index(&self, index_key: VectorIndexKey) -> StorageResult<()> {
+        Self::remove_index(&self.vector_indices, index_key)
+    }
+
+    /// Unregister the edge vector index on `index_key`, see
+    /// [`drop_vector_index`](Self::drop_vector_index)
+    pub fn drop_edge_vector_index(&self, index_key: VectorIndexKey) -> StorageResult<()> {
+        Self::remove_index(&self.edge_vector_indices, index_key)
+    }
+
+    fn remove_index(
+        indices: &DashMap<VectorIndexKey, Arc<RwLock<Box<dyn VectorIndex>>>>,
+        index_key: VectorIndexKey,
+    ) -> StorageResult<()> {
+        indices.remove(&index_key).map(|_| ()).ok_or_else(|| {
+            StorageError::VectorIndex(VectorIndexError::IndexNotFound(format!(
+                "label_id: {}, property_id: {}",
+                index_key.label_id, index_key.property_id
+            )))
+        })
+    }
+
+    /// Build and register a vector index over an edge property from a full edge scan, keyed by
+    /// edge label. Indexed and searched like vertex indexes, with edge IDs in place of node IDs;
+    /// see [`build_vector_index_with_config`](Self::build_vector_index_with_config).
+    pub fn build_edge_vector_index(
+        &self,
+        txn: &Arc<MemTransaction>,
+        index_key: VectorIndexKey,
+        adapter_config: AdapterConfig,
+    ) -> StorageResult<IndexBuildReport> {
+        let (vectors, skipped) = self.collect_vectors_for_edge_index(txn, index_key)?;
+        let (index, report) = Self::build_index_from_vectors(vectors, skipped, adapter_config)?;
+        self.edge_vector_indices.insert(index_key, index);
+        Ok(report)
+    }
+
+    /// Build an index over `(id, vector)` pairs scanned from the graph
+    #[allow(clippy::type_complexity)]
+    fn build_index_from_vectors(
+        vectors: Vec<(u64, VectorValue)>,
+        skipped: usize,
+        adapter_config: AdapterConfig,
+    ) -> StorageResult<(Arc<RwLock<Box<dyn VectorIndex>>>, IndexBuildReport)> {
         if vectors.is_empty() {
             return Err(StorageError::VectorIndex(VectorIndexError::EmptyDataset));
         }
@@ -1125,9 +1366,33 @@ pub fn build_vector_index(
         }
 
         // Create index configuration with intelligent capacity based on actual vector count
+        let index_config = create_vector_index_config(dimension, vectors.len());
+        Self::build_configured_index(vectors, skipped, index_config, adapter_config)
+    }
+
+    /// Build an index with `index_config` over `(id, vector)` pairs scanned from the graph,
+    /// each of which must have the configured dimension
+    #[allow(clippy::type_complexity)]
+    fn build_configured_index(
+        vectors: Vec<(u64, VectorValue)>,
+        skipped: usize,
+        index_config: IndexConfiguration,
+        adapter_config: AdapterConfig,
+    ) -> StorageResult<(Arc<RwLock<Box<dyn VectorIndex>>>, IndexBuildReport)> {
+        let dimension = index_config.dim;
+        if let Some((_, vector_value)) = vectors
+            .iter()
+            .find(|(_, vector_value)| vector_value.dimension() != dimension)
+        {
+            return Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension {
+                    expected: dimension,
+                    actual: vector_value.dimension(),
+                },
+            ));
+        }
         let vector_count = vectors.len();
-        let index_config = create_vector_index_config(dimension, vector_count);
-        let mut adapter = InMemANNAdapter::new(index_config)?;
+        let mut adapter = InMemANNAdapter::with_config(index_config, adapter_config)?;
         // Convert VectorValue to &[f32] for VectorIndex
         let f32_vectors: Vec<Vec<f32>> = vectors
             .iter()
@@ -1148,9 +1413,14 @@ pub fn build_vector_index(
         adapter.build(&vector_refs)?;
 
         let index = Arc::new(RwLock::new(Box::new(adapter) as Box<dyn VectorIndex>));
-        self.vector_indices.insert(index_key, index);
-
-        Ok(())
+        Ok((
+            index,
+            IndexBuildReport {
+                indexed: vector_count,
+                skipped,
+                dimension,
+            },
+        ))
     }
 
     /// Get vector index for the specified label and property
@@ -1163,6 +1433,16 @@ pub fn get_vector_index(
             .map(|entry| Arc::clone(entry.value()))
     }
 
+    /// Get the edge vector index for the specified edge label and property
+    pub fn get_edge_vector_index(
+        &self,
+        index_key: VectorIndexKey,
+    ) -> Option<Arc<RwLock<Box<dyn VectorIndex>>>> {
+        self.edge_vector_indices
+            .get(&index_key)
+            .map(|entry| Arc::clone(entry.value()))
+    }
+
     /// Perform vector similarity search
     ///
     /// # Arguments
@@ -1181,6 +1461,22 @@ pub fn vector_search(
         filter_bitmap: Option<&BooleanArray>,
         should_pre: bool,
     ) -> StorageResult<Vec<(u64, f32)>> {
+        self.vector_search_with_stats(index_key, query, k, l_value, filter_bitmap, should_pre)
+            .map(|(results, _)| results)
+    }
+
+    /// Same as [`vector_search`](Self::vector_search), additionally reporting how the time was
+    /// split between building the filter from the bitmap and searching the index. The bitmap
+    /// arrives built, so `vertices_scanned` stays 0.
+    pub fn vector_search_with_stats(
+        &self,
+        index_key: VectorIndexKey,
+        query: &VectorValue,
+        k: usize,
+        l_value: u32,
+        filter_bitmap: Option<&BooleanArray>,
+        should_pre: bool,
+    ) -> StorageResult<(Vec<(u64, f32)>, SearchStats)> {
         let index = self.get_vector_index(index_key).ok_or_else(|| {
             StorageError::VectorIndex(VectorIndexError::IndexNotFound(format!(
                 "index_key: {:?}",
@@ -1198,19 +1494,346 @@ pub fn vector_search(
         }
         let query_vec = query.to_f32_vec();
 
+        let mut stats = SearchStats::default();
+
         // Convert BooleanArray to optimal FilterMask if provided
+        let filter_start = Instant::now();
         let filter_mask = filter_bitmap.map(|bitmap| {
-            let candidate_vector_ids = Self::bitmap_to_vector_ids(bitmap, &**index_ref);
-            let total_vector_num = candidate_vector_ids
-                .iter()
-                .max()
-                .map(|x| x + 1)
-                .unwrap_or(0);
-            create_filter_mask(candidate_vector_ids, total_vector_num.try_into().unwrap())
+            Self::vector_ids_to_filter_mask(Self::bitmap_to_vector_ids(bitmap, &**index_ref))
         });
-        let results = index_ref.search(&query_vec, k, l_value, filter_mask.as_ref(), should_pre)?;
+        stats.filter_build_time = filter_start.elapsed();
+        stats.filter_candidates = filter_mask
+            .as_ref()
+            .map_or(0, |mask| mask.candidate_count());
+
+        let search_start = Instant::now();
+        let filter = filter_mask.as_ref().map(|mask| mask as &dyn SearchFilter);
+        let results = index_ref.search(&query_vec, k, l_value, filter, should_pre)?;
+        stats.search_time = search_start.elapsed();
+        stats.result_distance_stats = DistanceStats::from_results(&results);
+
+        Ok((results, stats))
+    }
 
-        Ok(results)
+    /// Vector search followed by a breadth-first expansion of `hops` steps around the hits,
+    /// following edges in both directions, for rendering "similar items and how they connect".
+    ///
+    /// Expansion stops once the subgraph holds `max_vertices` vertices (the hits always count,
+    /// even beyond the limit), which bounds the cost on high-degree vertices. The returned
+    /// edges form the subgraph induced by the collected vertices.
+    #[allow(clippy::too_many_arguments)]
+    pub fn vector_search_subgraph(
+        &self,
+        txn: &Arc<MemTransaction>,
+        index_key: VectorIndexKey,
+        query: &VectorValue,
+        k: usize,
+        l_value: u32,
+        hops: usize,
+        max_vertices: usize,
+        filter_bitmap: Option<&BooleanArray>,
+    ) -> StorageResult<SearchSubgraph> {
+        use std::collections::BTreeSet;
+
+        let results = self.vector_search(index_key, query, k, l_value, filter_bitmap, false)?;
+        let mut vertices: BTreeSet<VertexId> = results.iter().map(|(id, _)| *id).collect();
+        let mut frontier: Vec<VertexId> = vertices.iter().copied().collect();
+        let mut truncated = false;
+
+        'expand: for _ in 0..hops {
+            let mut next = Vec::new();
+            for &vid in &frontier {
+                for neighbor in self.iter_adjacency(txn, vid)? {
+                    let neighbor_id = neighbor?.neighbor_id();
+                    if vertices.contains(&neighbor_id) {
+                        continue;
+                    }
+                    if vertices.len() >= max_vertices {
+                        truncated = true;
+                        break 'expand;
+                    }
+                    vertices.insert(neighbor_id);
+                    next.push(neighbor_id);
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+
+        let mut edge_ids = BTreeSet::new();
+        for &vid in &vertices {
+            for neighbor in self.iter_adjacency(txn, vid)? {
+                let neighbor = neighbor?;
+                if vertices.contains(&neighbor.neighbor_id()) {
+                    edge_ids.insert(neighbor.eid());
+                }
+            }
+        }
+        let edges = edge_ids
+            .into_iter()
+            .map(|eid| self.get_edge(txn, eid))
+            .collect::<StorageResult<_>>()?;
+
+        Ok(SearchSubgraph {
+            results,
+            vertices: vertices.into_iter().collect(),
+            edges,
+            truncated,
+        })
+    }
+
+    /// Numeric-only vector search by property ID, independent of query-engine types.
+    ///
+    /// Locates the vector index built on `property_id`, for the label `label_id` when given,
+    /// and returns the node IDs of the `k` nearest neighbors of `query`, closest first. `filter`
+    /// is indexed by node ID: its candidate `i` makes node `i` a candidate, and is translated to
+    /// vector IDs as the search reads it. Filter candidates are `u32`, so nodes with IDs of 2^32
+    /// or above can not be selected by a filter. `metric`, when set, re-ranks the index candidates
+    /// by an exact distance under that metric (see [`VectorIndex::search_rescored`]). Errors if
+    /// no such index exists, or if `label_id` is `None` and indexes on several labels cover the
+    /// property.
+    #[allow(clippy::too_many_arguments)]
+    pub fn vector_search_raw(
+        &self,
+        label_id: Option<LabelId>,
+        property_id: u32,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter: Option<&dyn SearchFilter>,
+        metric: Option<DistanceMetric>,
+    ) -> StorageResult<Vec<u64>> {
+        let results = self.vector_search_raw_with_distances(
+            label_id,
+            property_id,
+            query,
+            k,
+            l_value,
+            filter,
+            metric,
+            DistanceUnits::Ranking,
+        )?;
+        Ok(results.into_iter().map(|(node_id, _)| node_id).collect())
+    }
+
+    /// Like [`vector_search_raw`](Self::vector_search_raw), returning `(node_id, distance)`
+    /// pairs. Distances are under `metric` when given and the index metric otherwise, in
+    /// `units` (see [`DistanceUnits`]): with `Ranking`, L2 distances are squared.
+    #[allow(clippy::too_many_arguments)]
+    pub fn vector_search_raw_with_distances(
+        &self,
+        label_id: Option<LabelId>,
+        property_id: u32,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter: Option<&dyn SearchFilter>,
+        metric: Option<DistanceMetric>,
+        units: DistanceUnits,
+    ) -> StorageResult<Vec<(u64, f32)>> {
+        self.vector_search_raw_with_stats(
+            label_id,
+            property_id,
+            query,
+            k,
+            l_value,
+            filter,
+            metric,
+            units,
+        )
+        .map(|(results, _)| results)
+    }
+
+    /// Same as [`vector_search_raw_with_distances`](Self::vector_search_raw_with_distances),
+    /// additionally reporting how the time was split between translating `filter` to vector
+    /// IDs and searching the index. The filter arrives built, so no vertices are scanned here;
+    /// callers that build it from a predicate add their scan to the returned stats.
+    #[allow(clippy::too_many_arguments)]
+    pub fn vector_search_raw_with_stats(
+        &self,
+        label_id: Option<LabelId>,
+        property_id: u32,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter: Option<&dyn SearchFilter>,
+        metric: Option<DistanceMetric>,
+        units: DistanceUnits,
+    ) -> StorageResult<(Vec<(u64, f32)>, SearchStats)> {
+        let index = Self::find_vector_index(&self.vector_indices, label_id, property_id)?;
+        let index_ref = index.read().unwrap();
+        Self::search_index_raw(&**index_ref, query, k, l_value, filter, metric, units)
+    }
+
+    /// Like [`vector_search_raw_with_distances`](Self::vector_search_raw_with_distances) over
+    /// the edge vector index built on `property_id` (for the label `label_id`, when given),
+    /// returning edge IDs. `filter` is indexed by edge ID.
+    #[allow(clippy::too_many_arguments)]
+    pub fn edge_vector_search_raw_with_distances(
+        &self,
+        label_id: Option<LabelId>,
+        property_id: u32,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter: Option<&dyn SearchFilter>,
+        metric: Option<DistanceMetric>,
+        units: DistanceUnits,
+    ) -> StorageResult<Vec<(u64, f32)>> {
+        self.edge_vector_search_raw_with_stats(
+            label_id,
+            property_id,
+            query,
+            k,
+            l_value,
+            filter,
+            metric,
+            units,
+        )
+        .map(|(results, _)| results)
+    }
+
+    /// [`vector_search_raw_with_stats`](Self::vector_search_raw_with_stats) over an edge
+    /// vector index, as for
+    /// [`edge_vector_search_raw_with_distances`](Self::edge_vector_search_raw_with_distances)
+    #[allow(clippy::too_many_arguments)]
+    pub fn edge_vector_search_raw_with_stats(
+        &self,
+        label_id: Option<LabelId>,
+        property_id: u32,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter: Option<&dyn SearchFilter>,
+        metric: Option<DistanceMetric>,
+        units: DistanceUnits,
+    ) -> StorageResult<(Vec<(u64, f32)>, SearchStats)> {
+        let index = Self::find_vector_index(&self.edge_vector_indices, label_id, property_id)?;
+        let index_ref = index.read().unwrap();
+        Self::search_index_raw(&**index_ref, query, k, l_value, filter, metric, units)
+    }
+
+    /// Search `index` as described on
+    /// [`vector_search_raw_with_stats`](Self::vector_search_raw_with_stats)
+    #[allow(clippy::too_many_arguments)]
+    fn search_index_raw(
+        index_ref: &dyn VectorIndex,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter: Option<&dyn SearchFilter>,
+        metric: Option<DistanceMetric>,
+        units: DistanceUnits,
+    ) -> StorageResult<(Vec<(u64, f32)>, SearchStats)> {
+        if query.len() != index_ref.get_dimension() {
+            return Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension {
+                    expected: index_ref.get_dimension(),
+                    actual: query.len(),
+                },
+            ));
+        }
+
+        let mut stats = SearchStats::default();
+        let filter_start = Instant::now();
+        let filter = filter.map(|nodes| NodeIdFilter {
+            nodes,
+            index: index_ref,
+        });
+        stats.filter_build_time = filter_start.elapsed();
+        stats.filter_candidates = filter.as_ref().map_or(0, |filter| filter.candidate_count());
+
+        let search_start = Instant::now();
+        let filter = filter.as_ref().map(|filter| filter as &dyn SearchFilter);
+        let mut results = match metric {
+            Some(metric) => index_ref.search_rescored(query, k, l_value, filter, metric)?,
+            None => index_ref.search(query, k, l_value, filter, false)?,
+        };
+        if units == DistanceUnits::Natural {
+            for (_, distance) in &mut results {
+                *distance = match metric {
+                    Some(metric) => metric.to_natural(*distance),
+                    None => index_ref.natural_distance(*distance),
+                };
+            }
+        }
+        stats.search_time = search_start.elapsed();
+        stats.result_distance_stats = DistanceStats::from_results(&results);
+        Ok((results, stats))
+    }
+
+    /// Dimension of the vector index built on `property_id`, looked up as for
+    /// [`vector_search_raw`](Self::vector_search_raw)
+    pub fn vector_index_dimension(
+        &self,
+        label_id: Option<LabelId>,
+        property_id: u32,
+    ) -> StorageResult<usize> {
+        let index = Self::find_vector_index(&self.vector_indices, label_id, property_id)?;
+        Ok(index.read().unwrap().get_dimension())
+    }
+
+    /// Dimension of the edge vector index built on `property_id`, looked up as for
+    /// [`vector_search_raw`](Self::vector_search_raw)
+    pub fn edge_vector_index_dimension(
+        &self,
+        label_id: Option<LabelId>,
+        property_id: u32,
+    ) -> StorageResult<usize> {
+        let index = Self::find_vector_index(&self.edge_vector_indices, label_id, property_id)?;
+        Ok(index.read().unwrap().get_dimension())
+    }
+
+    /// The index of `indices` keyed by `label_id` and `property_id`, or without a label the
+    /// single index built on `property_id`
+    #[allow(clippy::type_complexity)]
+    fn find_vector_index(
+        indices: &DashMap<VectorIndexKey, Arc<RwLock<Box<dyn VectorIndex>>>>,
+        label_id: Option<LabelId>,
+        property_id: u32,
+    ) -> StorageResult<Arc<RwLock<Box<dyn VectorIndex>>>> {
+        if let Some(label_id) = label_id {
+            let index_key = VectorIndexKey::new(label_id, property_id);
+            return indices
+                .get(&index_key)
+                .map(|entry| Arc::clone(entry.value()))
+                .ok_or_else(|| {
+                    StorageError::VectorIndex(VectorIndexError::IndexNotFound(format!(
+                        "index_key: {index_key:?}"
+                    )))
+                });
+        }
+        let mut matches = indices
+            .iter()
+            .filter(|entry| entry.key().property_id == property_id)
+            .map(|entry| (*entry.key(), Arc::clone(entry.value())));
+        let first = matches.next().ok_or_else(|| {
+            StorageError::VectorIndex(VectorIndexError::IndexNotFound(format!(
+                "property_id: {property_id}"
+            )))
+        })?;
+        if let Some((other_key, _)) = matches.next() {
+            return Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(
+                format!(
+                    "property_id {property_id} has vector indexes on multiple labels ({} and {}); \
+                     specify the label",
+                    first.0.label_id, other_key.label_id
+                ),
+            )));
+        }
+        Ok(first.1)
+    }
+
+    /// Build a FilterMask over candidate vector IDs
+    fn vector_ids_to_filter_mask(candidate_vector_ids: Vec<u32>) -> FilterMask {
+        let total_vector_num = candidate_vector_ids
+            .iter()
+            .max()
+            .map(|x| x + 1)
+            .unwrap_or(0);
+        create_filter_mask(candidate_vector_ids, total_vector_num as usize)
     }
 
     /// Extract node IDs from a boolean bitmap where the value is true
@@ -1341,6 +1964,7 @@ fn check_write_conflict(commit_ts: Timestamp, txn: &Arc<MemTransaction>) -> Stor
 pub mod tests {
     // use std::fs;
 
+    use bitvec::vec::BitVec;
     use minigu_common::types::{LabelId, PropertyId};
     use minigu_common::value::{F32, ScalarValue, VectorValue};
     use minigu_transaction::{GraphTxnManager, IsolationLevel, Transaction};
@@ -3870,4 +4494,592 @@ fn test_brute_force_search_accuracy() -> StorageResult<()> {
         txn.commit()?;
         Ok(())
     }
+
+    #[test]
+    fn test_vector_search_raw() -> StorageResult<()> {
+        let graph = mock_empty_graph();
+        let txn = graph
+            .txn_manager()
+            .begin_transaction(IsolationLevel::Serializable)
+            .unwrap();
+
+        let test_vectors = create_small_scale_test_vectors();
+        for (id, name, embedding) in &test_vectors {
+            let vertex = create_vertex_with_vector(*id, name, embedding.clone());
+            graph.create_vertex(&txn, vertex)?;
+        }
+
+        // No index yet for the property
+        assert!(matches!(
+            graph.vector_search_raw(
+                None,
+                EMBEDDING_PROPERTY_ID,
+                &test_vectors[0].2,
+                3,
+                50,
+                None,
+                None
+            ),
+            Err(StorageError::VectorIndex(VectorIndexError::IndexNotFound(
+                _
+            )))
+        ));
+
+        let index_key = VectorIndexKey::new(PERSON, EMBEDDING_PROPERTY_ID);
+        graph.build_vector_index(&txn, index_key)?;
+
+        let (query_id, _, query) = &test_vectors[5];
+        let raw = graph.vector_search_raw(None, EMBEDDING_PROPERTY_ID, query, 5, 50, None, None)?;
+        let full = graph.vector_search(
+            index_key,
+            &create_vector_value_from_f32(query.clone()),
+            5,
+            50,
+            None,
+            false,
+        )?;
+        assert_eq!(raw, full.iter().map(|(id, _)| *id).collect::<Vec<_>>());
+        assert_eq!(raw[0], *query_id);
+        let with_distances = graph.vector_search_raw_with_distances(
+            None,
+            EMBEDDING_PROPERTY_ID,
+            query,
+            5,
+            50,
+            None,
+            None,
+            DistanceUnits::Ranking,
+        )?;
+        assert_eq!(with_distances, full);
+        assert_eq!(with_distances[0].1, 0.0);
+        let natural = graph.vector_search_raw_with_distances(
+            None,
+            EMBEDDING_PROPERTY_ID,
+            query,
+            5,
+            50,
+            None,
+            None,
+            DistanceUnits::Natural,
+        )?;
+        for ((natural_id, euclidean), (id, squared)) in natural.iter().zip(&full) {
+            assert_eq!(natural_id, id);
+            assert!((euclidean - squared.sqrt()).abs() < 1e-4);
+        }
+
+        // Node-indexed filter restricts the candidates
+        let allowed: Vec<u64> = test_vectors
+            .iter()
+            .map(|(id, _, _)| *id)
+            .step_by(3)
+            .collect();
+        let mut filter = BitVec::repeat(false, *allowed.iter().max().unwrap() as usize + 1);
+        for id in &allowed {
+            filter.set(*id as usize, true);
+        }
+        let filter = FilterMask::from_bitmap(filter);
+        let filtered = graph.vector_search_raw(
+            None,
+            EMBEDDING_PROPERTY_ID,
+            query,
+            3,
+            50,
+            Some(&filter),
+            None,
+        )?;
+        assert!(!filtered.is_empty());
+        assert!(filtered.iter().all(|id| allowed.contains(id)));
+
+        // Re-ranking by an exact metric keeps the query's own vertex first
+        let rescored = graph.vector_search_raw(
+            None,
+            EMBEDDING_PROPERTY_ID,
+            query,
+            5,
+            50,
+            None,
+            Some(DistanceMetric::Cosine),
+        )?;
+        assert_eq!(rescored.len(), 5);
+        assert_eq!(rescored[0], *query_id);
+
+        assert!(matches!(
+            graph.vector_search_raw(None, EMBEDDING_PROPERTY_ID, &[1.0; 3], 3, 50, None, None),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension { .. }
+            ))
+        ));
+
+        txn.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_search_with_stats() -> StorageResult<()> {
+        let graph = mock_empty_graph();
+        let txn = graph
+            .txn_manager()
+            .begin_transaction(IsolationLevel::Serializable)
+            .unwrap();
+
+        let test_vectors = create_small_scale_test_vectors();
+        for (id, name, embedding) in &test_vectors {
+            let vertex = create_vertex_with_vector(*id, name, embedding.clone());
+            graph.create_vertex(&txn, vertex)?;
+        }
+        let index_key = VectorIndexKey::new(PERSON, EMBEDDING_PROPERTY_ID);
+        graph.build_vector_index(&txn, index_key)?;
+        let query = create_vector_value_from_f32(test_vectors[0].2.clone());
+
+        let (results, stats) =
+            graph.vector_search_with_stats(index_key, &query, 5, 50, None, false)?;
+        assert_eq!(results.len(), 5);
+        assert_eq!(stats.vertices_scanned, 0);
+        assert_eq!(stats.filter_candidates, 0);
+        let distances = stats.result_distance_stats.unwrap();
+        assert_eq!(distances.min, results[0].1);
+        assert_eq!(distances.max, results[4].1);
+
+        let max_id = test_vectors.iter().map(|(id, _, _)| *id).max().unwrap() as usize;
+        let bitmap = BooleanArray::from((0..=max_id).map(|i| i % 2 == 0).collect::<Vec<_>>());
+        let (results, stats) =
+            graph.vector_search_with_stats(index_key, &query, 5, 50, Some(&bitmap), false)?;
+        assert!(!results.is_empty());
+        assert_eq!(stats.vertices_scanned, 0);
+        let expected_candidates = test_vectors.iter().filter(|(id, _, _)| id % 2 == 0).count();
+        assert_eq!(stats.filter_candidates, expected_candidates);
+
+        // The raw path reports the same breakdown for node-indexed filters
+        let even_nodes = FilterMask::new(
+            test_vectors
+                .iter()
+                .map(|(id, _, _)| *id as u32)
+                .filter(|id| id % 2 == 0)
+                .collect(),
+            max_id + 1,
+        );
+        let (results, stats) = graph.vector_search_raw_with_stats(
+            Some(PERSON),
+            EMBEDDING_PROPERTY_ID,
+            &test_vectors[0].2,
+            5,
+            50,
+            Some(&even_nodes),
+            None,
+            DistanceUnits::Ranking,
+        )?;
+        assert!(!results.is_empty());
+        assert_eq!(stats.vertices_scanned, 0);
+        assert_eq!(stats.filter_candidates, expected_candidates);
+        assert!(stats.result_distance_stats.is_some());
+
+        txn.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_node_id_filter() -> StorageResult<()> {
+        let graph = mock_empty_graph();
+        let txn = graph
+            .txn_manager()
+            .begin_transaction(IsolationLevel::Serializable)
+            .unwrap();
+
+        let test_vectors = create_small_scale_test_vectors();
+        for (id, name, embedding) in &test_vectors {
+            let vertex = create_vertex_with_vector(*id, name, embedding.clone());
+            graph.create_vertex(&txn, vertex)?;
+        }
+        let index_key = VectorIndexKey::new(PERSON, EMBEDDING_PROPERTY_ID);
+        graph.build_vector_index(&txn, index_key)?;
+        let index = graph.vector_indices.get(&index_key).unwrap().clone();
+        let index = index.read().unwrap();
+
+        // One indexed node and enough nodes without a vector to take the filter out of the
+        // brute-force range
+        let indexed = test_vectors[1].0;
+        let max_id = test_vectors.iter().map(|(id, _, _)| *id).max().unwrap();
+        let missing = (max_id + 1..=max_id + 39).map(|id| id as u32);
+        let nodes = FilterMask::new(
+            std::iter::once(indexed as u32).chain(missing).collect(),
+            max_id as usize + 40,
+        );
+        let filter = NodeIdFilter {
+            nodes: &nodes,
+            index: index.as_ref(),
+        };
+
+        let vector_id = index.node_to_vector_id(indexed).unwrap();
+        assert_eq!(filter.iter_candidates().collect::<Vec<_>>(), [vector_id]);
+        assert!(filter.contains_vector(vector_id));
+        let other = index.node_to_vector_id(test_vectors[0].0).unwrap();
+        assert!(!filter.contains_vector(other));
+        assert_eq!(filter.candidate_count(), 40);
+        assert_eq!(filter.total_vector_num(), test_vectors.len());
+
+        // The overcounted candidate must not pad the results
+        drop(index);
+        let results = graph.vector_search_raw(
+            Some(PERSON),
+            EMBEDDING_PROPERTY_ID,
+            &test_vectors[1].2,
+            3,
+            50,
+            Some(&nodes),
+            None,
+        )?;
+        assert_eq!(results, [indexed]);
+
+        txn.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_search_subgraph() -> StorageResult<()> {
+        let graph = mock_empty_graph();
+        let txn = graph
+            .txn_manager()
+            .begin_transaction(IsolationLevel::Serializable)
+            .unwrap();
+
+        let test_vectors = create_small_scale_test_vectors();
+        for (id, name, embedding) in &test_vectors {
+            graph.create_vertex(
+                &txn,
+                create_vertex_with_vector(*id, name, embedding.clone()),
+            )?;
+        }
+        // A chain hanging off the first vector vertex: hit - 1000 - 1001 - 1002
+        let hit = test_vectors[0].0;
+        for id in 1000..1003 {
+            graph.create_vertex(&txn, create_vertex(id, FOLLOW, vec![]))?;
+        }
+        for (eid, (src, dst)) in [(hit, 1000), (1000, 1001), (1001, 1002)]
+            .into_iter()
+            .enumerate()
+        {
+            graph.create_edge(
+                &txn,
+                create_edge(eid as EdgeId + 1, src, dst, FRIEND, vec![]),
+            )?;
+        }
+        let index_key = VectorIndexKey::new(PERSON, EMBEDDING_PROPERTY_ID);
+        graph.build_vector_index(&txn, index_key)?;
+        let query = create_vector_value_from_f32(test_vectors[0].2.clone());
+
+        let subgraph =
+            graph.vector_search_subgraph(&txn, index_key, &query, 1, 50, 2, 100, None)?;
+        assert_eq!(subgraph.results[0].0, hit);
+        assert_eq!(subgraph.vertices, vec![hit, 1000, 1001]);
+        let edge_ids: Vec<EdgeId> = subgraph.edges.iter().map(|edge| edge.eid()).collect();
+        assert_eq!(edge_ids, vec![1, 2]);
+        assert!(!subgraph.truncated);
+
+        // The vertex limit bounds the expansion
+        let subgraph = graph.vector_search_subgraph(&txn, index_key, &query, 1, 50, 3, 2, None)?;
+        assert_eq!(subgraph.vertices, vec![hit, 1000]);
+        assert_eq!(subgraph.edges.len(), 1);
+        assert!(subgraph.truncated);
+
+        // Zero hops returns only the hits
+        let subgraph =
+            graph.vector_search_subgraph(&txn, index_key, &query, 1, 50, 0, 100, None)?;
+        assert_eq!(subgraph.vertices, vec![hit]);
+        assert!(subgraph.edges.is_empty());
+
+        txn.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_vector_index_report() -> StorageResult<()> {
+        let graph = mock_empty_graph();
+        let txn = graph
+            .txn_manager()
+            .begin_transaction(IsolationLevel::Serializable)
+            .unwrap();
+
+        let test_vectors = create_small_scale_test_vectors();
+        for (id, name, embedding) in &test_vectors {
+            let vertex = create_vertex_with_vector(*id, name, embedding.clone());
+            graph.create_vertex(&txn, vertex)?;
+        }
+        // Two vertices of the indexed label without a vector value
+        let null_vector_ids = [10_000, 10_001];
+        for id in null_vector_ids {
+            let vertex = Vertex::new(
+                id,
+                PERSON,
+                PropertyRecord::new(vec![
+                    ScalarValue::String(Some("no-embedding".to_string())),
+                    ScalarValue::new_vector(TEST_DIMENSION, None),
+                ]),
+            );
+            graph.create_vertex(&txn, vertex)?;
+        }
+
+        let index_key = VectorIndexKey::new(PERSON, EMBEDDING_PROPERTY_ID);
+        let report = graph.build_vector_index_with_config(
+            &txn,
+            index_key,
+            AdapterConfig::new().with_high_precision_distance(true),
+        )?;
+        assert_eq!(report.indexed, test_vectors.len());
+        assert_eq!(report.skipped, null_vector_ids.len());
+        assert_eq!(report.dimension, TEST_DIMENSION);
+        assert_eq!(
+            graph
+                .get_vector_index(index_key)
+                .unwrap()
+                .read()
+                .unwrap()
+                .size(),
+            test_vectors.len()
+        );
+
+        txn.commit()?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_index_lifecycle() -> StorageResult<()> {
+        let graph = mock_empty_graph();
+        let txn = graph
+            .txn_manager()
+            .begin_transaction(IsolationLevel::Serializable)
+            .unwrap();
+        let test_vectors = create_small_scale_test_vectors();
+        for (id, name, embedding) in &test_vectors {
+            let vector_value = create_vector_value_from_f32(embedding.clone());
+            graph.create_vertex(
+                &txn,
+                create_vertex(
+                    *id,
+                    PERSON,
+                    vec![
+                        ScalarValue::String(Some(name.clone())),
+                        ScalarValue::new_vector(vector_value.dimension(), Some(vector_value)),
+                    ],
+                ),
+            )?;
+        }
+
+        let index_key = VectorIndexKey::new(PERSON, EMBEDDING_PROPERTY_ID);
+        let mut index_config = create_vector_index_config(TEST_DIMENSION, test_vectors.len());
+        index_config.index_write_parameter.max_degree = 16;
+        let report = graph.create_vector_index(
+            &txn,
+            index_key,
+            index_config.clone(),
+            AdapterConfig::new(),
+        )?;
+        assert_eq!(report.indexed, test_vectors.len());
+        assert!(matches!(
+            graph.create_vector_index(&txn, index_key, index_config, AdapterConfig::new()),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::IndexAlreadyExists { .. }
+            ))
+        ));
+
+        // A second index on the same property under another label, with nothing to index yet
+        let other_key = VectorIndexKey::new(FRIEND, EMBEDDING_PROPERTY_ID);
+        let report = graph.create_vector_index(
+            &txn,
+            other_key,
+            create_vector_index_config(TEST_DIMENSION, 8),
+            AdapterConfig::new(),
+        )?;
+        assert_eq!(report.indexed, 0);
+        assert_eq!(
+            graph.vector_indexes(),
+            vec![
+                VectorIndexInfo {
+                    key: index_key,
+                    dimension: TEST_DIMENSION,
+                    metric: DistanceMetric::L2,
+                    size: test_vectors.len(),
+                },
+                VectorIndexInfo {
+                    key: other_key,
+                    dimension: TEST_DIMENSION,
+                    metric: DistanceMetric::L2,
+                    size: 0,
+                },
+            ]
+        );
+        assert!(graph.edge_vector_indexes().is_empty());
+
+        graph.drop_vector_index(other_key)?;
+        assert!(matches!(
+            graph.drop_vector_index(other_key),
+            Err(StorageError::VectorIndex(VectorIndexError::IndexNotFound(
+                _
+            )))
+        ));
+        assert_eq!(graph.vector_indexes().len(), 1);
+        assert_eq!(
+            graph.vector_index_dimension(None, EMBEDDING_PROPERTY_ID)?,
+            TEST_DIMENSION
+        );
+        graph.drop_vector_index(index_key)?;
+        assert!(graph.vector_indexes().is_empty());
+        assert!(graph.get_vector_index(index_key).is_none());
+
+        // Vectors of another dimension than configured are rejected
+        assert!(matches!(
+            graph.create_vector_index(
+                &txn,
+                index_key,
+                create_vector_index_config(256, test_vectors.len()),
+                AdapterConfig::new(),
+            ),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension { expected: 256, .. }
+            ))
+        ));
+        assert!(graph.vector_indexes().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_search_per_label_indexes() -> StorageResult<()> {
+        // One property indexed independently under two labels
+        const ROBOT: LabelId = LabelId::new(3).unwrap();
+        let graph = mock_empty_graph();
+        let txn = graph
+            .txn_manager()
+            .begin_transaction(IsolationLevel::Serializable)
+            .unwrap();
+        let test_vectors: Vec<_> = create_small_scale_test_vectors()
+            .into_iter()
+            .take(40)
+            .collect();
+        // Robots copy the people's embeddings one vertex ID later
+        for (label, offset) in [(PERSON, 0), (ROBOT, 1)] {
+            for (id, name, embedding) in &test_vectors {
+                let vector_value = create_vector_value_from_f32(embedding.clone());
+                graph.create_vertex(
+                    &txn,
+                    create_vertex(
+                        *id + offset,
+                        label,
+                        vec![
+                            ScalarValue::String(Some(name.clone())),
+                            ScalarValue::new_vector(vector_value.dimension(), Some(vector_value)),
+                        ],
+                    ),
+                )?;
+            }
+        }
+        for label in [PERSON, ROBOT] {
+            graph.create_vector_index(
+                &txn,
+                VectorIndexKey::new(label, EMBEDDING_PROPERTY_ID),
+                create_vector_index_config(TEST_DIMENSION, test_vectors.len()),
+                AdapterConfig::new(),
+            )?;
+        }
+
+        let (query_id, _, query) = &test_vectors[7];
+        for (label, offset) in [(PERSON, 0), (ROBOT, 1)] {
+            let results = graph.vector_search_raw(
+                Some(label),
+                EMBEDDING_PROPERTY_ID,
+                query,
+                5,
+                50,
+                None,
+                None,
+            )?;
+            assert_eq!(results.len(), 5);
+            assert_eq!(results[0], query_id + offset);
+            assert!(results.iter().all(|id| (id - offset - 5) % 3 == 0));
+            assert_eq!(
+                graph.vector_index_dimension(Some(label), EMBEDDING_PROPERTY_ID)?,
+                TEST_DIMENSION
+            );
+        }
+
+        // Without a label the property is ambiguous, and an unindexed label is not found
+        assert!(matches!(
+            graph.vector_search_raw(None, EMBEDDING_PROPERTY_ID, query, 5, 50, None, None),
+            Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(_)))
+        ));
+        assert!(matches!(
+            graph.vector_index_dimension(Some(FRIEND), EMBEDDING_PROPERTY_ID),
+            Err(StorageError::VectorIndex(VectorIndexError::IndexNotFound(
+                _
+            )))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_edge_vector_index_search() -> StorageResult<()> {
+        let graph = mock_empty_graph();
+        let txn = graph
+            .txn_manager()
+            .begin_transaction(IsolationLevel::Serializable)
+            .unwrap();
+
+        let test_vectors = create_small_scale_test_vectors();
+        graph.create_vertex(
+            &txn,
+            create_vertex(1, PERSON, vec![ScalarValue::String(None)]),
+        )?;
+        graph.create_vertex(
+            &txn,
+            create_vertex(2, PERSON, vec![ScalarValue::String(None)]),
+        )?;
+        // Edge IDs reuse the test vertex IDs; the edges carry the embedding instead
+        for (id, name, embedding) in &test_vectors {
+            let vector_value = create_vector_value_from_f32(embedding.clone());
+            let edge = create_edge(
+                *id,
+                1,
+                2,
+                FRIEND,
+                vec![
+                    ScalarValue::String(Some(name.clone())),
+                    ScalarValue::new_vector(vector_value.dimension(), Some(vector_value)),
+                ],
+            );
+            graph.create_edge(&txn, edge)?;
+        }
+
+        let index_key = VectorIndexKey::new(FRIEND, EMBEDDING_PROPERTY_ID);
+        let report = graph.build_edge_vector_index(&txn, index_key, AdapterConfig::new())?;
+        assert_eq!(report.indexed, test_vectors.len());
+        assert_eq!(report.dimension, TEST_DIMENSION);
+        assert!(graph.get_edge_vector_index(index_key).is_some());
+        // Edge indexes are kept apart from vertex indexes
+        assert!(graph.get_vector_index(index_key).is_none());
+        assert!(matches!(
+            graph.vector_index_dimension(None, EMBEDDING_PROPERTY_ID),
+            Err(StorageError::VectorIndex(VectorIndexError::IndexNotFound(
+                _
+            )))
+        ));
+        assert_eq!(
+            graph.edge_vector_index_dimension(None, EMBEDDING_PROPERTY_ID)?,
+            TEST_DIMENSION
+        );
+
+        let (query_id, _, query) = &test_vectors[7];
+        let results = graph.edge_vector_search_raw_with_distances(
+            None,
+            EMBEDDING_PROPERTY_ID,
+            query,
+            5,
+            50,
+            None,
+            None,
+            DistanceUnits::Natural,
+        )?;
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0], (*query_id, 0.0));
+
+        txn.commit()?;
+        Ok(())
+    }
 }
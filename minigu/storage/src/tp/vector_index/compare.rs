@@ -0,0 +1,202 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::index::VectorIndex;
+use crate::error::StorageResult;
+
+/// Relative tolerance under which two distances are treated as a tie
+const TIE_EPSILON: f32 = 1e-6;
+
+/// Per-query result of [`compare_indexes`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryComparison {
+    /// Position of the query in the input query set
+    pub query_index: usize,
+    /// Results of `b` matching `a`, counting boundary ties as matches
+    pub overlap: usize,
+    /// `overlap` divided by the number of results `a` returned (1.0 if `a` returned none)
+    pub overlap_ratio: f32,
+    /// Largest tie-aware rank shift among nodes returned by both indexes
+    pub max_rank_shift: usize,
+}
+
+/// Aggregated result equivalence between two indexes over a query set
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComparisonReport {
+    pub k: usize,
+    pub queries: Vec<QueryComparison>,
+    /// Mean `overlap_ratio` across queries (1.0 for an empty query set)
+    pub mean_overlap: f32,
+    /// Worst `overlap_ratio` across queries (1.0 for an empty query set)
+    pub min_overlap: f32,
+    /// Rank shift -> number of shared results with that shift, across all queries
+    pub rank_shift_histogram: BTreeMap<usize, usize>,
+}
+
+impl ComparisonReport {
+    /// Whether every query reaches at least `min_overlap_ratio`
+    pub fn passes(&self, min_overlap_ratio: f32) -> bool {
+        self.min_overlap >= min_overlap_ratio
+    }
+}
+
+/// Run every query against both indexes and report how closely `b` reproduces `a`.
+///
+/// Tie-order differences are tolerated: a result of `b` missing from `a` still counts as a
+/// match when its distance ties `a`'s k-th distance, and ranks are computed as the number of
+/// strictly closer results, so reordering equidistant nodes yields a shift of zero.
+pub fn compare_indexes(
+    a: &dyn VectorIndex,
+    b: &dyn VectorIndex,
+    queries: &[&[f32]],
+    k: usize,
+    l_value: u32,
+) -> StorageResult<ComparisonReport> {
+    let mut per_query = Vec::with_capacity(queries.len());
+    let mut rank_shift_histogram = BTreeMap::new();
+
+    for (query_index, query) in queries.iter().enumerate() {
+        let results_a = a.search(query, k, l_value, None, false)?;
+        let results_b = b.search(query, k, l_value, None, false)?;
+
+        let ranks_a = tie_aware_ranks(&results_a);
+        let ranks_b = tie_aware_ranks(&results_b);
+        let kth_distance_a = results_a.last().map(|(_, distance)| *distance);
+
+        let mut overlap = 0;
+        let mut max_rank_shift = 0;
+        for (node_id, distance) in &results_b {
+            if let Some(&rank_a) = ranks_a.get(node_id) {
+                overlap += 1;
+                let shift = rank_a.abs_diff(ranks_b[node_id]);
+                max_rank_shift = max_rank_shift.max(shift);
+                *rank_shift_histogram.entry(shift).or_insert(0) += 1;
+            } else if kth_distance_a.is_some_and(|kth| is_tie(*distance, kth)) {
+                overlap += 1;
+            }
+        }
+
+        let overlap_ratio = if results_a.is_empty() {
+            1.0
+        } else {
+            (overlap.min(results_a.len())) as f32 / results_a.len() as f32
+        };
+        per_query.push(QueryComparison {
+            query_index,
+            overlap,
+            overlap_ratio,
+            max_rank_shift,
+        });
+    }
+
+    let (mean_overlap, min_overlap) = if per_query.is_empty() {
+        (1.0, 1.0)
+    } else {
+        let sum: f32 = per_query.iter().map(|q| q.overlap_ratio).sum();
+        let min = per_query
+            .iter()
+            .map(|q| q.overlap_ratio)
+            .fold(f32::INFINITY, f32::min);
+        (sum / per_query.len() as f32, min)
+    };
+
+    Ok(ComparisonReport {
+        k,
+        queries: per_query,
+        mean_overlap,
+        min_overlap,
+        rank_shift_histogram,
+    })
+}
+
+fn is_tie(lhs: f32, rhs: f32) -> bool {
+    (lhs - rhs).abs() <= TIE_EPSILON * lhs.abs().max(rhs.abs()).max(1.0)
+}
+
+/// Rank of each node = number of results strictly closer than it (results are sorted)
+fn tie_aware_ranks(results: &[(u64, f32)]) -> HashMap<u64, usize> {
+    let mut ranks = HashMap::with_capacity(results.len());
+    let mut group_start = 0;
+    for (position, (node_id, distance)) in results.iter().enumerate() {
+        if !is_tie(*distance, results[group_start].1) {
+            group_start = position;
+        }
+        ranks.insert(*node_id, group_start);
+    }
+    ranks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tp::vector_index::InMemANNAdapter;
+    use crate::tp::vector_index::in_mem_diskann::create_vector_index_config;
+
+    const DIM: usize = 128;
+
+    fn build(vectors: &[(u64, Vec<f32>)]) -> StorageResult<InMemANNAdapter> {
+        let mut index = InMemANNAdapter::new(create_vector_index_config(DIM, vectors.len()))?;
+        let refs: Vec<(u64, &[f32])> = vectors.iter().map(|(id, v)| (*id, v.as_slice())).collect();
+        index.build(&refs)?;
+        Ok(index)
+    }
+
+    fn vectors(count: usize) -> Vec<(u64, Vec<f32>)> {
+        (0..count)
+            .map(|i| {
+                let mut v = vec![0.0f32; DIM];
+                v[0] = i as f32 * 3.0;
+                v[1] = (i as f32 * 0.7).sin();
+                (i as u64 + 1, v)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_identical_indexes_fully_overlap() -> StorageResult<()> {
+        let data = vectors(40);
+        let a = build(&data)?;
+        let b = build(&data)?;
+        let queries: Vec<&[f32]> = data.iter().step_by(7).map(|(_, v)| v.as_slice()).collect();
+
+        let report = compare_indexes(&a, &b, &queries, 5, 30)?;
+        assert_eq!(report.queries.len(), queries.len());
+        assert_eq!(report.mean_overlap, 1.0);
+        assert!(report.passes(1.0));
+        assert_eq!(
+            report
+                .rank_shift_histogram
+                .keys()
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![0]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_shifted_index_reports_regression() -> StorageResult<()> {
+        let a = build(&vectors(40))?;
+        // Same vectors under different node IDs: nothing overlaps
+        let shifted: Vec<(u64, Vec<f32>)> = vectors(40)
+            .into_iter()
+            .map(|(id, v)| (id + 1000, v))
+            .collect();
+        let b = build(&shifted)?;
+        let query = vectors(1).remove(0).1;
+
+        let report = compare_indexes(&a, &b, &[query.as_slice()], 5, 30)?;
+        // Only exact distance ties with a's k-th result may count as matches
+        assert!(report.min_overlap < 0.5);
+        assert!(!report.passes(0.9));
+        Ok(())
+    }
+
+    #[test]
+    fn test_tie_aware_ranks() {
+        let ranks = tie_aware_ranks(&[(1, 0.0), (2, 1.0), (3, 1.0), (4, 2.0)]);
+        assert_eq!(ranks[&1], 0);
+        assert_eq!(ranks[&2], 1);
+        assert_eq!(ranks[&3], 1);
+        assert_eq!(ranks[&4], 3);
+    }
+}
@@ -0,0 +1,555 @@
+use std::str::FromStr;
+use std::sync::Arc;
+
+use rayon::ThreadPool;
+
+use super::filter::{PRE_FILTER_SELECTIVITY_THRESHOLD, SELECTIVITY_THRESHOLD};
+use crate::error::{StorageError, VectorIndexError};
+
+/// Adapter-level options for [`InMemANNAdapter`], complementing DiskANN's `IndexConfiguration`.
+///
+/// [`InMemANNAdapter`]: super::InMemANNAdapter
+#[derive(Debug, Clone, Default)]
+pub struct AdapterConfig {
+    /// Caller guarantees `build` inputs are sorted by strictly increasing node ID.
+    /// The sort is skipped; unsorted or duplicate IDs are rejected during the build.
+    pub inputs_presorted: bool,
+    /// Accumulate brute-force L2 distances in f64 instead of the f32 SIMD kernels.
+    /// Improves ordering of near-ties for high-dimensional data; DiskANN graph traversal is
+    /// unaffected and keeps using f32 distances.
+    pub high_precision_distance: bool,
+    /// Dedicated rayon pool for build, insert and parallel search work.
+    /// `None` runs on the global rayon pool.
+    pub thread_pool: Option<Arc<ThreadPool>>,
+    /// Scale stored and query vectors to unit length, so L2 ranking follows cosine similarity.
+    pub normalize_vectors: bool,
+    /// Handling of all-zero vectors, whose direction is undefined, when `normalize_vectors` is
+    /// set. Unnormalized indexes accept zero vectors as ordinary points.
+    pub zero_norm_policy: ZeroNormPolicy,
+    /// Upper bound in bytes on the candidate buffers a single search may allocate (result
+    /// buffers, DiskANN's search list and the brute-force heap). Searches that would exceed it
+    /// fail with `SearchBudgetExceeded`. `None` means unbounded.
+    pub search_memory_budget: Option<usize>,
+    /// Handling of distinct node IDs with bit-identical vectors during `build`
+    pub duplicate_vector_policy: DuplicateVectorPolicy,
+    /// Count how often each node ID is returned by searches, tracking at most this many
+    /// distinct nodes. `None` disables the bookkeeping.
+    pub result_frequency_capacity: Option<usize>,
+    /// Keep the mappings removed by `soft_delete`, with their deletion time, until the next
+    /// rebuild (`build` or `load`) compacts the index. Costs one entry per deleted node.
+    pub retain_deleted_mappings: bool,
+    /// Fail searches with `IndexRebuilding` while a `build` is in progress instead of letting
+    /// them wait. See [`InMemANNAdapter::build_gate`] for checking before taking a lock.
+    ///
+    /// [`InMemANNAdapter::build_gate`]: super::InMemANNAdapter::build_gate
+    pub fail_fast_during_build: bool,
+    /// Whether `build_f64`/`insert_f64` may down-convert f64 vectors to the index's f32, and
+    /// how values beyond the f32 range are handled
+    pub f64_ingest: F64IngestPolicy,
+    /// Multiple of `k` fetched by searches whose post-processing drops results (such as
+    /// exclusions), so they can still return `k`. When post-processing leaves fewer than `k`
+    /// the fetch is doubled until `k` survive or the index is exhausted. `None` uses
+    /// [`DEFAULT_POST_PROCESS_OVERFETCH`]; values below 1.0 are treated as 1.0.
+    pub post_process_overfetch: Option<f32>,
+    /// Distance the index ranks by. `Cosine` normalizes stored and query vectors (as
+    /// `normalize_vectors` does) and reuses the L2 kernels, so ANN and brute-force search agree;
+    /// reported distances are then squared L2 between unit vectors, `2 * (1 - cos)`.
+    pub metric: IndexMetric,
+    /// Selectivity thresholds and search-list expansion of filtered searches
+    pub tuning: SearchTuning,
+    /// Score brute-force searches against product-quantized codes instead of the stored f32
+    /// vectors. `None` keeps exact distances.
+    pub product_quantization: Option<ProductQuantization>,
+    /// Precision of the vector copy brute-force searches scan, see [`VectorStorage`]
+    pub storage: VectorStorage,
+    /// Keep the L2 norm of every stored vector, computed once at build and insert time, for
+    /// exact cosine scoring of vectors that are not unit length: `search_rescored` with
+    /// [`DistanceMetric::Cosine`] over an L2 index then needs one dot product per candidate.
+    /// Ignored by indexes that normalize their vectors. Costs one f32 per vector slot.
+    pub cache_vector_norms: bool,
+    /// Zero the search and insert counters restored by `load`, as
+    /// [`InMemANNAdapter::reset_stats`] does, instead of continuing the saved index's counts
+    ///
+    /// [`InMemANNAdapter::reset_stats`]: super::InMemANNAdapter::reset_stats
+    pub reset_stats_on_load: bool,
+    /// Make builds and inserts reproducible: the same vectors under the same seed give the same
+    /// graph and therefore the same search results. diskann-rs draws no random numbers while
+    /// building (points are linked in vector ID order from the medoid), so the only source of
+    /// variation is parallel linking, and setting a seed forces DiskANN's `num_threads` to 1.
+    /// The seed itself initializes the adapter's search RNG, as
+    /// [`InMemANNAdapter::set_rng_seed`] does. Vector IDs are always assigned in sorted node ID
+    /// order. `None` keeps the configured thread count.
+    ///
+    /// [`InMemANNAdapter::set_rng_seed`]: super::InMemANNAdapter::set_rng_seed
+    pub build_seed: Option<u64>,
+}
+
+/// Representation of the vectors scanned by brute-force searches. DiskANN keeps its own f32
+/// copy for graph traversal either way, so `Int8` adds one byte per dimension (plus two f32s)
+/// per slot while cutting the bytes a scan reads by four, and `Float16` adds two bytes per
+/// dimension while halving them. `build` and `insert` still take f32 vectors, which are
+/// down-converted as they are stored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum VectorStorage {
+    /// Scan the stored f32 vectors with exact distances
+    #[default]
+    F32,
+    /// Scan per-vector scaled i8 copies, see [`int8`](super::int8). Distances are approximate
+    /// unless [`SearchTuning::rerank_factor`] is set. Cannot be combined with product
+    /// quantization.
+    Int8,
+    /// Scan half-precision copies, see [`float16`](super::float16). Distances carry the
+    /// rounding error of binary16, about three significant digits, unless
+    /// [`SearchTuning::rerank_factor`] is set. Cannot be combined with product quantization.
+    Float16,
+}
+
+/// Product quantization of brute-force scans, see [`pq`](super::pq).
+///
+/// Codebooks are trained on the live vectors when the index is built, loaded or compacted, and
+/// later inserts are encoded with them. DiskANN keeps its own f32 copy of every vector for graph
+/// traversal, so the codes are held in addition to it: a scan reads `subquantizers` bytes per
+/// candidate rather than `4 * dimension`, but resident memory grows slightly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProductQuantization {
+    /// Number of subspaces, each encoded in one byte; between 1 and the vector dimension
+    pub subquantizers: usize,
+    /// Bits per subspace code, 1 to 8: each codebook holds `2^bits_per_code` centroids
+    pub bits_per_code: u8,
+    /// Score [`PQ_RERANK_OVERFETCH`] times `k` candidates by PQ, then return the exact top-k
+    /// among them with exact distances. Without it, results carry PQ-estimated distances.
+    ///
+    /// [`PQ_RERANK_OVERFETCH`]: super::pq::PQ_RERANK_OVERFETCH
+    pub rerank_exact: bool,
+}
+
+/// Tuning of filtered searches. The defaults pick the strategy by [`SELECTIVITY_THRESHOLD`]
+/// and [`PRE_FILTER_SELECTIVITY_THRESHOLD`] and never expand the search list.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SearchTuning {
+    /// Selectivity below which filtered searches scan the candidates by brute force
+    pub brute_force_selectivity: f32,
+    /// Selectivity below which (and at or above `brute_force_selectivity`) filtered searches
+    /// scan the candidates guided by a graph search; above it the graph search is post-filtered
+    pub pre_filter_selectivity: f32,
+    /// Lower bound of the factor by which post-filtered graph searches widen `l_value`
+    pub min_expansion: f32,
+    /// Upper bound of the expansion factor; the widened list is also capped at the index size
+    pub max_expansion: f32,
+    /// Growth of the expansion factor as the filter narrows: `1 + c * ln(1 / selectivity)`
+    /// before clamping, so 0.0 pins it to `min_expansion`
+    pub expansion_log_coefficient: f32,
+    /// With [`VectorStorage::Int8`] or [`VectorStorage::Float16`], take the `k * rerank_factor`
+    /// best candidates by int8 or f16 distance and return the top `k` of them by exact f32
+    /// distance. 0 returns the approximate ranking and distances as they are.
+    pub rerank_factor: usize,
+    /// Largest search list unfiltered graph searches may grow to. One that finds fewer than
+    /// `k` live nodes, as happens when deleted nodes crowd its list, is retried with a doubled
+    /// `l_value` until it finds `k` (or every live node) or reaches this cap. At or below the
+    /// requested `l_value`, including the default 0, searches are never retried.
+    pub ensure_k_max_l_value: u32,
+}
+
+impl Default for SearchTuning {
+    fn default() -> Self {
+        Self {
+            brute_force_selectivity: SELECTIVITY_THRESHOLD,
+            pre_filter_selectivity: PRE_FILTER_SELECTIVITY_THRESHOLD,
+            min_expansion: 1.0,
+            max_expansion: 1.0,
+            expansion_log_coefficient: 0.0,
+            rerank_factor: 0,
+            ensure_k_max_l_value: 0,
+        }
+    }
+}
+
+impl SearchTuning {
+    /// Factor by which a post-filtered search with a filter of `selectivity` widens `l_value`,
+    /// never below 1.0
+    pub fn expansion(&self, selectivity: f32) -> f32 {
+        let unclamped =
+            1.0 + self.expansion_log_coefficient * (1.0 / selectivity.max(f32::MIN_POSITIVE)).ln();
+        // max/min rather than clamp: misordered bounds must not panic in the search path
+        unclamped
+            .max(self.min_expansion)
+            .min(self.max_expansion)
+            .max(1.0)
+    }
+}
+
+/// Over-fetch multiplier used when [`AdapterConfig::post_process_overfetch`] is unset
+pub const DEFAULT_POST_PROCESS_OVERFETCH: f32 = 2.0;
+
+/// Conversion of f64 input vectors to f32 by `build_f64` and `insert_f64`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum F64IngestPolicy {
+    /// f64 input is refused with `UnsupportedOperation`, so every lossy conversion is opt-in
+    #[default]
+    Disabled,
+    /// Round to the nearest f32; finite values beyond the f32 range fail with `InvalidInput`
+    Reject,
+    /// Round to the nearest f32, clamping finite values beyond the range to `±f32::MAX`
+    Clamp,
+}
+
+/// What `build` does when different node IDs carry identical vectors.
+/// Every mode except `Allow` hashes all vectors once per build.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicateVectorPolicy {
+    /// Index duplicates like any other vector
+    #[default]
+    Allow,
+    /// Index duplicates but count them in `IndexStats::duplicate_vectors`
+    Warn,
+    /// Fail the build with `InvalidInput` naming the first duplicate pair
+    Reject,
+}
+
+/// What to do with a vector whose norm is zero when vectors are normalized
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ZeroNormPolicy {
+    /// Fail `build`/`insert` with `InvalidInput` and reject zero-norm queries
+    #[default]
+    Reject,
+    /// Drop zero-norm vectors from `build`/`insert` batches; a zero-norm query returns no
+    /// results
+    Skip,
+    /// Keep the raw all-zero vector, which then sits at squared L2 distance 1 from every
+    /// normalized vector; a zero-norm query therefore ranks all vectors as equidistant
+    TreatAsOrigin,
+}
+
+impl AdapterConfig {
+    /// Creates a configuration with default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether `build` inputs are already sorted by node ID.
+    pub fn with_inputs_presorted(mut self, presorted: bool) -> Self {
+        self.inputs_presorted = presorted;
+        self
+    }
+
+    /// Sets whether brute-force distances are accumulated in f64.
+    pub fn with_high_precision_distance(mut self, enabled: bool) -> Self {
+        self.high_precision_distance = enabled;
+        self
+    }
+
+    /// Confines index work to the given rayon thread pool.
+    pub fn with_thread_pool(mut self, pool: Arc<ThreadPool>) -> Self {
+        self.thread_pool = Some(pool);
+        self
+    }
+
+    /// Sets whether vectors are normalized to unit length.
+    pub fn with_normalize_vectors(mut self, enabled: bool) -> Self {
+        self.normalize_vectors = enabled;
+        self
+    }
+
+    /// Sets how zero-norm vectors are handled when normalizing.
+    pub fn with_zero_norm_policy(mut self, policy: ZeroNormPolicy) -> Self {
+        self.zero_norm_policy = policy;
+        self
+    }
+
+    /// Caps the candidate buffer memory of a single search at `bytes`.
+    pub fn with_search_memory_budget(mut self, bytes: usize) -> Self {
+        self.search_memory_budget = Some(bytes);
+        self
+    }
+
+    /// Sets how `build` treats identical vectors under distinct node IDs.
+    pub fn with_duplicate_vector_policy(mut self, policy: DuplicateVectorPolicy) -> Self {
+        self.duplicate_vector_policy = policy;
+        self
+    }
+
+    /// Enables per-node result counting for up to `capacity` distinct node IDs.
+    pub fn with_result_frequency_tracking(mut self, capacity: usize) -> Self {
+        self.result_frequency_capacity = Some(capacity);
+        self
+    }
+
+    /// Sets whether soft-deleted mappings are kept for [`was_deleted`] lookups.
+    ///
+    /// [`was_deleted`]: super::InMemANNAdapter::was_deleted
+    pub fn with_retain_deleted_mappings(mut self, enabled: bool) -> Self {
+        self.retain_deleted_mappings = enabled;
+        self
+    }
+
+    /// Sets whether searches fail fast while the index is being built.
+    pub fn with_fail_fast_during_build(mut self, enabled: bool) -> Self {
+        self.fail_fast_during_build = enabled;
+        self
+    }
+
+    /// Sets whether and how f64 vectors are converted on ingest.
+    pub fn with_f64_ingest(mut self, policy: F64IngestPolicy) -> Self {
+        self.f64_ingest = policy;
+        self
+    }
+
+    /// Sets the over-fetch multiplier for post-processed searches.
+    pub fn with_post_process_overfetch(mut self, multiplier: f32) -> Self {
+        self.post_process_overfetch = Some(multiplier);
+        self
+    }
+
+    /// Sets the distance metric the index ranks by.
+    pub fn with_metric(mut self, metric: IndexMetric) -> Self {
+        self.metric = metric;
+        self
+    }
+
+    /// Sets the tuning of filtered searches.
+    pub fn with_tuning(mut self, tuning: SearchTuning) -> Self {
+        self.tuning = tuning;
+        self
+    }
+
+    /// Sets product quantization of brute-force scans.
+    pub fn with_product_quantization(mut self, pq: ProductQuantization) -> Self {
+        self.product_quantization = Some(pq);
+        self
+    }
+
+    /// Sets the vectors brute-force scans read. DiskANN's f32 vectors stay in memory with every
+    /// variant; `Int8` also keeps one byte per dimension plus a scale and norm per vector, and
+    /// `Float16` two bytes per dimension.
+    pub fn with_storage(mut self, storage: VectorStorage) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// Sets whether vector norms are cached for cosine re-scoring.
+    pub fn with_cache_vector_norms(mut self, enabled: bool) -> Self {
+        self.cache_vector_norms = enabled;
+        self
+    }
+
+    /// Sets whether `load` zeroes the restored counters.
+    pub fn with_reset_stats_on_load(mut self, enabled: bool) -> Self {
+        self.reset_stats_on_load = enabled;
+        self
+    }
+
+    /// Makes builds reproducible under `seed`.
+    pub fn with_build_seed(mut self, seed: u64) -> Self {
+        self.build_seed = Some(seed);
+        self
+    }
+
+    /// Whether stored and query vectors are scaled to unit length, explicitly or because the
+    /// metric is cosine
+    pub(crate) fn normalizes(&self) -> bool {
+        self.normalize_vectors || self.metric == IndexMetric::Cosine
+    }
+
+    /// Over-fetch multiplier in effect, at least 1.0
+    pub(crate) fn effective_overfetch(&self) -> f32 {
+        self.post_process_overfetch
+            .unwrap_or(DEFAULT_POST_PROCESS_OVERFETCH)
+            .max(1.0)
+    }
+
+    /// Runs `f` inside the configured thread pool, or on the caller's pool if none is set.
+    pub(crate) fn install<R: Send>(&self, f: impl FnOnce() -> R + Send) -> R {
+        match &self.thread_pool {
+            Some(pool) => pool.install(f),
+            None => f(),
+        }
+    }
+}
+
+/// Intent-based search effort, mapped to a DiskANN `l_value` by [`Quality::l_value`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Quality {
+    /// Smallest search list that still returns `k` results; lowest latency
+    Fast,
+    /// Good recall for typical workloads
+    #[default]
+    Balanced,
+    /// Wide search list for near-exhaustive recall
+    Accurate,
+}
+
+impl Quality {
+    /// Heuristic `l_value` for `k` results over an index of `index_size` vectors.
+    ///
+    /// Each level scales a base width with `k` and, except for `Fast`, grows with
+    /// `log2(index_size)` since larger graphs need wider beams for the same recall:
+    /// - `Fast`: `max(2k, 32)`
+    /// - `Balanced`: `max(4k, 64) + 8 * log2(n)`
+    /// - `Accurate`: `max(8k, 128) + 16 * log2(n)`
+    ///
+    /// The result is clamped to `[k, max(k, index_size)]`, since a list wider than the index
+    /// only costs time.
+    pub fn l_value(self, k: usize, index_size: usize) -> u32 {
+        let log_n = index_size.max(1).ilog2() as usize;
+        let l = match self {
+            Quality::Fast => (2 * k).max(32),
+            Quality::Balanced => (4 * k).max(64) + 8 * log_n,
+            Quality::Accurate => (8 * k).max(128) + 16 * log_n,
+        };
+        let clamped = l.min(index_size.max(k)).max(k);
+        clamped.min(u32::MAX as usize) as u32
+    }
+}
+
+/// Distance an index ranks by, see [`AdapterConfig::metric`]. The DiskANN graph has no inner
+/// product kernel, so [`DistanceMetric::InnerProduct`] is available only for re-scoring.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IndexMetric {
+    /// Squared Euclidean distance
+    #[default]
+    L2,
+    /// Squared L2 between unit vectors
+    Cosine,
+}
+
+impl From<IndexMetric> for DistanceMetric {
+    fn from(metric: IndexMetric) -> Self {
+        match metric {
+            IndexMetric::L2 => DistanceMetric::L2,
+            IndexMetric::Cosine => DistanceMetric::Cosine,
+        }
+    }
+}
+
+/// Distance function for comparing vectors; smaller distances are closer for every variant.
+/// Indexes rank by an [`IndexMetric`]; any variant can re-score their candidates.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DistanceMetric {
+    /// Squared Euclidean distance
+    #[default]
+    L2,
+    /// `1 - cos(a, b)`; a zero-norm operand yields 1.0
+    Cosine,
+    /// Negated dot product, so that larger inner products rank first
+    InnerProduct,
+}
+
+impl DistanceMetric {
+    /// Exact scalar distance between two vectors of equal length
+    pub fn distance(self, a: &[f32], b: &[f32]) -> f32 {
+        debug_assert_eq!(a.len(), b.len());
+        match self {
+            DistanceMetric::L2 => a
+                .iter()
+                .zip(b)
+                .map(|(x, y)| {
+                    let diff = x - y;
+                    diff * diff
+                })
+                .sum(),
+            DistanceMetric::Cosine => {
+                let (mut dot, mut norm_a, mut norm_b) = (0.0f32, 0.0f32, 0.0f32);
+                for (x, y) in a.iter().zip(b) {
+                    dot += x * y;
+                    norm_a += x * x;
+                    norm_b += y * y;
+                }
+                if norm_a == 0.0 || norm_b == 0.0 {
+                    1.0
+                } else {
+                    1.0 - dot / (norm_a.sqrt() * norm_b.sqrt())
+                }
+            }
+            DistanceMetric::InnerProduct => -a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>(),
+        }
+    }
+
+    /// Convert a distance in the units of [`distance`](Self::distance) to
+    /// [`DistanceUnits::Natural`]: the square root for `L2`, unchanged for `Cosine` and
+    /// `InnerProduct`. Monotone, so rankings are preserved.
+    pub fn to_natural(self, distance: f32) -> f32 {
+        match self {
+            DistanceMetric::L2 => distance.max(0.0).sqrt(),
+            DistanceMetric::Cosine | DistanceMetric::InnerProduct => distance,
+        }
+    }
+
+    /// Inverse of [`to_natural`](Self::to_natural), e.g. to turn a Euclidean threshold into
+    /// the squared distances searches rank by. A negative L2 distance stays negative.
+    pub fn natural_to_ranking(self, distance: f32) -> f32 {
+        match self {
+            DistanceMetric::L2 => distance * distance.abs(),
+            DistanceMetric::Cosine | DistanceMetric::InnerProduct => distance,
+        }
+    }
+}
+
+/// Units of the distances a search reports
+///
+/// | metric         | `Ranking`           | `Natural`           |
+/// |----------------|---------------------|---------------------|
+/// | `L2`           | squared Euclidean   | Euclidean           |
+/// | `Cosine`       | see below           | `1 - cos`           |
+/// | `InnerProduct` | negated dot product | negated dot product |
+///
+/// Ranking units are what the index compares internally and are cheapest to produce; natural
+/// units are what thresholds are usually written in. A cosine index ranks by squared L2
+/// between unit vectors, `2 * (1 - cos)`, while re-scoring under `Cosine` yields `1 - cos`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DistanceUnits {
+    #[default]
+    Ranking,
+    Natural,
+}
+
+impl FromStr for DistanceMetric {
+    type Err = StorageError;
+
+    /// Parses `l2`, `cosine` or `inner_product` (also `ip`), ignoring case
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "l2" => Ok(DistanceMetric::L2),
+            "cosine" => Ok(DistanceMetric::Cosine),
+            "inner_product" | "ip" => Ok(DistanceMetric::InnerProduct),
+            _ => Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(
+                format!("Unknown distance metric '{name}', expected l2, cosine or inner_product"),
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance_metrics() {
+        let a = [1.0, 0.0];
+        let b = [0.0, 2.0];
+        assert_eq!(DistanceMetric::L2.distance(&a, &b), 5.0);
+        assert_eq!(DistanceMetric::Cosine.distance(&a, &b), 1.0);
+        assert_eq!(DistanceMetric::Cosine.distance(&a, &[3.0, 0.0]), 0.0);
+        assert_eq!(DistanceMetric::Cosine.distance(&a, &[0.0, 0.0]), 1.0);
+        assert_eq!(DistanceMetric::InnerProduct.distance(&a, &[2.0, 5.0]), -2.0);
+
+        assert_eq!(
+            "Cosine".parse::<DistanceMetric>().unwrap(),
+            DistanceMetric::Cosine
+        );
+        assert_eq!(
+            "ip".parse::<DistanceMetric>().unwrap(),
+            DistanceMetric::InnerProduct
+        );
+        assert!("hamming".parse::<DistanceMetric>().is_err());
+    }
+
+    #[test]
+    fn test_natural_distances() {
+        assert_eq!(DistanceMetric::L2.to_natural(25.0), 5.0);
+        assert_eq!(DistanceMetric::L2.to_natural(-1e-7), 0.0);
+        assert_eq!(DistanceMetric::L2.natural_to_ranking(5.0), 25.0);
+        assert_eq!(DistanceMetric::L2.natural_to_ranking(-1.0), -1.0);
+        assert_eq!(DistanceMetric::Cosine.to_natural(0.25), 0.25);
+        assert_eq!(DistanceMetric::InnerProduct.natural_to_ranking(-3.0), -3.0);
+    }
+}
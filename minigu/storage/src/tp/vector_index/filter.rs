@@ -1,3 +1,4 @@
+use arrow::array::{Array, BooleanArray};
 use bitvec::prelude::*;
 use diskann::common::FilterIndex as DiskANNFilterMask;
 
@@ -6,6 +7,39 @@
 /// Above this threshold (e.g. >= 10% selectivity), use ann search.
 pub const SELECTIVITY_THRESHOLD: f32 = 0.1;
 
+/// Selectivity below which (and at or above [`SELECTIVITY_THRESHOLD`]) filtered searches scan
+/// only the filter's candidates, seeded by a graph search and stopped early once the top-k
+/// stops changing. At or above it, the graph traversal is post-filtered.
+pub const PRE_FILTER_SELECTIVITY_THRESHOLD: f32 = 0.4;
+
+/// Candidate set of a filtered search, indexed by vector ID. [`VectorIndex::search`] and the
+/// other filtered searches take any implementation, so lazy or borrowed masks are searched
+/// without first being copied into a [`FilterMask`].
+///
+/// The strategy is picked from [`selectivity`](Self::selectivity), and brute-force scans
+/// size their buffers from [`candidate_count`](Self::candidate_count); both may be estimates,
+/// but a count of zero must mean the filter selects nothing, since such searches return no
+/// results without scanning. [`iter_candidates`](Self::iter_candidates) must be exact.
+///
+/// [`VectorIndex::search`]: super::VectorIndex::search
+pub trait SearchFilter: DiskANNFilterMask {
+    fn selectivity(&self) -> f32;
+
+    fn candidate_count(&self) -> usize;
+
+    fn total_vector_num(&self) -> usize;
+
+    /// Candidate vector IDs, each once
+    fn iter_candidates(&self) -> Box<dyn Iterator<Item = u32> + '_>;
+
+    /// Candidate vector IDs collected, e.g. for splitting across threads
+    fn candidate_ids(&self) -> Vec<u32> {
+        let mut ids = Vec::with_capacity(self.candidate_count());
+        ids.extend(self.iter_candidates());
+        ids
+    }
+}
+
 /// Filter mask using BitVec for vector index filtering
 #[derive(Debug, Clone)]
 pub struct FilterMask {
@@ -33,6 +67,15 @@ pub fn new(candidates: Vec<u32>, total_vector_num: usize) -> Self {
         }
     }
 
+    /// Wrap an existing bitmap indexed by vector ID; its length is the total vector count
+    pub fn from_bitmap(bitmap: BitVec) -> Self {
+        let candidate_count = bitmap.count_ones();
+        Self {
+            bitmap,
+            candidate_count,
+        }
+    }
+
     pub fn bitmap(&self) -> &BitVec {
         &self.bitmap
     }
@@ -61,6 +104,13 @@ pub fn total_vector_num(&self) -> usize {
     pub fn iter_candidates(&self) -> impl Iterator<Item = u32> + '_ {
         self.bitmap.iter_ones().map(|i| i as u32)
     }
+
+    /// Candidate vector IDs in ascending order, e.g. for splitting across threads
+    pub fn candidate_ids(&self) -> Vec<u32> {
+        let mut ids = Vec::with_capacity(self.candidate_count);
+        ids.extend(self.iter_candidates());
+        ids
+    }
 }
 
 /// Implement DiskANN FilterMask trait for FilterMask
@@ -70,6 +120,448 @@ fn contains_vector(&self, vector_id: u32) -> bool {
     }
 }
 
+impl SearchFilter for FilterMask {
+    fn selectivity(&self) -> f32 {
+        self.selectivity()
+    }
+
+    fn candidate_count(&self) -> usize {
+        self.candidate_count()
+    }
+
+    fn total_vector_num(&self) -> usize {
+        self.total_vector_num()
+    }
+
+    fn iter_candidates(&self) -> Box<dyn Iterator<Item = u32> + '_> {
+        Box::new(self.iter_candidates())
+    }
+
+    fn candidate_ids(&self) -> Vec<u32> {
+        self.candidate_ids()
+    }
+}
+
+/// Filter over an Arrow [`BooleanArray`] indexed by vector ID, read in place: element `i` set
+/// means vector `i` is a candidate, and nulls count as false. The array's length is the total
+/// vector count.
+///
+/// It implements [`SearchFilter`] and DiskANN's filter trait, so it can be handed to
+/// [`VectorIndex::search`](super::VectorIndex::search) and
+/// [`VectorIndex::ann_search`](super::VectorIndex::ann_search) without building a bitmap.
+#[derive(Debug, Clone)]
+pub struct ArrowBooleanFilterMask {
+    array: BooleanArray,
+    candidate_count: usize,
+}
+
+impl ArrowBooleanFilterMask {
+    pub fn new(array: BooleanArray) -> Self {
+        let candidate_count = array.true_count();
+        Self {
+            array,
+            candidate_count,
+        }
+    }
+
+    pub fn array(&self) -> &BooleanArray {
+        &self.array
+    }
+
+    pub fn contains_vector(&self, vector_id: u32) -> bool {
+        let index = vector_id as usize;
+        index < self.array.len() && self.array.is_valid(index) && self.array.value(index)
+    }
+
+    pub fn selectivity(&self) -> f32 {
+        self.candidate_count as f32 / self.array.len().max(1) as f32
+    }
+
+    pub fn candidate_count(&self) -> usize {
+        self.candidate_count
+    }
+
+    pub fn total_vector_num(&self) -> usize {
+        self.array.len()
+    }
+
+    pub fn iter_candidates(&self) -> impl Iterator<Item = u32> + '_ {
+        self.array
+            .values()
+            .set_indices()
+            .filter(|&index| self.array.is_valid(index))
+            .map(|index| index as u32)
+    }
+
+    /// Copy into a [`FilterMask`] over the same candidates
+    pub fn to_filter_mask(&self) -> FilterMask {
+        let mut bitmap = bitvec![0; self.array.len()];
+        for vector_id in self.iter_candidates() {
+            bitmap.set(vector_id as usize, true);
+        }
+        FilterMask::from_bitmap(bitmap)
+    }
+}
+
+impl DiskANNFilterMask for ArrowBooleanFilterMask {
+    fn contains_vector(&self, vector_id: u32) -> bool {
+        self.contains_vector(vector_id)
+    }
+}
+
+impl SearchFilter for ArrowBooleanFilterMask {
+    fn selectivity(&self) -> f32 {
+        self.selectivity()
+    }
+
+    fn candidate_count(&self) -> usize {
+        self.candidate_count()
+    }
+
+    fn total_vector_num(&self) -> usize {
+        self.total_vector_num()
+    }
+
+    fn iter_candidates(&self) -> Box<dyn Iterator<Item = u32> + '_> {
+        Box::new(self.iter_candidates())
+    }
+}
+
+/// Complement of a [`FilterMask`], e.g. a deny-list of vectors, without materializing the
+/// complement bitmap. Vectors `0..total_vector_num` not in `inner` are candidates, including
+/// vector IDs past the end of `inner`'s bitmap.
+///
+/// Membership tests are as cheap as the inner mask's. [`iter_candidates`] scans the inner
+/// bitmap for zero bits a machine word at a time, so it costs `O(total_vector_num / 64)` plus
+/// the number of candidates yielded regardless of how few vectors are excluded. As a
+/// [`SearchFilter`] its exact count picks the strategy, so the broad complement of a short
+/// deny-list is searched on the graph by membership tests alone, without being iterated.
+///
+/// [`iter_candidates`]: Self::iter_candidates
+#[derive(Debug, Clone)]
+pub struct NotFilterMask<'a> {
+    inner: &'a FilterMask,
+    total_vector_num: usize,
+    candidate_count: usize,
+}
+
+impl<'a> NotFilterMask<'a> {
+    pub fn new(inner: &'a FilterMask, total_vector_num: usize) -> Self {
+        let covered = inner.total_vector_num().min(total_vector_num);
+        let excluded = inner.bitmap()[..covered].count_ones();
+        Self {
+            inner,
+            total_vector_num,
+            candidate_count: total_vector_num - excluded,
+        }
+    }
+
+    pub fn inner(&self) -> &'a FilterMask {
+        self.inner
+    }
+
+    pub fn contains_vector(&self, vector_id: u32) -> bool {
+        (vector_id as usize) < self.total_vector_num && !self.inner.contains_vector(vector_id)
+    }
+
+    /// `1.0 - inner.selectivity()` when both masks cover the same vectors
+    pub fn selectivity(&self) -> f32 {
+        self.candidate_count as f32 / self.total_vector_num.max(1) as f32
+    }
+
+    pub fn candidate_count(&self) -> usize {
+        self.candidate_count
+    }
+
+    pub fn total_vector_num(&self) -> usize {
+        self.total_vector_num
+    }
+
+    pub fn iter_candidates(&self) -> impl Iterator<Item = u32> + '_ {
+        let covered = self.inner.total_vector_num().min(self.total_vector_num);
+        self.inner.bitmap()[..covered]
+            .iter_zeros()
+            .chain(covered..self.total_vector_num)
+            .map(|i| i as u32)
+    }
+
+    /// Materialize the complement as a [`FilterMask`]
+    pub fn to_filter_mask(&self) -> FilterMask {
+        let covered = self.inner.total_vector_num().min(self.total_vector_num);
+        let mut bitmap = self.inner.bitmap()[..covered].to_bitvec();
+        bitmap.resize(self.total_vector_num, false);
+        bitmap = !bitmap;
+        bitmap[covered..].fill(true);
+        FilterMask::from_bitmap(bitmap)
+    }
+}
+
+impl DiskANNFilterMask for NotFilterMask<'_> {
+    fn contains_vector(&self, vector_id: u32) -> bool {
+        self.contains_vector(vector_id)
+    }
+}
+
+impl SearchFilter for NotFilterMask<'_> {
+    fn selectivity(&self) -> f32 {
+        self.selectivity()
+    }
+
+    fn candidate_count(&self) -> usize {
+        self.candidate_count()
+    }
+
+    fn total_vector_num(&self) -> usize {
+        self.total_vector_num()
+    }
+
+    fn iter_candidates(&self) -> Box<dyn Iterator<Item = u32> + '_> {
+        Box::new(self.iter_candidates())
+    }
+}
+
+/// Conjunction of [`FilterMask`]s evaluated lazily: a vector is a candidate only if every mask
+/// contains it. The masks may cover different numbers of vectors; the combination covers the
+/// largest, and a mask contains no vector past its own end.
+///
+/// [`candidate_count`] and [`selectivity`] are estimates assuming the masks are independent,
+/// and a [`SearchFilter`] search picks its strategy from them. [`iter_candidates`] walks the
+/// mask with the fewest candidates and tests the others, so it is exact and costs
+/// `O(min candidates * masks)`.
+///
+/// [`candidate_count`]: Self::candidate_count
+/// [`selectivity`]: Self::selectivity
+/// [`iter_candidates`]: Self::iter_candidates
+#[derive(Debug, Clone)]
+pub struct AndFilterMask<'a> {
+    masks: Vec<&'a FilterMask>,
+    total_vector_num: usize,
+}
+
+impl<'a> AndFilterMask<'a> {
+    pub fn new(masks: Vec<&'a FilterMask>) -> Self {
+        let total_vector_num = combined_total(&masks);
+        Self {
+            masks,
+            total_vector_num,
+        }
+    }
+
+    pub fn masks(&self) -> &[&'a FilterMask] {
+        &self.masks
+    }
+
+    /// False for an empty conjunction, which has nothing to select from
+    pub fn contains_vector(&self, vector_id: u32) -> bool {
+        !self.masks.is_empty()
+            && self
+                .masks
+                .iter()
+                .all(|mask| mask.contains_vector(vector_id))
+    }
+
+    /// Product of the masks' fractions of the combined vector count
+    pub fn selectivity(&self) -> f32 {
+        if self.masks.is_empty() {
+            return 0.0;
+        }
+        self.masks
+            .iter()
+            .map(|mask| fraction_of(mask, self.total_vector_num))
+            .product()
+    }
+
+    /// Estimated, never more than the smallest mask's count
+    pub fn candidate_count(&self) -> usize {
+        let smallest = self.smallest().map_or(0, FilterMask::candidate_count);
+        estimate_count(self.selectivity(), self.total_vector_num).min(smallest)
+    }
+
+    pub fn total_vector_num(&self) -> usize {
+        self.total_vector_num
+    }
+
+    /// Candidates in ascending order
+    pub fn iter_candidates(&self) -> impl Iterator<Item = u32> + '_ {
+        self.smallest()
+            .into_iter()
+            .flat_map(FilterMask::iter_candidates)
+            .filter(|&vector_id| self.contains_vector(vector_id))
+    }
+
+    /// Materialize the conjunction as a [`FilterMask`], e.g. to pick a search strategy from its
+    /// exact selectivity
+    pub fn to_filter_mask(&self) -> FilterMask {
+        collect_mask(self.iter_candidates(), self.total_vector_num)
+    }
+
+    fn smallest(&self) -> Option<&'a FilterMask> {
+        self.masks
+            .iter()
+            .copied()
+            .min_by_key(|mask| mask.candidate_count())
+    }
+}
+
+impl DiskANNFilterMask for AndFilterMask<'_> {
+    fn contains_vector(&self, vector_id: u32) -> bool {
+        self.contains_vector(vector_id)
+    }
+}
+
+impl SearchFilter for AndFilterMask<'_> {
+    fn selectivity(&self) -> f32 {
+        self.selectivity()
+    }
+
+    fn candidate_count(&self) -> usize {
+        self.candidate_count()
+    }
+
+    fn total_vector_num(&self) -> usize {
+        self.total_vector_num()
+    }
+
+    fn iter_candidates(&self) -> Box<dyn Iterator<Item = u32> + '_> {
+        Box::new(self.iter_candidates())
+    }
+}
+
+/// Disjunction of [`FilterMask`]s evaluated lazily: a vector is a candidate if any mask contains
+/// it. Covers the largest of the masks' vector counts, like [`AndFilterMask`].
+///
+/// [`candidate_count`] and [`selectivity`] are estimates assuming the masks are independent.
+/// [`iter_candidates`] yields each mask's candidates that no earlier mask contains, so it costs
+/// `O(total candidates * masks)` and is only ascending within each mask.
+///
+/// [`candidate_count`]: Self::candidate_count
+/// [`selectivity`]: Self::selectivity
+/// [`iter_candidates`]: Self::iter_candidates
+#[derive(Debug, Clone)]
+pub struct OrFilterMask<'a> {
+    masks: Vec<&'a FilterMask>,
+    total_vector_num: usize,
+}
+
+impl<'a> OrFilterMask<'a> {
+    pub fn new(masks: Vec<&'a FilterMask>) -> Self {
+        let total_vector_num = combined_total(&masks);
+        Self {
+            masks,
+            total_vector_num,
+        }
+    }
+
+    pub fn masks(&self) -> &[&'a FilterMask] {
+        &self.masks
+    }
+
+    pub fn contains_vector(&self, vector_id: u32) -> bool {
+        self.masks
+            .iter()
+            .any(|mask| mask.contains_vector(vector_id))
+    }
+
+    /// One minus the product of the masks' excluded fractions
+    pub fn selectivity(&self) -> f32 {
+        let excluded: f32 = self
+            .masks
+            .iter()
+            .map(|mask| 1.0 - fraction_of(mask, self.total_vector_num))
+            .product();
+        1.0 - excluded
+    }
+
+    /// Estimated, never less than the largest mask's count
+    pub fn candidate_count(&self) -> usize {
+        let largest = self
+            .masks
+            .iter()
+            .map(|mask| mask.candidate_count())
+            .max()
+            .unwrap_or(0);
+        estimate_count(self.selectivity(), self.total_vector_num).max(largest)
+    }
+
+    pub fn total_vector_num(&self) -> usize {
+        self.total_vector_num
+    }
+
+    pub fn iter_candidates(&self) -> impl Iterator<Item = u32> + '_ {
+        self.masks.iter().enumerate().flat_map(move |(i, mask)| {
+            mask.iter_candidates().filter(move |&vector_id| {
+                !self.masks[..i]
+                    .iter()
+                    .any(|earlier| earlier.contains_vector(vector_id))
+            })
+        })
+    }
+
+    /// Materialize the disjunction as a [`FilterMask`], e.g. to pick a search strategy from its
+    /// exact selectivity
+    pub fn to_filter_mask(&self) -> FilterMask {
+        let mut bitmap = bitvec![0; self.total_vector_num];
+        for mask in &self.masks {
+            let covered = mask.bitmap().len();
+            bitmap[..covered] |= mask.bitmap().as_bitslice();
+        }
+        FilterMask::from_bitmap(bitmap)
+    }
+}
+
+impl DiskANNFilterMask for OrFilterMask<'_> {
+    fn contains_vector(&self, vector_id: u32) -> bool {
+        self.contains_vector(vector_id)
+    }
+}
+
+impl SearchFilter for OrFilterMask<'_> {
+    fn selectivity(&self) -> f32 {
+        self.selectivity()
+    }
+
+    fn candidate_count(&self) -> usize {
+        self.candidate_count()
+    }
+
+    fn total_vector_num(&self) -> usize {
+        self.total_vector_num()
+    }
+
+    fn iter_candidates(&self) -> Box<dyn Iterator<Item = u32> + '_> {
+        Box::new(self.iter_candidates())
+    }
+}
+
+fn combined_total(masks: &[&FilterMask]) -> usize {
+    masks
+        .iter()
+        .map(|mask| mask.total_vector_num())
+        .max()
+        .unwrap_or(0)
+}
+
+/// Fraction of `total_vector_num` vectors selected by `mask`
+fn fraction_of(mask: &FilterMask, total_vector_num: usize) -> f32 {
+    mask.candidate_count() as f32 / total_vector_num.max(1) as f32
+}
+
+/// Rounded, but at least one for a nonzero selectivity so that only an empty combination
+/// counts as zero (see [`SearchFilter`])
+fn estimate_count(selectivity: f32, total_vector_num: usize) -> usize {
+    let estimate = (selectivity * total_vector_num as f32).round() as usize;
+    let floor = usize::from(selectivity > 0.0);
+    estimate.max(floor).min(total_vector_num)
+}
+
+fn collect_mask(candidates: impl Iterator<Item = u32>, total_vector_num: usize) -> FilterMask {
+    let mut bitmap = bitvec![0; total_vector_num];
+    for vector_id in candidates {
+        bitmap.set(vector_id as usize, true);
+    }
+    FilterMask::from_bitmap(bitmap)
+}
+
 /// Factory function to create FilterMask
 pub fn create_filter_mask(candidates: Vec<u32>, total_vector_num: usize) -> FilterMask {
     FilterMask::new(candidates, total_vector_num)
@@ -245,4 +737,119 @@ fn test_filter_mask_large_scale() {
         assert!(!mask.contains_vector(1));
         assert!(!mask.contains_vector(5001));
     }
+
+    #[test]
+    fn test_filter_mask_from_bitmap() {
+        let mut bitmap = bitvec![0; 20];
+        bitmap.set(3, true);
+        bitmap.set(17, true);
+        let mask = FilterMask::from_bitmap(bitmap.clone());
+
+        assert_eq!(mask.candidate_count(), 2);
+        assert_eq!(mask.total_vector_num(), 20);
+        assert_eq!(mask.bitmap(), &bitmap);
+        assert_eq!(
+            mask.iter_candidates().collect::<Vec<_>>(),
+            FilterMask::new(vec![3, 17], 20)
+                .iter_candidates()
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_arrow_boolean_filter_mask() {
+        let array = BooleanArray::from(vec![
+            Some(true),
+            None,
+            Some(false),
+            Some(true),
+            Some(true),
+            None,
+        ]);
+        let mask = ArrowBooleanFilterMask::new(array);
+
+        assert_eq!(mask.candidate_count(), 3);
+        assert_eq!(mask.total_vector_num(), 6);
+        assert_eq!(mask.selectivity(), 0.5);
+        assert_eq!(mask.iter_candidates().collect::<Vec<_>>(), vec![0, 3, 4]);
+        assert!(mask.contains_vector(3));
+        assert!(!mask.contains_vector(1));
+        assert!(!mask.contains_vector(2));
+        assert!(!mask.contains_vector(6));
+        assert!(DiskANNFilterMask::contains_vector(&mask, 4));
+
+        let copied = mask.to_filter_mask();
+        assert_eq!(copied.candidate_count(), 3);
+        assert_eq!(copied.total_vector_num(), 6);
+        assert_eq!(
+            copied.iter_candidates().collect::<Vec<_>>(),
+            mask.iter_candidates().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_not_filter_mask() {
+        let deny = FilterMask::new(vec![1, 3, 4], 6);
+        let mask = NotFilterMask::new(&deny, 6);
+
+        assert_eq!(mask.candidate_count(), 3);
+        assert_eq!(mask.selectivity(), 1.0 - deny.selectivity());
+        assert_eq!(mask.iter_candidates().collect::<Vec<_>>(), vec![0, 2, 5]);
+        assert!(mask.contains_vector(0));
+        assert!(!mask.contains_vector(3));
+        assert!(!mask.contains_vector(6));
+        assert!(DiskANNFilterMask::contains_vector(&mask, 5));
+
+        // Vectors past the inner bitmap are not excluded
+        let wider = NotFilterMask::new(&deny, 8);
+        assert_eq!(wider.candidate_count(), 5);
+        assert!(wider.contains_vector(7));
+        assert_eq!(
+            wider.iter_candidates().collect::<Vec<_>>(),
+            vec![0, 2, 5, 6, 7]
+        );
+        let copied = wider.to_filter_mask();
+        assert_eq!(copied.total_vector_num(), 8);
+        assert_eq!(copied.candidate_ids(), vec![0, 2, 5, 6, 7]);
+
+        let narrower = NotFilterMask::new(&deny, 4);
+        assert_eq!(narrower.iter_candidates().collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(narrower.to_filter_mask().candidate_ids(), vec![0, 2]);
+    }
+
+    #[test]
+    fn test_and_or_filter_masks() {
+        let evens = FilterMask::new((0..10).step_by(2).collect(), 10);
+        let low = FilterMask::new(vec![0, 1, 2, 3], 10);
+        let wide = FilterMask::new(vec![3, 11], 12);
+
+        let and = AndFilterMask::new(vec![&evens, &low]);
+        assert_eq!(and.total_vector_num(), 10);
+        assert_eq!(and.iter_candidates().collect::<Vec<_>>(), vec![0, 2]);
+        assert!(and.contains_vector(2));
+        assert!(!and.contains_vector(3));
+        // Independence estimate: 0.5 * 0.4 of 10 vectors
+        assert!((and.selectivity() - 0.2).abs() < 1e-6);
+        assert_eq!(and.candidate_count(), 2);
+        assert_eq!(and.to_filter_mask().candidate_ids(), vec![0, 2]);
+        assert!(!AndFilterMask::new(Vec::new()).contains_vector(0));
+        // 0.1 * 0.1 of 10 vectors rounds to zero, but only an empty conjunction may count zero
+        let one = FilterMask::new(vec![7], 10);
+        let tiny = AndFilterMask::new(vec![&one, &one]);
+        assert_eq!(tiny.candidate_count(), 1);
+        assert_eq!(SearchFilter::candidate_ids(&tiny), vec![7]);
+
+        let or = OrFilterMask::new(vec![&evens, &low, &wide]);
+        assert_eq!(or.total_vector_num(), 12);
+        let mut union: Vec<_> = or.iter_candidates().collect();
+        union.sort_unstable();
+        assert_eq!(union, vec![0, 1, 2, 3, 4, 6, 8, 11]);
+        assert!(or.contains_vector(11));
+        assert!(!or.contains_vector(5));
+        assert!(DiskANNFilterMask::contains_vector(&or, 1));
+        assert!(or.candidate_count() >= evens.candidate_count());
+        let copied = or.to_filter_mask();
+        assert_eq!(copied.total_vector_num(), 12);
+        assert_eq!(copied.candidate_ids(), union);
+    }
 }
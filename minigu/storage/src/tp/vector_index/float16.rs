@@ -0,0 +1,108 @@
+//! Half-precision copies of indexed vectors for brute-force scans.
+//!
+//! Each value is rounded to the nearest IEEE 754 binary16, which keeps about three significant
+//! decimal digits over a range of roughly ±65504 (larger magnitudes become infinite). The query
+//! is rounded the same way once per search, and squared L2 distances are accumulated in f32
+//! after widening both operands, so only storage and memory traffic are halved.
+
+use half::f16;
+
+/// Lanes of the distance loop accumulated side by side, so LLVM can vectorize the widening
+/// without intrinsics
+const DISTANCE_LANES: usize = 8;
+
+/// Half-precision values of every allocated vector slot of an index, indexed by vector ID
+#[derive(Debug, Clone)]
+pub(crate) struct Float16Vectors {
+    dimension: usize,
+    /// Row-major values, `dimension` per slot
+    values: Vec<f16>,
+}
+
+impl Float16Vectors {
+    pub(crate) fn new(dimension: usize) -> Self {
+        Self {
+            dimension,
+            values: Vec::new(),
+        }
+    }
+
+    /// Number of slots encoded so far; slot `i` has vector ID `i`
+    pub(crate) fn encoded_slots(&self) -> usize {
+        self.values.len() / self.dimension
+    }
+
+    /// Round `vector` to half precision as the next slot
+    pub(crate) fn push(&mut self, vector: &[f32]) {
+        debug_assert_eq!(vector.len(), self.dimension);
+        self.values.extend(vector.iter().map(|&x| f16::from_f32(x)));
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.values.clear();
+    }
+
+    /// Squared L2 distance from `query` to slot `vector_id`, or `None` if the slot is not
+    /// encoded
+    pub(crate) fn distance(&self, query: &[f16], vector_id: u32) -> Option<f32> {
+        let start = vector_id as usize * self.dimension;
+        let values = self.values.get(start..start + self.dimension)?;
+        Some(squared_l2_f16(query, values))
+    }
+
+    pub(crate) fn memory_bytes(&self) -> usize {
+        self.values.capacity() * std::mem::size_of::<f16>()
+    }
+}
+
+/// `vector` rounded to half precision, as queries are before a scan
+pub(crate) fn to_f16(vector: &[f32]) -> Vec<f16> {
+    vector.iter().map(|&x| f16::from_f32(x)).collect()
+}
+
+/// Squared L2 distance of equal-length half-precision slices, accumulated in f32
+fn squared_l2_f16(a: &[f16], b: &[f16]) -> f32 {
+    let mut lanes = [0.0f32; DISTANCE_LANES];
+    let mut a_chunks = a.chunks_exact(DISTANCE_LANES);
+    let mut b_chunks = b.chunks_exact(DISTANCE_LANES);
+    for (a_chunk, b_chunk) in (&mut a_chunks).zip(&mut b_chunks) {
+        for lane in 0..DISTANCE_LANES {
+            let diff = a_chunk[lane].to_f32() - b_chunk[lane].to_f32();
+            lanes[lane] += diff * diff;
+        }
+    }
+    let tail: f32 = a_chunks
+        .remainder()
+        .iter()
+        .zip(b_chunks.remainder())
+        .map(|(x, y)| {
+            let diff = x.to_f32() - y.to_f32();
+            diff * diff
+        })
+        .sum();
+    lanes.iter().sum::<f32>() + tail
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance_approximates_l2() {
+        let a: Vec<f32> = (0..37).map(|i| (i as f32 * 0.37).sin()).collect();
+        let b: Vec<f32> = (0..37).map(|i| (i as f32 * 0.21).cos() * 2.0).collect();
+        let exact: f32 = a.iter().zip(&b).map(|(x, y)| (x - y) * (x - y)).sum();
+
+        let mut store = Float16Vectors::new(37);
+        store.push(&b);
+        store.push(&a);
+        assert_eq!(store.encoded_slots(), 2);
+
+        let query = to_f16(&a);
+        let approx = store.distance(&query, 0).unwrap();
+        assert!((approx - exact).abs() / exact < 1e-3, "{approx} vs {exact}");
+        assert_eq!(store.distance(&query, 1), Some(0.0));
+        assert!(store.distance(&query, 2).is_none());
+        assert_eq!(store.memory_bytes(), store.values.capacity() * 2);
+    }
+}
@@ -1,6 +1,10 @@
+use std::borrow::Cow;
 use std::collections::BinaryHeap;
-use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::time::{Duration, Instant};
 
+use bitvec::vec::BitVec;
 use dashmap::DashMap;
 use diskann::common::{AlignedBoxWithSlice, FilterIndex as DiskANNFilterMask};
 use diskann::index::{ANNInmemIndex, create_inmem_index};
@@ -8,11 +12,27 @@
 use diskann::model::configuration::index_write_parameters::IndexWriteParametersBuilder;
 use diskann::model::vertex::{DIM_104, DIM_128, DIM_256};
 use ordered_float::OrderedFloat;
-use parking_lot::RwLock;
-use vector::{Metric, distance_l2_vector_f32};
-
-use super::filter::{FilterMask, SELECTIVITY_THRESHOLD};
+use parking_lot::{Mutex, RwLock};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use vector::Metric;
+#[cfg(feature = "simd")]
+use vector::distance_l2_vector_f32;
+
+use super::config::{
+    AdapterConfig, DistanceMetric, DuplicateVectorPolicy, F64IngestPolicy, IndexMetric, Quality,
+    SearchTuning, VectorStorage, ZeroNormPolicy,
+};
+use super::filter::SearchFilter;
+use super::float16::{Float16Vectors, to_f16};
 use super::index::VectorIndex;
+use super::int8::{Int8Vector, Int8Vectors};
+use super::pq::{MAX_TRAINING_SAMPLES, PQ_RERANK_OVERFETCH, ProductQuantizer, QuantizedVectors};
+use super::provenance::IndexProvenance;
+use super::stats::{
+    BuildPlan, DimStat, FragmentationReport, IndexStats, InsertFailure, InsertReport,
+    StatsReporter, StatsSink,
+};
 use crate::error::{StorageError, StorageResult, VectorIndexError};
 
 /// Sharded vector-to-node mapping
@@ -85,6 +105,38 @@ pub fn set(&self, vector_id: u32, node_id: u64) -> StorageResult<()> {
         Ok(())
     }
 
+    /// Preallocate every shard for vector IDs `0..slots`, so filling them does not reallocate
+    pub fn reserve(&self, slots: usize) {
+        let per_shard = slots.div_ceil(self.shards.len());
+        for shard in &self.shards {
+            let mut vec = shard.write();
+            let additional = per_shard.saturating_sub(vec.len());
+            vec.reserve(additional);
+        }
+    }
+
+    /// Number of mapping entries allocated across all shards
+    pub fn capacity(&self) -> usize {
+        self.shards
+            .iter()
+            .map(|shard| shard.read().capacity())
+            .sum()
+    }
+
+    /// All `(vector_id, node_id)` mappings, in no particular order
+    pub fn entries(&self) -> Vec<(u32, u64)> {
+        let mut entries = Vec::new();
+        for (shard_idx, shard) in self.shards.iter().enumerate() {
+            for (local_idx, node_id) in shard.read().iter().enumerate() {
+                if let Some(node_id) = node_id {
+                    let vector_id = ((local_idx as u32) << self.shard_bits) | shard_idx as u32;
+                    entries.push((vector_id, *node_id));
+                }
+            }
+        }
+        entries
+    }
+
     /// Get total number of shards
     pub fn shard_count(&self) -> usize {
         self.shards.len()
@@ -188,6 +240,100 @@ pub fn batch_soft_delete(&self, vector_ids: &[u32]) -> StorageResult<Vec<u64>> {
     }
 }
 
+/// First bytes of every snapshot file written by `save`
+const SNAPSHOT_MAGIC: [u8; 8] = *b"MGUVIDX\0";
+/// Snapshot format version following the magic as a little-endian u32. Bump on any change to
+/// [`PersistedIndex`]; `load` refuses every other version.
+const SNAPSHOT_VERSION: u32 = 6;
+
+/// On-disk snapshot of an [`InMemANNAdapter`], stored postcard-encoded after the magic and
+/// version header.
+///
+/// Every vector ID slot is kept, including soft-deleted ones, so a reload reproduces the exact
+/// vector_id <-> node_id correspondence that externally built filter bitmaps rely on. The graph is
+/// kept too, so a reload skips graph construction and searches exactly like the saved index.
+#[derive(Serialize, Deserialize)]
+struct PersistedIndex {
+    dimension: usize,
+    /// Node ID per vector ID; `None` marks a soft-deleted slot
+    slots: Vec<Option<u64>>,
+    /// Row-major vector data for every slot
+    vectors: Vec<f32>,
+    /// DiskANN out-neighbors of every slot, by vector ID
+    adjacency: Vec<Vec<u32>>,
+    /// Vector ID every search starts from
+    entry_point: u32,
+    provenance: IndexProvenance,
+    stats: IndexStats,
+}
+
+/// First bytes of every delta file written by `save_delta`
+const DELTA_MAGIC: [u8; 8] = *b"MGUVDLT\0";
+/// Delta format version, versioned like [`SNAPSHOT_VERSION`]
+const DELTA_VERSION: u32 = 3;
+
+/// On-disk changes of an [`InMemANNAdapter`] since its previous checkpoint (the base snapshot
+/// or the previous delta), stored postcard-encoded after the delta magic and version header.
+///
+/// A delta applies only on top of the exact base it was taken from, identified by the CRC32 of
+/// the base snapshot file, and only after every earlier delta of that base.
+#[derive(Serialize, Deserialize)]
+struct PersistedDelta {
+    /// CRC32 of the base snapshot file
+    base_checksum: u32,
+    /// 1 for the first delta after the base, then increasing by one
+    sequence: u64,
+    dimension: usize,
+    /// Vector ID of the first slot in `slots`, which was the slot count at the previous
+    /// checkpoint
+    first_slot: u32,
+    /// Node ID per slot allocated since the previous checkpoint; `None` marks a slot that was
+    /// deleted again (or never mapped)
+    slots: Vec<Option<u64>>,
+    /// Row-major vector data for every slot in `slots`
+    vectors: Vec<f32>,
+    /// Slots below `first_slot` that were live at the previous checkpoint and have since been
+    /// soft-deleted
+    deleted: Vec<u32>,
+    stats: IndexStats,
+}
+
+/// State at the last `save`, `load` or delta checkpoint that the next `save_delta` diffs against
+#[derive(Debug, Clone)]
+struct DeltaCheckpoint {
+    base_checksum: u32,
+    /// Sequence number of the last delta written or applied, 0 right after the base
+    sequence: u64,
+    /// Liveness of every slot allocated at the checkpoint, indexed by vector ID
+    live: BitVec,
+}
+
+impl DeltaCheckpoint {
+    fn new(base_checksum: u32, slots: &[Option<u64>]) -> Self {
+        Self {
+            base_checksum,
+            sequence: 0,
+            live: slots.iter().map(Option::is_some).collect(),
+        }
+    }
+
+    fn slot_count(&self) -> u32 {
+        self.live.len() as u32
+    }
+
+    /// Move the checkpoint past `delta`
+    fn advance(&mut self, delta: &PersistedDelta) {
+        self.sequence = delta.sequence;
+        for &vector_id in &delta.deleted {
+            self.live.set(vector_id as usize, false);
+        }
+        self.live.extend(delta.slots.iter().map(Option::is_some));
+    }
+}
+
+/// Node ID and vector pairs owned by the adapter, e.g. after normalization
+type OwnedVectors = Vec<(u64, Vec<f32>)>;
+
 /// Aligned query buffer that maintains 64-byte alignment guarantee
 enum AlignedQueryBuffer<'a> {
     Borrowed(&'a [f32]),
@@ -203,29 +349,303 @@ fn as_slice(&self) -> &[f32] {
     }
 }
 
+/// Deleted share of the allocated slots at which [`InMemANNAdapter::fragmentation_report`]
+/// recommends compaction
+pub const COMPACTION_DELETED_RATIO: f64 = 0.2;
+
+/// Aligned dimensions DiskANN can build a graph for. Vectors of any length up to the
+/// `aligned_dim` of the configuration are accepted; distances outside these sizes, as in
+/// brute-force search over padded data, use the scalar kernel.
+pub const SUPPORTED_ALIGNED_DIMS: [usize; 3] = [DIM_104, DIM_128, DIM_256];
+
+/// Neighbors fetched by each search of [`InMemANNAdapter::warmup`]
+const WARMUP_K: usize = 10;
+
+/// Number of problems [`InMemANNAdapter::check_consistency`] describes in its error
+pub const MAX_REPORTED_INCONSISTENCIES: usize = 5;
+
+/// Candidate count from which brute-force search scans in parallel; smaller scans are not worth
+/// the per-thread heaps
+pub const PARALLEL_BRUTE_FORCE_MIN_CANDIDATES: usize = 4096;
+
+/// Candidates scored per rayon task of a parallel brute-force scan
+const BRUTE_FORCE_CHUNK: usize = 1024;
+
+/// Shareable build-in-progress state of an [`InMemANNAdapter`], from
+/// [`InMemANNAdapter::build_gate`].
+///
+/// A search and a build cannot overlap on one adapter (`build` takes `&mut self`), so behind an
+/// `Arc<RwLock<_>>` a search arriving mid-build blocks on the lock. Checking the gate before
+/// acquiring the read lock lets the caller fail fast instead.
+#[derive(Debug, Clone, Default)]
+pub struct BuildGate(Arc<AtomicBool>);
+
+impl BuildGate {
+    pub fn is_building(&self) -> bool {
+        self.0.load(Ordering::Acquire)
+    }
+
+    /// `Err(IndexRebuilding)` while a build is in progress
+    pub fn check(&self) -> StorageResult<()> {
+        if self.is_building() {
+            return Err(StorageError::VectorIndex(VectorIndexError::IndexRebuilding));
+        }
+        Ok(())
+    }
+}
+
+/// Phase of a build, as reported by [`InMemANNAdapter::build_with_progress`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildPhase {
+    /// Input checks, normalization and duplicate detection
+    Validation,
+    /// Assigning vector IDs to node IDs
+    Mapping,
+    /// DiskANN graph construction, normally the bulk of the build
+    GraphConstruction,
+    /// Quantized copies and stats
+    Finalization,
+    /// The build succeeded
+    Done,
+}
+
+impl BuildPhase {
+    /// Coarse share of the build completed when the phase starts. DiskANN reports nothing while
+    /// it builds the graph, so these are fixed milestones rather than measurements.
+    pub fn percent(self) -> u8 {
+        match self {
+            BuildPhase::Validation => 0,
+            BuildPhase::Mapping => 10,
+            BuildPhase::GraphConstruction => 15,
+            BuildPhase::Finalization => 95,
+            BuildPhase::Done => 100,
+        }
+    }
+}
+
+/// Progress report passed to a [`InMemANNAdapter::build_with_progress`] callback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildProgress {
+    /// Phase being entered
+    pub phase: BuildPhase,
+    pub percent: u8,
+}
+
+impl BuildProgress {
+    fn new(phase: BuildPhase) -> Self {
+        Self {
+            phase,
+            percent: phase.percent(),
+        }
+    }
+}
+
+/// SplitMix64 generator backing every randomized decision on the search path, so searches are
+/// reproducible from a single adapter seed
+#[derive(Debug, Clone)]
+pub(crate) struct SearchRng(u64);
+
+impl SearchRng {
+    pub(crate) fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Output of [`InMemANNAdapter::search_debug`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchDebugOutput {
+    /// Seed of this query's random stream, for replaying its randomized decisions
+    pub rng_seed: u64,
+    /// `(node_id, distance, returned)` in ascending distance order
+    pub candidates: Vec<(u64, f32, bool)>,
+}
+
+/// Marks a build as in progress until dropped, so failed or panicking builds clear the flag
+struct BuildingGuard(Arc<AtomicBool>);
+
+/// Strategy that answered a search, for the search counters of [`IndexStats`]
+#[derive(Debug, Clone, Copy)]
+enum SearchPath {
+    /// The filter admitted no candidates
+    Empty,
+    Ann,
+    /// Scan of the filter's candidates seeded by a graph search; counted as brute force
+    PreFilter,
+    BruteForce,
+    /// Brute force scored by product-quantized codes
+    QuantizedBruteForce,
+    /// Brute force scored by int8 copies of the vectors
+    Int8BruteForce,
+    /// Brute force scored by half-precision copies of the vectors
+    Float16BruteForce,
+    /// Zero-norm query under [`ZeroNormPolicy::Skip`]; not counted as a search
+    Skipped,
+}
+
+impl SearchPath {
+    /// Count a search that took `elapsed` and visited `visited` candidates in `stats`
+    fn count(self, stats: &mut IndexStats, elapsed: Duration, visited: usize) {
+        if matches!(self, SearchPath::Skipped) {
+            return;
+        }
+        stats.total_searches += 1;
+        stats.record_search_time(elapsed);
+        stats.record_candidates_visited(visited);
+        match self {
+            SearchPath::Empty | SearchPath::Skipped => {}
+            SearchPath::Ann => stats.ann_searches += 1,
+            SearchPath::BruteForce
+            | SearchPath::PreFilter
+            | SearchPath::Int8BruteForce
+            | SearchPath::Float16BruteForce => stats.brute_force_searches += 1,
+            SearchPath::QuantizedBruteForce => {
+                stats.brute_force_searches += 1;
+                stats.pq_searches += 1;
+            }
+        }
+    }
+}
+
+impl BuildingGuard {
+    fn enter(gate: &BuildGate) -> Self {
+        gate.0.store(true, Ordering::Release);
+        Self(Arc::clone(&gate.0))
+    }
+}
+
+impl Drop for BuildingGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
 #[allow(clippy::upper_case_acronyms)]
 pub struct InMemANNAdapter {
-    inner: Box<dyn ANNInmemIndex<f32> + 'static>,
+    // Written only to add or replace its points, so that `insert_concurrent` can run beside
+    // searches. Readers use `read_recursive`: a search holding a read guard, or a rayon task
+    // it waits on, must not queue behind a pending insert.
+    inner: RwLock<Box<dyn ANNInmemIndex<f32> + 'static>>,
+    index_config: IndexConfiguration,
     dimension: usize,
+    adapter_config: AdapterConfig,
 
     node_to_vector: DashMap<u64, u32>,
     vector_to_node: ShardedVectorMap,
-    next_vector_id: AtomicU32, // Next vector ID to be allocated
+    // Next vector ID to be allocated. Advanced only under the `inner` write lock, after DiskANN
+    // holds the new points and their mappings are published, with `Release`; a reader that
+    // loads it with `Acquire` sees the mapping of every slot below it (or its deletion).
+    next_vector_id: AtomicU32,
+    // Set by a successful build or load, including an empty build; an index whose vectors were
+    // all deleted stays built
+    built: bool,
+    provenance: Option<IndexProvenance>,
+    // PQ codes per vector slot; trained lazily once the index has vectors, only when
+    // product_quantization is configured
+    quantized: RwLock<Option<QuantizedVectors>>,
+    // Int8 copies per vector slot; present only with VectorStorage::Int8
+    int8: RwLock<Option<Int8Vectors>>,
+    // Half-precision copies per vector slot; present only with VectorStorage::Float16
+    float16: RwLock<Option<Float16Vectors>>,
+    // L2 norm per vector slot; present only with cache_vector_norms on a non-normalizing index
+    norms: RwLock<Option<Vec<f32>>>,
+
+    // parking_lot's lock does not poison, so a panic mid-update cannot wedge later operations
+    stats: Arc<RwLock<IndexStats>>,
+    stats_reporter: Mutex<Option<StatsReporter>>,
+    // node_id -> times returned; present only when result frequency tracking is enabled
+    result_frequency: Option<Mutex<std::collections::HashMap<u64, u64>>>,
+    // node_id -> (former vector_id, deletion time); filled only with retain_deleted_mappings
+    deleted_nodes: DashMap<u64, (u32, Instant)>,
+    building: BuildGate,
+    // Source of per-query seeds; reset by set_rng_seed
+    rng: Mutex<SearchRng>,
+    rng_seed: u64,
+    // What the next save_delta diffs against; set by save and load, cleared by builds
+    checkpoint: Option<DeltaCheckpoint>,
 }
 
 impl InMemANNAdapter {
     pub fn new(config: IndexConfiguration) -> StorageResult<Self> {
-        // Validate distance metric type: only L2 distance is supported
-        if config.dist_metric != Metric::L2 {
+        Self::with_config(config, AdapterConfig::default())
+    }
+
+    /// Create an adapter with explicit adapter-level options.
+    ///
+    /// A `Metric::Cosine` configuration selects [`IndexMetric::Cosine`] (as does
+    /// `AdapterConfig::metric`); DiskANN itself always runs the L2 kernel, over unit vectors
+    /// in the cosine case.
+    pub fn with_config(
+        mut config: IndexConfiguration,
+        mut adapter_config: AdapterConfig,
+    ) -> StorageResult<Self> {
+        match (config.dist_metric, adapter_config.metric) {
+            (Metric::L2, _) => {}
+            (Metric::Cosine, _) => {
+                adapter_config.metric = IndexMetric::Cosine;
+                config.dist_metric = Metric::L2;
+            }
+        }
+
+        // DiskANN reports an unsupported size only as an opaque index error
+        if config.dim == 0 || config.dim > config.aligned_dim {
+            return Err(StorageError::VectorIndex(VectorIndexError::Configuration(
+                format!(
+                    "dim must be between 1 and aligned_dim, got dim {} and aligned_dim {}",
+                    config.dim, config.aligned_dim
+                ),
+            )));
+        }
+        if !SUPPORTED_ALIGNED_DIMS.contains(&config.aligned_dim) {
+            let expected = SUPPORTED_ALIGNED_DIMS
+                .into_iter()
+                .find(|&supported| supported >= config.dim)
+                .unwrap_or(DIM_256);
             return Err(StorageError::VectorIndex(
-                VectorIndexError::UnsupportedOperation(format!(
-                    "Unsupported metric type: {:?}. Only L2 distance is supported.",
-                    config.dist_metric
-                )),
+                VectorIndexError::InvalidDimension {
+                    expected,
+                    actual: config.aligned_dim,
+                },
             ));
         }
+        if let Some(pq) = adapter_config.product_quantization
+            && (!(1..=config.dim).contains(&pq.subquantizers)
+                || !(1..=8).contains(&pq.bits_per_code))
+        {
+            return Err(StorageError::VectorIndex(VectorIndexError::Configuration(
+                format!(
+                    "product quantization needs 1 to {} subquantizers and 1 to 8 bits per code, \
+                     got {} and {}",
+                    config.dim, pq.subquantizers, pq.bits_per_code
+                ),
+            )));
+        }
+        if adapter_config.product_quantization.is_some()
+            && adapter_config.storage != VectorStorage::F32
+        {
+            return Err(StorageError::VectorIndex(VectorIndexError::Configuration(
+                format!(
+                    "product quantization and {:?} storage are mutually exclusive",
+                    adapter_config.storage
+                ),
+            )));
+        }
+
+        // Serial linking is what makes a DiskANN build deterministic, see `build_seed`
+        if adapter_config.build_seed.is_some() {
+            config.index_write_parameter.num_threads = 1;
+        }
+        let rng_seed = adapter_config.build_seed.unwrap_or_default();
 
         let dimension = config.dim;
+        let index_config = config.clone();
         let inner = create_inmem_index::<f32>(config)
             .map_err(|e| StorageError::VectorIndex(VectorIndexError::DiskANN(e)))?;
 
@@ -233,359 +653,591 @@ pub fn new(config: IndexConfiguration) -> StorageResult<Self> {
         const SHARD_BITS: u32 = 4; // 2^4 = 16 shards for parallelism
 
         Ok(Self {
-            inner,
+            inner: RwLock::new(inner),
+            index_config,
             dimension, // raw dimension not aligned
             node_to_vector: DashMap::new(),
             vector_to_node: ShardedVectorMap::new(SHARD_BITS)?,
             next_vector_id: AtomicU32::new(0),
+            built: false,
+            provenance: None,
+            quantized: RwLock::new(None),
+            int8: RwLock::new(
+                (adapter_config.storage == VectorStorage::Int8)
+                    .then(|| Int8Vectors::new(dimension)),
+            ),
+            float16: RwLock::new(
+                (adapter_config.storage == VectorStorage::Float16)
+                    .then(|| Float16Vectors::new(dimension)),
+            ),
+            norms: RwLock::new(
+                (adapter_config.cache_vector_norms && !adapter_config.normalizes()).then(Vec::new),
+            ),
+            stats: Arc::new(RwLock::new(IndexStats {
+                dimension,
+                ..Default::default()
+            })),
+            stats_reporter: Mutex::new(None),
+            result_frequency: adapter_config
+                .result_frequency_capacity
+                .map(|_| Mutex::new(std::collections::HashMap::new())),
+            deleted_nodes: DashMap::new(),
+            building: BuildGate::default(),
+            rng: Mutex::new(SearchRng::new(rng_seed)),
+            rng_seed,
+            checkpoint: None,
+            adapter_config,
         })
     }
 
-    pub fn mapping_count(&self) -> usize {
-        self.node_to_vector.len()
+    pub fn adapter_config(&self) -> &AdapterConfig {
+        &self.adapter_config
     }
 
-    // Private implementation methods for InMemANNAdapter
-    fn clear_mappings(&mut self) {
-        self.node_to_vector.clear();
-        self.vector_to_node.clear();
-        self.next_vector_id.store(0, Ordering::Relaxed);
+    /// Distance metric the index ranks by
+    pub fn metric(&self) -> DistanceMetric {
+        self.adapter_config.metric.into()
     }
 
-    /// Create aligned query vector for optimal SIMD performance.
-    /// DiskANN uses AVX-512 SIMD instructions, which require 64-byte alignment for optimal
-    /// performance and to avoid undefined behavior. Uses diskann-rs AlignedBoxWithSlice for
-    /// memory-safe 64-byte alignment.
-    /// Returns AlignedQueryBuffer to maintain alignment guarantee.
-    fn ensure_query_aligned(query: &[f32]) -> StorageResult<AlignedQueryBuffer<'_>> {
-        if query.as_ptr().align_offset(64) == 0 {
-            Ok(AlignedQueryBuffer::Borrowed(query))
-        } else {
-            let mut aligned = AlignedBoxWithSlice::<f32>::new(query.len(), 64)
-                .map_err(|e| StorageError::VectorIndex(VectorIndexError::DiskANN(e)))?;
-            aligned.as_mut_slice().copy_from_slice(query);
-            Ok(AlignedQueryBuffer::Owned(aligned))
-        }
+    pub fn tuning(&self) -> &SearchTuning {
+        &self.adapter_config.tuning
     }
 
-    /// Brute force search with SIMD-optimized distance computation
-    /// Direct iteration over candidate vectors for optimal low selectivity performance
-    fn brute_force_search(
-        &self,
-        query: &[f32],
-        k: usize,
-        filter_mask: &FilterMask,
-    ) -> StorageResult<Vec<(u64, f32)>> {
-        if k == 0 {
-            return Ok(Vec::new());
-        }
+    /// Replace the filtered-search tuning; takes effect with the next search
+    pub fn set_tuning(&mut self, tuning: SearchTuning) {
+        self.adapter_config.tuning = tuning;
+    }
 
-        // Ensure query vector is 64-byte aligned for SIMD requirements
-        let aligned_query = Self::ensure_query_aligned(query)?;
+    /// Provenance of the current build, or of the build that produced a loaded snapshot.
+    /// `None` until the index is built or loaded.
+    pub fn provenance(&self) -> Option<&IndexProvenance> {
+        self.provenance.as_ref()
+    }
 
-        let mut heap = BinaryHeap::<(OrderedFloat<f32>, u32)>::with_capacity(k);
+    /// DiskANN max out-degree (R) new vectors are linked with. A loaded snapshot keeps the graph
+    /// of the build that wrote it, so its existing links follow the
+    /// [`provenance`](Self::provenance) instead, which may differ.
+    pub fn max_degree(&self) -> u32 {
+        self.index_config.index_write_parameter.max_degree
+    }
 
-        for vector_id in filter_mask.iter_candidates() {
-            // Get 64-byte aligned vector data from DiskANN (zero-copy access)
-            let stored_vector = self
-                .inner
-                .get_aligned_vector_data(vector_id)
-                .map_err(|e| StorageError::VectorIndex(VectorIndexError::DiskANN(e)))?;
-            let distance = Self::compute_l2_distance(aligned_query.as_slice(), stored_vector)?;
+    /// DiskANN build search list size (L) new vectors are linked with, see
+    /// [`max_degree`](Self::max_degree)
+    pub fn build_list_size(&self) -> u32 {
+        self.index_config.index_write_parameter.search_list_size
+    }
 
-            if heap.len() < k {
-                heap.push((OrderedFloat(distance), vector_id));
-            } else if let Some((max_distance, _)) = heap.peek()
-                && OrderedFloat(distance) < *max_distance
-            {
-                heap.pop();
-                heap.push((OrderedFloat(distance), vector_id));
-            }
-        }
-        let results: Vec<_> = heap.into_sorted_vec();
+    /// Vector ID of the medoid DiskANN starts every search from, `None` until a vector is
+    /// indexed
+    pub fn entry_vector_id(&self) -> Option<u32> {
+        self.inner.read_recursive().entry_point()
+    }
 
-        let results_with_distances: Vec<(u64, f32)> = results
-            .into_iter()
-            .filter_map(|(distance, vector_id)| {
-                self.vector_to_node
-                    .get(vector_id)
-                    .map(|node_id| (node_id, distance.0))
-            })
-            .collect();
+    /// Node of [`entry_vector_id`](Self::entry_vector_id). Also `None` once that node is
+    /// deleted: DiskANN keeps entering the graph through a soft-deleted medoid until a rebuild.
+    pub fn entry_node_id(&self) -> Option<u64> {
+        self.entry_vector_id()
+            .and_then(|vector_id| self.vector_to_node.get(vector_id))
+    }
 
-        Ok(results_with_distances)
+    pub fn mapping_count(&self) -> usize {
+        self.node_to_vector.len()
     }
 
-    /// filter search: DiskANN search with FilterMask filtering
-    /// Used for larger candidate sets where diskann index search is more efficient
-    fn filter_search(
-        &self,
-        query: &[f32],
-        k: usize,
-        l_value: u32,
-        filter_mask: &FilterMask,
-        should_pre: bool,
-    ) -> StorageResult<Vec<(u64, f32)>> {
-        // Convert miniGU FilterMask to DiskANN FilterMask
-        let diskann_filter = filter_mask as &dyn DiskANNFilterMask;
-        let filtered_results =
-            self.ann_search(query, k, l_value, Some(diskann_filter), should_pre)?;
+    /// Verify that `node_to_vector` and `vector_to_node` are exact inverses, that every vector ID
+    /// is below the next one to be allocated, and that both hold `mapping_count()` entries.
+    /// Fails with `InconsistentMappings` describing the first [`MAX_REPORTED_INCONSISTENCIES`]
+    /// problems. Takes a lock per shard, so run it while the index is not being modified.
+    pub fn check_consistency(&self) -> StorageResult<()> {
+        let mut count = 0;
+        let mut examples = Vec::new();
+        let mut report = |issue: String| {
+            count += 1;
+            if examples.len() < MAX_REPORTED_INCONSISTENCIES {
+                examples.push(issue);
+            }
+        };
 
-        Ok(filtered_results)
-    }
+        let next_vector_id = self.next_vector_id.load(Ordering::Relaxed);
+        for entry in self.node_to_vector.iter() {
+            let (node_id, vector_id) = (*entry.key(), *entry.value());
+            match self.vector_to_node.get(vector_id) {
+                Some(mapped) if mapped == node_id => {}
+                Some(mapped) => report(format!(
+                    "node {node_id} maps to vector {vector_id}, which maps to node {mapped}"
+                )),
+                None => report(format!(
+                    "node {node_id} maps to vector {vector_id}, which has no node"
+                )),
+            }
+            if vector_id >= next_vector_id {
+                report(format!(
+                    "node {node_id} maps to vector {vector_id}, not below the next vector ID \
+                     {next_vector_id}"
+                ));
+            }
+        }
 
-    /// Compute L2 squared distance between query vector and stored vector
-    /// Returns squared distance (without sqrt) for consistency with DiskANN SIMD implementation
-    #[inline]
-    fn compute_l2_distance(query: &[f32], stored: &[f32]) -> StorageResult<f32> {
-        if query.len() != stored.len() {
-            return Err(StorageError::VectorIndex(
-                VectorIndexError::InvalidDimension {
-                    expected: stored.len(),
-                    actual: query.len(),
-                },
-            ));
+        let entries = self.vector_to_node.entries();
+        for &(vector_id, node_id) in &entries {
+            match self.node_to_vector.get(&node_id).map(|entry| *entry) {
+                Some(mapped) if mapped == vector_id => {}
+                Some(mapped) => report(format!(
+                    "vector {vector_id} maps to node {node_id}, which maps to vector {mapped}"
+                )),
+                None => report(format!(
+                    "vector {vector_id} maps to node {node_id}, which has no vector"
+                )),
+            }
         }
 
-        let dimension = query.len();
+        if entries.len() != self.mapping_count() {
+            report(format!(
+                "vector_to_node has {} entries, node_to_vector has {}",
+                entries.len(),
+                self.mapping_count()
+            ));
+        }
 
-        macro_rules! simd_distance {
-            ($const_dim:expr) => {{
-                // Verify exact dimension match at runtime
-                if query.len() != $const_dim || stored.len() != $const_dim {
-                    return Err(StorageError::VectorIndex(
-                        VectorIndexError::InvalidDimension {
-                            expected: $const_dim,
-                            actual: query.len(),
-                        },
-                    ));
-                }
+        if count == 0 {
+            Ok(())
+        } else {
+            Err(StorageError::VectorIndex(
+                VectorIndexError::InconsistentMappings { count, examples },
+            ))
+        }
+    }
 
-                // Enforce 64-byte alignment for AVX-512 optimizations (matches DiskANN standard)
-                if query.as_ptr().align_offset(64) != 0 {
-                    return Err(StorageError::VectorIndex(
-                        VectorIndexError::UnsupportedOperation(
-                            "Query vector not 64-byte aligned for optimal SIMD performance"
-                                .to_string(),
-                        ),
-                    ));
-                }
-                if stored.as_ptr().align_offset(64) != 0 {
-                    return Err(StorageError::VectorIndex(
-                        VectorIndexError::UnsupportedOperation(
-                            "Stored vector not 64-byte aligned for optimal SIMD performance"
-                                .to_string(),
-                        ),
-                    ));
-                }
+    /// Every live `(node_id, vector_id)` mapping, sorted by node ID
+    pub fn export_mappings(&self) -> Vec<(u64, u32)> {
+        let mut mappings: Vec<(u64, u32)> = self
+            .node_to_vector
+            .iter()
+            .map(|entry| (*entry.key(), *entry.value()))
+            .collect();
+        mappings.sort_unstable();
+        mappings
+    }
 
-                // Safety: Verified exact length and 64-byte alignment
-                unsafe {
-                    let query_array = &*(query.as_ptr() as *const [f32; $const_dim]);
-                    let stored_array = &*(stored.as_ptr() as *const [f32; $const_dim]);
-                    distance_l2_vector_f32::<$const_dim>(query_array, stored_array)
-                }
-            }};
+    /// Replace the live mappings with `mappings`, as from [`export_mappings`], so each listed
+    /// node is answered by the vector in its slot.
+    ///
+    /// Node IDs and vector IDs must each be distinct, and every vector ID must be a currently
+    /// live slot: DiskANN cannot revive a deleted slot or hold a vector for an unallocated one.
+    /// Live slots left out are soft-deleted in DiskANN, so the import fails before changing
+    /// anything if that fails. `next_vector_id` stays at the allocated slot count, which the
+    /// imported IDs are checked against. Deltas track slots rather than nodes, so the next
+    /// [`save_delta`](Self::save_delta) needs a full `save` first.
+    ///
+    /// [`export_mappings`]: Self::export_mappings
+    pub fn import_mappings(&mut self, mappings: &[(u64, u32)]) -> StorageResult<()> {
+        if !self.built {
+            return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
         }
-
-        let distance = match dimension {
-            DIM_104 => simd_distance!(DIM_104),
-            DIM_128 => simd_distance!(DIM_128),
-            DIM_256 => simd_distance!(DIM_256),
-            _ => {
+        let mut node_ids = std::collections::HashSet::with_capacity(mappings.len());
+        let mut vector_ids = std::collections::HashSet::with_capacity(mappings.len());
+        for &(node_id, vector_id) in mappings {
+            if !node_ids.insert(node_id) {
                 return Err(StorageError::VectorIndex(
-                    VectorIndexError::InvalidDimension {
-                        expected: stored.len(),
-                        actual: dimension,
-                    },
+                    VectorIndexError::DuplicateNodeId { node_id },
                 ));
             }
-        };
+            if !vector_ids.insert(vector_id) {
+                return Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(
+                    format!("Vector ID {vector_id} is mapped to more than one node"),
+                )));
+            }
+            if !self.vector_to_node.contains_key(vector_id) {
+                return Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(
+                    format!("Vector ID {vector_id} of node {node_id} is not a live slot"),
+                )));
+            }
+        }
 
-        Ok(distance)
-    }
-}
+        let dropped: Vec<(u64, u32)> = self
+            .node_to_vector
+            .iter()
+            .filter(|entry| !vector_ids.contains(entry.value()))
+            .map(|entry| (*entry.key(), *entry.value()))
+            .collect();
+        if !dropped.is_empty() {
+            let dropped_vector_ids: Vec<u32> =
+                dropped.iter().map(|(_, vector_id)| *vector_id).collect();
+            self.inner
+                .get_mut()
+                .soft_delete(dropped_vector_ids, dropped.len())
+                .map_err(|e| StorageError::VectorIndex(VectorIndexError::DiskANN(e)))?;
+        }
 
-impl VectorIndex for InMemANNAdapter {
-    fn build(&mut self, vectors: &[(u64, &[f32])]) -> StorageResult<()> {
-        if vectors.is_empty() {
-            return Err(StorageError::VectorIndex(VectorIndexError::EmptyDataset));
+        self.node_to_vector.clear();
+        self.vector_to_node.clear();
+        for &(node_id, vector_id) in mappings {
+            self.vector_to_node.set(vector_id, node_id)?;
+            self.node_to_vector.insert(node_id, vector_id);
+            self.deleted_nodes.remove(&node_id);
+        }
+        if self.adapter_config.retain_deleted_mappings {
+            let deleted_at = Instant::now();
+            for (node_id, vector_id) in dropped {
+                if !node_ids.contains(&node_id) {
+                    self.deleted_nodes.insert(node_id, (vector_id, deleted_at));
+                }
+            }
         }
+        self.checkpoint = None;
+        self.sync_size_stats();
+        Ok(())
+    }
 
-        self.clear_mappings();
+    /// Check which node IDs are currently indexed and live (not soft-deleted).
+    /// The result preserves input order: `result[i]` answers `node_ids[i]`.
+    pub fn contains_all(&self, node_ids: &[u64]) -> Vec<bool> {
+        node_ids
+            .iter()
+            .map(|node_id| self.node_to_vector.contains_key(node_id))
+            .collect()
+    }
 
-        // Verify dimension consistency with index configuration
-        // Note: Upper layer should ensure all vectors have consistent dimensions
-        if let Some((_, first_vector)) = vectors.first()
-            && first_vector.len() != self.dimension
-        {
-            return Err(StorageError::VectorIndex(
-                VectorIndexError::InvalidDimension {
-                    expected: self.dimension,
-                    actual: first_vector.len(),
-                },
-            ));
+    /// Disaster recovery: re-derive both ID mappings from the node ID owning each vector slot,
+    /// for when the mappings were lost but the DiskANN index is intact.
+    ///
+    /// `build` assigns vector IDs by array position in sorted node-ID order and `insert`
+    /// appends in batch order, so `node_ids_in_build_order[i]` is the node of vector ID `i`.
+    /// One node ID per allocated slot is required. Slots recorded as deleted by
+    /// `retain_deleted_mappings` stay unmapped; without that record, nodes deleted since the
+    /// build would be mapped again, so only use this on indexes without deletions. On error
+    /// nothing is changed.
+    pub fn rebuild_mappings(&mut self, node_ids_in_build_order: &[u64]) -> StorageResult<()> {
+        use std::collections::HashSet;
+
+        if !self.built {
+            return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
+        }
+        let slot_count = self.next_vector_id.load(Ordering::Relaxed) as usize;
+        if node_ids_in_build_order.len() != slot_count {
+            return Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(
+                format!(
+                    "Expected {slot_count} node IDs, one per vector slot, got {}",
+                    node_ids_in_build_order.len()
+                ),
+            )));
+        }
+        let mut seen = HashSet::with_capacity(slot_count);
+        for &node_id in node_ids_in_build_order {
+            if !seen.insert(node_id) {
+                return Err(StorageError::VectorIndex(
+                    VectorIndexError::DuplicateNodeId { node_id },
+                ));
+            }
         }
 
-        let mut sorted_vectors: Vec<(u64, &[f32])> = vectors.to_vec();
-        sorted_vectors.sort_by_key(|(node_id, _)| *node_id);
-
-        // Basic boundary check: ensure vector count fits in u32 for DiskANN compatibility
-        if sorted_vectors.len() > u32::MAX as usize {
-            self.clear_mappings();
-            return Err(StorageError::VectorIndex(
-                VectorIndexError::UnsupportedOperation(format!(
-                    "Vector count {} exceeds u32::MAX limit for DiskANN",
-                    sorted_vectors.len()
-                )),
-            ));
+        let deleted_slots: HashSet<u32> = self
+            .deleted_nodes
+            .iter()
+            .map(|entry| entry.value().0)
+            .collect();
+        self.node_to_vector.clear();
+        self.vector_to_node.clear();
+        for (vector_id, &node_id) in node_ids_in_build_order.iter().enumerate() {
+            let vector_id = vector_id as u32;
+            if deleted_slots.contains(&vector_id) {
+                continue;
+            }
+            self.node_to_vector.insert(node_id, vector_id);
+            self.vector_to_node.set(vector_id, node_id)?;
         }
+        self.sync_size_stats();
+        Ok(())
+    }
 
-        // Note: Removed max_points capacity check to rely on DiskANN's internal capacity management
-        //
-        // DiskANN Capacity Management:
-        // - growth_potential is a PRE-ALLOCATION multiplier, not dynamic expansion
-        // - Physical capacity = max_points × growth_potential (set at initialization)
-        // - Once physical capacity is reached, no more vectors can be inserted
-        // - This is the correct behavior - DiskANN has fixed pre-allocated memory
+    /// Rename indexed nodes according to `(old_node_id, new_node_id)` pairs, keeping their
+    /// vectors and vector IDs. All old IDs must be indexed, and a new ID may only reuse an ID
+    /// that is itself being remapped in the same call (so swaps are allowed). On error nothing
+    /// is changed. Deltas do not record remaps, so this discards the `save_delta` checkpoint.
+    pub fn remap_node_ids(&mut self, mapping: &[(u64, u64)]) -> StorageResult<()> {
+        use std::collections::HashSet;
 
-        // Validate node IDs and establish ID mappings BEFORE calling DiskANN
-        let mut seen_nodes = std::collections::HashSet::new();
+        if !self.built {
+            return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
+        }
+        let mut old_ids = HashSet::with_capacity(mapping.len());
+        let mut vector_ids = Vec::with_capacity(mapping.len());
+        for &(old_node_id, _) in mapping {
+            if !old_ids.insert(old_node_id) {
+                return Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(
+                    format!("Node ID {old_node_id} is remapped more than once"),
+                )));
+            }
+            let vector_id =
+                self.node_to_vector_id(old_node_id)
+                    .ok_or(StorageError::VectorIndex(
+                        VectorIndexError::NodeIdNotFound {
+                            node_id: old_node_id,
+                        },
+                    ))?;
+            vector_ids.push(vector_id);
+        }
 
-        for (array_index, (node_id, _)) in sorted_vectors.iter().enumerate() {
-            if !seen_nodes.insert(*node_id) {
-                self.clear_mappings();
+        let mut new_ids = HashSet::with_capacity(mapping.len());
+        for &(_, new_node_id) in mapping {
+            let collides =
+                self.node_to_vector.contains_key(&new_node_id) && !old_ids.contains(&new_node_id);
+            if !new_ids.insert(new_node_id) || collides {
                 return Err(StorageError::VectorIndex(
-                    VectorIndexError::DuplicateNodeId { node_id: *node_id },
+                    VectorIndexError::DuplicateNodeId {
+                        node_id: new_node_id,
+                    },
                 ));
             }
-            // Establish ID mapping - DiskANN will assign vector_id = array_index
-            let vector_id = array_index as u32;
-            self.node_to_vector.insert(*node_id, vector_id);
-            if let Err(e) = self.vector_to_node.set(vector_id, *node_id) {
-                self.clear_mappings();
-                return Err(e);
-            }
         }
 
-        // Extract vector slices directly (no conversion needed)
-        let vector_slices: Vec<&[f32]> = sorted_vectors.iter().map(|(_, v)| *v).collect();
+        // Remove every old entry first so swapped IDs do not overwrite each other
+        for &(old_node_id, _) in mapping {
+            self.node_to_vector.remove(&old_node_id);
+        }
+        for (&(_, new_node_id), vector_id) in mapping.iter().zip(vector_ids) {
+            self.node_to_vector.insert(new_node_id, vector_id);
+            self.vector_to_node.set(vector_id, new_node_id)?;
+            // The new ID is live now, whatever was recorded about an earlier node of that ID
+            self.deleted_nodes.remove(&new_node_id);
+        }
+        self.checkpoint = None;
+        self.sync_size_stats();
+        Ok(())
+    }
 
-        match self.inner.build_from_memory(&vector_slices) {
-            Ok(()) => {
-                self.next_vector_id
-                    .store(sorted_vectors.len() as u32, Ordering::Relaxed);
+    /// Handle reporting whether a `build` is in progress, usable without holding any lock on
+    /// the adapter
+    pub fn build_gate(&self) -> BuildGate {
+        self.building.clone()
+    }
 
-                Ok(())
-            }
-            Err(e) => {
-                self.clear_mappings();
-                Err(StorageError::VectorIndex(VectorIndexError::BuildError(
-                    e.to_string(),
-                )))
-            }
+    /// `Err(IndexRebuilding)` if fail-fast mode is on and a build is in progress
+    fn check_not_rebuilding(&self) -> StorageResult<()> {
+        if self.adapter_config.fail_fast_during_build {
+            self.building.check()?;
         }
+        Ok(())
     }
 
-    fn ann_search(
-        &self,
-        query: &[f32],
-        k: usize,
-        l_value: u32,
-        filter_mask: Option<&dyn DiskANNFilterMask>,
-        should_pre: bool,
-    ) -> StorageResult<Vec<(u64, f32)>> {
-        // Check if index is built
-        if self.vector_to_node.is_empty() {
-            return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
-        }
+    /// When `node_id` was soft-deleted, if `retain_deleted_mappings` is enabled and no rebuild
+    /// or re-insert of the node has happened since. Always `None` with the option disabled.
+    pub fn was_deleted(&self, node_id: u64) -> Option<Instant> {
+        self.deleted_nodes.get(&node_id).map(|entry| entry.1)
+    }
 
-        // Perform DiskANN search
-        let effective_k = std::cmp::min(k, self.size());
-        if effective_k == 0 {
-            return Ok(Vec::new()); // No active vectors
-        }
-        let mut vector_ids = vec![0u32; effective_k];
-        let mut distances = vec![0.0f32; effective_k];
-        let actual_count = self
-            .inner
-            .search(
-                query,
-                effective_k,
-                l_value,
-                &mut vector_ids,
-                &mut distances,
-                filter_mask,
-                should_pre,
-            )
-            .map_err(|e| StorageError::VectorIndex(VectorIndexError::SearchError(e.to_string())))?;
-        let mut results = Vec::with_capacity(actual_count as usize);
-        for (&vector_id, &distance) in vector_ids
+    /// [`build`] from f64 vectors, down-converted to f32 under the `f64_ingest` policy
+    ///
+    /// [`build`]: VectorIndex::build
+    pub fn build_f64(&mut self, vectors: &[(u64, &[f64])]) -> StorageResult<()> {
+        let converted = self.convert_f64_batch(vectors)?;
+        let refs: Vec<(u64, &[f32])> = converted
             .iter()
-            .zip(distances.iter())
-            .take(actual_count as usize)
-        {
-            if let Some(node_id) = self.vector_to_node.get(vector_id) {
-                // Verify the node is still active (not soft-deleted)
-                if self.node_to_vector.contains_key(&node_id) {
-                    results.push((node_id, distance));
-                }
-            }
-        }
+            .map(|(id, v)| (*id, v.as_slice()))
+            .collect();
+        self.build(&refs)
+    }
 
-        Ok(results)
+    /// [`insert`] of f64 vectors, down-converted to f32 under the `f64_ingest` policy
+    ///
+    /// [`insert`]: VectorIndex::insert
+    pub fn insert_f64(&mut self, vectors: &[(u64, &[f64])]) -> StorageResult<()> {
+        let converted = self.convert_f64_batch(vectors)?;
+        let refs: Vec<(u64, &[f32])> = converted
+            .iter()
+            .map(|(id, v)| (*id, v.as_slice()))
+            .collect();
+        self.insert(&refs)
     }
 
-    fn search(
-        &self,
-        query: &[f32],
-        k: usize,
-        l_value: u32,
-        filter_mask: Option<&FilterMask>,
-        should_pre: bool,
-    ) -> StorageResult<Vec<(u64, f32)>> {
-        // No filter provided, DiskANN search without filter
-        let Some(mask) = filter_mask else {
-            return self.ann_search(query, k, l_value, None, should_pre);
+    /// f32 copy of an f64 batch. Non-finite values convert as-is and are rejected later by the
+    /// usual input validation.
+    fn convert_f64_batch(&self, vectors: &[(u64, &[f64])]) -> StorageResult<OwnedVectors> {
+        let clamp = match self.adapter_config.f64_ingest {
+            F64IngestPolicy::Disabled => {
+                return Err(StorageError::VectorIndex(
+                    VectorIndexError::UnsupportedOperation(
+                        "f64 vectors need an f64_ingest policy in AdapterConfig".to_string(),
+                    ),
+                ));
+            }
+            F64IngestPolicy::Reject => false,
+            F64IngestPolicy::Clamp => true,
         };
+        const F32_MAX: f64 = f32::MAX as f64;
 
-        if self.vector_to_node.is_empty() {
-            return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
+        vectors
+            .iter()
+            .map(|(node_id, vector)| {
+                let converted = vector
+                    .iter()
+                    .enumerate()
+                    .map(|(index, &x)| {
+                        if x.is_finite() && x.abs() > F32_MAX {
+                            if !clamp {
+                                return Err(StorageError::VectorIndex(
+                                    VectorIndexError::InvalidInput(format!(
+                                        "Value {x} at index {index} of node {node_id} is outside \
+                                         the f32 range"
+                                    )),
+                                ));
+                            }
+                            return Ok(x.clamp(-F32_MAX, F32_MAX) as f32);
+                        }
+                        Ok(x as f32)
+                    })
+                    .collect::<StorageResult<Vec<f32>>>()?;
+                Ok((*node_id, converted))
+            })
+            .collect()
+    }
+
+    /// Check `build` inputs without building: the vector count must fit DiskANN's u32 vector
+    /// IDs, node IDs must be distinct (and increasing when `inputs_presorted` is set), and
+    /// every vector must have the index dimension and only finite values. Returns the count
+    /// and an estimate of the built index's memory. `build` runs the same checks first.
+    pub fn validate_build_inputs(&self, vectors: &[(u64, &[f32])]) -> StorageResult<BuildPlan> {
+        self.validate_inputs(vectors, self.adapter_config.inputs_presorted)
+    }
+
+    fn validate_inputs(
+        &self,
+        vectors: &[(u64, &[f32])],
+        presorted: bool,
+    ) -> StorageResult<BuildPlan> {
+        if vectors.len() > u32::MAX as usize {
+            return Err(Self::vector_count_overflow(vectors.len()));
         }
-        if mask.candidate_count() == 0 {
-            return Ok(Vec::new());
+
+        for (node_id, vector) in vectors {
+            self.check_build_vector(*node_id, vector)?;
         }
 
-        let selectivity = mask.selectivity();
-        if selectivity < SELECTIVITY_THRESHOLD {
-            self.brute_force_search(query, k, mask)
+        if presorted {
+            for (array_index, pair) in vectors.windows(2).enumerate() {
+                let (prev, node_id) = (pair[0].0, pair[1].0);
+                if prev == node_id {
+                    return Err(StorageError::VectorIndex(
+                        VectorIndexError::DuplicateNodeId { node_id },
+                    ));
+                }
+                if prev > node_id {
+                    return Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(
+                        format!(
+                            "inputs_presorted is set but node ID {node_id} at position {} \
+                             follows larger ID {prev}",
+                            array_index + 1
+                        ),
+                    )));
+                }
+            }
         } else {
-            self.filter_search(query, k, l_value, mask, should_pre)
+            let mut node_ids: Vec<u64> = vectors.iter().map(|(node_id, _)| *node_id).collect();
+            node_ids.sort_unstable();
+            if let Some(pair) = node_ids.windows(2).find(|pair| pair[0] == pair[1]) {
+                return Err(StorageError::VectorIndex(
+                    VectorIndexError::DuplicateNodeId { node_id: pair[0] },
+                ));
+            }
         }
+
+        let count = vectors.len();
+        // node_to_vector entry plus vector_to_node slot
+        let mapping_bytes = std::mem::size_of::<(u64, u32)>() + std::mem::size_of::<Option<u64>>();
+        Ok(BuildPlan {
+            vector_count: count,
+            dimension: self.dimension,
+            estimated_memory_bytes: count * (self.slot_bytes() + mapping_bytes),
+        })
     }
 
-    fn get_dimension(&self) -> usize {
-        self.dimension
+    /// Preallocate both ID mappings for `additional` more vectors, so a large build or insert
+    /// batch does not rehash `node_to_vector` repeatedly as it fills. `build` reserves for its
+    /// batch itself. Growing an already populated map rehashes it once here instead.
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self.node_to_vector.len() + additional;
+        if self.node_to_vector.capacity() < needed {
+            let grown = DashMap::with_capacity(needed);
+            for (node_id, vector_id) in std::mem::take(&mut self.node_to_vector) {
+                grown.insert(node_id, vector_id);
+            }
+            self.node_to_vector = grown;
+        }
+        let allocated_slots = self.next_vector_id.load(Ordering::Relaxed) as usize;
+        self.vector_to_node.reserve(allocated_slots + additional);
     }
 
-    fn size(&self) -> usize {
-        // Return the actual number of active vectors based on our mappings
-        // This correctly excludes deleted vectors, unlike get_num_active_pts()
-        self.node_to_vector.len()
+    /// Deleted-slot accounting and whether a rebuild is worth it.
+    ///
+    /// Soft-deleted vectors keep their DiskANN slot, data and adjacency list until the index is
+    /// rebuilt, so IDs and memory fragment as deletions accumulate. [`compact`] is recommended
+    /// once [`COMPACTION_DELETED_RATIO`] of the allocated slots are deleted. Graph degradation
+    /// itself is not measured: diskann-rs does not expose per-node edge statistics.
+    ///
+    /// [`compact`]: Self::compact
+    pub fn fragmentation_report(&self) -> FragmentationReport {
+        let deleted_vectors = self.num_deleted();
+        let deleted_ratio = self.deletion_ratio();
+        FragmentationReport {
+            allocated_slots: self.next_vector_id.load(Ordering::Relaxed) as usize,
+            live_vectors: self.node_to_vector.len(),
+            deleted_vectors,
+            deleted_ratio,
+            reclaimable_bytes: deleted_vectors * self.slot_bytes(),
+            recommend_compaction: deleted_vectors > 0 && deleted_ratio >= COMPACTION_DELETED_RATIO,
+        }
     }
 
-    fn node_to_vector_id(&self, node_id: u64) -> Option<u32> {
-        self.node_to_vector.get(&node_id).map(|entry| *entry)
+    /// Number of vector slots DiskANN still holds without a live node: soft-deleted vectors and
+    /// the replaced vectors of upserts. `size() + num_deleted()` is the number of slots allocated
+    /// since the last build, load or compaction, which tracks the graph's physical size; DiskANN
+    /// preallocates beyond it, so it is not the graph's capacity. Compaction resets it to 0.
+    pub fn num_deleted(&self) -> usize {
+        let allocated_slots = self.next_vector_id.load(Ordering::Relaxed) as usize;
+        allocated_slots.saturating_sub(self.node_to_vector.len())
     }
 
-    fn insert(&mut self, vectors: &[(u64, &[f32])]) -> StorageResult<()> {
+    /// `num_deleted()` as a share of the allocated slots, 0.0 for an empty index
+    pub fn deletion_ratio(&self) -> f64 {
+        let allocated_slots = self.next_vector_id.load(Ordering::Relaxed) as usize;
+        if allocated_slots == 0 {
+            0.0
+        } else {
+            self.num_deleted() as f64 / allocated_slots as f64
+        }
+    }
+
+    /// [`insert`](VectorIndex::insert) through a shared reference, so that searches keep running
+    /// on other threads. Normalization, validation and encoding the new slots for quantized
+    /// scans take no exclusive lock; searches wait only while DiskANN links the new points into
+    /// its graph, which needs exclusive access. Concurrent inserts are serialized there.
+    ///
+    /// Vector IDs are allocated under that lock, so they follow the order in which DiskANN
+    /// stores the points, and the mappings are published only once DiskANN holds the points: a
+    /// failed insert leaves the mappings and `next_vector_id` untouched, and a search never
+    /// sees a mapping without its vector. Until its codes are synced, just before this returns,
+    /// a new vector is skipped by PQ, int8 and f16 brute-force scans.
+    pub fn insert_concurrent(&self, vectors: &[(u64, &[f32])]) -> StorageResult<()> {
         if vectors.is_empty() {
             return Ok(());
         }
-        if self.node_to_vector.is_empty() {
+        if !self.built {
             return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
         }
 
+        let normalized = self.normalize_batch(vectors)?;
+        let normalized_refs: Vec<(u64, &[f32])>;
+        let vectors = match &normalized {
+            Some(owned) => {
+                normalized_refs = owned.iter().map(|(id, v)| (*id, v.as_slice())).collect();
+                normalized_refs.as_slice()
+            }
+            None => vectors,
+        };
+        if vectors.is_empty() {
+            return Ok(()); // Every vector was skipped by the zero-norm policy
+        }
+
         // Verify dimension consistency with index configuration
         // Note: Upper layer should ensure all vectors have consistent dimensions
         for (_, vector) in vectors.iter() {
@@ -599,9 +1251,10 @@ fn insert(&mut self, vectors: &[(u64, &[f32])]) -> StorageResult<()> {
             }
         }
 
-        // Check for duplicate node IDs
+        // A node repeated within the batch would get two slots, one of them never freed
+        let mut batch_ids = std::collections::HashSet::with_capacity(vectors.len());
         for (node_id, _) in vectors {
-            if self.node_to_vector.contains_key(node_id) {
+            if !batch_ids.insert(*node_id) {
                 return Err(StorageError::VectorIndex(
                     VectorIndexError::DuplicateNodeId { node_id: *node_id },
                 ));
@@ -616,383 +1269,6346 @@ fn insert(&mut self, vectors: &[(u64, &[f32])]) -> StorageResult<()> {
         // - DiskANN will return error if insertion would exceed pre-allocated capacity
         // - This is expected behavior for memory-based indices with fixed allocation
 
-        // Safe atomic ID allocation (max_points ≤ u32::MAX guaranteed by build())
-        let base_vector_id = self
-            .next_vector_id
-            .fetch_add(vectors.len() as u32, Ordering::Relaxed);
-
-        let mut inserted_mappings = Vec::new();
-        for (array_index, (node_id, _)) in vectors.iter().enumerate() {
-            let vector_id = base_vector_id + array_index as u32;
-
-            self.node_to_vector.insert(*node_id, vector_id);
-            if let Err(e) = self.vector_to_node.set(vector_id, *node_id) {
-                // Rollback already inserted mappings
-                for (prev_node_id, prev_vector_id) in inserted_mappings {
-                    self.node_to_vector.remove(&prev_node_id);
-                    self.vector_to_node.remove(prev_vector_id);
+        // Extract vector slices directly (no conversion needed)
+        let vector_data: Vec<&[f32]> = vectors.iter().map(|(_, v)| *v).collect();
+
+        {
+            let mut inner = self.inner.write();
+
+            // Checked under the lock so that a concurrent insert cannot add the same node
+            for (node_id, _) in vectors {
+                if self.node_to_vector.contains_key(node_id) {
+                    return Err(StorageError::VectorIndex(
+                        VectorIndexError::DuplicateNodeId { node_id: *node_id },
+                    ));
                 }
-                self.next_vector_id
-                    .fetch_sub((array_index + 1) as u32, Ordering::Relaxed);
-                return Err(e);
             }
 
-            // Track for potential rollback
-            inserted_mappings.push((*node_id, vector_id));
-        }
+            // Only writers of the lock held here advance it (max_points ≤ u32::MAX guaranteed by
+            // build())
+            let base_vector_id = self.next_vector_id.load(Ordering::Relaxed);
+            // After an empty build the DiskANN graph does not exist yet, so the first insert
+            // builds it
+            if base_vector_id == 0 {
+                let mut fresh = self.fresh_inner(vectors.len())?;
+                self.adapter_config
+                    .install(|| fresh.build_from_memory(&vector_data))
+                    .map_err(|e| {
+                        StorageError::VectorIndex(VectorIndexError::BuildError(e.to_string()))
+                    })?;
+                *inner = fresh;
+            } else {
+                let inner = &mut **inner;
+                self.adapter_config
+                    .install(|| inner.insert_from_memory(&vector_data))
+                    .map_err(|e| {
+                        StorageError::VectorIndex(VectorIndexError::BuildError(e.to_string()))
+                    })?;
+            }
 
-        // Extract vector slices directly (no conversion needed)
-        let vector_data: Vec<&[f32]> = vectors.iter().map(|(_, v)| *v).collect();
+            for (array_index, (node_id, _)) in vectors.iter().enumerate() {
+                let vector_id = base_vector_id + array_index as u32;
+                self.vector_to_node.set(vector_id, *node_id)?;
+                self.node_to_vector.insert(*node_id, vector_id);
+            }
+            self.next_vector_id
+                .store(base_vector_id + vectors.len() as u32, Ordering::Release);
+        }
 
-        match self.inner.insert_from_memory(&vector_data) {
-            Ok(()) => Ok(()),
-            Err(e) => {
-                for (node_id, vector_id) in inserted_mappings {
-                    self.node_to_vector.remove(&node_id);
-                    self.vector_to_node.remove(vector_id);
-                }
+        self.sync_quantized_codes()?;
+        self.sync_size_stats();
+        self.stats.write().total_inserts += vectors.len() as u64;
+        if !self.deleted_nodes.is_empty() {
+            for (node_id, _) in vectors {
+                self.deleted_nodes.remove(node_id);
+            }
+        }
+        Ok(())
+    }
 
-                self.next_vector_id
-                    .fetch_sub(vectors.len() as u32, Ordering::Relaxed);
+    /// [`insert`](VectorIndex::insert) that inserts what it can instead of failing the batch.
+    ///
+    /// Duplicate node IDs (already indexed or repeated in the batch), invalid vectors and
+    /// vectors beyond the u32 vector ID space are reported and left out before DiskANN sees the
+    /// batch; the rest are inserted in one call. If that call fails, none of them are indexed
+    /// and each is reported with the DiskANN error. Vectors skipped by [`ZeroNormPolicy::Skip`]
+    /// appear in neither list. Only an unbuilt index fails the whole call.
+    pub fn insert_lenient(&mut self, vectors: &[(u64, &[f32])]) -> StorageResult<InsertReport> {
+        let mut report = InsertReport::default();
+        if vectors.is_empty() {
+            return Ok(report);
+        }
+        if !self.built {
+            return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
+        }
 
-                Err(StorageError::VectorIndex(VectorIndexError::BuildError(
-                    e.to_string(),
+        let rejects_zero_norm = self.adapter_config.normalizes()
+            && self.adapter_config.zero_norm_policy == ZeroNormPolicy::Reject;
+        let mut free_slots = u32::MAX - self.next_vector_id.load(Ordering::Relaxed);
+        let mut seen = std::collections::HashSet::with_capacity(vectors.len());
+        let mut valid = Vec::with_capacity(vectors.len());
+        for &(node_id, vector) in vectors {
+            let failure = if self.node_to_vector.contains_key(&node_id) || !seen.insert(node_id) {
+                Some(InsertFailure::DuplicateNodeId)
+            } else if let Err(e) = self.check_build_vector(node_id, vector) {
+                Some(InsertFailure::InvalidVector(e.to_string()))
+            } else if rejects_zero_norm && vector_norm(vector) == 0.0 {
+                Some(InsertFailure::InvalidVector(format!(
+                    "Vector of node {node_id} has zero norm and cannot be normalized"
                 )))
+            } else if free_slots == 0 {
+                Some(InsertFailure::VectorIdOverflow)
+            } else {
+                free_slots -= 1;
+                None
+            };
+            match failure {
+                Some(failure) => report.failed.push((node_id, failure)),
+                None => valid.push((node_id, vector)),
+            }
+        }
+
+        let error = self.insert_concurrent(&valid).err();
+        for (node_id, _) in valid {
+            if self.node_to_vector.contains_key(&node_id) {
+                report.inserted.push(node_id);
+            } else if let Some(e) = &error {
+                report
+                    .failed
+                    .push((node_id, InsertFailure::DiskANN(e.to_string())));
             }
         }
+        Ok(report)
     }
 
-    fn soft_delete(&mut self, node_ids: &[u64]) -> StorageResult<()> {
-        if node_ids.is_empty() {
+    /// Insert each vector, replacing the vector of node IDs that are already indexed.
+    ///
+    /// Replaced nodes get a new vector ID and their old slot is soft-deleted, so upserts
+    /// fragment the index like deletions do. Node IDs must be distinct within the batch. If any
+    /// step fails, both ID mappings are restored exactly as before the call; DiskANN may keep
+    /// the newly written vectors in unmapped slots, which searches never return. A replacement
+    /// skipped by [`ZeroNormPolicy::Skip`] still removes the old vector.
+    pub fn upsert(&mut self, vectors: &[(u64, Vec<f32>)]) -> StorageResult<()> {
+        if vectors.is_empty() {
             return Ok(());
         }
-
-        if self.node_to_vector.is_empty() {
+        if !self.built {
             return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
         }
-
-        // Validate all node_ids exist and collect vector_ids to delete
-        let mut vector_ids_to_delete = Vec::with_capacity(node_ids.len());
-        for &node_id in node_ids {
-            if let Some(vector_id) = self.node_to_vector.get(&node_id) {
-                // Check if mapping exists in vector_to_node (should always exist if node_to_vector
-                // exists)
-                if self.vector_to_node.contains_key(*vector_id) {
-                    vector_ids_to_delete.push(*vector_id);
-                } else {
-                    return Err(StorageError::VectorIndex(
-                        VectorIndexError::NodeIdNotFound { node_id },
-                    ));
-                }
-            } else {
+        let mut seen = std::collections::HashSet::with_capacity(vectors.len());
+        for (node_id, _) in vectors {
+            if !seen.insert(*node_id) {
                 return Err(StorageError::VectorIndex(
-                    VectorIndexError::NodeIdNotFound { node_id },
+                    VectorIndexError::DuplicateNodeId { node_id: *node_id },
                 ));
             }
         }
 
-        match self
-            .inner
-            .soft_delete(vector_ids_to_delete.clone(), vector_ids_to_delete.len())
-        {
-            Ok(()) => {
-                // DiskANN soft deletion successful
-                // Update both mappings to maintain consistency
-                let deleted_nodes = self
-                    .vector_to_node
-                    .batch_soft_delete(&vector_ids_to_delete)?;
+        // Detach replaced nodes so `insert` gives them fresh slots; their old slots stay mapped
+        // in `vector_to_node` until the new vectors are in
+        let replaced: Vec<(u64, u32)> = vectors
+            .iter()
+            .filter_map(|(node_id, _)| self.node_to_vector.remove(node_id))
+            .collect();
+        let restore_replaced = |adapter: &Self| {
+            for (node_id, vector_id) in &replaced {
+                adapter.node_to_vector.insert(*node_id, *vector_id);
+            }
+            adapter.sync_size_stats();
+        };
 
-                // Remove from node_to_vector mapping
-                for node_id in deleted_nodes {
-                    self.node_to_vector.remove(&node_id);
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(node_id, vector)| (*node_id, vector.as_slice()))
+            .collect();
+        if let Err(e) = self.insert(&refs) {
+            restore_replaced(self);
+            return Err(e);
+        }
+        if replaced.is_empty() {
+            return Ok(());
+        }
+
+        let old_vector_ids: Vec<u32> = replaced.iter().map(|(_, vector_id)| *vector_id).collect();
+        let deleted = self
+            .inner
+            .get_mut()
+            .soft_delete(old_vector_ids.clone(), old_vector_ids.len())
+            .map_err(|e| StorageError::VectorIndex(VectorIndexError::DiskANN(e)))
+            .and_then(|()| self.vector_to_node.batch_soft_delete(&old_vector_ids));
+        if let Err(e) = deleted {
+            // Unmap the slots just written, then point replaced nodes back at their old ones
+            for (node_id, _) in vectors {
+                if let Some((_, vector_id)) = self.node_to_vector.remove(node_id) {
+                    self.vector_to_node.remove(vector_id);
                 }
             }
-            Err(e) => {
-                return Err(StorageError::VectorIndex(VectorIndexError::DiskANN(e)));
-            }
+            restore_replaced(self);
+            return Err(e);
         }
-
+        self.sync_size_stats();
         Ok(())
     }
 
-    fn save(&mut self, _path: &str) -> StorageResult<()> {
-        Err(StorageError::VectorIndex(VectorIndexError::NotSupported(
-            "save() is not yet implemented".to_string(),
-        )))
+    /// Soft-delete every live node whose vector ID `mask` selects, returning how many were
+    /// deleted. Mask candidates that are already deleted or were never allocated are ignored;
+    /// the resolved nodes then go through [`soft_delete`](VectorIndex::soft_delete), so they are
+    /// deleted in one DiskANN call and the mappings change only if it succeeds.
+    pub fn soft_delete_matching(&mut self, mask: &dyn SearchFilter) -> StorageResult<usize> {
+        if !self.built {
+            return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
+        }
+        let node_ids: Vec<u64> = mask
+            .iter_candidates()
+            .filter_map(|vector_id| self.vector_to_node.get(vector_id))
+            .collect();
+        self.soft_delete(&node_ids)?;
+        Ok(node_ids.len())
     }
 
-    fn load(&mut self, _path: &str) -> StorageResult<()> {
-        Err(StorageError::VectorIndex(VectorIndexError::NotSupported(
-            "load() is not yet implemented for InMemANNAdapter".to_string(),
-        )))
+    /// Physically remove soft-deleted vectors by rebuilding the DiskANN graph from the live
+    /// vectors, returning the stats afterwards (compare `memory_usage` to see what was
+    /// reclaimed).
+    ///
+    /// diskann-rs has no in-place consolidation, so this is a full rebuild over the stored
+    /// (already normalized) vectors. Live vectors keep their relative order and get dense vector
+    /// IDs `0..live_count`; node IDs are unchanged, but vector IDs, and masks built from them,
+    /// are invalidated. Mappings retained by `retain_deleted_mappings` are dropped as on any
+    /// rebuild. The new graph and mappings are built aside and swapped in only on success, so a
+    /// failure leaves the index as it was.
+    pub fn compact(&mut self) -> StorageResult<IndexStats> {
+        if !self.built {
+            return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
+        }
+        self.reindex_live(self.index_config.clone())?;
+        Ok(self.stats.read().clone())
     }
-}
 
-/// Create a vector index configuration with intelligent capacity management
-///
-/// This function calculates optimal DiskANN configuration parameters based on the actual
-/// dataset size, using a headroom ratio to provide growth capacity while maintaining
-/// efficiency.
-pub fn create_vector_index_config(dimension: usize, vector_count: usize) -> IndexConfiguration {
+    /// Rebuild the index from its own stored vectors under a new DiskANN configuration, such
+    /// as a different graph degree or build search list size, without re-supplying the vectors.
+    ///
+    /// This is [`compact`](Self::compact) with `config` in place of the current configuration:
+    /// deleted vectors are dropped and live vectors get dense vector IDs under unchanged node
+    /// IDs. `config` must keep the index's dimension, and may not switch an L2 index to cosine
+    /// since the stored vectors are not normalized; a cosine index accepts either metric, its
+    /// unit vectors being searched with the L2 kernel. The provenance is recaptured. On failure
+    /// the index is left as it was.
+    pub fn rebuild_with_config(&mut self, mut config: IndexConfiguration) -> StorageResult<()> {
+        if !self.built {
+            return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
+        }
+        if config.dim != self.dimension
+            || config.dim > config.aligned_dim
+            || !SUPPORTED_ALIGNED_DIMS.contains(&config.aligned_dim)
+        {
+            return Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension {
+                    expected: self.dimension,
+                    actual: config.dim,
+                },
+            ));
+        }
+        match (config.dist_metric, self.adapter_config.metric) {
+            (Metric::L2, _) => {}
+            (Metric::Cosine, IndexMetric::Cosine) => config.dist_metric = Metric::L2,
+            (Metric::Cosine, metric) => {
+                return Err(StorageError::VectorIndex(VectorIndexError::Configuration(
+                    format!("can't rebuild a {metric:?} index with metric Cosine"),
+                )));
+            }
+        }
+
+        if self.adapter_config.build_seed.is_some() {
+            config.index_write_parameter.num_threads = 1;
+        }
+
+        self.reindex_live(config)?;
+        self.provenance = Some(IndexProvenance::capture(
+            &self.index_config,
+            &self.adapter_config,
+        ));
+        Ok(())
+    }
+
+    /// Rebuild the DiskANN graph from the live stored vectors under `config` and swap it in,
+    /// see [`compact`](Self::compact)
+    fn reindex_live(&mut self, config: IndexConfiguration) -> StorageResult<()> {
+        let _building = BuildingGuard::enter(&self.building);
+
+        let mut live: Vec<(u32, u64)> = self
+            .node_to_vector
+            .iter()
+            .map(|entry| (*entry.value(), *entry.key()))
+            .collect();
+        live.sort_unstable();
+        let vectors = live
+            .iter()
+            .map(|&(vector_id, _)| self.stored_vector(vector_id))
+            .collect::<StorageResult<Vec<_>>>()?;
+
+        let build_start = Instant::now();
+        let mut inner = Self::create_inner(&config, live.len())?;
+        if !vectors.is_empty() {
+            let slices: Vec<&[f32]> = vectors.iter().map(Vec::as_slice).collect();
+            self.adapter_config
+                .install(|| inner.build_from_memory(&slices))
+                .map_err(|e| {
+                    StorageError::VectorIndex(VectorIndexError::BuildError(e.to_string()))
+                })?;
+        }
+        let node_to_vector = DashMap::with_capacity(live.len());
+        let vector_to_node = ShardedVectorMap::new(self.vector_to_node.shard_bits)?;
+        vector_to_node.reserve(live.len());
+        for (new_vector_id, &(_, node_id)) in live.iter().enumerate() {
+            node_to_vector.insert(node_id, new_vector_id as u32);
+            vector_to_node.set(new_vector_id as u32, node_id)?;
+        }
+
+        *self.inner.get_mut() = inner;
+        self.index_config = config;
+        self.node_to_vector = node_to_vector;
+        self.vector_to_node = vector_to_node;
+        self.deleted_nodes.clear();
+        // Vector IDs were renumbered, so a delta against the old ones would not replay
+        self.checkpoint = None;
+        self.next_vector_id
+            .store(live.len() as u32, Ordering::Relaxed);
+        *self.quantized.get_mut() = None;
+        if let Some(int8) = self.int8.get_mut() {
+            int8.clear();
+        }
+        if let Some(float16) = self.float16.get_mut() {
+            float16.clear();
+        }
+        self.sync_quantized_codes()?;
+        self.sync_size_stats();
+        self.stats.write().build_time_ms = build_start.elapsed().as_millis() as u64;
+        Ok(())
+    }
+
+    /// `build` reporting each phase to `progress` as it starts, and checking `cancel` between
+    /// phases. A cancelled build fails with `BuildCancelled` and leaves the adapter unbuilt with
+    /// its mappings cleared, as a failed build does; an earlier index is not restored. The
+    /// DiskANN graph construction itself cannot be interrupted, so cancellation during it takes
+    /// effect once it finishes.
+    pub fn build_with_progress(
+        &mut self,
+        vectors: &[(u64, &[f32])],
+        mut progress: impl FnMut(BuildProgress),
+        cancel: &AtomicBool,
+    ) -> StorageResult<()> {
+        let presorted = self.adapter_config.inputs_presorted;
+        self.build_tracked(vectors, &mut progress, Some(cancel), presorted)
+    }
+
+    /// `build` from owned vectors as a source such as a file reader produces them. Each vector's
+    /// dimension and values are checked on arrival, so bad input fails before the rest is read,
+    /// and the buffered vectors are sorted by node ID in place instead of being copied.
+    /// DiskANN builds its graph from all vectors at once, so the source is still drained into
+    /// one buffer first; duplicate node IDs are reported once it is sorted.
+    pub fn build_from_iter<I>(&mut self, vectors: I) -> StorageResult<()>
+    where
+        I: IntoIterator<Item = (u64, Vec<f32>)>,
+    {
+        let vectors = vectors.into_iter();
+        let mut owned: Vec<(u64, Vec<f32>)> = Vec::with_capacity(vectors.size_hint().0);
+        for (node_id, vector) in vectors {
+            if owned.len() == u32::MAX as usize {
+                return Err(Self::vector_count_overflow(owned.len() + 1));
+            }
+            self.check_build_vector(node_id, &vector)?;
+            owned.push((node_id, vector));
+        }
+        owned.sort_unstable_by_key(|(node_id, _)| *node_id);
+        let refs: Vec<(u64, &[f32])> = owned
+            .iter()
+            .map(|(node_id, vector)| (*node_id, vector.as_slice()))
+            .collect();
+        self.build_tracked(&refs, &mut |_| {}, None, true)
+    }
+
+    /// `build` over `vectors`, which must be sorted by node ID when `presorted` is set
+    fn build_tracked(
+        &mut self,
+        vectors: &[(u64, &[f32])],
+        progress: &mut dyn FnMut(BuildProgress),
+        cancel: Option<&AtomicBool>,
+        presorted: bool,
+    ) -> StorageResult<()> {
+        let cancelled = || cancel.is_some_and(|flag| flag.load(Ordering::Acquire));
+        progress(BuildProgress::new(BuildPhase::Validation));
+        self.validate_inputs(vectors, presorted)?;
+        let _building = BuildingGuard::enter(&self.building);
+
+        let normalized = self.normalize_batch(vectors)?;
+        let normalized_refs: Vec<(u64, &[f32])>;
+        let vectors = match &normalized {
+            Some(owned) => {
+                normalized_refs = owned.iter().map(|(id, v)| (*id, v.as_slice())).collect();
+                normalized_refs.as_slice()
+            }
+            None => vectors,
+        };
+        self.check_duplicate_vectors(vectors)?;
+        if cancelled() {
+            return Err(self.abandon_build());
+        }
+
+        self.clear_mappings();
+        self.reserve(vectors.len());
+
+        if vectors.is_empty() {
+            // Drop the vectors of any previous build so no stale data backs the new vector IDs
+            *self.inner.get_mut() = self.fresh_inner(0)?;
+            self.built = true;
+            self.provenance = Some(IndexProvenance::capture(
+                &self.index_config,
+                &self.adapter_config,
+            ));
+            self.sync_size_stats();
+            progress(BuildProgress::new(BuildPhase::Done));
+            return Ok(());
+        }
+
+        // Build order as positions in `vectors`, so sorting moves IDs rather than the pairs
+        let mut order: Vec<u32> = (0..vectors.len() as u32).collect();
+        if !presorted {
+            order.sort_by_key(|&position| vectors[position as usize].0);
+        }
+
+        // Note: Removed max_points capacity check to rely on DiskANN's internal capacity management
+        //
+        // DiskANN Capacity Management:
+        // - growth_potential is a PRE-ALLOCATION multiplier, not dynamic expansion
+        // - Physical capacity = max_points × growth_potential (set at initialization)
+        // - Once physical capacity is reached, no more vectors can be inserted
+        // - This is the correct behavior - DiskANN has fixed pre-allocated memory
+
+        progress(BuildProgress::new(BuildPhase::Mapping));
+        // Establish ID mappings BEFORE calling DiskANN; IDs were validated up front.
+        // DiskANN will assign vector_id = array_index
+        for (array_index, &position) in order.iter().enumerate() {
+            let vector_id = array_index as u32;
+            let node_id = vectors[position as usize].0;
+            self.node_to_vector.insert(node_id, vector_id);
+            if let Err(e) = self.vector_to_node.set(vector_id, node_id) {
+                self.clear_mappings();
+                return Err(e);
+            }
+        }
+
+        if cancelled() {
+            return Err(self.abandon_build());
+        }
+
+        // Extract vector slices directly (no conversion needed)
+        let vector_slices: Vec<&[f32]> = order
+            .iter()
+            .map(|&position| vectors[position as usize].1)
+            .collect();
+
+        progress(BuildProgress::new(BuildPhase::GraphConstruction));
+        let build_start = Instant::now();
+        let inner = self.inner.get_mut();
+        let build_result = self
+            .adapter_config
+            .install(|| inner.build_from_memory(&vector_slices));
+        match build_result {
+            Ok(()) if cancelled() => Err(self.abandon_build()),
+            Ok(()) => {
+                progress(BuildProgress::new(BuildPhase::Finalization));
+                self.next_vector_id
+                    .store(vectors.len() as u32, Ordering::Relaxed);
+                self.built = true;
+                self.provenance = Some(IndexProvenance::capture(
+                    &self.index_config,
+                    &self.adapter_config,
+                ));
+
+                self.sync_quantized_codes()?;
+                self.sync_size_stats();
+                self.stats.write().build_time_ms = build_start.elapsed().as_millis() as u64;
+                progress(BuildProgress::new(BuildPhase::Done));
+                Ok(())
+            }
+            Err(e) => {
+                self.clear_mappings();
+                self.built = false;
+                Err(StorageError::VectorIndex(VectorIndexError::BuildError(
+                    e.to_string(),
+                )))
+            }
+        }
+    }
+
+    /// Reset to the state of a failed build after a cancellation
+    fn abandon_build(&mut self) -> StorageError {
+        self.clear_mappings();
+        self.built = false;
+        self.sync_size_stats();
+        StorageError::VectorIndex(VectorIndexError::BuildCancelled)
+    }
+
+    /// Estimated resident bytes of the index, as kept in [`IndexStats::memory_usage`]
+    pub fn memory_usage(&self) -> usize {
+        self.stats.read().memory_usage
+    }
+
+    /// DiskANN slots (deleted ones keep their data), padded to `aligned_dim`, plus the
+    /// allocated capacity of the ID mappings
+    fn estimated_memory_usage(&self) -> usize {
+        let allocated_slots = self.next_vector_id.load(Ordering::Relaxed) as usize;
+        allocated_slots * self.slot_bytes()
+            + self.node_to_vector.capacity() * std::mem::size_of::<(u64, u32)>()
+            + self.vector_to_node.capacity() * std::mem::size_of::<Option<u64>>()
+            + self.deleted_nodes.capacity() * std::mem::size_of::<(u64, (u32, Instant))>()
+            + self
+                .quantized
+                .read()
+                .as_ref()
+                .map_or(0, QuantizedVectors::memory_bytes)
+            + self
+                .int8
+                .read()
+                .as_ref()
+                .map_or(0, Int8Vectors::memory_bytes)
+            + self
+                .float16
+                .read()
+                .as_ref()
+                .map_or(0, Float16Vectors::memory_bytes)
+            + self
+                .norms
+                .read()
+                .as_ref()
+                .map_or(0, |norms| norms.capacity() * std::mem::size_of::<f32>())
+    }
+
+    /// Estimated bytes DiskANN holds per slot: aligned vector data plus a full adjacency list
+    fn slot_bytes(&self) -> usize {
+        let max_degree = self.index_config.index_write_parameter.max_degree as usize;
+        self.index_config.aligned_dim * std::mem::size_of::<f32>()
+            + max_degree * std::mem::size_of::<u32>()
+    }
+
+    /// Owned, unpadded copy of the vector stored for `node_id`, or `None` if the node is not
+    /// indexed; for debugging recall. Fails with `IndexNotBuilt` before the first build or load.
+    pub fn get_vector(&self, node_id: u64) -> StorageResult<Option<Vec<f32>>> {
+        if !self.built {
+            return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
+        }
+        self.node_to_vector_id(node_id)
+            .map(|vector_id| self.stored_vector(vector_id))
+            .transpose()
+    }
+
+    /// Stored vector of each node, or `None` for nodes not in the index. With
+    /// `normalize_vectors` the stored vectors are the normalized ones.
+    pub fn get_vectors(&self, node_ids: &[u64]) -> StorageResult<Vec<Option<Vec<f32>>>> {
+        node_ids
+            .iter()
+            .map(|&node_id| {
+                self.node_to_vector_id(node_id)
+                    .map(|vector_id| self.stored_vector(vector_id))
+                    .transpose()
+            })
+            .collect()
+    }
+
+    /// Iterate over the `(node_id, vector)` pairs of all live vectors, in no particular order.
+    /// Vectors are copied out of the index one at a time.
+    pub fn iter_vectors(&self) -> impl Iterator<Item = StorageResult<(u64, Vec<f32>)>> + '_ {
+        self.node_to_vector.iter().map(|entry| {
+            let (node_id, vector_id) = (*entry.key(), *entry.value());
+            Ok((node_id, self.stored_vector(vector_id)?))
+        })
+    }
+
+    /// Min, max, mean and variance of every dimension over the live vectors, for spotting dead
+    /// or saturated dimensions left by a broken embedding pipeline. Empty when the index holds
+    /// no vectors.
+    ///
+    /// Computed on demand by scanning every live vector, which is O(n * d): meant for periodic
+    /// data-quality checks, not for use on the query path.
+    pub fn dimension_stats(&self) -> StorageResult<Vec<DimStat>> {
+        let mut stats = vec![
+            DimStat {
+                min: f32::INFINITY,
+                max: f32::NEG_INFINITY,
+                ..Default::default()
+            };
+            self.dimension
+        ];
+        // Welford's online algorithm; `variance` holds the sum of squared deviations until the
+        // final division
+        let mut count = 0u64;
+        for item in self.iter_vectors() {
+            let (_, vector) = item?;
+            count += 1;
+            for (stat, &x) in stats.iter_mut().zip(&vector) {
+                stat.min = stat.min.min(x);
+                stat.max = stat.max.max(x);
+                let delta = x as f64 - stat.mean;
+                stat.mean += delta / count as f64;
+                stat.variance += delta * (x as f64 - stat.mean);
+            }
+        }
+        if count == 0 {
+            return Ok(Vec::new());
+        }
+        for stat in &mut stats {
+            stat.variance /= count as f64;
+        }
+        Ok(stats)
+    }
+
+    /// Two-pass search with pseudo-relevance feedback: the vectors of the top `m` results of an
+    /// initial search are averaged, blended with the query as
+    /// `alpha * query + (1 - alpha) * centroid`, and the blend is searched for `k` results.
+    ///
+    /// `alpha` must lie in `[0, 1]`; `alpha == 1.0` or `m == 0` degrade to a plain search. Both
+    /// passes use `l_value` and `filter_mask`, so `m` must not exceed `l_value`. When
+    /// `normalize_vectors` is set the blend is taken between unit vectors.
+    pub fn search_with_prf(
+        &self,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        m: usize,
+        alpha: f32,
+        filter_mask: Option<&dyn SearchFilter>,
+    ) -> StorageResult<Vec<(u64, f32)>> {
+        if !(0.0..=1.0).contains(&alpha) {
+            return Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(
+                format!("PRF blending weight alpha must be within [0, 1], got {alpha}"),
+            )));
+        }
+        if m == 0 || alpha == 1.0 {
+            return self.search(query, k, l_value, filter_mask, false);
+        }
+
+        let feedback_l = l_value.max(m.min(u32::MAX as usize) as u32);
+        let feedback = self.search(query, m, feedback_l, filter_mask, false)?;
+        if feedback.is_empty() {
+            return Ok(feedback);
+        }
+        let Some(query) = self.prepare_query(query)? else {
+            return Ok(Vec::new());
+        };
+
+        let node_ids: Vec<u64> = feedback.iter().map(|(node_id, _)| *node_id).collect();
+        let mut centroid = vec![0.0f32; self.dimension];
+        let mut feedback_count = 0usize;
+        for vector in self.get_vectors(&node_ids)?.into_iter().flatten() {
+            for (sum, x) in centroid.iter_mut().zip(&vector) {
+                *sum += x;
+            }
+            feedback_count += 1;
+        }
+        if feedback_count == 0 {
+            return self.search(&query, k, l_value, filter_mask, false);
+        }
+
+        let scale = (1.0 - alpha) / feedback_count as f32;
+        let expanded: Vec<f32> = query
+            .iter()
+            .zip(&centroid)
+            .map(|(q, sum)| alpha * q + scale * sum)
+            .collect();
+        self.search(&expanded, k, l_value, filter_mask, false)
+    }
+
+    /// Search for `k` results that survive `post_process`, which may drop or reorder the
+    /// distance-sorted candidates it is given. Candidates are over-fetched by the configured
+    /// `post_process_overfetch` multiplier, doubling the fetch while fewer than `k` survive and
+    /// the index can still return more. `l_value` is raised to the fetch size when needed.
+    pub fn search_post_processed(
+        &self,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter_mask: Option<&dyn SearchFilter>,
+        post_process: impl Fn(Vec<(u64, f32)>) -> Vec<(u64, f32)>,
+    ) -> StorageResult<Vec<(u64, f32)>> {
+        if k == 0 {
+            return Ok(Vec::new());
+        }
+        let available = self.size().max(k);
+        let mut fetch = ((k as f64 * self.adapter_config.effective_overfetch() as f64).ceil()
+            as usize)
+            .min(available);
+        loop {
+            let search_l = l_value.max(fetch.min(u32::MAX as usize) as u32);
+            let candidates = self.search(query, fetch, search_l, filter_mask, false)?;
+            let exhausted = candidates.len() < fetch || fetch >= available;
+            let mut results = post_process(candidates);
+            if results.len() >= k || exhausted {
+                results.truncate(k);
+                return Ok(results);
+            }
+            fetch = fetch.saturating_mul(2).min(available);
+        }
+    }
+
+    /// Run `search` for every query, returning node IDs in the order of `queries`.
+    ///
+    /// Queries run in parallel on the configured thread pool (or the caller's rayon pool);
+    /// like `search`, this only needs `&self`, so batches may also run concurrently with other
+    /// searches. Statistics are accumulated locally and written with one lock acquisition
+    /// after the batch. The first failing query, in input order, fails the batch.
+    pub fn batch_search(
+        &self,
+        queries: &[&[f32]],
+        k: usize,
+        l_value: u32,
+        filter_mask: Option<&dyn SearchFilter>,
+    ) -> StorageResult<Vec<Vec<u64>>> {
+        self.check_not_rebuilding()?;
+        let outcomes: Vec<_> = self.adapter_config.install(|| {
+            queries
+                .par_iter()
+                .map(|query| {
+                    let started = Instant::now();
+                    self.search_uncounted(query, k, l_value, filter_mask, false)
+                        .map(|(results, path, visited)| (results, path, visited, started.elapsed()))
+                })
+                .collect()
+        });
+
+        {
+            let mut stats = self.stats.write();
+            for (_, path, visited, elapsed) in outcomes.iter().flatten() {
+                path.count(&mut stats, *elapsed, *visited);
+            }
+        }
+
+        outcomes
+            .into_iter()
+            .map(|outcome| {
+                outcome.map(|(results, _, _, _)| {
+                    results.into_iter().map(|(node_id, _)| node_id).collect()
+                })
+            })
+            .collect()
+    }
+
+    /// The strategy selection of `search`, leaving the statistics to the caller. Also returns
+    /// the path taken and the number of candidates visited.
+    #[allow(clippy::type_complexity)]
+    fn search_uncounted(
+        &self,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter_mask: Option<&dyn SearchFilter>,
+        should_pre: bool,
+    ) -> StorageResult<(Vec<(u64, f32)>, SearchPath, usize)> {
+        if !self.built {
+            return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
+        }
+        self.check_query_dimension(query)?;
+        Self::check_search_params(k, l_value)?;
+        if filter_mask.is_some_and(|mask| mask.candidate_count() == 0) {
+            return Ok((Vec::new(), SearchPath::Empty, 0));
+        }
+        let Some(query) = self.prepare_query(query)? else {
+            return Ok((Vec::new(), SearchPath::Skipped, 0));
+        };
+        let query = query.as_ref();
+
+        // No filter provided, DiskANN search without filter
+        let Some(mask) = filter_mask else {
+            let (results, visited) = self.unfiltered_search(query, k, l_value, should_pre)?;
+            self.record_returned(&results);
+            return Ok((results, SearchPath::Ann, visited));
+        };
+
+        let path = self.filter_strategy(mask);
+        let path = match path {
+            SearchPath::BruteForce if self.quantized.read().is_some() => {
+                SearchPath::QuantizedBruteForce
+            }
+            SearchPath::BruteForce if self.int8.read().is_some() => SearchPath::Int8BruteForce,
+            SearchPath::BruteForce if self.float16.read().is_some() => {
+                SearchPath::Float16BruteForce
+            }
+            path => path,
+        };
+        // Brute-force paths score every live candidate of the mask
+        let (results, visited) = match path {
+            SearchPath::BruteForce => (
+                self.brute_force_search(query, k, mask)?,
+                mask.candidate_count(),
+            ),
+            SearchPath::QuantizedBruteForce => (
+                self.quantized_brute_force_search(query, k, mask)?,
+                mask.candidate_count(),
+            ),
+            SearchPath::Int8BruteForce => (
+                self.int8_brute_force_search(query, k, mask)?,
+                mask.candidate_count(),
+            ),
+            SearchPath::Float16BruteForce => (
+                self.float16_brute_force_search(query, k, mask)?,
+                mask.candidate_count(),
+            ),
+            SearchPath::PreFilter => self.guided_brute_force_search(query, k, l_value, mask)?,
+            _ => self.filter_search(query, k, l_value, mask, should_pre)?,
+        };
+        self.record_returned(&results);
+        Ok((results, path, visited))
+    }
+
+    /// Strategy for a filtered search by the selectivity of `mask`: brute force over the
+    /// candidates for very selective filters, a guided scan of the candidates in the mid range,
+    /// and post-filtered traversal for broad filters
+    fn filter_strategy(&self, mask: &dyn SearchFilter) -> SearchPath {
+        let selectivity = mask.selectivity();
+        let tuning = &self.adapter_config.tuning;
+        if selectivity < tuning.brute_force_selectivity {
+            SearchPath::BruteForce
+        } else if selectivity < tuning.pre_filter_selectivity {
+            SearchPath::PreFilter
+        } else {
+            SearchPath::Ann
+        }
+    }
+
+    /// Search for `k` results, never returning any of `excluded` (e.g. items a user has already
+    /// seen). Over-fetches as described on [`search_post_processed`].
+    ///
+    /// [`search_post_processed`]: Self::search_post_processed
+    pub fn search_excluding(
+        &self,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter_mask: Option<&dyn SearchFilter>,
+        excluded: &[u64],
+    ) -> StorageResult<Vec<(u64, f32)>> {
+        let excluded: std::collections::HashSet<u64> = excluded.iter().copied().collect();
+        self.search_post_processed(query, k, l_value, filter_mask, |mut results| {
+            results.retain(|(node_id, _)| !excluded.contains(node_id));
+            results
+        })
+    }
+
+    /// Return the natural cluster of close matches: up to `max_results` nearest neighbors, cut
+    /// before the first distance that exceeds its predecessor by more than `jump_factor`, but
+    /// never below `min_results` (fewer only if the index has fewer matches).
+    ///
+    /// A jump from distance 0 is not a ratio and never cuts, so exact duplicates of the query
+    /// stay together with their nearest distinct neighbors. `jump_factor` must be at least 1.0
+    /// and `min_results <= max_results`.
+    pub fn search_knee(
+        &self,
+        query: &[f32],
+        l_value: u32,
+        min_results: usize,
+        max_results: usize,
+        jump_factor: f32,
+        filter_mask: Option<&dyn SearchFilter>,
+    ) -> StorageResult<Vec<(u64, f32)>> {
+        if !jump_factor.is_finite() || jump_factor < 1.0 {
+            return Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidSearchParams(format!(
+                    "jump_factor must be a finite value of at least 1.0, got {jump_factor}"
+                )),
+            ));
+        }
+        if min_results > max_results {
+            return Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidSearchParams(format!(
+                    "min_results ({min_results}) exceeds max_results ({max_results})"
+                )),
+            ));
+        }
+        if max_results == 0 {
+            return Ok(Vec::new());
+        }
+
+        let search_l = l_value.max(max_results.min(u32::MAX as usize) as u32);
+        let mut results = self.search(query, max_results, search_l, filter_mask, false)?;
+        let cut = results
+            .windows(2)
+            .enumerate()
+            .skip(min_results.saturating_sub(1))
+            .find(|(_, pair)| pair[0].1 > 0.0 && pair[1].1 > pair[0].1 * jump_factor)
+            .map_or(results.len(), |(index, _)| index + 1);
+        results.truncate(cut);
+        Ok(results)
+    }
+
+    /// Search with an intent-based [`Quality`] level instead of a raw `l_value`
+    pub fn search_quality(
+        &self,
+        query: &[f32],
+        k: usize,
+        quality: Quality,
+        filter_mask: Option<&dyn SearchFilter>,
+    ) -> StorageResult<Vec<(u64, f32)>> {
+        let l_value = quality.l_value(k, self.size());
+        self.search(query, k, l_value, filter_mask, false)
+    }
+
+    /// Tiered search: search under each filter in order, keeping earlier (stricter) matches and
+    /// using later filters only to fill the remaining slots, until `k` unique results are found
+    /// or the filters are exhausted. Results are grouped by filter tier, each tier in ascending
+    /// distance order.
+    pub fn search_cascade(
+        &self,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filters: &[&dyn SearchFilter],
+    ) -> StorageResult<Vec<(u64, f32)>> {
+        let mut results: Vec<(u64, f32)> = Vec::with_capacity(k);
+        let mut seen = std::collections::HashSet::with_capacity(k);
+
+        for filter in filters {
+            if results.len() >= k {
+                break;
+            }
+            // Search for the full k so overlap with earlier tiers cannot starve this tier
+            for (node_id, distance) in self.search(query, k, l_value, Some(*filter), false)? {
+                if results.len() >= k {
+                    break;
+                }
+                if seen.insert(node_id) {
+                    results.push((node_id, distance));
+                }
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Unfiltered DiskANN search returning internal vector IDs instead of node IDs.
+    ///
+    /// Vector IDs are DiskANN slot numbers; they are not stable across a rebuild or compaction
+    /// and must not be stored. Results skip the node ID mapping entirely, which makes this
+    /// useful for debugging the graph independently of the mapping layer. Not counted in
+    /// [`IndexStats`].
+    pub fn ann_search_vector_ids(
+        &self,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+    ) -> StorageResult<Vec<(u32, f32)>> {
+        self.check_query_dimension(query)?;
+        Self::check_search_params(k, l_value)?;
+        let Some(query) = self.prepare_query(query)? else {
+            return Ok(Vec::new());
+        };
+        self.diskann_search_raw(&query, k, l_value, None, false)
+    }
+
+    /// Cheap recall proxy: the mean top-`k` overlap between searches at `l_value` and at
+    /// `2 * l_value`, over `queries`.
+    ///
+    /// This measures convergence, not ground-truth recall: a value near 1.0 means widening the
+    /// search list no longer changes the results, which usually (but not necessarily) means
+    /// they are the true neighbors. Use [`compare_indexes`] against a brute-force index when
+    /// real recall is needed. Returns 1.0 for an empty query set; searches are not counted in
+    /// [`IndexStats`]. Every query and the parameters are validated as for a regular search
+    /// before any is run.
+    ///
+    /// [`compare_indexes`]: super::compare_indexes
+    pub fn estimate_recall_selfcheck(
+        &self,
+        queries: &[&[f32]],
+        k: usize,
+        l_value: u32,
+    ) -> StorageResult<f64> {
+        Self::check_search_params(k, l_value)?;
+        for query in queries {
+            self.check_query_dimension(query)?;
+        }
+        if queries.is_empty() {
+            return Ok(1.0);
+        }
+        let wide_l_value = l_value.saturating_mul(2);
+        let mut total_overlap = 0.0f64;
+        for query in queries {
+            let Some(query) = self.prepare_query(query)? else {
+                total_overlap += 1.0;
+                continue;
+            };
+            let narrow = self.diskann_search(&query, k, l_value, None, false)?;
+            let wide = self.diskann_search(&query, k, wide_l_value, None, false)?;
+            if wide.is_empty() {
+                total_overlap += 1.0;
+                continue;
+            }
+            let narrow_ids: std::collections::HashSet<u64> =
+                narrow.iter().map(|(node_id, _)| *node_id).collect();
+            let shared = wide
+                .iter()
+                .filter(|(node_id, _)| narrow_ids.contains(node_id))
+                .count();
+            total_overlap += shared as f64 / wide.len() as f64;
+        }
+        Ok(total_overlap / queries.len() as f64)
+    }
+
+    /// Mean recall@`k` of unfiltered ANN searches at `l_value` against `ground_truth`, which
+    /// holds the expected node IDs of each query (e.g. from [`brute_force_ground_truth`]) and
+    /// is truncated to `k`. A query whose truth is empty counts as 1.0, as does an empty query
+    /// set. Runs the graph search of `ann_search` without counting it in [`IndexStats`], so
+    /// `l_value` can be swept freely. Every query and the parameters are validated as for a
+    /// regular search before any is run.
+    ///
+    /// [`brute_force_ground_truth`]: Self::brute_force_ground_truth
+    pub fn evaluate_recall(
+        &self,
+        queries: &[Vec<f32>],
+        ground_truth: &[Vec<u64>],
+        k: usize,
+        l_value: u32,
+    ) -> StorageResult<f64> {
+        if queries.len() != ground_truth.len() {
+            return Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(
+                format!(
+                    "{} queries but ground truth for {}",
+                    queries.len(),
+                    ground_truth.len()
+                ),
+            )));
+        }
+        Self::check_search_params(k, l_value)?;
+        for query in queries {
+            self.check_query_dimension(query)?;
+        }
+        if queries.is_empty() {
+            return Ok(1.0);
+        }
+        let mut total_recall = 0.0f64;
+        for (query, truth) in queries.iter().zip(ground_truth) {
+            let truth = &truth[..truth.len().min(k)];
+            if truth.is_empty() {
+                total_recall += 1.0;
+                continue;
+            }
+            let found: std::collections::HashSet<u64> = match self.prepare_query(query)? {
+                Some(query) => self
+                    .diskann_search(&query, k, l_value, None, false)?
+                    .into_iter()
+                    .map(|(node_id, _)| node_id)
+                    .collect(),
+                None => std::collections::HashSet::new(),
+            };
+            let hits = truth
+                .iter()
+                .filter(|node_id| found.contains(node_id))
+                .count();
+            total_recall += hits as f64 / truth.len() as f64;
+        }
+        Ok(total_recall / queries.len() as f64)
+    }
+
+    /// Fault in the pages a first search would otherwise pay for, e.g. at service startup after
+    /// a build or load: reads every stored vector and both ID mappings, then runs
+    /// `sample_queries` graph searches from evenly spaced live vectors. Nothing is written:
+    /// searches are not counted in [`IndexStats`] or result frequencies, and the search seed
+    /// stream is not advanced. A no-op on an unbuilt or empty index.
+    pub fn warmup(&self, sample_queries: usize) -> StorageResult<()> {
+        let live: Vec<u32> = self
+            .node_to_vector
+            .iter()
+            .map(|entry| *entry.value())
+            .collect();
+        if !self.built || live.is_empty() {
+            return Ok(());
+        }
+
+        let slot_count = self.next_vector_id.load(Ordering::Acquire);
+        let mut checksum = 0.0f32;
+        {
+            let inner = self.inner.read_recursive();
+            for vector_id in 0..slot_count {
+                let data = inner
+                    .get_aligned_vector_data(vector_id)
+                    .map_err(|e| StorageError::VectorIndex(VectorIndexError::DiskANN(e)))?;
+                checksum += data[0];
+                if let Some(node_id) = self.vector_to_node.get(vector_id) {
+                    checksum += node_id as f32;
+                }
+            }
+        }
+        std::hint::black_box(checksum);
+
+        let k = WARMUP_K.min(live.len());
+        let l_value = Quality::Balanced.l_value(k, live.len());
+        let step = live.len().div_ceil(sample_queries.max(1));
+        for &vector_id in live.iter().step_by(step).take(sample_queries) {
+            let query = self.stored_vector(vector_id)?;
+            std::hint::black_box(self.diskann_search(&query, k, l_value, None, false)?);
+        }
+        Ok(())
+    }
+
+    /// Node IDs of the exact `k` nearest live vectors to `query`, closest first, by scanning
+    /// every slot; for [`evaluate_recall`](Self::evaluate_recall). Not counted in
+    /// [`IndexStats`].
+    pub fn brute_force_ground_truth(&self, query: &[f32], k: usize) -> StorageResult<Vec<u64>> {
+        if !self.built {
+            return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
+        }
+        self.check_query_dimension(query)?;
+        let Some(query) = self.prepare_query(query)? else {
+            return Ok(Vec::new());
+        };
+        let aligned_query = Self::ensure_query_aligned(&query)?;
+        let mut heap = BinaryHeap::with_capacity(k.min(self.mapping_count()));
+        if k > 0 {
+            let slot_count = self.next_vector_id.load(Ordering::Acquire);
+            self.scan_candidates(aligned_query.as_slice(), k, 0..slot_count, &mut heap)?;
+        }
+        Ok(heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(_, node_id)| node_id)
+            .collect())
+    }
+
+    /// Every node within `radius` of `query`, closest first, e.g. for near-duplicate detection.
+    ///
+    /// `radius` is in the units of the index's distances: squared L2, or `2 * (1 - cos)` for
+    /// cosine indexes (see [`VectorIndex::ann_search`]). Filters selective enough for brute
+    /// force are scanned in full, over the same vector storage as [`VectorIndex::search`];
+    /// otherwise DiskANN is searched with a doubling `k` until the
+    /// furthest neighbor returned lies outside the radius or the index is exhausted, so like any
+    /// graph search the result may miss some in-range nodes. Counts as one search in the stats.
+    /// A negative radius matches nothing; a NaN radius is rejected.
+    pub fn range_search(
+        &self,
+        query: &[f32],
+        radius: f32,
+        l_value: u32,
+        filter_mask: Option<&dyn SearchFilter>,
+    ) -> StorageResult<Vec<(u64, f32)>> {
+        if radius.is_nan() {
+            return Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidSearchParams("radius must not be NaN".to_string()),
+            ));
+        }
+        self.check_not_rebuilding()?;
+        if !self.built {
+            return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
+        }
+        self.check_query_dimension(query)?;
+        let started = Instant::now();
+
+        // A filtered search returns at most the filter's candidates; a brute-force filter is
+        // scanned, through the configured vector storage, in one pass over all of them
+        let available =
+            filter_mask.map_or(self.size(), |mask| self.size().min(mask.candidate_count()));
+        let mut k = match filter_mask {
+            Some(mask) if matches!(self.filter_strategy(mask), SearchPath::BruteForce) => {
+                available.max(1)
+            }
+            _ => (l_value as usize).clamp(1, available.max(1)),
+        };
+        let mut total_visited = 0;
+        loop {
+            let search_l = l_value.max(k.min(u32::MAX as usize) as u32);
+            let (mut results, path, visited) =
+                self.search_uncounted(query, k, search_l, filter_mask, false)?;
+            total_visited += visited;
+            let exhausted = results.len() < k || k >= available;
+            let beyond_radius = results.last().is_some_and(|&(_, d)| d > radius);
+            if exhausted || beyond_radius {
+                path.count(&mut self.stats.write(), started.elapsed(), total_visited);
+                results.retain(|&(_, distance)| distance <= radius);
+                return Ok(results);
+            }
+            k = k.saturating_mul(2).min(available);
+        }
+    }
+
+    /// Up to `k` nearest neighbors of `query`, closest first, dropping any further than
+    /// `max_distance`: a [`VectorIndex::search`] whose results are cut at the threshold, so
+    /// between 0 and `k` nodes are returned and an empty result is not an error.
+    ///
+    /// `max_distance` is in the units of the index's distances, as for
+    /// [`range_search`](Self::range_search): squared L2, or `2 * (1 - cos)` for cosine indexes.
+    /// Unlike `range_search` the search depth stays at `k`. A negative cutoff matches nothing; a
+    /// NaN cutoff is rejected.
+    pub fn search_with_cutoff(
+        &self,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        max_distance: f32,
+        filter_mask: Option<&dyn SearchFilter>,
+    ) -> StorageResult<Vec<(u64, f32)>> {
+        if max_distance.is_nan() {
+            return Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidSearchParams("max_distance must not be NaN".to_string()),
+            ));
+        }
+        let mut results = self.search(query, k, l_value, filter_mask, false)?;
+        // Results are sorted by distance, so the cutoff is a prefix
+        let within = results.partition_point(|&(_, distance)| distance <= max_distance);
+        results.truncate(within);
+        Ok(results)
+    }
+
+    /// Distance from `query` to its `k`-th nearest neighbor, or `None` if fewer than `k` vectors
+    /// match. Runs [`VectorIndex::search`], so the query is validated, the search counted and
+    /// repeated nodes dropped exactly as there.
+    pub fn kth_distance(
+        &self,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter_mask: Option<&dyn SearchFilter>,
+    ) -> StorageResult<Option<f32>> {
+        if k == 0 {
+            return Ok(None);
+        }
+        let results = self.search(query, k, l_value, filter_mask, false)?;
+        Ok(results.get(k - 1).map(|(_, distance)| *distance))
+    }
+
+    /// Start a background thread pushing a stats snapshot to `sink` every `interval`.
+    /// Any previously running reporter is stopped first.
+    pub fn start_stats_reporter(&self, interval: Duration, sink: StatsSink) -> StorageResult<()> {
+        if interval.is_zero() {
+            return Err(StorageError::VectorIndex(VectorIndexError::Configuration(
+                "Stats reporter interval must be greater than zero".to_string(),
+            )));
+        }
+        let reporter = StatsReporter::spawn(Arc::clone(&self.stats), interval, sink);
+        // Dropping the previous reporter joins its thread
+        let previous = self.stats_reporter.lock().replace(reporter);
+        drop(previous);
+        Ok(())
+    }
+
+    /// Write the changes since the last checkpoint to `path`: the vectors and node IDs of every
+    /// slot allocated since, and the earlier slots deleted since. Much cheaper than `save` for
+    /// append-heavy workloads, whose deltas hold little more than the new vectors.
+    ///
+    /// The checkpoint is the last `save`, `load` or `save_delta`, so consecutive deltas form a
+    /// numbered chain on top of one base snapshot, replayed by [`load_with_deltas`]. `save`
+    /// starts a new chain; builds, compactions and node ID remaps discard the checkpoint, after
+    /// which only `save` is possible.
+    ///
+    /// [`load_with_deltas`]: Self::load_with_deltas
+    pub fn save_delta(&mut self, path: &str) -> StorageResult<()> {
+        let Some(checkpoint) = &self.checkpoint else {
+            return Err(StorageError::VectorIndex(VectorIndexError::Persistence(
+                "save_delta needs a base snapshot: call save or load first".to_string(),
+            )));
+        };
+        let first_slot = checkpoint.slot_count();
+        let slot_count = self.next_vector_id.load(Ordering::Relaxed);
+        let (slots, vectors) = self.persisted_slots(first_slot..slot_count)?;
+        let deleted = checkpoint
+            .live
+            .iter_ones()
+            .map(|vector_id| vector_id as u32)
+            .filter(|&vector_id| !self.vector_to_node.contains_key(vector_id))
+            .collect();
+        let delta = PersistedDelta {
+            base_checksum: checkpoint.base_checksum,
+            sequence: checkpoint.sequence + 1,
+            dimension: self.dimension,
+            first_slot,
+            slots,
+            vectors,
+            deleted,
+            stats: self.stats.read().clone(),
+        };
+        write_persisted(path, DELTA_MAGIC, DELTA_VERSION, &delta)?;
+        if let Some(checkpoint) = &mut self.checkpoint {
+            checkpoint.advance(&delta);
+        }
+        Ok(())
+    }
+
+    /// Apply the delta at `path` on top of the loaded index. The delta must come from the
+    /// snapshot last loaded (or saved) and immediately follow the last delta applied, and the
+    /// index must not have changed since; gaps, reordering and foreign deltas are rejected
+    /// before anything is modified. Stats are restored from the delta like `load` does.
+    pub fn load_delta(&mut self, path: &str) -> StorageResult<()> {
+        let (delta, _): (PersistedDelta, u32) =
+            read_persisted(path, DELTA_MAGIC, DELTA_VERSION, "delta")?;
+        let persistence_error =
+            |message: String| StorageError::VectorIndex(VectorIndexError::Persistence(message));
+        let Some(checkpoint) = &self.checkpoint else {
+            return Err(persistence_error(format!(
+                "{path} can only be applied after loading its base snapshot"
+            )));
+        };
+        if delta.base_checksum != checkpoint.base_checksum {
+            return Err(persistence_error(format!(
+                "{path} was taken from a different base snapshot"
+            )));
+        }
+        if delta.sequence != checkpoint.sequence + 1 {
+            return Err(persistence_error(format!(
+                "{path} is delta {} but the index expects delta {}; deltas must be applied in \
+                 order without gaps",
+                delta.sequence,
+                checkpoint.sequence + 1
+            )));
+        }
+        if delta.dimension != self.dimension {
+            return Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension {
+                    expected: self.dimension,
+                    actual: delta.dimension,
+                },
+            ));
+        }
+        let slot_count = self.next_vector_id.load(Ordering::Relaxed);
+        if delta.first_slot != checkpoint.slot_count() || delta.first_slot != slot_count {
+            return Err(persistence_error(format!(
+                "{path} starts at vector slot {} but the index has {slot_count} slots; it \
+                 changed since its last checkpoint",
+                delta.first_slot
+            )));
+        }
+        if delta.vectors.len() != delta.slots.len() * self.dimension
+            || delta.slots.len() > (u32::MAX - slot_count) as usize
+        {
+            return Err(persistence_error(
+                "Delta vector data does not match its slot count".to_string(),
+            ));
+        }
+        let mut deleted_nodes = std::collections::HashSet::with_capacity(delta.deleted.len());
+        for &vector_id in &delta.deleted {
+            match self.vector_to_node.get(vector_id) {
+                Some(node_id) if checkpoint.live[vector_id as usize] => {
+                    deleted_nodes.insert(node_id);
+                }
+                _ => {
+                    return Err(persistence_error(format!(
+                        "{path} deletes vector slot {vector_id}, which is not live"
+                    )));
+                }
+            }
+        }
+        for node_id in delta.slots.iter().flatten() {
+            if self.node_to_vector.contains_key(node_id) && !deleted_nodes.contains(node_id) {
+                return Err(StorageError::VectorIndex(
+                    VectorIndexError::DuplicateNodeId { node_id: *node_id },
+                ));
+            }
+        }
+
+        let vector_slices: Vec<&[f32]> = delta.vectors.chunks(self.dimension).collect();
+        if slot_count == 0 && !vector_slices.is_empty() {
+            // The base was empty, so there is no graph to insert into yet
+            let mut fresh = self.fresh_inner(vector_slices.len())?;
+            self.adapter_config
+                .install(|| fresh.build_from_memory(&vector_slices))
+                .map_err(|e| {
+                    StorageError::VectorIndex(VectorIndexError::BuildError(e.to_string()))
+                })?;
+            *self.inner.get_mut() = fresh;
+        } else if !vector_slices.is_empty() {
+            let inner = &mut **self.inner.get_mut();
+            self.adapter_config
+                .install(|| inner.insert_from_memory(&vector_slices))
+                .map_err(|e| {
+                    StorageError::VectorIndex(VectorIndexError::BuildError(e.to_string()))
+                })?;
+        }
+        let mut to_delete = delta.deleted.clone();
+        to_delete.extend(
+            (0..delta.slots.len() as u32)
+                .filter(|&offset| delta.slots[offset as usize].is_none())
+                .map(|offset| slot_count + offset),
+        );
+        if !to_delete.is_empty() {
+            let count = to_delete.len();
+            self.inner
+                .get_mut()
+                .soft_delete(to_delete, count)
+                .map_err(|e| StorageError::VectorIndex(VectorIndexError::DiskANN(e)))?;
+        }
+
+        for node_id in self.vector_to_node.batch_soft_delete(&delta.deleted)? {
+            self.node_to_vector.remove(&node_id);
+        }
+        for (offset, node_id) in delta.slots.iter().enumerate() {
+            if let Some(node_id) = node_id {
+                let vector_id = slot_count + offset as u32;
+                self.vector_to_node.set(vector_id, *node_id)?;
+                self.node_to_vector.insert(*node_id, vector_id);
+            }
+        }
+        self.next_vector_id
+            .store(slot_count + delta.slots.len() as u32, Ordering::Release);
+        if let Some(checkpoint) = &mut self.checkpoint {
+            checkpoint.advance(&delta);
+        }
+        self.restore_stats(delta.stats);
+        self.sync_quantized_codes()?;
+        self.sync_size_stats();
+        Ok(())
+    }
+
+    /// [`load`](VectorIndex::load) the base snapshot at `base`, then apply each of `deltas` in
+    /// order with [`load_delta`](Self::load_delta). On error the index holds the base and the
+    /// deltas applied before the failing one; a failure inside DiskANN while inserting a
+    /// delta's vectors can leave that delta partly applied, and the base should be reloaded.
+    pub fn load_with_deltas(&mut self, base: &str, deltas: &[&str]) -> StorageResult<()> {
+        self.load(base)?;
+        for delta in deltas {
+            self.load_delta(delta)?;
+        }
+        Ok(())
+    }
+
+    /// Node ID (`None` for deleted slots) and unpadded vector of every slot in `vector_ids`
+    fn persisted_slots(
+        &self,
+        vector_ids: std::ops::Range<u32>,
+    ) -> StorageResult<(Vec<Option<u64>>, Vec<f32>)> {
+        let mut slots = Vec::with_capacity(vector_ids.len());
+        let mut vectors = Vec::with_capacity(vector_ids.len() * self.dimension);
+        let inner = self.inner.read_recursive();
+        for vector_id in vector_ids {
+            slots.push(self.vector_to_node.get(vector_id));
+            let data = inner
+                .get_aligned_vector_data(vector_id)
+                .map_err(|e| StorageError::VectorIndex(VectorIndexError::DiskANN(e)))?;
+            vectors.extend_from_slice(&data[..self.dimension]);
+        }
+        Ok((slots, vectors))
+    }
+
+    /// Install the stats of a loaded snapshot, zeroed when `reset_stats_on_load` is set
+    fn restore_stats(&self, stats: IndexStats) {
+        *self.stats.write() = stats;
+        if self.adapter_config.reset_stats_on_load {
+            self.reset_stats();
+        }
+    }
+
+    /// Zero all search and insert counters, keeping the vector count, dimension and last build
+    /// time, and forget tracked result frequencies
+    pub fn reset_stats(&self) {
+        {
+            let mut stats = self.stats.write();
+            *stats = IndexStats {
+                vector_count: stats.vector_count,
+                dimension: stats.dimension,
+                build_time_ms: stats.build_time_ms,
+                ..Default::default()
+            };
+        }
+        if let Some(frequency) = &self.result_frequency {
+            frequency.lock().clear();
+        }
+    }
+
+    /// The `n` node IDs returned most often by searches since the last reset, with their
+    /// counts, most frequent first (ties by ascending node ID). Empty unless
+    /// [`AdapterConfig::result_frequency_capacity`] is set. Once the capacity is reached, nodes
+    /// not yet tracked are no longer counted.
+    pub fn top_returned(&self, n: usize) -> Vec<(u64, u64)> {
+        let Some(frequency) = &self.result_frequency else {
+            return Vec::new();
+        };
+        let mut counts: Vec<(u64, u64)> = frequency
+            .lock()
+            .iter()
+            .map(|(node_id, count)| (*node_id, *count))
+            .collect();
+        counts.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        counts.truncate(n);
+        counts
+    }
+
+    /// Count the node IDs of a search result when frequency tracking is enabled
+    fn record_returned(&self, results: &[(u64, f32)]) {
+        let (Some(frequency), Some(capacity)) = (
+            &self.result_frequency,
+            self.adapter_config.result_frequency_capacity,
+        ) else {
+            return;
+        };
+        let mut frequency = frequency.lock();
+        for (node_id, _) in results {
+            if let Some(count) = frequency.get_mut(node_id) {
+                *count += 1;
+            } else if frequency.len() < capacity {
+                frequency.insert(*node_id, 1);
+            }
+        }
+    }
+
+    /// Stop the background stats reporter, if one is running
+    pub fn stop_stats_reporter(&self) {
+        if let Some(reporter) = self.stats_reporter.lock().take() {
+            reporter.stop();
+        }
+    }
+
+    /// Reseed the random stream of the search path. Each query that makes randomized decisions
+    /// draws its own seed from this stream, so the same adapter seed and query sequence replay
+    /// identically. The seed defaults to `AdapterConfig::build_seed`, or 0; no current search
+    /// strategy is randomized, so this only affects the seeds reported by
+    /// [`search_debug`](Self::search_debug) for now.
+    pub fn set_rng_seed(&mut self, seed: u64) {
+        self.rng_seed = seed;
+        *self.rng.get_mut() = SearchRng::new(seed);
+    }
+
+    /// Seed most recently passed to [`set_rng_seed`](Self::set_rng_seed)
+    pub fn rng_seed(&self) -> u64 {
+        self.rng_seed
+    }
+
+    /// Random stream for one query, seeded from the adapter stream. Returns the query seed for
+    /// debug output along with the generator.
+    pub(crate) fn query_rng(&self) -> (u64, SearchRng) {
+        let seed = self.rng.lock().next_u64();
+        (seed, SearchRng::new(seed))
+    }
+
+    /// Diagnostic search returning the whole final candidate list instead of only the top `k`.
+    ///
+    /// Each candidate is `(node_id, distance, returned)` in ascending distance order, where
+    /// `returned` marks the entries that make the top-`k` cut of a regular search with the same
+    /// parameters. The list holds up to `l_value` candidates (DiskANN's search list); nodes
+    /// visited earlier but evicted from the list are not reported. The query's random seed is
+    /// reported for replay. Queries and parameters a regular search rejects are rejected the
+    /// same way. This is expensive and not counted in [`IndexStats`].
+    pub fn search_debug(
+        &self,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter_mask: Option<&dyn SearchFilter>,
+    ) -> StorageResult<SearchDebugOutput> {
+        self.check_query_dimension(query)?;
+        Self::check_search_params(k, l_value)?;
+        let (rng_seed, _rng) = self.query_rng();
+        let Some(query) = self.prepare_query(query)? else {
+            return Ok(SearchDebugOutput {
+                rng_seed,
+                candidates: Vec::new(),
+            });
+        };
+        let filter_mask = filter_mask.map(|mask| mask as &dyn DiskANNFilterMask);
+        let candidates =
+            self.diskann_search(&query, l_value as usize, l_value, filter_mask, false)?;
+        Ok(SearchDebugOutput {
+            rng_seed,
+            candidates: candidates
+                .into_iter()
+                .enumerate()
+                .map(|(rank, (node_id, distance))| (node_id, distance, rank < k))
+                .collect(),
+        })
+    }
+
+    /// Encode the vector slots allocated since the last call into the int8 or f16 copies or PQ
+    /// codes, first training the PQ codebooks on (a sample of) the live vectors if there are
+    /// none. A no-op with none of them configured. Concurrent calls encode each slot once, as the
+    /// codes stay write-locked throughout.
+    fn sync_quantized_codes(&self) -> StorageResult<()> {
+        if let Some(int8) = self.int8.write().as_mut() {
+            let slot_count = self.next_vector_id.load(Ordering::Acquire);
+            for vector_id in int8.encoded_slots() as u32..slot_count {
+                int8.push(&self.stored_vector(vector_id)?);
+            }
+        }
+        if let Some(float16) = self.float16.write().as_mut() {
+            let slot_count = self.next_vector_id.load(Ordering::Acquire);
+            for vector_id in float16.encoded_slots() as u32..slot_count {
+                float16.push(&self.stored_vector(vector_id)?);
+            }
+        }
+        if let Some(norms) = self.norms.write().as_mut() {
+            let slot_count = self.next_vector_id.load(Ordering::Acquire);
+            for vector_id in norms.len() as u32..slot_count {
+                norms.push(vector_norm(&self.stored_vector(vector_id)?));
+            }
+        }
+        let Some(pq) = self.adapter_config.product_quantization else {
+            return Ok(());
+        };
+        let mut quantized = self.quantized.write();
+        if quantized.is_none() {
+            let mut live: Vec<u32> = self
+                .node_to_vector
+                .iter()
+                .map(|entry| *entry.value())
+                .collect();
+            if live.is_empty() {
+                return Ok(());
+            }
+            live.sort_unstable();
+            let step = live.len().div_ceil(MAX_TRAINING_SAMPLES);
+            let samples = live
+                .into_iter()
+                .step_by(step)
+                .map(|vector_id| self.stored_vector(vector_id))
+                .collect::<StorageResult<Vec<_>>>()?;
+            let sample_refs: Vec<&[f32]> = samples.iter().map(Vec::as_slice).collect();
+            let quantizer = self.adapter_config.install(|| {
+                ProductQuantizer::train(pq.subquantizers, pq.bits_per_code, &sample_refs)
+            });
+            *quantized = Some(QuantizedVectors::new(quantizer));
+        }
+
+        let quantized = quantized.as_mut().expect("trained above");
+        let slot_count = self.next_vector_id.load(Ordering::Acquire);
+        // Deleted slots are encoded too, so that a slot's code sits at its vector ID
+        for vector_id in quantized.encoded_slots() as u32..slot_count {
+            quantized.push(&self.stored_vector(vector_id)?);
+        }
+        Ok(())
+    }
+
+    /// Refresh the vector counts and memory estimate in stats after mappings change
+    fn sync_size_stats(&self) {
+        let memory_usage = self.estimated_memory_usage();
+        let (deleted_count, deletion_ratio) = (self.num_deleted(), self.deletion_ratio());
+        let mut stats = self.stats.write();
+        stats.vector_count = self.node_to_vector.len();
+        stats.memory_usage = memory_usage;
+        stats.deleted_count = deleted_count;
+        stats.deletion_ratio = deletion_ratio;
+    }
+
+    // Private implementation methods for InMemANNAdapter
+
+    /// Create a fresh DiskANN index from the stored configuration with room for at least
+    /// `min_points` vectors
+    fn fresh_inner(&self, min_points: usize) -> StorageResult<Box<dyn ANNInmemIndex<f32>>> {
+        Self::create_inner(&self.index_config, min_points)
+    }
+
+    /// Create an empty DiskANN index from `config` with room for at least `min_points` vectors
+    fn create_inner(
+        config: &IndexConfiguration,
+        min_points: usize,
+    ) -> StorageResult<Box<dyn ANNInmemIndex<f32>>> {
+        let mut config = config.clone();
+        config.max_points = config.max_points.max(min_points);
+        create_inmem_index::<f32>(config)
+            .map_err(|e| StorageError::VectorIndex(VectorIndexError::DiskANN(e)))
+    }
+
+    /// Unit-length copies of `vectors` when `normalize_vectors` is set or the metric is cosine,
+    /// with the zero-norm policy applied; `None` when vectors are indexed as given
+    fn normalize_batch(&self, vectors: &[(u64, &[f32])]) -> StorageResult<Option<OwnedVectors>> {
+        if !self.adapter_config.normalizes() {
+            return Ok(None);
+        }
+        let mut normalized = Vec::with_capacity(vectors.len());
+        for (node_id, vector) in vectors {
+            match Self::unit_vector(vector) {
+                Some(unit) => normalized.push((*node_id, unit)),
+                None => match self.adapter_config.zero_norm_policy {
+                    ZeroNormPolicy::Reject => {
+                        return Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(
+                            format!(
+                                "Vector of node {node_id} has zero norm and cannot be normalized"
+                            ),
+                        )));
+                    }
+                    ZeroNormPolicy::Skip => {}
+                    ZeroNormPolicy::TreatAsOrigin => normalized.push((*node_id, vector.to_vec())),
+                },
+            }
+        }
+        Ok(Some(normalized))
+    }
+
+    /// The query as searched: normalized when vectors are normalized (see `normalize_batch`).
+    /// `None` is a zero-norm query under [`ZeroNormPolicy::Skip`], which matches nothing.
+    fn prepare_query<'a>(&self, query: &'a [f32]) -> StorageResult<Option<Cow<'a, [f32]>>> {
+        if !self.adapter_config.normalizes() {
+            return Ok(Some(Cow::Borrowed(query)));
+        }
+        match Self::unit_vector(query) {
+            Some(unit) => Ok(Some(Cow::Owned(unit))),
+            None => match self.adapter_config.zero_norm_policy {
+                ZeroNormPolicy::Reject => {
+                    Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(
+                        "Query vector has zero norm and cannot be normalized".to_string(),
+                    )))
+                }
+                ZeroNormPolicy::Skip => Ok(None),
+                ZeroNormPolicy::TreatAsOrigin => Ok(Some(Cow::Borrowed(query))),
+            },
+        }
+    }
+
+    /// Apply `duplicate_vector_policy` to a build batch, recording the number of duplicates
+    /// found in `IndexStats::duplicate_vectors`
+    fn check_duplicate_vectors(&self, vectors: &[(u64, &[f32])]) -> StorageResult<()> {
+        let policy = self.adapter_config.duplicate_vector_policy;
+        self.stats.write().duplicate_vectors = 0;
+        if policy == DuplicateVectorPolicy::Allow {
+            return Ok(());
+        }
+
+        // Key on the bit patterns, folding -0.0 into 0.0 so equal vectors hash equally
+        let mut first_owner: std::collections::HashMap<Vec<u32>, u64> =
+            std::collections::HashMap::with_capacity(vectors.len());
+        let mut duplicate_pairs = 0usize;
+        for (node_id, vector) in vectors {
+            let key: Vec<u32> = vector
+                .iter()
+                .map(|x| if *x == 0.0 { 0 } else { x.to_bits() })
+                .collect();
+            match first_owner.entry(key) {
+                std::collections::hash_map::Entry::Occupied(entry) => {
+                    if policy == DuplicateVectorPolicy::Reject {
+                        return Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(
+                            format!("Nodes {} and {node_id} have identical vectors", entry.get()),
+                        )));
+                    }
+                    duplicate_pairs += 1;
+                }
+                std::collections::hash_map::Entry::Vacant(entry) => {
+                    entry.insert(*node_id);
+                }
+            }
+        }
+        self.stats.write().duplicate_vectors = duplicate_pairs;
+        Ok(())
+    }
+
+    /// Dimension and finiteness check of one build input
+    fn check_build_vector(&self, node_id: u64, vector: &[f32]) -> StorageResult<()> {
+        if vector.len() != self.dimension {
+            return Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension {
+                    expected: self.dimension,
+                    actual: vector.len(),
+                },
+            ));
+        }
+        if let Some(position) = vector.iter().position(|x| !x.is_finite()) {
+            return Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(
+                format!("Vector of node {node_id} has a non-finite value at index {position}"),
+            )));
+        }
+        Ok(())
+    }
+
+    fn vector_count_overflow(count: usize) -> StorageError {
+        StorageError::VectorIndex(VectorIndexError::UnsupportedOperation(format!(
+            "Vector count {count} exceeds u32::MAX limit for DiskANN"
+        )))
+    }
+
+    /// Copy of the stored vector in DiskANN slot `vector_id`, without alignment padding
+    fn stored_vector(&self, vector_id: u32) -> StorageResult<Vec<f32>> {
+        let inner = self.inner.read_recursive();
+        let stored = inner
+            .get_aligned_vector_data(vector_id)
+            .map_err(|e| StorageError::VectorIndex(VectorIndexError::DiskANN(e)))?;
+        Ok(stored[..self.dimension].to_vec())
+    }
+
+    /// `vector` scaled to unit length, or `None` if its norm is zero
+    fn unit_vector(vector: &[f32]) -> Option<Vec<f32>> {
+        let norm = vector_norm(vector);
+        (norm > 0.0).then(|| vector.iter().map(|x| x / norm).collect())
+    }
+
+    fn clear_mappings(&mut self) {
+        self.checkpoint = None;
+        self.deleted_nodes.clear();
+        self.node_to_vector.clear();
+        self.vector_to_node.clear();
+        self.next_vector_id.store(0, Ordering::Relaxed);
+        *self.quantized.get_mut() = None;
+        if let Some(int8) = self.int8.get_mut() {
+            int8.clear();
+        }
+        if let Some(float16) = self.float16.get_mut() {
+            float16.clear();
+        }
+        if let Some(norms) = self.norms.get_mut() {
+            norms.clear();
+        }
+    }
+
+    /// Create aligned query vector for optimal SIMD performance.
+    /// DiskANN uses AVX-512 SIMD instructions, which require 64-byte alignment for optimal
+    /// performance and to avoid undefined behavior. Uses diskann-rs AlignedBoxWithSlice for
+    /// memory-safe 64-byte alignment.
+    /// Returns AlignedQueryBuffer to maintain alignment guarantee.
+    fn ensure_query_aligned(query: &[f32]) -> StorageResult<AlignedQueryBuffer<'_>> {
+        if query.as_ptr().align_offset(64) == 0 {
+            Ok(AlignedQueryBuffer::Borrowed(query))
+        } else {
+            let mut aligned = AlignedBoxWithSlice::<f32>::new(query.len(), 64)
+                .map_err(|e| StorageError::VectorIndex(VectorIndexError::DiskANN(e)))?;
+            aligned.as_mut_slice().copy_from_slice(query);
+            Ok(AlignedQueryBuffer::Owned(aligned))
+        }
+    }
+
+    /// Brute force search with SIMD-optimized distance computation
+    /// Direct iteration over candidate vectors for optimal low selectivity performance
+    fn brute_force_search(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter_mask: &dyn SearchFilter,
+    ) -> StorageResult<Vec<(u64, f32)>> {
+        let results_with_distances: Vec<(u64, f32)> = self
+            .brute_force_heap(query, k, filter_mask)?
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(distance, node_id)| (node_id, distance.0))
+            .collect();
+
+        Ok(self.dedup_results(results_with_distances))
+    }
+
+    /// Brute force over the candidates of `filter_mask` by PQ-estimated distances, optionally
+    /// reranking the best `PQ_RERANK_OVERFETCH * k` of them exactly
+    fn quantized_brute_force_search(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter_mask: &dyn SearchFilter,
+    ) -> StorageResult<Vec<(u64, f32)>> {
+        let quantized = self.quantized.read();
+        let quantized = quantized
+            .as_ref()
+            .expect("quantized search requires trained codebooks");
+        let rerank_factor = match self.adapter_config.product_quantization {
+            Some(pq) if pq.rerank_exact => PQ_RERANK_OVERFETCH,
+            _ => 0,
+        };
+        let table = quantized.quantizer.distance_table(query);
+        self.approximate_brute_force_search(query, k, filter_mask, rerank_factor, |vector_id| {
+            quantized.code(vector_id).map(|code| table.distance(code))
+        })
+    }
+
+    /// Brute force over the candidates of `filter_mask` by int8 distances, reranked exactly
+    /// per [`SearchTuning::rerank_factor`]
+    fn int8_brute_force_search(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter_mask: &dyn SearchFilter,
+    ) -> StorageResult<Vec<(u64, f32)>> {
+        let int8 = self.int8.read();
+        let int8 = int8.as_ref().expect("int8 search requires int8 storage");
+        let query_int8 = Int8Vector::quantize(query);
+        let rerank_factor = self.adapter_config.tuning.rerank_factor;
+        self.approximate_brute_force_search(query, k, filter_mask, rerank_factor, |vector_id| {
+            int8.distance(&query_int8, vector_id)
+        })
+    }
+
+    /// Brute force over the candidates of `filter_mask` by f16 distances, reranked exactly
+    /// per [`SearchTuning::rerank_factor`]
+    fn float16_brute_force_search(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter_mask: &dyn SearchFilter,
+    ) -> StorageResult<Vec<(u64, f32)>> {
+        let float16 = self.float16.read();
+        let float16 = float16.as_ref().expect("f16 search requires f16 storage");
+        let query_f16 = to_f16(query);
+        let rerank_factor = self.adapter_config.tuning.rerank_factor;
+        self.approximate_brute_force_search(query, k, filter_mask, rerank_factor, |vector_id| {
+            float16.distance(&query_f16, vector_id)
+        })
+    }
+
+    /// Top `k` live candidates of `filter_mask` by `distance`, which yields `None` for slots
+    /// without an encoding. A nonzero `rerank_factor` keeps `k * rerank_factor` candidates and
+    /// returns the best `k` of them by exact distance over the stored vectors.
+    fn approximate_brute_force_search(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter_mask: &dyn SearchFilter,
+        rerank_factor: usize,
+        distance: impl Fn(u32) -> Option<f32>,
+    ) -> StorageResult<Vec<(u64, f32)>> {
+        let fetch = k.saturating_mul(rerank_factor.max(1));
+        let capacity = fetch.min(filter_mask.candidate_count());
+        self.check_search_budget(capacity * std::mem::size_of::<(OrderedFloat<f32>, u32)>())?;
+        if k == 0 {
+            return Ok(Vec::new());
+        }
+        let mut heap = BinaryHeap::<(OrderedFloat<f32>, u32)>::with_capacity(capacity);
+        for vector_id in filter_mask.iter_candidates() {
+            if !self.vector_to_node.contains_key(vector_id) {
+                continue; // Soft-deleted or never assigned
+            }
+            let Some(distance) = distance(vector_id) else {
+                continue;
+            };
+            let distance = OrderedFloat(distance);
+            if heap.len() < fetch {
+                heap.push((distance, vector_id));
+            } else if let Some((max_distance, _)) = heap.peek()
+                && distance < *max_distance
+            {
+                heap.pop();
+                heap.push((distance, vector_id));
+            }
+        }
+
+        let mut results = Vec::with_capacity(heap.len());
+        if rerank_factor > 0 {
+            let aligned_query = Self::ensure_query_aligned(query)?;
+            let inner = self.inner.read_recursive();
+            for (_, vector_id) in heap {
+                let Some(node_id) = self.vector_to_node.get(vector_id) else {
+                    continue;
+                };
+                let stored_vector = inner
+                    .get_aligned_vector_data(vector_id)
+                    .map_err(|e| StorageError::VectorIndex(VectorIndexError::DiskANN(e)))?;
+                let distance =
+                    self.brute_force_distance(aligned_query.as_slice(), stored_vector)?;
+                results.push((node_id, distance));
+            }
+            results.sort_by(|a, b| a.1.total_cmp(&b.1));
+            results.truncate(k);
+        } else {
+            for (distance, vector_id) in heap.into_sorted_vec() {
+                if let Some(node_id) = self.vector_to_node.get(vector_id) {
+                    results.push((node_id, distance.0));
+                }
+            }
+        }
+        Ok(self.dedup_results(results))
+    }
+
+    /// Pre-filtered search for mid-range selectivity: only the candidates of `filter_mask` are
+    /// scored. A post-filtered graph search seeds the top-k, then candidates are scanned
+    /// exactly until `max(k, l_value)` consecutive ones fail to improve it, so good seeds end
+    /// the scan early. DiskANN's own pre-filtered traversal (`should_pre`) is not used here:
+    /// it stops at non-passing frontier nodes and can return far fewer than `k` results.
+    fn guided_brute_force_search(
+        &self,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter_mask: &dyn SearchFilter,
+    ) -> StorageResult<(Vec<(u64, f32)>, usize)> {
+        if k == 0 {
+            return Ok((Vec::new(), 0));
+        }
+        let capacity = k.min(filter_mask.candidate_count());
+        self.check_search_budget(capacity * std::mem::size_of::<(OrderedFloat<f32>, u64)>())?;
+        let (seeds, mut visited) =
+            self.filter_search(query, capacity, l_value, filter_mask, false)?;
+        let mut seen: std::collections::HashSet<u64> =
+            seeds.iter().map(|(node_id, _)| *node_id).collect();
+        let mut heap: BinaryHeap<(OrderedFloat<f32>, u64)> = seeds
+            .into_iter()
+            .map(|(node_id, distance)| (OrderedFloat(distance), node_id))
+            .collect();
+
+        let aligned_query = Self::ensure_query_aligned(query)?;
+        let patience = k.max(l_value as usize);
+        let mut since_improvement = 0;
+        let inner = self.inner.read_recursive();
+        for vector_id in filter_mask.iter_candidates() {
+            if heap.len() == k && since_improvement >= patience {
+                break;
+            }
+            let Some(node_id) = self.vector_to_node.get(vector_id) else {
+                continue; // Soft-deleted or never assigned
+            };
+            if !seen.insert(node_id) {
+                continue;
+            }
+            let stored_vector = inner
+                .get_aligned_vector_data(vector_id)
+                .map_err(|e| StorageError::VectorIndex(VectorIndexError::DiskANN(e)))?;
+            let distance = self.brute_force_distance(aligned_query.as_slice(), stored_vector)?;
+            visited += 1;
+
+            if heap.len() < k {
+                heap.push((OrderedFloat(distance), node_id));
+                since_improvement = 0;
+            } else if let Some((max_distance, _)) = heap.peek()
+                && OrderedFloat(distance) < *max_distance
+            {
+                heap.pop();
+                heap.push((OrderedFloat(distance), node_id));
+                since_improvement = 0;
+            } else {
+                since_improvement += 1;
+            }
+        }
+
+        let results = heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(distance, node_id)| (node_id, distance.0))
+            .collect();
+        Ok((results, visited))
+    }
+
+    /// Max-heap of the `k` closest live candidates of `filter_mask`, keyed by distance.
+    /// Candidates without a live node mapping are skipped so they cannot occupy a top-k slot.
+    fn brute_force_heap(
+        &self,
+        query: &[f32],
+        k: usize,
+        filter_mask: &dyn SearchFilter,
+    ) -> StorageResult<BinaryHeap<(OrderedFloat<f32>, u64)>> {
+        let capacity = k.min(filter_mask.candidate_count());
+        self.check_search_budget(capacity * std::mem::size_of::<(OrderedFloat<f32>, u64)>())?;
+        let mut heap = BinaryHeap::<(OrderedFloat<f32>, u64)>::with_capacity(capacity);
+        if k == 0 {
+            return Ok(heap);
+        }
+
+        // Ensure query vector is 64-byte aligned for SIMD requirements
+        let aligned_query = Self::ensure_query_aligned(query)?;
+        let query = aligned_query.as_slice();
+        if filter_mask.candidate_count() < PARALLEL_BRUTE_FORCE_MIN_CANDIDATES {
+            self.scan_candidates(query, k, filter_mask.iter_candidates(), &mut heap)?;
+            return Ok(heap);
+        }
+
+        // Per-task heaps merged pairwise. Entries are totally ordered by (distance, node_id), so
+        // the merged top-k does not depend on how the candidates were split.
+        self.adapter_config.install(|| {
+            filter_mask
+                .candidate_ids()
+                .par_chunks(BRUTE_FORCE_CHUNK)
+                .map(|chunk| {
+                    let mut heap = BinaryHeap::with_capacity(k.min(chunk.len()));
+                    self.scan_candidates(query, k, chunk.iter().copied(), &mut heap)?;
+                    Ok(heap)
+                })
+                .try_reduce(BinaryHeap::new, |mut merged, heap| {
+                    for entry in heap {
+                        Self::push_bounded(&mut merged, entry, k);
+                    }
+                    Ok(merged)
+                })
+        })
+    }
+
+    /// Score `candidates` exactly into the bounded top-`k` `heap`, skipping unmapped slots
+    fn scan_candidates(
+        &self,
+        query: &[f32],
+        k: usize,
+        candidates: impl Iterator<Item = u32>,
+        heap: &mut BinaryHeap<(OrderedFloat<f32>, u64)>,
+    ) -> StorageResult<()> {
+        let inner = self.inner.read_recursive();
+        for vector_id in candidates {
+            let Some(node_id) = self.vector_to_node.get(vector_id) else {
+                continue; // Soft-deleted or never assigned
+            };
+            // Get 64-byte aligned vector data from DiskANN (zero-copy access)
+            let stored_vector = inner
+                .get_aligned_vector_data(vector_id)
+                .map_err(|e| StorageError::VectorIndex(VectorIndexError::DiskANN(e)))?;
+            let distance = self.brute_force_distance(query, stored_vector)?;
+            Self::push_bounded(heap, (OrderedFloat(distance), node_id), k);
+        }
+        Ok(())
+    }
+
+    /// Push `entry` into a max-heap holding at most `k` entries, evicting the largest
+    fn push_bounded(
+        heap: &mut BinaryHeap<(OrderedFloat<f32>, u64)>,
+        entry: (OrderedFloat<f32>, u64),
+        k: usize,
+    ) {
+        if heap.len() < k {
+            heap.push(entry);
+        } else if let Some(max) = heap.peek()
+            && entry < *max
+        {
+            heap.pop();
+            heap.push(entry);
+        }
+    }
+
+    /// filter search: DiskANN search with filtering by the mask
+    /// Used for larger candidate sets where diskann index search is more efficient.
+    /// Also returns the number of ANN results examined.
+    fn filter_search(
+        &self,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter_mask: &dyn SearchFilter,
+        should_pre: bool,
+    ) -> StorageResult<(Vec<(u64, f32)>, usize)> {
+        // Post-filtering keeps only the passing share of the search list, so widen it as the
+        // filter narrows (by the tuning's expansion, capped at the index size)
+        let l_value = if should_pre {
+            l_value
+        } else {
+            let expansion = self
+                .adapter_config
+                .tuning
+                .expansion(filter_mask.selectivity());
+            let expanded = (l_value as f64 * expansion as f64).ceil() as usize;
+            expanded
+                .min(self.size().max(l_value as usize))
+                .min(u32::MAX as usize) as u32
+        };
+        let diskann_filter = filter_mask as &dyn DiskANNFilterMask;
+        self.diskann_search_counted(query, k, l_value, Some(diskann_filter), should_pre)
+    }
+
+    /// Unfiltered graph search, retried with a doubled search list while it finds fewer than
+    /// `k` live nodes (or every live node) and `l_value` is below the tuning's
+    /// [`ensure_k_max_l_value`](SearchTuning::ensure_k_max_l_value). Visits are summed over
+    /// the attempts, and retries are counted in the stats.
+    fn unfiltered_search(
+        &self,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        should_pre: bool,
+    ) -> StorageResult<(Vec<(u64, f32)>, usize)> {
+        let cap = self.adapter_config.tuning.ensure_k_max_l_value;
+        let wanted = k.min(self.size());
+        let (mut results, mut visited) =
+            self.diskann_search_counted(query, k, l_value, None, should_pre)?;
+        let mut l_value = l_value;
+        let mut retries = 0;
+        while results.len() < wanted && l_value < cap {
+            l_value = l_value.saturating_mul(2).min(cap);
+            let (retried, retried_visited) =
+                self.diskann_search_counted(query, k, l_value, None, should_pre)?;
+            results = retried;
+            visited += retried_visited;
+            retries += 1;
+        }
+        if retries > 0 {
+            let mut stats = self.stats.write();
+            stats.ensure_k_retried_searches += 1;
+            stats.ensure_k_retries += retries;
+        }
+        Ok((results, visited))
+    }
+
+    /// DiskANN graph search shared by `ann_search` and the filtered search path
+    fn diskann_search(
+        &self,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter_mask: Option<&dyn DiskANNFilterMask>,
+        should_pre: bool,
+    ) -> StorageResult<Vec<(u64, f32)>> {
+        self.diskann_search_counted(query, k, l_value, filter_mask, should_pre)
+            .map(|(results, _)| results)
+    }
+
+    /// [`diskann_search`](Self::diskann_search) also returning the number of results DiskANN
+    /// reported, before deleted nodes are dropped. DiskANN does not expose how many graph nodes
+    /// its traversal visited.
+    fn diskann_search_counted(
+        &self,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter_mask: Option<&dyn DiskANNFilterMask>,
+        should_pre: bool,
+    ) -> StorageResult<(Vec<(u64, f32)>, usize)> {
+        let raw_results = self.diskann_search_raw(query, k, l_value, filter_mask, should_pre)?;
+        let examined = raw_results.len();
+        let mut results = Vec::with_capacity(raw_results.len());
+        for (vector_id, distance) in raw_results {
+            if let Some(node_id) = self.vector_to_node.get(vector_id) {
+                // Verify the node is still active (not soft-deleted)
+                if self.node_to_vector.contains_key(&node_id) {
+                    results.push((node_id, distance));
+                }
+            }
+        }
+
+        Ok((self.dedup_results(results), examined))
+    }
+
+    /// DiskANN graph search returning `(vector_id, distance)` exactly as DiskANN reports them
+    fn diskann_search_raw(
+        &self,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter_mask: Option<&dyn DiskANNFilterMask>,
+        should_pre: bool,
+    ) -> StorageResult<Vec<(u32, f32)>> {
+        if !self.built {
+            return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
+        }
+
+        // Perform DiskANN search
+        let effective_k = std::cmp::min(k, self.size());
+        if effective_k == 0 {
+            return Ok(Vec::new()); // No active vectors
+        }
+        // Output buffers plus DiskANN's search list of `l_value` candidates
+        self.check_search_budget(
+            (effective_k + l_value as usize) * std::mem::size_of::<(u32, f32)>(),
+        )?;
+        // DiskANN takes queries of exactly `aligned_dim`; stored vectors are zero-padded to it
+        let padded_query;
+        let query = if query.len() < self.index_config.aligned_dim {
+            let mut padded = query.to_vec();
+            padded.resize(self.index_config.aligned_dim, 0.0);
+            padded_query = padded;
+            padded_query.as_slice()
+        } else {
+            query
+        };
+        let mut vector_ids = vec![0u32; effective_k];
+        let mut distances = vec![0.0f32; effective_k];
+        let actual_count = self
+            .inner
+            .read_recursive()
+            .search(
+                query,
+                effective_k,
+                l_value,
+                &mut vector_ids,
+                &mut distances,
+                filter_mask,
+                should_pre,
+            )
+            .map_err(|e| StorageError::VectorIndex(VectorIndexError::SearchError(e.to_string())))?;
+        vector_ids.truncate(actual_count as usize);
+        distances.truncate(actual_count as usize);
+        Ok(vector_ids.into_iter().zip(distances).collect())
+    }
+
+    /// Reject a query whose length is not the index dimension, including an empty one, before
+    /// it reaches the distance kernels
+    fn check_query_dimension(&self, query: &[f32]) -> StorageResult<()> {
+        if query.len() != self.dimension {
+            return Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension {
+                    expected: self.dimension,
+                    actual: query.len(),
+                },
+            ));
+        }
+        Ok(())
+    }
+
+    /// Reject a zero `k` or `l_value`, and a search list too short to hold `k` results, which
+    /// DiskANN would answer with fewer or worse neighbors without reporting an error
+    fn check_search_params(k: usize, l_value: u32) -> StorageResult<()> {
+        let message = if k == 0 || l_value == 0 {
+            format!("k and l_value must be positive, got k={k}, l_value={l_value}")
+        } else if (l_value as usize) < k {
+            format!("l_value ({l_value}) must be at least k ({k})")
+        } else {
+            return Ok(());
+        };
+        Err(StorageError::VectorIndex(
+            VectorIndexError::InvalidSearchParams(message),
+        ))
+    }
+
+    /// Fail if a search needing `required` bytes of candidate buffers exceeds the configured
+    /// budget
+    fn check_search_budget(&self, required: usize) -> StorageResult<()> {
+        match self.adapter_config.search_memory_budget {
+            Some(budget) if required > budget => Err(StorageError::VectorIndex(
+                VectorIndexError::SearchBudgetExceeded { required, budget },
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Drop repeated node IDs from distance-sorted results, keeping the closest occurrence.
+    /// Duplicates only arise from an inconsistent vector_id -> node_id mapping, so they are
+    /// counted in `IndexStats::duplicate_results_dropped` rather than silently merged.
+    fn dedup_results(&self, results: Vec<(u64, f32)>) -> Vec<(u64, f32)> {
+        let mut seen = std::collections::HashSet::with_capacity(results.len());
+        let before = results.len();
+        let deduped: Vec<(u64, f32)> = results
+            .into_iter()
+            .filter(|(node_id, _)| seen.insert(*node_id))
+            .collect();
+        if deduped.len() != before {
+            self.stats.write().duplicate_results_dropped += (before - deduped.len()) as u64;
+        }
+        deduped
+    }
+
+    /// Distance used by brute-force search, honouring `high_precision_distance`
+    #[inline]
+    fn brute_force_distance(&self, query: &[f32], stored: &[f32]) -> StorageResult<f32> {
+        // DiskANN pads stored vectors to `aligned_dim` with zeros
+        let stored = stored.get(..self.dimension).unwrap_or(stored);
+        if self.adapter_config.high_precision_distance {
+            Self::compute_l2_distance_f64(query, stored)
+        } else {
+            Self::compute_l2_distance(query, stored)
+        }
+    }
+
+    /// Compute L2 squared distance with f64 accumulation.
+    /// Scalar path without alignment requirements; the result is rounded to f32 once at the end.
+    fn compute_l2_distance_f64(query: &[f32], stored: &[f32]) -> StorageResult<f32> {
+        if query.len() != stored.len() {
+            return Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension {
+                    expected: stored.len(),
+                    actual: query.len(),
+                },
+            ));
+        }
+        let sum: f64 = query
+            .iter()
+            .zip(stored)
+            .map(|(&a, &b)| {
+                let diff = a as f64 - b as f64;
+                diff * diff
+            })
+            .sum();
+        Ok(sum as f32)
+    }
+
+    /// Squared L2 distance in f32 without alignment requirements: the fallback of
+    /// `compute_l2_distance` for operands the SIMD kernel cannot take, and its only kernel
+    /// without the `simd` feature. Equal lengths are the caller's responsibility.
+    #[inline]
+    fn compute_scalar_l2_squared(query: &[f32], stored: &[f32]) -> f32 {
+        debug_assert_eq!(query.len(), stored.len());
+        query
+            .iter()
+            .zip(stored)
+            .map(|(&a, &b)| {
+                let diff = a - b;
+                diff * diff
+            })
+            .sum()
+    }
+
+    /// Compute L2 squared distance between query vector and stored vector
+    /// Returns squared distance (without sqrt) for consistency with DiskANN SIMD implementation
+    #[cfg(not(feature = "simd"))]
+    #[inline]
+    fn compute_l2_distance(query: &[f32], stored: &[f32]) -> StorageResult<f32> {
+        if query.len() != stored.len() {
+            return Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension {
+                    expected: stored.len(),
+                    actual: query.len(),
+                },
+            ));
+        }
+        Ok(Self::compute_scalar_l2_squared(query, stored))
+    }
+
+    /// Compute L2 squared distance between query vector and stored vector
+    /// Returns squared distance (without sqrt) for consistency with DiskANN SIMD implementation
+    #[cfg(feature = "simd")]
+    #[inline]
+    fn compute_l2_distance(query: &[f32], stored: &[f32]) -> StorageResult<f32> {
+        if query.len() != stored.len() {
+            return Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension {
+                    expected: stored.len(),
+                    actual: query.len(),
+                },
+            ));
+        }
+
+        let dimension = query.len();
+
+        macro_rules! simd_distance {
+            ($const_dim:expr) => {{
+                // Verify exact dimension match at runtime
+                if query.len() != $const_dim || stored.len() != $const_dim {
+                    return Err(StorageError::VectorIndex(
+                        VectorIndexError::InvalidDimension {
+                            expected: $const_dim,
+                            actual: query.len(),
+                        },
+                    ));
+                }
+
+                // The AVX-512 kernel needs 64-byte alignment (matches DiskANN standard). Slots of
+                // DIM_104 vectors (416 bytes) alternate alignment, so unaligned operands are
+                // common and take the scalar path instead of failing.
+                if query.as_ptr().align_offset(64) != 0 || stored.as_ptr().align_offset(64) != 0 {
+                    Self::compute_scalar_l2_squared(query, stored)
+                } else {
+                    // Safety: Verified exact length and 64-byte alignment
+                    unsafe {
+                        let query_array = &*(query.as_ptr() as *const [f32; $const_dim]);
+                        let stored_array = &*(stored.as_ptr() as *const [f32; $const_dim]);
+                        distance_l2_vector_f32::<$const_dim>(query_array, stored_array)
+                    }
+                }
+            }};
+        }
+
+        let distance = match dimension {
+            DIM_104 => simd_distance!(DIM_104),
+            DIM_128 => simd_distance!(DIM_128),
+            DIM_256 => simd_distance!(DIM_256),
+            _ => Self::compute_scalar_l2_squared(query, stored),
+        };
+
+        Ok(distance)
+    }
+}
+
+impl VectorIndex for InMemANNAdapter {
+    /// An empty `vectors` slice yields an empty but built index: searches return no results and
+    /// the DiskANN graph is created by the first `insert`.
+    fn build(&mut self, vectors: &[(u64, &[f32])]) -> StorageResult<()> {
+        let presorted = self.adapter_config.inputs_presorted;
+        self.build_tracked(vectors, &mut |_| {}, None, presorted)
+    }
+
+    fn ann_search(
+        &self,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter_mask: Option<&dyn DiskANNFilterMask>,
+        should_pre: bool,
+    ) -> StorageResult<Vec<(u64, f32)>> {
+        self.check_not_rebuilding()?;
+        self.check_query_dimension(query)?;
+        Self::check_search_params(k, l_value)?;
+        let started = Instant::now();
+        let Some(query) = self.prepare_query(query)? else {
+            return Ok(Vec::new());
+        };
+        let (results, visited) = match filter_mask {
+            None => self.unfiltered_search(&query, k, l_value, should_pre)?,
+            Some(_) => self.diskann_search_counted(&query, k, l_value, filter_mask, should_pre)?,
+        };
+        self.record_returned(&results);
+        SearchPath::Ann.count(&mut self.stats.write(), started.elapsed(), visited);
+        Ok(results)
+    }
+
+    fn search(
+        &self,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter_mask: Option<&dyn SearchFilter>,
+        should_pre: bool,
+    ) -> StorageResult<Vec<(u64, f32)>> {
+        self.check_not_rebuilding()?;
+        self.check_query_dimension(query)?;
+        let started = Instant::now();
+        let (results, path, visited) =
+            self.search_uncounted(query, k, l_value, filter_mask, should_pre)?;
+        path.count(&mut self.stats.write(), started.elapsed(), visited);
+        Ok(results)
+    }
+
+    fn search_rescored(
+        &self,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter_mask: Option<&dyn SearchFilter>,
+        metric: DistanceMetric,
+    ) -> StorageResult<Vec<(u64, f32)>> {
+        let candidate_count = k.max(l_value as usize);
+        let candidates = self.search(query, candidate_count, l_value, filter_mask, false)?;
+
+        let node_ids: Vec<u64> = candidates.iter().map(|(node_id, _)| *node_id).collect();
+        let mut rescored = Vec::with_capacity(candidates.len());
+        let norms = self.norms.read();
+        let cached_norms = norms.as_ref().filter(|_| metric == DistanceMetric::Cosine);
+        let query_norm = vector_norm(query);
+        for (node_id, stored) in node_ids.iter().zip(self.get_vectors(&node_ids)?) {
+            let Some(stored) = stored else {
+                continue;
+            };
+            let stored_norm = cached_norms.and_then(|norms| {
+                let vector_id = self.node_to_vector_id(*node_id)?;
+                norms.get(vector_id as usize).copied()
+            });
+            let distance = match stored_norm {
+                Some(stored_norm) => cosine_distance(query, query_norm, &stored, stored_norm),
+                None => metric.distance(query, &stored),
+            };
+            rescored.push((*node_id, distance));
+        }
+        rescored.sort_by_key(|(_, distance)| OrderedFloat(*distance));
+        rescored.truncate(k);
+        Ok(rescored)
+    }
+
+    fn natural_distance(&self, distance: f32) -> f32 {
+        match self.metric() {
+            // Squared L2 between unit vectors is twice the cosine distance
+            DistanceMetric::Cosine => distance / 2.0,
+            metric => metric.to_natural(distance),
+        }
+    }
+
+    fn get_dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn metric(&self) -> DistanceMetric {
+        self.adapter_config.metric.into()
+    }
+
+    fn size(&self) -> usize {
+        // Return the actual number of active vectors based on our mappings
+        // This correctly excludes deleted vectors, unlike get_num_active_pts()
+        self.node_to_vector.len()
+    }
+
+    fn node_to_vector_id(&self, node_id: u64) -> Option<u32> {
+        self.node_to_vector.get(&node_id).map(|entry| *entry)
+    }
+
+    fn vector_to_node_id(&self, vector_id: u32) -> Option<u64> {
+        self.vector_to_node.get(vector_id)
+    }
+
+    fn stats(&self) -> IndexStats {
+        self.stats.read().clone()
+    }
+
+    fn insert(&mut self, vectors: &[(u64, &[f32])]) -> StorageResult<()> {
+        self.insert_concurrent(vectors)
+    }
+
+    fn soft_delete(&mut self, node_ids: &[u64]) -> StorageResult<()> {
+        if node_ids.is_empty() {
+            return Ok(());
+        }
+
+        if !self.built {
+            return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
+        }
+
+        // Validate all node_ids exist and collect vector_ids to delete
+        let mut vector_ids_to_delete = Vec::with_capacity(node_ids.len());
+        for &node_id in node_ids {
+            if let Some(vector_id) = self.node_to_vector.get(&node_id) {
+                // Check if mapping exists in vector_to_node (should always exist if node_to_vector
+                // exists)
+                if self.vector_to_node.contains_key(*vector_id) {
+                    vector_ids_to_delete.push(*vector_id);
+                } else {
+                    return Err(StorageError::VectorIndex(
+                        VectorIndexError::NodeIdNotFound { node_id },
+                    ));
+                }
+            } else {
+                return Err(StorageError::VectorIndex(
+                    VectorIndexError::NodeIdNotFound { node_id },
+                ));
+            }
+        }
+
+        match self
+            .inner
+            .get_mut()
+            .soft_delete(vector_ids_to_delete.clone(), vector_ids_to_delete.len())
+        {
+            Ok(()) => {
+                // DiskANN soft deletion successful
+                // Update both mappings to maintain consistency
+                let deleted_nodes = self
+                    .vector_to_node
+                    .batch_soft_delete(&vector_ids_to_delete)?;
+
+                // Remove from node_to_vector mapping, keeping it aside for audit if requested
+                let deleted_at = Instant::now();
+                for node_id in deleted_nodes {
+                    if let Some((node_id, vector_id)) = self.node_to_vector.remove(&node_id)
+                        && self.adapter_config.retain_deleted_mappings
+                    {
+                        self.deleted_nodes.insert(node_id, (vector_id, deleted_at));
+                    }
+                }
+                self.sync_size_stats();
+            }
+            Err(e) => {
+                return Err(StorageError::VectorIndex(VectorIndexError::DiskANN(e)));
+            }
+        }
+
+        Ok(())
+    }
+
+    fn save(&mut self, path: &str) -> StorageResult<()> {
+        if !self.built {
+            return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
+        }
+
+        let slot_count = self.next_vector_id.load(Ordering::Relaxed);
+        let (slots, vectors) = self.persisted_slots(0..slot_count)?;
+        let (adjacency, entry_point) = if slots.is_empty() {
+            (Vec::new(), 0)
+        } else {
+            self.inner
+                .get_mut()
+                .export_graph()
+                .map_err(|e| StorageError::VectorIndex(VectorIndexError::DiskANN(e)))?
+        };
+        if adjacency.len() != slots.len() {
+            return Err(StorageError::VectorIndex(VectorIndexError::Persistence(
+                format!(
+                    "DiskANN graph has {} vertices but the index has {} vector slots",
+                    adjacency.len(),
+                    slots.len()
+                ),
+            )));
+        }
+
+        let provenance = self
+            .provenance
+            .clone()
+            .unwrap_or_else(|| IndexProvenance::capture(&self.index_config, &self.adapter_config));
+        let snapshot = PersistedIndex {
+            dimension: self.dimension,
+            slots,
+            vectors,
+            adjacency,
+            entry_point,
+            provenance,
+            stats: self.stats.read().clone(),
+        };
+        let bytes = write_persisted(path, SNAPSHOT_MAGIC, SNAPSHOT_VERSION, &snapshot)?;
+        self.checkpoint = Some(DeltaCheckpoint::new(
+            crc32fast::hash(&bytes),
+            &snapshot.slots,
+        ));
+        Ok(())
+    }
+
+    /// Replace the index contents with a snapshot written by `save`.
+    /// The saved graph and entry point are restored as they were, without relinking, and
+    /// soft-deleted slots are deleted again, so ID mappings and search results match the saved
+    /// index exactly.
+    fn load(&mut self, path: &str) -> StorageResult<()> {
+        let (snapshot, base_checksum): (PersistedIndex, u32) =
+            read_persisted(path, SNAPSHOT_MAGIC, SNAPSHOT_VERSION, "snapshot")?;
+
+        if snapshot.dimension != self.dimension {
+            return Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension {
+                    expected: self.dimension,
+                    actual: snapshot.dimension,
+                },
+            ));
+        }
+        if snapshot.slots.is_empty() {
+            // Snapshot of an empty but built index
+            *self.inner.get_mut() = self.fresh_inner(0)?;
+            self.clear_mappings();
+            self.built = true;
+            self.provenance = Some(snapshot.provenance);
+            self.restore_stats(snapshot.stats);
+            self.sync_size_stats();
+            self.checkpoint = Some(DeltaCheckpoint::new(base_checksum, &[]));
+            return Ok(());
+        }
+        if snapshot.vectors.len() != snapshot.slots.len() * self.dimension
+            || snapshot.adjacency.len() != snapshot.slots.len()
+            || snapshot.slots.len() > u32::MAX as usize
+        {
+            return Err(StorageError::VectorIndex(VectorIndexError::Persistence(
+                "Snapshot vector data does not match its slot count".to_string(),
+            )));
+        }
+
+        let mut inner = self.fresh_inner(snapshot.slots.len())?;
+
+        let vector_slices: Vec<&[f32]> = snapshot.vectors.chunks(self.dimension).collect();
+        inner
+            .build_from_graph(&vector_slices, &snapshot.adjacency, snapshot.entry_point)
+            .map_err(|e| StorageError::VectorIndex(VectorIndexError::Persistence(e.to_string())))?;
+
+        let deleted: Vec<u32> = (0..snapshot.slots.len() as u32)
+            .filter(|&vector_id| snapshot.slots[vector_id as usize].is_none())
+            .collect();
+        if !deleted.is_empty() {
+            let count = deleted.len();
+            inner
+                .soft_delete(deleted, count)
+                .map_err(|e| StorageError::VectorIndex(VectorIndexError::DiskANN(e)))?;
+        }
+
+        *self.inner.get_mut() = inner;
+        self.clear_mappings();
+        for (vector_id, node_id) in snapshot.slots.iter().enumerate() {
+            if let Some(node_id) = node_id {
+                self.node_to_vector.insert(*node_id, vector_id as u32);
+                self.vector_to_node.set(vector_id as u32, *node_id)?;
+            }
+        }
+        self.next_vector_id
+            .store(snapshot.slots.len() as u32, Ordering::Relaxed);
+        self.built = true;
+        self.provenance = Some(snapshot.provenance);
+        self.restore_stats(snapshot.stats);
+        self.sync_quantized_codes()?;
+        self.sync_size_stats();
+        self.checkpoint = Some(DeltaCheckpoint::new(base_checksum, &snapshot.slots));
+        Ok(())
+    }
+}
+
+/// L2 norm of `vector`
+fn vector_norm(vector: &[f32]) -> f32 {
+    vector.iter().map(|x| x * x).sum::<f32>().sqrt()
+}
+
+/// Cosine distance `1 - dot / (|a| |b|)` given both norms, in `[0, 2]`; 1.0 when either vector
+/// is zero, like [`DistanceMetric::distance`]. Any dimension, no alignment requirements.
+fn cosine_distance(a: &[f32], a_norm: f32, b: &[f32], b_norm: f32) -> f32 {
+    debug_assert_eq!(a.len(), b.len());
+    if a_norm == 0.0 || b_norm == 0.0 {
+        return 1.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    // Rounding can push |cos| slightly past 1 for (anti)parallel vectors
+    1.0 - (dot / (a_norm * b_norm)).clamp(-1.0, 1.0)
+}
+
+/// Write `payload` postcard-encoded after `magic` and `version` to `path`, returning the
+/// written bytes
+fn write_persisted<T: Serialize>(
+    path: &str,
+    magic: [u8; 8],
+    version: u32,
+    payload: &T,
+) -> StorageResult<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(magic.len() + 4);
+    bytes.extend_from_slice(&magic);
+    bytes.extend_from_slice(&version.to_le_bytes());
+    let bytes = postcard::to_extend(payload, bytes)
+        .map_err(|e| StorageError::VectorIndex(VectorIndexError::Persistence(e.to_string())))?;
+    std::fs::write(path, &bytes).map_err(|e| {
+        StorageError::VectorIndex(VectorIndexError::Persistence(format!(
+            "Failed to write {path}: {e}"
+        )))
+    })?;
+    Ok(bytes)
+}
+
+/// Read a file written by [`write_persisted`] with the same `magic` and `version`, returning
+/// the payload and the CRC32 of the whole file. `kind` names the format in errors.
+fn read_persisted<T: serde::de::DeserializeOwned>(
+    path: &str,
+    magic: [u8; 8],
+    version: u32,
+    kind: &str,
+) -> StorageResult<(T, u32)> {
+    let bytes = std::fs::read(path).map_err(|e| {
+        StorageError::VectorIndex(VectorIndexError::Persistence(format!(
+            "Failed to read {path}: {e}"
+        )))
+    })?;
+    let persistence_error =
+        |message: String| StorageError::VectorIndex(VectorIndexError::Persistence(message));
+    let header_len = magic.len() + 4;
+    if bytes.len() < header_len || bytes[..magic.len()] != magic {
+        return Err(persistence_error(format!(
+            "{path} is not a vector index {kind} (missing magic header)"
+        )));
+    }
+    let found = u32::from_le_bytes(
+        bytes[magic.len()..header_len]
+            .try_into()
+            .expect("header slice is 4 bytes"),
+    );
+    if found != version {
+        return Err(persistence_error(format!(
+            "{path} uses {kind} format version {found}, this build reads version {version}"
+        )));
+    }
+    let payload =
+        postcard::from_bytes(&bytes[header_len..]).map_err(|e| persistence_error(e.to_string()))?;
+    Ok((payload, crc32fast::hash(&bytes)))
+}
+
+/// Create a vector index configuration with intelligent capacity management
+///
+/// This function calculates optimal DiskANN configuration parameters based on the actual
+/// dataset size, using a headroom ratio to provide growth capacity while maintaining
+/// efficiency.
+pub fn create_vector_index_config(dimension: usize, vector_count: usize) -> IndexConfiguration {
     let write_params = IndexWriteParametersBuilder::new(100, 64)
         .with_alpha(1.2)
         .with_num_threads(1)
         .build();
 
-    // Set max_points to actual vector count
-    let calculated_max_points = vector_count.min(u32::MAX as usize);
+    // Set max_points to actual vector count
+    let calculated_max_points = vector_count.min(u32::MAX as usize);
+
+    IndexConfiguration {
+        index_write_parameter: write_params,
+        dist_metric: Metric::L2,
+        dim: dimension,
+        aligned_dim: dimension,
+        max_points: calculated_max_points,
+        num_frozen_pts: 0,
+        use_pq_dist: false,
+        num_pq_chunks: 0,
+        use_opq: false,
+        growth_potential: 2.0, // Pre-allocation capacity in InmemDataset of DiskANN to insert
+    }
+}
+
+#[cfg(test)]
+mod sharded_vector_map_tests {
+    use super::*;
+
+    #[test]
+    fn test_basic_operations_and_consistency() -> StorageResult<()> {
+        let map = ShardedVectorMap::new(4)?; // 2^4 = 16 shards  
+        assert_eq!(map.shard_count(), 16);
+
+        // Test basic set and get operations
+        map.set(0, 100)?;
+        map.set(15, 115)?;
+
+        assert_eq!(map.get(0), Some(100));
+        assert_eq!(map.get(15), Some(115));
+        assert_eq!(map.get(999), None); // Non-existent
+
+        // Test consistency between get() and contains_key()
+        for i in 16..36u32 {
+            // Before setting
+            assert_eq!(map.get(i), None);
+            assert!(!map.contains_key(i));
+
+            // After setting
+            map.set(i, i as u64 + 500)?;
+            assert_eq!(map.get(i), Some(i as u64 + 500));
+            assert!(map.contains_key(i));
+        }
+
+        // Verify initial values still exist
+        assert!(map.contains_key(0));
+        assert!(map.contains_key(15));
+        assert!(!map.contains_key(999));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sharding_and_distribution() -> StorageResult<()> {
+        let map = ShardedVectorMap::new(4)?; // 16 shards
+        for vector_id in 0..64u32 {
+            map.set(vector_id, vector_id as u64 + 1000)?;
+        }
+        let mut shard_counts = [0; 16];
+        for vector_id in 0..64u32 {
+            let (shard_idx, _) = map.get_shard_and_index(vector_id);
+            shard_counts[shard_idx] += 1;
+        }
+
+        // Verify bit-striping sharding spreads data evenly
+        let min_count = *shard_counts.iter().min().unwrap();
+        let max_count = *shard_counts.iter().max().unwrap();
+        let range = max_count - min_count;
+
+        assert!(
+            range <= 8,
+            "Bit-striping sharding should distribute evenly, range: {}",
+            range
+        );
+        assert!(min_count > 0, "All shards should have data");
+
+        // Test shard boundary conditions with specific vector_ids
+        let boundary_map = ShardedVectorMap::new(2)?; // 4 shards, mask = 3
+        let boundary_ids = vec![0, 1, 2, 3, 4, 7, 8, 11, 12, 15, 16];
+
+        for &vector_id in &boundary_ids {
+            boundary_map.set(vector_id, vector_id as u64 + 2000)?;
+        }
+
+        // Verify shard calculation consistency
+        for &vector_id in &boundary_ids {
+            let (shard_idx, local_idx) = boundary_map.get_shard_and_index(vector_id);
+            assert!(shard_idx < boundary_map.shard_count());
+
+            // Verify reverse calculation matches
+            let reconstructed = (local_idx << 2) | shard_idx;
+            assert_eq!(reconstructed, vector_id as usize);
+
+            assert_eq!(boundary_map.get(vector_id), Some(vector_id as u64 + 2000));
+            assert!(boundary_map.contains_key(vector_id));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_soft_delete_comprehensive() -> StorageResult<()> {
+        let map = ShardedVectorMap::new(3)?; // 8 shards
+
+        // Set up test data distributed across shards
+        let test_data = vec![
+            (0, 1000),
+            (1, 1001),
+            (2, 1002),
+            (3, 1003),
+            (10, 1010),
+            (20, 1020),
+            (30, 1030),
+            (40, 1040),
+            (100, 1100),
+            (200, 1200),
+            (300, 1300),
+            (400, 1400),
+        ];
+
+        for (vector_id, node_id) in &test_data {
+            map.set(*vector_id, *node_id)?;
+        }
+
+        // Verify initial state
+        for (vector_id, node_id) in &test_data {
+            assert_eq!(map.get(*vector_id), Some(*node_id));
+            assert!(map.contains_key(*vector_id));
+        }
+
+        // Test single soft delete
+        let deleted = map.soft_delete(20)?;
+        assert_eq!(deleted, Some(1020));
+        assert_eq!(map.get(20), None);
+        assert!(!map.contains_key(20));
+
+        // Test double deletion (should return None)
+        let deleted_again = map.soft_delete(20)?;
+        assert_eq!(deleted_again, None);
+
+        // Test batch soft delete on mixed shards
+        let delete_ids = vec![1, 30, 200, 400];
+        let deleted_nodes = map.batch_soft_delete(&delete_ids)?;
+
+        assert_eq!(deleted_nodes.len(), 4);
+        assert!(deleted_nodes.contains(&1001));
+        assert!(deleted_nodes.contains(&1030));
+        assert!(deleted_nodes.contains(&1200));
+        assert!(deleted_nodes.contains(&1400));
+
+        // Verify batch deletion effects
+        for &id in &delete_ids {
+            assert_eq!(map.get(id), None);
+            assert!(!map.contains_key(id));
+        }
+
+        // Test batch delete edge cases
+        let empty_deleted = map.batch_soft_delete(&[])?;
+        assert!(empty_deleted.is_empty());
+
+        let nonexistent_deleted = map.batch_soft_delete(&[999, 888])?;
+        assert!(nonexistent_deleted.is_empty());
+
+        // Verify remaining data intact
+        let remaining_ids = vec![0, 2, 3, 10, 40, 100, 300];
+        for &id in &remaining_ids {
+            assert!(map.contains_key(id));
+            assert!(map.get(id).is_some());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dynamic_expansion() -> StorageResult<()> {
+        let map = ShardedVectorMap::new(2)?; // 4 shards
+
+        // Test setting vector_ids that require expansion across different shards
+        // With 2 shard_bits: shard_idx = vector_id & 3, local_idx = vector_id >> 2
+
+        // Small initial value
+        map.set(10, 1010)?;
+
+        // Larger values that will expand different shards
+        map.set(1000, 2000)?; // shard 0, local_idx 250
+        map.set(10001, 20001)?; // shard 1, local_idx 2500  
+        map.set(100002, 200002)?; // shard 2, local_idx 25000
+        map.set(1000003, 2000003)?; // shard 3, local_idx 250000
+
+        // Verify all values are stored correctly
+        assert_eq!(map.get(10), Some(1010));
+        assert_eq!(map.get(1000), Some(2000));
+        assert_eq!(map.get(10001), Some(20001));
+        assert_eq!(map.get(100002), Some(200002));
+        assert_eq!(map.get(1000003), Some(2000003));
+
+        // Test intermediate values are None (sparse storage)
+        assert_eq!(map.get(500), None);
+        assert_eq!(map.get(5000), None);
+        assert_eq!(map.get(50000), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reserve_preallocates_shards() -> StorageResult<()> {
+        let map = ShardedVectorMap::new(2)?; // 4 shards
+        map.reserve(1001);
+        assert!(map.capacity() >= 4 * 251);
+        assert!(map.is_empty());
+        let before = map.capacity();
+        for vector_id in 0..1001u32 {
+            map.set(vector_id, vector_id as u64)?;
+        }
+        assert_eq!(map.capacity(), before);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parameter_validation() {
+        // Test boundary conditions for shard_bits parameter
+        assert!(ShardedVectorMap::new(17).is_err()); // > 16 should fail
+        assert!(ShardedVectorMap::new(0).is_ok()); // min valid value
+        assert!(ShardedVectorMap::new(16).is_ok()); // max valid value
+
+        // Verify shard count calculation: 2^shard_bits
+        assert_eq!(ShardedVectorMap::new(2).unwrap().shard_count(), 4);
+        assert_eq!(ShardedVectorMap::new(0).unwrap().shard_count(), 1);
+    }
+
+    #[test]
+    fn test_empty_map_behavior() -> StorageResult<()> {
+        let map = ShardedVectorMap::new(2)?;
+
+        // Basic empty map properties
+        assert!(map.is_empty());
+        assert_eq!(map.get(0), None);
+        assert!(!map.contains_key(0));
+
+        // All operations on empty map should return None/empty
+        assert_eq!(map.soft_delete(0)?, None);
+        assert!(map.batch_soft_delete(&[1, 2, 3])?.is_empty());
+        assert_eq!(map.remove(0), None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_removal_operations() -> StorageResult<()> {
+        let map = ShardedVectorMap::new(2)?;
+
+        // Setup test data
+        map.set(10, 110)?;
+        map.set(20, 120)?;
+
+        // Test remove (used for rollback)
+        assert_eq!(map.remove(10), Some(110));
+        assert_eq!(map.get(10), None);
+        assert_eq!(map.remove(10), None); // Already removed
+
+        // Test clear
+        assert!(!map.is_empty());
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.get(20), None);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod adapter_tests {
+    use std::sync::mpsc;
+
+    use arrow::array::BooleanArray;
+
+    use super::*;
+    use crate::tp::vector_index::config::ProductQuantization;
+    use crate::tp::vector_index::filter::{
+        AndFilterMask, ArrowBooleanFilterMask, FilterMask, NotFilterMask, OrFilterMask,
+    };
+
+    const TEST_DIM: usize = 128;
+
+    /// Deterministic clustered vectors: node IDs 1000.. with well-separated coordinates
+    fn test_vectors(count: usize) -> Vec<(u64, Vec<f32>)> {
+        (0..count)
+            .map(|i| {
+                let mut vector = vec![0.0f32; TEST_DIM];
+                vector[0] = (i / 10) as f32 * 20.0 + (i % 10) as f32;
+                vector[1] = ((i as f32) * 1.7).sin() * 5.0;
+                vector[2] = ((i as f32) * 0.9).cos() * 5.0;
+                (1000 + i as u64, vector)
+            })
+            .collect()
+    }
+
+    fn build_adapter(vectors: &[(u64, Vec<f32>)]) -> StorageResult<InMemANNAdapter> {
+        let mut adapter =
+            InMemANNAdapter::new(create_vector_index_config(TEST_DIM, vectors.len()))?;
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        adapter.build(&refs)?;
+        Ok(adapter)
+    }
+
+    #[test]
+    fn test_stats_track_operations() -> StorageResult<()> {
+        let vectors = test_vectors(50);
+        let mut adapter = build_adapter(&vectors)?;
+
+        let stats = adapter.stats();
+        assert_eq!(stats.vector_count, 50);
+        assert_eq!(stats.dimension, TEST_DIM);
+        assert_eq!(stats.total_searches, 0);
+
+        adapter.ann_search(&vectors[0].1, 5, 50, None, false)?;
+        // The graph search examines the results DiskANN returned
+        assert_eq!(adapter.stats().last_candidates_visited, 5);
+        let mask = FilterMask::new(vec![1, 2], adapter.size());
+        adapter.search(&vectors[1].1, 2, 50, Some(&mask), false)?;
+        // The brute-force scan scores each candidate of the filter
+        assert_eq!(adapter.stats().last_candidates_visited, 2);
+        assert_eq!(adapter.stats().total_candidates_visited, 7);
+        adapter.soft_delete(&[vectors[3].0])?;
+
+        let stats = adapter.stats();
+        assert_eq!(stats.total_searches, 2);
+        assert_eq!(stats.ann_searches, 1);
+        assert_eq!(stats.brute_force_searches, 1);
+        assert_eq!(stats.vector_count, 49);
+        // Both the ANN and the brute-force search feed the latency average
+        assert_eq!(stats.timed_searches, stats.total_searches);
+        assert!(stats.min_search_time_us as f64 <= stats.avg_search_time_us);
+        assert!(stats.avg_search_time_us <= stats.max_search_time_us as f64);
+
+        adapter.reset_stats();
+        let stats = adapter.stats();
+        assert_eq!((stats.timed_searches, stats.total_search_time_us), (0, 0));
+        assert_eq!(stats.avg_search_time_us, 0.0);
+        assert_eq!(stats.total_candidates_visited, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stats_reporter_lifecycle() -> StorageResult<()> {
+        let vectors = test_vectors(30);
+        let adapter = build_adapter(&vectors)?;
+
+        assert!(
+            adapter
+                .start_stats_reporter(Duration::ZERO, Box::new(|_| {}))
+                .is_err()
+        );
+
+        let (tx, rx) = mpsc::channel();
+        adapter.start_stats_reporter(
+            Duration::from_millis(5),
+            Box::new(move |snapshot| {
+                let _ = tx.send(snapshot);
+            }),
+        )?;
+        let snapshot = rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(snapshot.vector_count, 30);
+
+        adapter.stop_stats_reporter();
+        while rx.try_recv().is_ok() {}
+        assert!(matches!(
+            rx.recv_timeout(Duration::from_millis(50)),
+            Err(mpsc::RecvTimeoutError::Disconnected)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_presorted_build() -> StorageResult<()> {
+        let vectors = test_vectors(40);
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        let presorted = AdapterConfig::new().with_inputs_presorted(true);
+
+        let mut adapter = InMemANNAdapter::with_config(
+            create_vector_index_config(TEST_DIM, vectors.len()),
+            presorted.clone(),
+        )?;
+        adapter.build(&refs)?;
+        assert_eq!(adapter.size(), 40);
+        assert_eq!(adapter.node_to_vector_id(1000), Some(0));
+        assert_eq!(adapter.node_to_vector_id(1039), Some(39));
+
+        // A wrong hint is detected instead of silently producing a bad mapping
+        let mut unsorted = refs.clone();
+        unsorted.swap(3, 7);
+        let mut adapter = InMemANNAdapter::with_config(
+            create_vector_index_config(TEST_DIM, vectors.len()),
+            presorted.clone(),
+        )?;
+        assert!(matches!(
+            adapter.build(&unsorted),
+            Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(_)))
+        ));
+        assert_eq!(adapter.mapping_count(), 0);
+
+        let mut duplicated = refs.clone();
+        duplicated[5].0 = duplicated[4].0;
+        assert!(matches!(
+            adapter.build(&duplicated),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::DuplicateNodeId { node_id: 1004 }
+            ))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_debug_reports_candidate_list() -> StorageResult<()> {
+        let vectors = test_vectors(60);
+        let adapter = build_adapter(&vectors)?;
+        let query = &vectors[10].1;
+
+        let debug = adapter.search_debug(query, 5, 20, None)?.candidates;
+        assert!(debug.len() > 5 && debug.len() <= 20);
+        assert_eq!(debug.iter().filter(|(_, _, returned)| *returned).count(), 5);
+        assert!(debug.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+
+        // Returned entries match a regular search with the same parameters
+        let regular = adapter.ann_search(query, 5, 20, None, false)?;
+        let returned: Vec<u64> = debug
+            .iter()
+            .filter(|(_, _, returned)| *returned)
+            .map(|(node_id, _, _)| *node_id)
+            .collect();
+        let expected: Vec<u64> = regular.iter().map(|(node_id, _)| *node_id).collect();
+        assert_eq!(returned, expected);
+
+        // Filtered candidates all pass the filter
+        let mask = FilterMask::new((0..30).collect(), adapter.size());
+        let filtered = adapter.search_debug(query, 5, 20, Some(&mask))?.candidates;
+        assert!(!filtered.is_empty());
+        assert!(filtered.iter().all(|(node_id, _, _)| {
+            mask.contains_vector(adapter.node_to_vector_id(*node_id).unwrap())
+        }));
+
+        for (query, k, l_value) in [
+            (query.as_slice(), 30, 20),
+            (query, 0, 20),
+            (&[1.0; 3], 5, 20),
+        ] {
+            assert!(matches!(
+                adapter.search_debug(query, k, l_value, None),
+                Err(StorageError::VectorIndex(
+                    VectorIndexError::InvalidSearchParams(_)
+                        | VectorIndexError::InvalidDimension { .. }
+                ))
+            ));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_high_precision_distance() -> StorageResult<()> {
+        let query: Vec<f32> = (0..TEST_DIM).map(|i| 1.0e4 + i as f32 * 0.5).collect();
+        let stored: Vec<f32> = query.iter().map(|v| v + 1.0e-3).collect();
+        let aligned_query = InMemANNAdapter::ensure_query_aligned(&query)?;
+        let aligned_stored = InMemANNAdapter::ensure_query_aligned(&stored)?;
+
+        let exact: f64 = query
+            .iter()
+            .zip(&stored)
+            .map(|(&a, &b)| (a as f64 - b as f64).powi(2))
+            .sum();
+        let high = InMemANNAdapter::compute_l2_distance_f64(&query, &stored)?;
+        let simd = InMemANNAdapter::compute_l2_distance(
+            aligned_query.as_slice(),
+            aligned_stored.as_slice(),
+        )?;
+        assert!((high as f64 - exact).abs() <= (simd as f64 - exact).abs());
+
+        // Brute-force search produces the same ranking under either mode
+        let vectors = test_vectors(50);
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        let mut adapter = InMemANNAdapter::with_config(
+            create_vector_index_config(TEST_DIM, vectors.len()),
+            AdapterConfig::new().with_high_precision_distance(true),
+        )?;
+        adapter.build(&refs)?;
+        let mask = FilterMask::new(vec![0, 2, 4], adapter.size());
+        let results = adapter.search(&vectors[2].1, 3, 50, Some(&mask), false)?;
+        assert_eq!(results[0], (vectors[2].0, 0.0));
+        assert_eq!(results.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_contains_all() -> StorageResult<()> {
+        let vectors = test_vectors(20);
+        let mut adapter = build_adapter(&vectors)?;
+        adapter.soft_delete(&[1005])?;
+
+        assert_eq!(
+            adapter.contains_all(&[1010, 1005, 42, 1000, 1010]),
+            vec![true, false, false, true, true]
+        );
+        assert!(adapter.contains_all(&[]).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_cascade_fills_from_later_filters() -> StorageResult<()> {
+        let vectors = test_vectors(50);
+        let adapter = build_adapter(&vectors)?;
+        let total = adapter.size();
+
+        // Strict tier only has two candidates; the broad tier overlaps with it
+        let strict = FilterMask::new(vec![40, 41], total);
+        let broad = FilterMask::new((0..total as u32).collect(), total);
+        let results = adapter.search_cascade(&vectors[0].1, 5, 50, &[&strict, &broad])?;
+
+        assert_eq!(results.len(), 5);
+        let mut strict_hits: Vec<u64> = results[..2].iter().map(|(id, _)| *id).collect();
+        strict_hits.sort_unstable();
+        assert_eq!(strict_hits, vec![1040, 1041]);
+        // Broad tier fills the rest with unique, closest-first matches
+        assert_eq!(results[2].0, vectors[0].0);
+        let unique: std::collections::HashSet<u64> = results.iter().map(|(id, _)| *id).collect();
+        assert_eq!(unique.len(), 5);
+
+        // Enough strict matches means later filters are not consulted
+        let results = adapter.search_cascade(&vectors[0].1, 2, 50, &[&strict, &broad])?;
+        assert_eq!(results.len(), 2);
+        assert!(
+            adapter
+                .search_cascade(&vectors[0].1, 5, 50, &[])?
+                .is_empty()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_dedicated_thread_pool() -> StorageResult<()> {
+        /// Filter recording the thread that collects its candidates for a parallel scan
+        struct ThreadProbe {
+            mask: FilterMask,
+            collected_on: Mutex<Option<String>>,
+        }
+
+        impl DiskANNFilterMask for ThreadProbe {
+            fn contains_vector(&self, vector_id: u32) -> bool {
+                self.mask.contains_vector(vector_id)
+            }
+        }
+
+        impl SearchFilter for ThreadProbe {
+            fn selectivity(&self) -> f32 {
+                self.mask.selectivity()
+            }
+
+            fn candidate_count(&self) -> usize {
+                self.mask.candidate_count()
+            }
+
+            fn total_vector_num(&self) -> usize {
+                self.mask.total_vector_num()
+            }
+
+            fn iter_candidates(&self) -> Box<dyn Iterator<Item = u32> + '_> {
+                Box::new(self.mask.iter_candidates())
+            }
+
+            fn candidate_ids(&self) -> Vec<u32> {
+                *self.collected_on.lock() = std::thread::current().name().map(str::to_string);
+                self.mask.candidate_ids()
+            }
+        }
+
+        let on_pool = || {
+            std::thread::current()
+                .name()
+                .is_some_and(|name| name.starts_with("vector-index-"))
+        };
+        // Three threads, so the pool size cannot coincide with the global pool's
+        let pool = Arc::new(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(3)
+                .thread_name(|i| format!("vector-index-{i}"))
+                .build()
+                .unwrap(),
+        );
+        let config = AdapterConfig::new().with_thread_pool(Arc::clone(&pool));
+        assert_eq!(config.install(rayon::current_num_threads), 3);
+        assert!(config.install(on_pool));
+        assert!(config.install(|| (0..64).into_par_iter().all(|_| on_pool())));
+        assert!(!on_pool());
+
+        let vectors = test_vectors(PARALLEL_BRUTE_FORCE_MIN_CANDIDATES);
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        let mut adapter = InMemANNAdapter::with_config(
+            create_vector_index_config(TEST_DIM, vectors.len() * 2),
+            config,
+        )?;
+        let split = vectors.len() - 10;
+        adapter.build(&refs[..split])?;
+        adapter.insert(&refs[split..])?;
+        assert_eq!(adapter.size(), vectors.len());
+        let results = adapter.ann_search(&vectors[split + 5].1, 1, 20, None, false)?;
+        assert_eq!(results[0].0, vectors[split + 5].0);
+
+        let probe = ThreadProbe {
+            mask: FilterMask::new((0..vectors.len() as u32).collect(), adapter.size()),
+            collected_on: Mutex::new(None),
+        };
+        let results = adapter.brute_force_search(&vectors[7].1, 3, &probe)?;
+        assert_eq!(results[0].0, vectors[7].0);
+        let collected_on = probe.collected_on.lock().clone();
+        assert!(collected_on.is_some_and(|name| name.starts_with("vector-index-")));
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_quality() -> StorageResult<()> {
+        assert_eq!(Quality::Fast.l_value(10, 1_000_000), 32);
+        assert_eq!(Quality::Balanced.l_value(10, 1024), 64 + 80);
+        assert_eq!(Quality::Accurate.l_value(100, 1024), 800 + 160);
+        // Never below k, never wider than needed for a tiny index
+        assert_eq!(Quality::Accurate.l_value(5, 20), 20);
+        assert_eq!(Quality::Fast.l_value(50, 10), 50);
+        assert!(Quality::Fast.l_value(10, 1 << 20) < Quality::Balanced.l_value(10, 1 << 20));
+        assert!(Quality::Balanced.l_value(10, 1 << 20) < Quality::Accurate.l_value(10, 1 << 20));
+
+        let vectors = test_vectors(60);
+        let adapter = build_adapter(&vectors)?;
+        for quality in [Quality::Fast, Quality::Balanced, Quality::Accurate] {
+            let results = adapter.search_quality(&vectors[7].1, 5, quality, None)?;
+            assert_eq!(results.len(), 5);
+            assert_eq!(results[0].0, vectors[7].0);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_results_have_distinct_node_ids() -> StorageResult<()> {
+        let vectors = test_vectors(30);
+        let adapter = build_adapter(&vectors)?;
+
+        // Corrupt the mapping so vector 1 also resolves to node of vector 0
+        adapter.vector_to_node.set(1, vectors[0].0)?;
+
+        let mask = FilterMask::new(vec![0, 1, 2], adapter.size());
+        let results = adapter.search(&vectors[0].1, 3, 30, Some(&mask), false)?;
+        assert_eq!(
+            results.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![vectors[0].0, vectors[2].0]
+        );
+        assert_eq!(results[0].1, 0.0);
+        assert_eq!(adapter.stats().duplicate_results_dropped, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_filtered_search_round_trip_after_load() -> StorageResult<()> {
+        let vectors = test_vectors(60);
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        let mut adapter = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 80))?;
+        adapter.build(&refs[..50])?;
+        adapter.insert(&refs[50..])?;
+        adapter.soft_delete(&[1003, 1042])?;
+
+        // Filters are expressed in vector IDs, exactly as an external bitmap would be
+        let total = adapter.next_vector_id.load(Ordering::Relaxed) as usize;
+        let sparse_mask = FilterMask::new(vec![1, 3, 5, 52, 55], total);
+        let dense_mask = FilterMask::new((0..total as u32).step_by(2).collect(), total);
+        let query = &vectors[20].1;
+        let before_sparse = adapter.search(query, 4, 60, Some(&sparse_mask), false)?;
+        let before_dense = adapter.search(query, 5, 60, Some(&dense_mask), false)?;
+
+        let mut path = std::env::temp_dir();
+        path.push(format!(
+            "vector_index_round_trip_{}.bin",
+            std::process::id()
+        ));
+        let path_str = path.to_str().unwrap();
+        adapter.save(path_str)?;
+
+        let mut loaded = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 10))?;
+        loaded.load(path_str)?;
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(loaded.size(), adapter.size());
+        assert_eq!(loaded.provenance(), adapter.provenance());
+        for (node_id, _) in &vectors {
+            assert_eq!(
+                loaded.node_to_vector_id(*node_id),
+                adapter.node_to_vector_id(*node_id)
+            );
+        }
+        assert_eq!(
+            loaded.search(query, 4, 60, Some(&sparse_mask), false)?,
+            before_sparse
+        );
+        assert_eq!(
+            loaded.search(query, 5, 60, Some(&dense_mask), false)?,
+            before_dense
+        );
+        // Deleted nodes stay deleted and new inserts continue after the saved ID range
+        assert!(!loaded.contains_all(&[1003])[0]);
+        let extra = vec![0.5f32; TEST_DIM];
+        loaded.insert(&[(5000, extra.as_slice())])?;
+        assert_eq!(loaded.node_to_vector_id(5000), Some(total as u32));
+        Ok(())
+    }
+
+    #[test]
+    fn test_load_restores_saved_graph() -> StorageResult<()> {
+        // A multi-threaded build links nondeterministically, so only a restored graph searches
+        // exactly like the saved one
+        let vectors = test_vectors(80);
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        let mut config = create_vector_index_config(TEST_DIM, 80);
+        config.index_write_parameter.num_threads = 4;
+        config.index_write_parameter.max_degree = 8;
+        let mut adapter = InMemANNAdapter::new(config.clone())?;
+        adapter.build(&refs)?;
+        adapter.soft_delete(&[1010])?;
+
+        let mut path = std::env::temp_dir();
+        path.push(format!("vector_index_graph_{}.bin", std::process::id()));
+        let path_str = path.to_str().unwrap();
+        adapter.save(path_str)?;
+        let mut loaded = InMemANNAdapter::new(config)?;
+        loaded.load(path_str)?;
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(
+            loaded.inner.read().export_graph().unwrap(),
+            adapter.inner.read().export_graph().unwrap()
+        );
+        assert_eq!(loaded.entry_vector_id(), adapter.entry_vector_id());
+        for (_, query) in &vectors {
+            assert_eq!(
+                loaded.ann_search(query, 5, 10, None, false)?,
+                adapter.ann_search(query, 5, 10, None, false)?
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_empty_build_then_insert() -> StorageResult<()> {
+        let mut adapter = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 0))?;
+        assert!(matches!(
+            adapter.search(&[0.0; TEST_DIM], 1, 10, None, false),
+            Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt))
+        ));
+
+        // Empty input is a no-op for every mutator; build still marks the index as built
+        adapter.build(&[])?;
+        adapter.insert(&[])?;
+        adapter.soft_delete(&[])?;
+        assert_eq!(adapter.size(), 0);
+        assert!(
+            adapter
+                .search(&[0.0; TEST_DIM], 3, 10, None, false)?
+                .is_empty()
+        );
+
+        let vectors = test_vectors(20);
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        adapter.insert(&refs[..15])?;
+        adapter.insert(&refs[15..])?;
+        assert_eq!(adapter.size(), 20);
+        assert_eq!(adapter.node_to_vector_id(1015), Some(15));
+
+        let results = adapter.search(&vectors[7].1, 3, 20, None, false)?;
+        assert_eq!(results[0].0, vectors[7].0);
+
+        // Rebuilding with nothing discards the previous contents
+        adapter.build(&[])?;
+        assert_eq!(adapter.size(), 0);
+        assert_eq!(adapter.stats().vector_count, 0);
+        assert!(
+            adapter
+                .search(&vectors[7].1, 3, 20, None, false)?
+                .is_empty()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_kth_distance_matches_search() -> StorageResult<()> {
+        let vectors = test_vectors(40);
+        let adapter = build_adapter(&vectors)?;
+        let query = &vectors[12].1;
+
+        let results = adapter.search(query, 5, 40, None, false)?;
+        assert_eq!(
+            adapter.kth_distance(query, 5, 40, None)?,
+            Some(results[4].1)
+        );
+
+        // Low selectivity takes the brute-force path
+        let mask = FilterMask::new(vec![2, 9, 17], adapter.size());
+        let filtered = adapter.search(query, 3, 40, Some(&mask), false)?;
+        assert_eq!(
+            adapter.kth_distance(query, 3, 40, Some(&mask))?,
+            Some(filtered[2].1)
+        );
+        assert_eq!(adapter.kth_distance(query, 4, 40, Some(&mask))?, None);
+        assert_eq!(adapter.kth_distance(query, 0, 40, None)?, None);
+        assert!(matches!(
+            adapter.kth_distance(&query[1..], 3, 40, Some(&mask)),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension { .. }
+            ))
+        ));
+
+        // A node reached through two vector IDs counts once
+        adapter.vector_to_node.set(9, vectors[2].0)?;
+        assert_eq!(adapter.kth_distance(query, 3, 40, Some(&mask))?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_zero_norm_policies() -> StorageResult<()> {
+        let mut vectors = test_vectors(20);
+        vectors[4].1 = vec![0.0; TEST_DIM];
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        let zero = [0.0f32; TEST_DIM];
+        let adapter_with = |policy| {
+            InMemANNAdapter::with_config(
+                create_vector_index_config(TEST_DIM, 20),
+                AdapterConfig::new()
+                    .with_normalize_vectors(true)
+                    .with_zero_norm_policy(policy),
+            )
+        };
+
+        let mut reject = adapter_with(ZeroNormPolicy::Reject)?;
+        assert!(matches!(
+            reject.build(&refs),
+            Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(_)))
+        ));
+        reject.build(&refs[5..])?;
+        assert!(reject.search(&zero, 1, 20, None, false).is_err());
+
+        let mut skip = adapter_with(ZeroNormPolicy::Skip)?;
+        skip.build(&refs)?;
+        assert_eq!(skip.size(), 19);
+        assert!(!skip.contains_all(&[vectors[4].0])[0]);
+        assert!(skip.search(&zero, 3, 20, None, false)?.is_empty());
+
+        let mut origin = adapter_with(ZeroNormPolicy::TreatAsOrigin)?;
+        origin.build(&refs)?;
+        assert_eq!(origin.size(), 20);
+        // The raw zero vector is at squared distance 1 from every unit vector
+        let results = origin.search(&vectors[7].1, 20, 20, None, false)?;
+        let zero_hit = results.iter().find(|(id, _)| *id == vectors[4].0).unwrap();
+        assert!((zero_hit.1 - 1.0).abs() < 1e-4);
+
+        // Normalization makes scaled copies of a vector equivalent
+        let scaled: Vec<f32> = vectors[7].1.iter().map(|x| x * 10.0).collect();
+        assert_eq!(
+            origin.search(&scaled, 1, 20, None, false)?[0].0,
+            vectors[7].0
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_ann_search_vector_ids() -> StorageResult<()> {
+        let vectors = test_vectors(30);
+        let adapter = build_adapter(&vectors)?;
+        let query = &vectors[11].1;
+
+        let raw = adapter.ann_search_vector_ids(query, 5, 30)?;
+        let by_node = adapter.search(query, 5, 30, None, false)?;
+        assert_eq!(raw.len(), by_node.len());
+        for ((vector_id, raw_distance), (node_id, distance)) in raw.iter().zip(&by_node) {
+            assert_eq!(adapter.node_to_vector_id(*node_id), Some(*vector_id));
+            assert_eq!(raw_distance, distance);
+        }
+        assert_eq!(adapter.stats().ann_searches, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_remap_node_ids() -> StorageResult<()> {
+        let mut unbuilt = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 20))?;
+        assert!(matches!(
+            unbuilt.remap_node_ids(&[(1000, 2000)]),
+            Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt))
+        ));
+
+        let vectors = test_vectors(20);
+        let mut adapter = build_adapter(&vectors)?;
+        let vector_id_of_1005 = adapter.node_to_vector_id(1005);
+
+        // Plain rename plus a swap of two existing IDs
+        adapter.remap_node_ids(&[(1005, 7005), (1001, 1002), (1002, 1001)])?;
+        assert_eq!(adapter.node_to_vector_id(7005), vector_id_of_1005);
+        assert_eq!(adapter.node_to_vector_id(1005), None);
+        assert_eq!(adapter.node_to_vector_id(1001), Some(2));
+        assert_eq!(adapter.node_to_vector_id(1002), Some(1));
+        assert_eq!(adapter.size(), 20);
+        assert_eq!(
+            adapter.search(&vectors[5].1, 1, 20, None, false)?[0].0,
+            7005
+        );
+
+        assert!(matches!(
+            adapter.remap_node_ids(&[(1005, 9000)]),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::NodeIdNotFound { node_id: 1005 }
+            ))
+        ));
+        assert!(matches!(
+            adapter.remap_node_ids(&[(1003, 1004)]),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::DuplicateNodeId { node_id: 1004 }
+            ))
+        ));
+        assert!(
+            adapter
+                .remap_node_ids(&[(1003, 9000), (1004, 9000)])
+                .is_err()
+        );
+        assert_eq!(adapter.node_to_vector_id(1003), Some(3));
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_memory_budget() -> StorageResult<()> {
+        let vectors = test_vectors(40);
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        // 10 + 20 candidate entries of 8 bytes fit, wider searches do not
+        let mut adapter = InMemANNAdapter::with_config(
+            create_vector_index_config(TEST_DIM, 40),
+            AdapterConfig::new().with_search_memory_budget(240),
+        )?;
+        adapter.build(&refs)?;
+        let query = &vectors[3].1;
+
+        assert_eq!(adapter.search(query, 10, 20, None, false)?.len(), 10);
+        assert!(matches!(
+            adapter.search(query, 10, 40, None, false),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::SearchBudgetExceeded {
+                    required: 400,
+                    budget: 240
+                }
+            ))
+        ));
+
+        // The brute-force heap is bounded by the candidate count, not by k
+        let mask = FilterMask::new(vec![1, 2, 3], adapter.size());
+        assert_eq!(
+            adapter
+                .search(query, 1_000, 1_000, Some(&mask), false)?
+                .len(),
+            3
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_provenance_captured_at_build() -> StorageResult<()> {
+        let mut adapter = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 10))?;
+        assert!(adapter.provenance().is_none());
+
+        let vectors = test_vectors(10);
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        adapter.build(&refs)?;
+        let provenance = adapter.provenance().unwrap();
+        assert_eq!(provenance.crate_version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(provenance.metric, "L2");
+        assert_eq!(provenance.dimension, TEST_DIM);
+        assert_eq!(provenance.max_degree, 64);
+        assert_eq!(provenance.build_list_size, 100);
+        assert!(!provenance.normalized);
+        assert!(provenance.built_at_unix_secs > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_estimate_recall_selfcheck() -> StorageResult<()> {
+        let vectors = test_vectors(60);
+        let adapter = build_adapter(&vectors)?;
+        let queries: Vec<&[f32]> = vectors
+            .iter()
+            .step_by(6)
+            .map(|(_, v)| v.as_slice())
+            .collect();
+
+        let estimate = adapter.estimate_recall_selfcheck(&queries, 5, 60)?;
+        assert!((0.0..=1.0).contains(&estimate));
+        // A search list covering the whole index has converged
+        assert_eq!(estimate, 1.0);
+        assert_eq!(adapter.estimate_recall_selfcheck(&[], 5, 60)?, 1.0);
+        assert_eq!(adapter.stats().total_searches, 0);
+
+        // A malformed query anywhere in the set, or bad parameters, fail the whole check
+        let mut malformed = queries.clone();
+        malformed.push(&[1.0; 3]);
+        assert!(matches!(
+            adapter.estimate_recall_selfcheck(&malformed, 5, 60),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension { .. }
+            ))
+        ));
+        assert!(matches!(
+            adapter.estimate_recall_selfcheck(&queries, 0, 60),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidSearchParams(_)
+            ))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_rescored_with_other_metric() -> StorageResult<()> {
+        let vectors = test_vectors(30);
+        let adapter = build_adapter(&vectors)?;
+        // Same direction as vector 25 but far away in L2 terms
+        let query: Vec<f32> = vectors[25].1.iter().map(|x| x * 0.01).collect();
+
+        let l2 = adapter.search_rescored(&query, 3, 30, None, DistanceMetric::L2)?;
+        let ids = |results: &[(u64, f32)]| results.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+        assert_eq!(ids(&l2), ids(&adapter.search(&query, 3, 30, None, false)?));
+
+        let cosine = adapter.search_rescored(&query, 3, 30, None, DistanceMetric::Cosine)?;
+        assert_eq!(cosine.len(), 3);
+        assert_eq!(cosine[0].0, vectors[25].0);
+        assert!(cosine[0].1.abs() < 1e-5);
+        assert!(cosine.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_duplicate_vector_policy() -> StorageResult<()> {
+        let mut vectors = test_vectors(10);
+        vectors[6].1 = vectors[2].1.clone();
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        let adapter_with = |policy| {
+            InMemANNAdapter::with_config(
+                create_vector_index_config(TEST_DIM, 10),
+                AdapterConfig::new().with_duplicate_vector_policy(policy),
+            )
+        };
+
+        let mut allow = adapter_with(DuplicateVectorPolicy::Allow)?;
+        allow.build(&refs)?;
+        assert_eq!(allow.size(), 10);
+
+        assert_eq!(allow.stats().duplicate_vectors, 0);
+
+        let mut warn = adapter_with(DuplicateVectorPolicy::Warn)?;
+        warn.build(&refs)?;
+        assert_eq!(warn.size(), 10);
+        assert_eq!(warn.stats().duplicate_vectors, 1);
+        warn.build(&refs[..6])?;
+        assert_eq!(warn.stats().duplicate_vectors, 0);
+
+        let mut reject = adapter_with(DuplicateVectorPolicy::Reject)?;
+        let error = reject.build(&refs).unwrap_err();
+        assert!(error.to_string().contains("1002 and 1006"));
+        assert_eq!(reject.size(), 0);
+        reject.build(&refs[..6])?;
+        assert_eq!(reject.size(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_top_returned_and_reset() -> StorageResult<()> {
+        let vectors = test_vectors(30);
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        let mut adapter = InMemANNAdapter::with_config(
+            create_vector_index_config(TEST_DIM, 30),
+            AdapterConfig::new().with_result_frequency_tracking(100),
+        )?;
+        adapter.build(&refs)?;
+
+        for _ in 0..3 {
+            adapter.search(&vectors[4].1, 1, 30, None, false)?;
+        }
+        let mask = FilterMask::new(vec![9], adapter.size());
+        adapter.search(&vectors[4].1, 1, 30, Some(&mask), false)?;
+
+        assert_eq!(adapter.top_returned(2), vec![(1004, 3), (1009, 1)]);
+        assert_eq!(adapter.stats().total_searches, 4);
+
+        adapter.reset_stats();
+        assert!(adapter.top_returned(5).is_empty());
+        let stats = adapter.stats();
+        assert_eq!(stats.total_searches, 0);
+        assert_eq!(stats.vector_count, 30);
+
+        // Tracking is opt-in
+        assert!(build_adapter(&vectors)?.top_returned(5).is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_validate_build_inputs() {
+        let adapter = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 10)).unwrap();
+        let data = test_vectors(10);
+        let vectors: Vec<(u64, &[f32])> = data.iter().map(|(id, v)| (*id, v.as_slice())).collect();
+
+        let plan = adapter.validate_build_inputs(&vectors).unwrap();
+        assert_eq!(plan.vector_count, 10);
+        assert_eq!(plan.dimension, TEST_DIM);
+        assert!(plan.estimated_memory_bytes >= 10 * TEST_DIM * 4);
+
+        // A wrong dimension deep in the batch is caught, not just on the first vector
+        let short = vec![0.5f32; TEST_DIM - 1];
+        let mut bad = vectors.clone();
+        bad[7].1 = short.as_slice();
+        assert!(matches!(
+            adapter.validate_build_inputs(&bad),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension { .. }
+            ))
+        ));
+
+        let mut nan = data[3].1.clone();
+        nan[5] = f32::NAN;
+        let mut bad = vectors.clone();
+        bad[3].1 = nan.as_slice();
+        assert!(matches!(
+            adapter.validate_build_inputs(&bad),
+            Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(_)))
+        ));
+
+        let mut bad = vectors.clone();
+        bad[9].0 = bad[2].0;
+        assert!(matches!(
+            adapter.validate_build_inputs(&bad),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::DuplicateNodeId { node_id: 1002 }
+            ))
+        ));
+
+        // Nothing was built by the dry run, and a failed build keeps the previous index
+        assert_eq!(adapter.mapping_count(), 0);
+        let mut adapter = adapter;
+        adapter.build(&vectors).unwrap();
+        assert!(adapter.build(&bad).is_err());
+        assert_eq!(adapter.mapping_count(), 10);
+    }
+
+    #[test]
+    fn test_retain_deleted_mappings() -> StorageResult<()> {
+        let vectors = test_vectors(20);
+        let refs: Vec<(u64, &[f32])> = vectors.iter().map(|(id, v)| (*id, v.as_slice())).collect();
+
+        // Disabled by default: deletions leave no trace
+        let mut adapter = build_adapter(&vectors)?;
+        adapter.soft_delete(&[1003])?;
+        assert_eq!(adapter.was_deleted(1003), None);
+
+        let mut adapter = InMemANNAdapter::with_config(
+            create_vector_index_config(TEST_DIM, 40),
+            AdapterConfig::new().with_retain_deleted_mappings(true),
+        )?;
+        adapter.build(&refs)?;
+        let before = Instant::now();
+        adapter.soft_delete(&[1003, 1007])?;
+        let deleted_at = adapter.was_deleted(1003).expect("deletion recorded");
+        assert!(deleted_at >= before);
+        assert!(adapter.was_deleted(1007).is_some());
+        assert_eq!(adapter.was_deleted(1004), None);
+        assert_eq!(adapter.contains_all(&[1003]), vec![false]);
+
+        // Re-inserting the node makes it live again
+        adapter.insert(&[(1007, vectors[7].1.as_slice())])?;
+        assert_eq!(adapter.was_deleted(1007), None);
+
+        // So does remapping a live node to the deleted ID
+        adapter.remap_node_ids(&[(1004, 1003)])?;
+        assert_eq!(adapter.was_deleted(1003), None);
+        assert_eq!(adapter.was_deleted(1004), None);
+        adapter.remap_node_ids(&[(1003, 1004)])?;
+
+        // A rebuild compacts the index and forgets deletions
+        adapter.build(&refs)?;
+        assert_eq!(adapter.was_deleted(1003), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_with_prf() -> StorageResult<()> {
+        let vectors = test_vectors(60);
+        let adapter = build_adapter(&vectors)?;
+        let query = &vectors[12].1;
+
+        // alpha = 1 keeps the original query
+        assert_eq!(
+            adapter.search_with_prf(query, 5, 50, 3, 1.0, None)?,
+            adapter.search(query, 5, 50, None, false)?
+        );
+        assert!(adapter.search_with_prf(query, 5, 50, 3, 1.5, None).is_err());
+
+        // Feedback from the query's own cluster keeps the expanded search in that cluster
+        let results = adapter.search_with_prf(query, 5, 50, 3, 0.5, None)?;
+        assert_eq!(results.len(), 5);
+        assert!(
+            results
+                .iter()
+                .all(|(node_id, _)| (1010..1020).contains(node_id))
+        );
+
+        // Filters apply to both passes
+        let mask = FilterMask::new(vec![40, 41, 42], adapter.size());
+        let results = adapter.search_with_prf(query, 2, 50, 2, 0.3, Some(&mask))?;
+        assert!(
+            results
+                .iter()
+                .all(|(node_id, _)| (1040..1043).contains(node_id))
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_dimension_stats() -> StorageResult<()> {
+        let empty = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 10))?;
+        assert!(empty.dimension_stats()?.is_empty());
+
+        let vectors = test_vectors(20);
+        let mut adapter = build_adapter(&vectors)?;
+        assert_eq!(adapter.iter_vectors().count(), 20);
+
+        let stats = adapter.dimension_stats()?;
+        assert_eq!(stats.len(), TEST_DIM);
+        // Dimension 0 holds 0..9 and 20..29
+        assert_eq!((stats[0].min, stats[0].max), (0.0, 29.0));
+        assert!((stats[0].mean - 14.5).abs() < 1e-9);
+        assert!((stats[0].variance - 108.25).abs() < 1e-6);
+        // Trailing dimensions are always zero: a dead dimension
+        assert_eq!(
+            stats[TEST_DIM - 1],
+            DimStat {
+                min: 0.0,
+                max: 0.0,
+                mean: 0.0,
+                variance: 0.0
+            }
+        );
+
+        // Deleted vectors no longer contribute
+        adapter.soft_delete(&(1010..1020).collect::<Vec<_>>())?;
+        assert_eq!(adapter.dimension_stats()?[0].max, 9.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_fail_fast_during_build() -> StorageResult<()> {
+        let vectors = test_vectors(20);
+        let refs: Vec<(u64, &[f32])> = vectors.iter().map(|(id, v)| (*id, v.as_slice())).collect();
+        let mut adapter = InMemANNAdapter::with_config(
+            create_vector_index_config(TEST_DIM, 20),
+            AdapterConfig::new().with_fail_fast_during_build(true),
+        )?;
+        let gate = adapter.build_gate();
+        assert!(!gate.is_building());
+        adapter.build(&refs)?;
+        // The guard clears the flag once build returns, successfully or not
+        assert!(!gate.is_building());
+        assert!(adapter.build(&refs[..0]).is_ok());
+        adapter.build(&refs)?;
+
+        // Simulate a build in progress on another handle
+        let guard = BuildingGuard::enter(&gate);
+        assert!(matches!(
+            gate.check(),
+            Err(StorageError::VectorIndex(VectorIndexError::IndexRebuilding))
+        ));
+        let mask = FilterMask::new(vec![1, 2], adapter.size());
+        for filter in [None, Some(&mask as &dyn SearchFilter)] {
+            assert!(matches!(
+                adapter.search(&vectors[0].1, 2, 20, filter, false),
+                Err(StorageError::VectorIndex(VectorIndexError::IndexRebuilding))
+            ));
+        }
+        drop(guard);
+        assert_eq!(
+            adapter.search(&vectors[0].1, 1, 20, None, false)?[0].0,
+            1000
+        );
+
+        // Without fail-fast the flag is informational only
+        let plain = build_adapter(&vectors)?;
+        let _guard = BuildingGuard::enter(&plain.build_gate());
+        assert!(plain.search(&vectors[0].1, 1, 20, None, false).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn test_f64_ingest() -> StorageResult<()> {
+        let vectors: Vec<(u64, Vec<f64>)> = test_vectors(10)
+            .into_iter()
+            .map(|(id, v)| (id, v.into_iter().map(f64::from).collect()))
+            .collect();
+        let refs: Vec<(u64, &[f64])> = vectors.iter().map(|(id, v)| (*id, v.as_slice())).collect();
+
+        // Conversion must be opted into
+        let mut adapter = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 20))?;
+        assert!(matches!(
+            adapter.build_f64(&refs),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::UnsupportedOperation(_)
+            ))
+        ));
+
+        let mut huge = vec![0.0f64; TEST_DIM];
+        huge[0] = 1e300;
+        let config = create_vector_index_config(TEST_DIM, 20);
+        let mut adapter = InMemANNAdapter::with_config(
+            config.clone(),
+            AdapterConfig::new().with_f64_ingest(F64IngestPolicy::Reject),
+        )?;
+        adapter.build_f64(&refs)?;
+        assert_eq!(adapter.size(), 10);
+        let query: Vec<f32> = vectors[4].1.iter().map(|&x| x as f32).collect();
+        assert_eq!(adapter.search(&query, 1, 10, None, false)?[0].0, 1004);
+        assert!(matches!(
+            adapter.insert_f64(&[(2000, huge.as_slice())]),
+            Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(_)))
+        ));
+
+        let mut adapter = InMemANNAdapter::with_config(
+            config,
+            AdapterConfig::new().with_f64_ingest(F64IngestPolicy::Clamp),
+        )?;
+        adapter.build_f64(&refs)?;
+        adapter.insert_f64(&[(2000, huge.as_slice())])?;
+        assert_eq!(
+            adapter.get_vectors(&[2000])?[0].as_ref().unwrap()[0],
+            f32::MAX
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_fragmentation_report() -> StorageResult<()> {
+        let empty = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 10))?;
+        assert_eq!(empty.fragmentation_report(), FragmentationReport::default());
+
+        let vectors = test_vectors(20);
+        let mut adapter = build_adapter(&vectors)?;
+        let report = adapter.fragmentation_report();
+        assert_eq!((report.allocated_slots, report.live_vectors), (20, 20));
+        assert!(!report.recommend_compaction);
+
+        adapter.soft_delete(&[1000, 1001])?;
+        let report = adapter.fragmentation_report();
+        assert_eq!(report.deleted_vectors, 2);
+        assert!((report.deleted_ratio - 0.1).abs() < 1e-12);
+        assert!(report.reclaimable_bytes >= 2 * TEST_DIM * 4);
+        assert!(!report.recommend_compaction);
+
+        adapter.soft_delete(&[1002, 1003])?;
+        assert!(adapter.fragmentation_report().recommend_compaction);
+
+        // A rebuild reclaims everything
+        let live: Vec<(u64, &[f32])> = vectors[4..]
+            .iter()
+            .map(|(id, v)| (*id, v.as_slice()))
+            .collect();
+        adapter.build(&live)?;
+        assert_eq!(adapter.fragmentation_report().deleted_vectors, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rebuild_mappings() -> StorageResult<()> {
+        let vectors = test_vectors(20);
+        let refs: Vec<(u64, &[f32])> = vectors.iter().map(|(id, v)| (*id, v.as_slice())).collect();
+        let mut adapter = InMemANNAdapter::with_config(
+            create_vector_index_config(TEST_DIM, 20),
+            AdapterConfig::new().with_retain_deleted_mappings(true),
+        )?;
+        // Shuffled input: vector IDs follow sorted node-ID order
+        let mut shuffled = refs.clone();
+        shuffled.reverse();
+        adapter.build(&shuffled)?;
+        adapter.soft_delete(&[1005])?;
+        let expected = adapter.search(&vectors[3].1, 3, 20, None, false)?;
+
+        // Lose the mappings
+        adapter.node_to_vector.clear();
+        adapter.vector_to_node.clear();
+        assert!(
+            adapter
+                .search(&vectors[3].1, 3, 20, None, false)?
+                .is_empty()
+        );
+
+        let order: Vec<u64> = vectors.iter().map(|(id, _)| *id).collect();
+        assert!(adapter.rebuild_mappings(&order[..19]).is_err());
+        let mut duplicated = order.clone();
+        duplicated[1] = duplicated[0];
+        assert!(matches!(
+            adapter.rebuild_mappings(&duplicated),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::DuplicateNodeId { node_id: 1000 }
+            ))
+        ));
+
+        adapter.rebuild_mappings(&order)?;
+        assert_eq!(adapter.size(), 19);
+        assert_eq!(adapter.contains_all(&[1005, 1006]), vec![false, true]);
+        assert_eq!(adapter.search(&vectors[3].1, 3, 20, None, false)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_excluding_overfetch() -> StorageResult<()> {
+        let vectors = test_vectors(40);
+        let adapter = build_adapter(&vectors)?;
+        let query = &vectors[0].1;
+
+        // Excluding the whole first cluster forces the fetch past the default 2x over-fetch
+        let excluded: Vec<u64> = (1000..1010).collect();
+        let results = adapter.search_excluding(query, 5, 20, None, &excluded)?;
+        assert_eq!(results.len(), 5);
+        assert!(results.iter().all(|(id, _)| !excluded.contains(id)));
+        assert!(results.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+
+        // Excluding nearly everything returns what is left instead of looping
+        let almost_all: Vec<u64> = (1000..1038).collect();
+        let results = adapter.search_excluding(query, 5, 20, None, &almost_all)?;
+        // A full-size ANN fetch may still miss a vector, so only the survivors are certain
+        assert!(!results.is_empty());
+        assert!(results.iter().all(|(id, _)| *id >= 1038));
+
+        // The multiplier is configurable and counts searches accordingly
+        let mut adapter = InMemANNAdapter::with_config(
+            create_vector_index_config(TEST_DIM, 40),
+            AdapterConfig::new().with_post_process_overfetch(4.0),
+        )?;
+        let refs: Vec<(u64, &[f32])> = vectors.iter().map(|(id, v)| (*id, v.as_slice())).collect();
+        adapter.build(&refs)?;
+        let results = adapter.search_excluding(query, 3, 20, None, &[1000, 1001])?;
+        assert_eq!(results.len(), 3);
+        assert_eq!(adapter.stats().total_searches, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_knee() -> StorageResult<()> {
+        // Two tight pairs far apart: a query at the first cluster should return just that pair
+        let mut vectors = Vec::new();
+        for (node_id, x) in [(1, 1.0f32), (2, 1.5), (3, 100.0), (4, 100.5)] {
+            let mut vector = vec![0.0f32; TEST_DIM];
+            vector[0] = x;
+            vectors.push((node_id, vector));
+        }
+        let adapter = build_adapter(&vectors)?;
+        let mut query = vec![0.0f32; TEST_DIM];
+        query[0] = 1.2;
+
+        let ids = |results: Vec<(u64, f32)>| -> Vec<u64> {
+            results.into_iter().map(|(node_id, _)| node_id).collect()
+        };
+        assert_eq!(
+            ids(adapter.search_knee(&query, 10, 1, 4, 3.0, None)?),
+            vec![1, 2]
+        );
+        // min_results forces results past the knee, and no later jump cuts them; max_results
+        // caps them
+        assert_eq!(
+            ids(adapter.search_knee(&query, 10, 3, 4, 3.0, None)?),
+            vec![1, 2, 3, 4]
+        );
+        assert_eq!(
+            ids(adapter.search_knee(&query, 10, 1, 1, 3.0, None)?),
+            vec![1]
+        );
+        // A huge factor never cuts
+        assert_eq!(
+            adapter.search_knee(&query, 10, 1, 4, 1e30, None)?,
+            adapter.search(&query, 4, 10, None, false)?
+        );
+
+        assert!(adapter.search_knee(&query, 10, 1, 4, 0.5, None).is_err());
+        assert!(adapter.search_knee(&query, 10, 5, 4, 2.0, None).is_err());
+        Ok(())
+    }
 
-    IndexConfiguration {
-        index_write_parameter: write_params,
-        dist_metric: Metric::L2,
-        dim: dimension,
-        aligned_dim: dimension,
-        max_points: calculated_max_points,
-        num_frozen_pts: 0,
-        use_pq_dist: false,
-        num_pq_chunks: 0,
-        use_opq: false,
-        growth_potential: 2.0, // Pre-allocation capacity in InmemDataset of DiskANN to insert
+    #[test]
+    fn test_rng_seed_replay() -> StorageResult<()> {
+        let vectors = test_vectors(20);
+        let mut adapter = build_adapter(&vectors)?;
+        let query = &vectors[0].1;
+        assert_eq!(adapter.rng_seed(), 0);
+
+        adapter.set_rng_seed(42);
+        let first: Vec<u64> = (0..3)
+            .map(|_| {
+                adapter
+                    .search_debug(query, 2, 10, None)
+                    .map(|debug| debug.rng_seed)
+            })
+            .collect::<StorageResult<_>>()?;
+        assert_ne!(first[0], first[1]);
+
+        // Reseeding replays the same per-query seeds
+        adapter.set_rng_seed(42);
+        let replay: Vec<u64> = (0..3)
+            .map(|_| {
+                adapter
+                    .search_debug(query, 2, 10, None)
+                    .map(|debug| debug.rng_seed)
+            })
+            .collect::<StorageResult<_>>()?;
+        assert_eq!(first, replay);
+        assert_eq!(adapter.rng_seed(), 42);
+
+        let mut a = SearchRng::new(first[0]);
+        let mut b = SearchRng::new(first[0]);
+        assert_eq!(a.next_u64(), b.next_u64());
+        Ok(())
     }
-}
 
-#[cfg(test)]
-mod sharded_vector_map_tests {
-    use super::*;
+    #[test]
+    fn test_cosine_metric() -> StorageResult<()> {
+        // Same directions at very different magnitudes: cosine ignores the scale
+        let mut vectors = Vec::new();
+        for i in 0..20u64 {
+            let mut vector = vec![0.0f32; TEST_DIM];
+            let angle = i as f32 * 0.07;
+            let scale = if i % 2 == 0 { 1.0 } else { 500.0 };
+            vector[0] = angle.cos() * scale;
+            vector[1] = angle.sin() * scale;
+            vectors.push((i, vector));
+        }
+        let refs: Vec<(u64, &[f32])> = vectors.iter().map(|(id, v)| (*id, v.as_slice())).collect();
+        let mut query = vec![0.0f32; TEST_DIM];
+        query[0] = (0.07f32 * 5.0).cos();
+        query[1] = (0.07f32 * 5.0).sin();
+
+        let mut adapter = InMemANNAdapter::with_config(
+            create_vector_index_config(TEST_DIM, 20),
+            AdapterConfig::new().with_metric(IndexMetric::Cosine),
+        )?;
+        adapter.build(&refs)?;
+        assert_eq!(adapter.metric(), DistanceMetric::Cosine);
+        assert_eq!(adapter.provenance().unwrap().metric, "Cosine");
+        let ann = adapter.search(&query, 3, 20, None, false)?;
+        assert_eq!(ann[0].0, 5);
+        assert!(ann[0].1 < 1e-4);
+
+        // The brute-force path ranks by the same metric
+        let mask = FilterMask::new((0..20).collect(), adapter.size());
+        let brute = adapter.search(&query, 3, 20, Some(&mask), false)?;
+        let ids = |results: &[(u64, f32)]| results.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+        assert_eq!(ids(&brute), ids(&ann));
+
+        // A Cosine IndexConfiguration selects the metric too
+        let mut config = create_vector_index_config(TEST_DIM, 20);
+        config.dist_metric = Metric::Cosine;
+        assert_eq!(
+            InMemANNAdapter::new(config)?.metric(),
+            DistanceMetric::Cosine
+        );
+        Ok(())
+    }
 
     #[test]
-    fn test_basic_operations_and_consistency() -> StorageResult<()> {
-        let map = ShardedVectorMap::new(4)?; // 2^4 = 16 shards  
-        assert_eq!(map.shard_count(), 16);
+    fn test_snapshot_format_header() -> StorageResult<()> {
+        let vectors = test_vectors(30);
+        let mut adapter = build_adapter(&vectors)?;
+        let query = &vectors[7].1;
+        let before = adapter.ann_search(query, 5, 30, None, false)?;
+
+        let mut path = std::env::temp_dir();
+        path.push(format!("vector_index_header_{}.bin", std::process::id()));
+        let path_str = path.to_str().unwrap().to_string();
+        adapter.save(&path_str)?;
+        let bytes = std::fs::read(&path).unwrap();
+        assert_eq!(&bytes[..8], &SNAPSHOT_MAGIC);
+        assert_eq!(bytes[8..12], SNAPSHOT_VERSION.to_le_bytes());
+
+        let mut loaded = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 10))?;
+        loaded.load(&path_str)?;
+        let ids =
+            |results: Vec<(u64, f32)>| results.into_iter().map(|(id, _)| id).collect::<Vec<_>>();
+        assert_eq!(
+            ids(loaded.ann_search(query, 5, 30, None, false)?),
+            ids(before)
+        );
+        // Stats survive the round trip; the searches above were counted on each side
+        let stats = loaded.stats();
+        assert_eq!(stats.vector_count, 30);
+        assert_eq!(stats.total_searches, 2);
+        assert_eq!(stats.timed_searches, 2);
+        assert!(stats.max_search_time_us >= adapter.stats().max_search_time_us);
+        assert_eq!(stats.build_time_ms, adapter.stats().build_time_ms);
+
+        // Or start counting afresh
+        let mut reset = InMemANNAdapter::with_config(
+            create_vector_index_config(TEST_DIM, 10),
+            AdapterConfig::new().with_reset_stats_on_load(true),
+        )?;
+        reset.load(&path_str)?;
+        let stats = reset.stats();
+        assert_eq!((stats.total_searches, stats.timed_searches), (0, 0));
+        assert_eq!(stats.vector_count, 30);
+        assert_eq!(stats.build_time_ms, adapter.stats().build_time_ms);
+
+        let expect_persistence_error = |bytes: &[u8]| {
+            std::fs::write(&path, bytes).unwrap();
+            let mut target =
+                InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 10)).unwrap();
+            match target.load(&path_str) {
+                Err(StorageError::VectorIndex(VectorIndexError::Persistence(message))) => message,
+                other => panic!("expected a persistence error, got {other:?}"),
+            }
+        };
+        let mut future = bytes.clone();
+        future[8..12].copy_from_slice(&(SNAPSHOT_VERSION + 1).to_le_bytes());
+        assert!(expect_persistence_error(&future).contains("version"));
+        assert!(expect_persistence_error(&bytes[12..]).contains("magic"));
+        assert!(expect_persistence_error(b"MGU").contains("magic"));
+        let _ = std::fs::remove_file(&path);
+        Ok(())
+    }
 
-        // Test basic set and get operations
-        map.set(0, 100)?;
-        map.set(15, 115)?;
+    #[test]
+    fn test_l2_distance_unaligned_fallback() -> StorageResult<()> {
+        for dim in [DIM_104, DIM_128, DIM_256] {
+            // A 64-byte aligned buffer with a spare element: `buf[1..=dim]` is misaligned
+            let mut query = AlignedBoxWithSlice::<f32>::new(dim + 16, 64).unwrap();
+            let mut stored = AlignedBoxWithSlice::<f32>::new(dim + 16, 64).unwrap();
+            for i in 0..dim + 16 {
+                query[i] = (i as f32 * 0.37).sin();
+                stored[i] = (i as f32 * 0.11).cos();
+            }
+            let misaligned_query = &query[1..=dim];
+            let misaligned_stored = &stored[1..=dim];
+            assert_ne!(misaligned_query.as_ptr().align_offset(64), 0);
+
+            // The same values copied to aligned buffers go through the SIMD kernel
+            let mut aligned_query = AlignedBoxWithSlice::<f32>::new(dim, 64).unwrap();
+            let mut aligned_stored = AlignedBoxWithSlice::<f32>::new(dim, 64).unwrap();
+            aligned_query.copy_from_slice(misaligned_query);
+            aligned_stored.copy_from_slice(misaligned_stored);
+            let simd = InMemANNAdapter::compute_l2_distance(&aligned_query, &aligned_stored)?;
+
+            for (q, s) in [
+                (misaligned_query, misaligned_stored),
+                (misaligned_query, &aligned_stored[..]),
+                (&aligned_query[..], misaligned_stored),
+            ] {
+                let distance = InMemANNAdapter::compute_l2_distance(q, s)?;
+                assert!((distance - simd).abs() <= 1e-4 * simd.max(1.0));
+            }
+        }
+        Ok(())
+    }
 
-        assert_eq!(map.get(0), Some(100));
-        assert_eq!(map.get(15), Some(115));
-        assert_eq!(map.get(999), None); // Non-existent
+    #[test]
+    fn test_arbitrary_dimensions() -> StorageResult<()> {
+        // Dimensions without a SIMD specialization take the scalar kernel
+        for dim in [3, 100, 384, 768] {
+            let query: Vec<f32> = (0..dim).map(|i| i as f32).collect();
+            let stored = vec![1.0f32; dim];
+            let expected: f32 = query.iter().map(|&x| (x - 1.0) * (x - 1.0)).sum();
+            let distance = InMemANNAdapter::compute_l2_distance(&query, &stored)?;
+            assert!((distance - expected).abs() <= 1e-4 * expected);
+        }
+        assert!(InMemANNAdapter::compute_l2_distance(&[1.0; 3], &[1.0; 4]).is_err());
+
+        // DiskANN itself needs a supported aligned dimension; reject others up front
+        for (dim, aligned_dim) in [(384, 384), (768, 768)] {
+            let mut config = create_vector_index_config(dim, 10);
+            config.aligned_dim = aligned_dim;
+            assert!(matches!(
+                InMemANNAdapter::new(config),
+                Err(StorageError::VectorIndex(
+                    VectorIndexError::InvalidDimension { actual, .. }
+                )) if actual == aligned_dim
+            ));
+        }
+        // A raw dimension that does not fit the aligned one names both
+        for dim in [0, 200] {
+            let mut config = create_vector_index_config(dim, 10);
+            config.aligned_dim = DIM_128;
+            assert!(matches!(
+                InMemANNAdapter::new(config),
+                Err(StorageError::VectorIndex(VectorIndexError::Configuration(message)))
+                    if message.contains(&format!("dim {dim} and aligned_dim {DIM_128}"))
+            ));
+        }
 
-        // Test consistency between get() and contains_key()
-        for i in 16..36u32 {
-            // Before setting
-            assert_eq!(map.get(i), None);
-            assert!(!map.contains_key(i));
+        // A raw dimension below the aligned one is fine
+        let mut config = create_vector_index_config(100, 10);
+        config.aligned_dim = DIM_104;
+        let mut adapter = InMemANNAdapter::new(config)?;
+        assert_eq!(adapter.get_dimension(), 100);
 
-            // After setting
-            map.set(i, i as u64 + 500)?;
-            assert_eq!(map.get(i), Some(i as u64 + 500));
-            assert!(map.contains_key(i));
+        // No SIMD kernel covers 100 dimensions: graph search pads the query, brute force takes
+        // the scalar path over the unpadded prefix, and a query of the wrong length is an error
+        let vectors: Vec<(u64, Vec<f32>)> = (0..10)
+            .map(|i| (i, (0..100).map(|d| (i * 100 + d) as f32).collect()))
+            .collect();
+        let refs: Vec<(u64, &[f32])> = vectors.iter().map(|(id, v)| (*id, v.as_slice())).collect();
+        adapter.build(&refs)?;
+        let mask = FilterMask::new(vec![2, 3, 4], 10);
+        let results = adapter.search(&vectors[3].1, 2, 10, Some(&mask), false)?;
+        assert_eq!(results[0], (3, 0.0));
+        assert_eq!(adapter.search(&vectors[5].1, 1, 10, None, false)?[0].0, 5);
+        assert!(matches!(
+            adapter.search(&[0.0; 104], 2, 10, Some(&mask), false),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension { .. }
+            ))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_vector_index_trait_object() -> StorageResult<()> {
+        let vectors = test_vectors(30);
+        let index: Box<dyn VectorIndex> = Box::new(build_adapter(&vectors)?);
+
+        let unfiltered = index.search(&vectors[4].1, 3, 30, None, false)?;
+        assert_eq!(unfiltered[0].0, vectors[4].0);
+
+        let mut bitmap = bitvec::bitvec![0; index.size()];
+        for node_id in [vectors[7].0, vectors[21].0] {
+            bitmap.set(index.node_to_vector_id(node_id).unwrap() as usize, true);
         }
+        let mask = FilterMask::from_bitmap(bitmap);
+        let filtered = index.search(&vectors[4].1, 2, 30, Some(&mask), false)?;
+        let mut node_ids: Vec<u64> = filtered.iter().map(|&(node_id, _)| node_id).collect();
+        node_ids.sort_unstable();
+        assert_eq!(node_ids, vec![vectors[7].0, vectors[21].0]);
+        Ok(())
+    }
 
-        // Verify initial values still exist
-        assert!(map.contains_key(0));
-        assert!(map.contains_key(15));
-        assert!(!map.contains_key(999));
+    #[test]
+    fn test_batch_search_matches_search() -> StorageResult<()> {
+        let vectors = test_vectors(60);
+        let adapter = build_adapter(&vectors)?;
+        let queries: Vec<&[f32]> = vectors
+            .iter()
+            .step_by(7)
+            .map(|(_, v)| v.as_slice())
+            .collect();
+        let sparse = FilterMask::new(vec![1, 12, 33], adapter.size());
+
+        for mask in [None, Some(&sparse as &dyn SearchFilter)] {
+            let expected: Vec<Vec<u64>> = queries
+                .iter()
+                .map(|query| {
+                    adapter
+                        .search(query, 5, 60, mask, false)
+                        .map(|results| results.into_iter().map(|(node_id, _)| node_id).collect())
+                })
+                .collect::<StorageResult<_>>()?;
+            let before = adapter.stats();
+            assert_eq!(adapter.batch_search(&queries, 5, 60, mask)?, expected);
+
+            // The batch counts one search per query, under the same strategy
+            let after = adapter.stats();
+            let searched = queries.len() as u64;
+            assert_eq!(after.total_searches - before.total_searches, searched);
+            if mask.is_some() {
+                assert_eq!(
+                    after.brute_force_searches - before.brute_force_searches,
+                    searched
+                );
+            } else {
+                assert_eq!(after.ann_searches - before.ann_searches, searched);
+            }
+        }
+
+        assert!(adapter.batch_search(&[], 5, 60, None)?.is_empty());
+        let wrong_dim = [1.0f32; 3];
+        assert!(adapter.batch_search(&[&wrong_dim], 5, 60, None).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_usage_tracks_size() -> StorageResult<()> {
+        let vectors = test_vectors(40);
+        let mut adapter = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 80))?;
+        assert_eq!(adapter.memory_usage(), 0);
+
+        let refs: Vec<(u64, &[f32])> = vectors[..30]
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        adapter.build(&refs)?;
+        let after_build = adapter.memory_usage();
+        assert!(after_build >= 30 * TEST_DIM * std::mem::size_of::<f32>());
+        assert_eq!(adapter.stats().memory_usage, after_build);
+
+        let refs: Vec<(u64, &[f32])> = vectors[30..]
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        adapter.insert(&refs)?;
+        let after_insert = adapter.memory_usage();
+        assert!(after_insert > after_build);
+
+        // Soft-deleted slots keep their DiskANN data, so the estimate does not shrink
+        adapter.soft_delete(&[vectors[0].0, vectors[1].0])?;
+        assert!(adapter.memory_usage() >= 40 * adapter.slot_bytes());
+        Ok(())
+    }
+
+    #[test]
+    fn test_filter_strategy_thresholds() -> StorageResult<()> {
+        let vectors = test_vectors(100);
+        let adapter = build_adapter(&vectors)?;
+        let total = adapter.size();
+        let sparse = FilterMask::new((0..5).collect(), total);
+        let mid = FilterMask::new((0..100).step_by(4).collect(), total);
+        let broad = FilterMask::new((0..100).step_by(2).collect(), total);
+        assert!(matches!(
+            adapter.filter_strategy(&sparse),
+            SearchPath::BruteForce
+        ));
+        assert!(matches!(
+            adapter.filter_strategy(&mid),
+            SearchPath::PreFilter
+        ));
+        assert!(matches!(adapter.filter_strategy(&broad), SearchPath::Ann));
+
+        // Pre-filtered results only contain passing nodes and match an exact scan
+        let before = adapter.stats();
+        let results = adapter.search(&vectors[8].1, 5, 100, Some(&mid), false)?;
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0].0, vectors[8].0);
+        for (node_id, _) in &results {
+            assert!(mid.contains_vector(adapter.node_to_vector_id(*node_id).unwrap()));
+        }
+        let exact = adapter.brute_force_search(&vectors[8].1, 5, &mid)?;
+        if cfg!(feature = "simd") {
+            assert_eq!(results, exact);
+        } else {
+            // The scan's scalar kernel may round differently from DiskANN's own distances
+            for ((node_id, distance), (exact_id, exact_distance)) in results.iter().zip(&exact) {
+                assert_eq!(node_id, exact_id);
+                assert!((distance - exact_distance).abs() <= 1e-4 * exact_distance.max(1.0));
+            }
+        }
+        let after = adapter.stats();
+        assert_eq!(after.brute_force_searches, before.brute_force_searches + 1);
+
+        // Thresholds are configurable
+        let config = AdapterConfig::new().with_tuning(SearchTuning {
+            brute_force_selectivity: 0.3,
+            pre_filter_selectivity: 0.3,
+            ..Default::default()
+        });
+        let mut tuned =
+            InMemANNAdapter::with_config(create_vector_index_config(TEST_DIM, 100), config)?;
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        tuned.build(&refs)?;
+        assert!(matches!(
+            tuned.filter_strategy(&mid),
+            SearchPath::BruteForce
+        ));
+        assert!(matches!(tuned.filter_strategy(&broad), SearchPath::Ann));
+        Ok(())
+    }
+
+    #[test]
+    fn test_search_tuning_expansion_improves_recall() -> StorageResult<()> {
+        let vectors = test_vectors(200);
+        let mut adapter = build_adapter(&vectors)?;
+        // Half the vectors pass: broad enough for the post-filtered graph search
+        let mask = FilterMask::new((1..200).step_by(2).collect(), adapter.size());
+        let query = &vectors[100].1;
+        let exact: std::collections::HashSet<u64> = adapter
+            .brute_force_search(query, 20, &mask)?
+            .into_iter()
+            .map(|(node_id, _)| node_id)
+            .collect();
+        let recall = |results: &[(u64, f32)]| {
+            results
+                .iter()
+                .filter(|(node_id, _)| exact.contains(node_id))
+                .count()
+        };
+
+        // Without expansion only the passing share of the 20-entry search list survives
+        assert_eq!(adapter.tuning(), &SearchTuning::default());
+        let baseline = adapter.search(query, 20, 20, Some(&mask), false)?;
+        assert!(baseline.len() < 20);
+
+        adapter.set_tuning(SearchTuning {
+            min_expansion: 4.0,
+            max_expansion: 50.0,
+            expansion_log_coefficient: 2.0,
+            ..Default::default()
+        });
+        let expanded = adapter.search(query, 20, 20, Some(&mask), false)?;
+        assert_eq!(expanded.len(), 20);
+        assert!(recall(&expanded) > recall(&baseline));
+
+        let tuning = adapter.tuning();
+        assert_eq!(tuning.expansion(1.0), 4.0);
+        assert!(tuning.expansion(0.01) > tuning.expansion(0.5));
+        assert_eq!(tuning.expansion(1e-30), 50.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_compact_reclaims_deleted_slots() -> StorageResult<()> {
+        let vectors = test_vectors(40);
+        let config = AdapterConfig::new().with_retain_deleted_mappings(true);
+        let mut adapter =
+            InMemANNAdapter::with_config(create_vector_index_config(TEST_DIM, 80), config)?;
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        adapter.build(&refs)?;
+        let deleted: Vec<u64> = vectors.iter().step_by(4).map(|(id, _)| *id).collect();
+        adapter.soft_delete(&deleted)?;
+        let before = adapter.memory_usage();
+        assert_eq!(adapter.fragmentation_report().deleted_vectors, 10);
+
+        let stats = adapter.compact()?;
+        assert_eq!(stats.vector_count, 30);
+        assert!(stats.memory_usage < before);
+        let report = adapter.fragmentation_report();
+        assert_eq!((report.allocated_slots, report.deleted_vectors), (30, 0));
+        assert!(adapter.was_deleted(deleted[0]).is_none());
+
+        // Live nodes keep their vectors under dense vector IDs, in their previous order
+        let survivors: Vec<&(u64, Vec<f32>)> = vectors
+            .iter()
+            .filter(|(id, _)| !deleted.contains(id))
+            .collect();
+        for (expected_vector_id, (node_id, vector)) in survivors.iter().enumerate() {
+            assert_eq!(
+                adapter.node_to_vector_id(*node_id),
+                Some(expected_vector_id as u32)
+            );
+            assert_eq!(
+                adapter.get_vectors(&[*node_id])?[0].as_deref(),
+                Some(vector.as_slice())
+            );
+        }
+        let results = adapter.search(&survivors[5].1, 3, 30, None, false)?;
+        assert_eq!(results[0].0, survivors[5].0);
+        assert!(results.iter().all(|(id, _)| !deleted.contains(id)));
+
+        // The compacted index accepts inserts, including of previously deleted nodes
+        adapter.insert(&[(deleted[0], vectors[0].1.as_slice())])?;
+        assert_eq!(adapter.node_to_vector_id(deleted[0]), Some(30));
+
+        // Compacting an index whose vectors were all deleted leaves an empty built index
+        let all: Vec<u64> = adapter
+            .iter_vectors()
+            .map(|v| v.map(|(id, _)| id))
+            .collect::<StorageResult<_>>()?;
+        adapter.soft_delete(&all)?;
+        assert_eq!(adapter.compact()?.vector_count, 0);
+        assert!(
+            adapter
+                .search(&vectors[1].1, 3, 30, None, false)?
+                .is_empty()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_upsert_replaces_and_inserts() -> StorageResult<()> {
+        let vectors = test_vectors(20);
+        let mut adapter = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 20))?;
+        let refs: Vec<(u64, &[f32])> = vectors[..10]
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        adapter.build(&refs)?;
+
+        // Node 1002 is replaced by the vector of (not yet indexed) vector 15; 1010 is new
+        let batch = vec![
+            (vectors[2].0, vectors[15].1.clone()),
+            (vectors[10].0, vectors[10].1.clone()),
+        ];
+        adapter.upsert(&batch)?;
+        assert_eq!(adapter.size(), 11);
+        assert_eq!(
+            adapter.get_vectors(&[vectors[2].0])?[0].as_deref(),
+            Some(vectors[15].1.as_slice())
+        );
+        assert_eq!(adapter.node_to_vector_id(vectors[10].0), Some(11));
+        let results = adapter.search(&vectors[15].1, 1, 20, None, false)?;
+        assert_eq!(results[0], (vectors[2].0, 0.0));
+        // The old vector of node 1002 is gone
+        let results = adapter.search(&vectors[2].1, 11, 20, None, false)?;
+        assert!(results.iter().all(|&(_, distance)| distance > 0.0));
+        assert_eq!(adapter.fragmentation_report().deleted_vectors, 1);
+
+        assert!(matches!(
+            adapter.upsert(&[
+                (vectors[3].0, vectors[3].1.clone()),
+                (vectors[3].0, vectors[4].1.clone())
+            ]),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::DuplicateNodeId { .. }
+            ))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_upsert_failure_restores_mappings() -> StorageResult<()> {
+        let vectors = test_vectors(60);
+        let mut adapter = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 10))?;
+        let refs: Vec<(u64, &[f32])> = vectors[..10]
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        adapter.build(&refs)?;
+        let mappings_before: Vec<Option<u32>> = vectors
+            .iter()
+            .map(|(node_id, _)| adapter.node_to_vector_id(*node_id))
+            .collect();
+
+        // Existing and new nodes together exceed DiskANN's preallocated capacity
+        let mut batch: Vec<(u64, Vec<f32>)> = vectors[..5].to_vec();
+        batch.extend_from_slice(&vectors[10..]);
+        assert!(adapter.upsert(&batch).is_err());
+
+        let mappings_after: Vec<Option<u32>> = vectors
+            .iter()
+            .map(|(node_id, _)| adapter.node_to_vector_id(*node_id))
+            .collect();
+        assert_eq!(mappings_after, mappings_before);
+        assert_eq!(adapter.size(), 10);
+        let results = adapter.search(&vectors[3].1, 1, 10, None, false)?;
+        assert_eq!(results[0], (vectors[3].0, 0.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_vector() -> StorageResult<()> {
+        let vectors = test_vectors(10);
+        let unbuilt = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 10))?;
+        assert!(matches!(
+            unbuilt.get_vector(vectors[0].0),
+            Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt))
+        ));
+
+        let mut adapter = build_adapter(&vectors)?;
+        let stored = adapter.get_vector(vectors[4].0)?.unwrap();
+        assert_eq!(stored.len(), TEST_DIM);
+        assert_eq!(stored, vectors[4].1);
+        assert_eq!(adapter.get_vector(1)?, None);
+
+        adapter.soft_delete(&[vectors[4].0])?;
+        assert_eq!(adapter.get_vector(vectors[4].0)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_range_search() -> StorageResult<()> {
+        let vectors = test_vectors(60);
+        let adapter = build_adapter(&vectors)?;
+        // Exact in-range set from brute force over all vectors
+        let all = FilterMask::new((0..60).collect(), adapter.size());
+        let query = &vectors[25].1;
+        let radius = 150.0;
+        let exact: Vec<(u64, f32)> = adapter
+            .brute_force_search(query, 60, &all)?
+            .into_iter()
+            .filter(|&(_, distance)| distance <= radius)
+            .collect();
+        assert!(exact.len() > 1);
+
+        let results = adapter.range_search(query, radius, 8, None)?;
+        assert!(results.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+        assert!(results.iter().all(|&(_, distance)| distance <= radius));
+        assert_eq!(results[0], (vectors[25].0, 0.0));
+        // More nodes are in range than the initial `l_value` of 8, so `k` had to grow
+        assert_eq!(exact.len(), 9);
+        assert_eq!(results.len(), exact.len());
+
+        // Selective filters are scanned exactly
+        let sparse = FilterMask::new(vec![20, 24, 25, 26, 59], adapter.size());
+        let filtered = adapter.range_search(query, radius, 8, Some(&sparse))?;
+        let expected: Vec<(u64, f32)> = exact
+            .iter()
+            .copied()
+            .filter(|(node_id, _)| [1020, 1024, 1025, 1026, 1059].contains(node_id))
+            .collect();
+        assert_eq!(filtered, expected);
+
+        // Nothing in range is an empty result, not an error
+        let far = vec![1.0e4f32; TEST_DIM];
+        assert!(adapter.range_search(&far, 1.0, 8, None)?.is_empty());
+        assert!(
+            adapter
+                .range_search(&far, 1.0, 8, Some(&sparse))?
+                .is_empty()
+        );
+        assert!(adapter.range_search(query, -1.0, 8, None)?.is_empty());
+        assert!(adapter.range_search(query, f32::NAN, 8, None).is_err());
+
+        // The query is checked even when no candidate is left to score
+        let mut adapter = adapter;
+        adapter.soft_delete(&[vectors[59].0])?;
+        let deleted = FilterMask::new(vec![59], adapter.size() + 1);
+        assert!(matches!(
+            adapter.range_search(&query[1..], radius, 8, Some(&deleted)),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension { .. }
+            ))
+        ));
+
+        // Brute-force scans read the configured storage, as searches do
+        let refs: Vec<(u64, &[f32])> = vectors.iter().map(|(id, v)| (*id, v.as_slice())).collect();
+        let mut int8 = InMemANNAdapter::with_config(
+            create_vector_index_config(TEST_DIM, 60),
+            AdapterConfig::new().with_storage(VectorStorage::Int8),
+        )?;
+        int8.build(&refs)?;
+        let mut expected = int8.search(query, 5, 8, Some(&sparse), false)?;
+        expected.retain(|&(_, distance)| distance <= radius);
+        assert_eq!(
+            int8.range_search(query, radius, 8, Some(&sparse))?,
+            expected
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_panic_holding_stats_lock_does_not_block_index() {
+        let vectors = test_vectors(50);
+        let mut adapter = build_adapter(&vectors).unwrap();
+
+        let stats = Arc::clone(&adapter.stats);
+        let panicked = std::thread::spawn(move || {
+            let _guard = stats.write();
+            panic!("stats update failed");
+        })
+        .join();
+        assert!(panicked.is_err());
+
+        let results = adapter.search(&vectors[0].1, 5, 20, None, false).unwrap();
+        assert_eq!(results.len(), 5);
+        adapter.insert(&[(5000, vectors[1].1.as_slice())]).unwrap();
+        let stats = adapter.stats();
+        assert_eq!(stats.total_searches, 1);
+        assert_eq!(stats.total_inserts, 1);
+    }
+
+    #[test]
+    fn test_product_quantized_brute_force() -> StorageResult<()> {
+        let vectors = test_vectors(300);
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        let pq_adapter = |rerank_exact| -> StorageResult<InMemANNAdapter> {
+            let pq = ProductQuantization {
+                subquantizers: 8,
+                bits_per_code: 8,
+                rerank_exact,
+            };
+            let mut adapter = InMemANNAdapter::with_config(
+                create_vector_index_config(TEST_DIM, vectors.len()),
+                AdapterConfig::new().with_product_quantization(pq),
+            )?;
+            adapter.build(&refs)?;
+            Ok(adapter)
+        };
+        let exact_adapter = build_adapter(&vectors)?;
+        // 20 of 300 candidates pass: selective enough for brute force
+        let mask = FilterMask::new((0..300).step_by(15).collect(), exact_adapter.size());
+        let query = &vectors[150].1;
+        let exact = exact_adapter.search(query, 5, 20, Some(&mask), false)?;
+
+        let mut reranked_adapter = pq_adapter(true)?;
+        assert!(reranked_adapter.memory_usage() > exact_adapter.memory_usage());
+        let reranked = reranked_adapter.search(query, 5, 20, Some(&mask), false)?;
+        assert_eq!(reranked, exact);
+        let stats = reranked_adapter.stats();
+        assert_eq!((stats.brute_force_searches, stats.pq_searches), (1, 1));
+
+        let estimated = pq_adapter(false)?.search(query, 5, 20, Some(&mask), false)?;
+        assert_eq!(estimated.len(), 5);
+        assert!(estimated.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+
+        // Inserted vectors are encoded with the trained codebooks
+        reranked_adapter.insert(&[(9000, query.as_slice())])?;
+        let only_new = FilterMask::new(vec![300], reranked_adapter.size());
+        let results = reranked_adapter.search(query, 1, 20, Some(&only_new), false)?;
+        assert_eq!(results, vec![(9000, 0.0)]);
+        assert_eq!(reranked_adapter.stats().pq_searches, 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_product_quantization_config_validated() {
+        let config = |subquantizers, bits_per_code| {
+            AdapterConfig::new().with_product_quantization(ProductQuantization {
+                subquantizers,
+                bits_per_code,
+                rerank_exact: false,
+            })
+        };
+        for (subquantizers, bits_per_code) in [(0, 8), (TEST_DIM + 1, 8), (8, 0), (8, 9)] {
+            let result = InMemANNAdapter::with_config(
+                create_vector_index_config(TEST_DIM, 10),
+                config(subquantizers, bits_per_code),
+            );
+            match result {
+                Err(StorageError::VectorIndex(VectorIndexError::Configuration(message))) => {
+                    assert_eq!(
+                        message,
+                        format!(
+                            "product quantization needs 1 to {TEST_DIM} subquantizers and 1 to 8 \
+                             bits per code, got {subquantizers} and {bits_per_code}"
+                        )
+                    );
+                }
+                Err(other) => panic!("expected a configuration error, got {other:?}"),
+                Ok(_) => panic!("expected a configuration error"),
+            }
+        }
+    }
+
+    /// 400 fixed pseudo-random embeddings in [-1, 1)
+    fn random_vectors() -> Vec<(u64, Vec<f32>)> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        (0..400)
+            .map(|i| {
+                let vector = (0..TEST_DIM)
+                    .map(|_| {
+                        state = state
+                            .wrapping_mul(6_364_136_223_846_793_005)
+                            .wrapping_add(1_442_695_040_888_963_407);
+                        (state >> 40) as f32 / (1u64 << 23) as f32 - 1.0
+                    })
+                    .collect();
+                (i, vector)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_int8_storage_recall_against_f32() -> StorageResult<()> {
+        let vectors = random_vectors();
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        let mut int8_adapter = InMemANNAdapter::with_config(
+            create_vector_index_config(TEST_DIM, vectors.len()),
+            AdapterConfig::new().with_storage(VectorStorage::Int8),
+        )?;
+        int8_adapter.build(&refs)?;
+        let f32_adapter = build_adapter(&vectors)?;
+        assert!(int8_adapter.memory_usage() > f32_adapter.memory_usage());
+
+        // 36 of 400 candidates pass, so both indexes answer by brute force
+        let mask = FilterMask::new((0..400).step_by(11).collect(), f32_adapter.size());
+        let k = 10;
+        let mut int8_hits = 0;
+        let mut reranked_hits = 0;
+        for query in vectors.iter().step_by(40).map(|(_, vector)| vector) {
+            let baseline: std::collections::HashSet<u64> = f32_adapter
+                .search(query, k, 20, Some(&mask), false)?
+                .into_iter()
+                .map(|(node_id, _)| node_id)
+                .collect();
+
+            int8_adapter.set_tuning(SearchTuning::default());
+            let int8 = int8_adapter.search(query, k, 20, Some(&mask), false)?;
+            int8_hits += int8.iter().filter(|(id, _)| baseline.contains(id)).count();
+
+            int8_adapter.set_tuning(SearchTuning {
+                rerank_factor: 3,
+                ..Default::default()
+            });
+            let reranked = int8_adapter.search(query, k, 20, Some(&mask), false)?;
+            let exact = f32_adapter.search(query, k, 20, Some(&mask), false)?;
+            assert_eq!(reranked, exact);
+            reranked_hits += reranked
+                .iter()
+                .filter(|(id, _)| baseline.contains(id))
+                .count();
+        }
+        let queries = 10;
+        assert!(
+            int8_hits as f64 / (queries * k) as f64 >= 0.9,
+            "{int8_hits}"
+        );
+        assert_eq!(reranked_hits, queries * k);
+        assert_eq!(int8_adapter.stats().brute_force_searches, 20);
+        Ok(())
+    }
+
+    #[test]
+    fn test_float16_storage_recall_against_f32() -> StorageResult<()> {
+        let vectors = random_vectors();
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        let mut f16_adapter = InMemANNAdapter::with_config(
+            create_vector_index_config(TEST_DIM, vectors.len()),
+            AdapterConfig::new().with_storage(VectorStorage::Float16),
+        )?;
+        f16_adapter.build(&refs)?;
+        let f32_adapter = build_adapter(&vectors)?;
+        assert!(
+            f16_adapter.memory_usage() >= f32_adapter.memory_usage() + vectors.len() * TEST_DIM * 2
+        );
+
+        // 36 of 400 candidates pass, so both indexes answer by brute force
+        let mask = FilterMask::new((0..400).step_by(11).collect(), f32_adapter.size());
+        let k = 10;
+        let mut f16_hits = 0;
+        for query in vectors.iter().step_by(40).map(|(_, vector)| vector) {
+            let exact = f32_adapter.search(query, k, 20, Some(&mask), false)?;
+            let f16 = f16_adapter.search(query, k, 20, Some(&mask), false)?;
+            f16_hits += f16
+                .iter()
+                .filter(|(id, _)| exact.iter().any(|(exact_id, _)| exact_id == id))
+                .count();
+            for ((_, approx), (_, distance)) in f16.iter().zip(&exact) {
+                assert!((approx - distance).abs() <= 0.01 * distance.max(1.0));
+            }
+        }
+        assert!(f16_hits as f64 / (10 * k) as f64 >= 0.95, "{f16_hits}");
+
+        // Inserted vectors are converted as well
+        let new_vector = vectors[5].1.iter().map(|x| x * 0.5).collect::<Vec<_>>();
+        f16_adapter.soft_delete(&[0])?;
+        f16_adapter.insert(&[(1000, new_vector.as_slice())])?;
+        let inserted = f16_adapter.node_to_vector_id(1000).unwrap();
+        let mask = FilterMask::new(vec![inserted], inserted as usize + 1);
+        let results = f16_adapter.search(&new_vector, 1, 20, Some(&mask), false)?;
+        assert_eq!(results, vec![(1000, 0.0)]);
+        assert_eq!(f16_adapter.stats().brute_force_searches, 11);
+        Ok(())
+    }
+
+    #[test]
+    fn test_int8_storage_rejects_product_quantization() {
+        let config = AdapterConfig::new()
+            .with_storage(VectorStorage::Int8)
+            .with_product_quantization(ProductQuantization {
+                subquantizers: 8,
+                bits_per_code: 8,
+                rerank_exact: false,
+            });
+        let result = InMemANNAdapter::with_config(create_vector_index_config(TEST_DIM, 10), config);
+        assert!(matches!(
+            result,
+            Err(StorageError::VectorIndex(VectorIndexError::Configuration(
+                _
+            )))
+        ));
+    }
+
+    #[test]
+    fn test_build_with_progress_and_cancellation() -> StorageResult<()> {
+        let vectors = test_vectors(50);
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        let mut adapter = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 50))?;
+
+        let mut phases = Vec::new();
+        let never = AtomicBool::new(false);
+        adapter.build_with_progress(&refs, |report| phases.push(report.phase), &never)?;
+        assert_eq!(
+            phases,
+            [
+                BuildPhase::Validation,
+                BuildPhase::Mapping,
+                BuildPhase::GraphConstruction,
+                BuildPhase::Finalization,
+                BuildPhase::Done,
+            ]
+        );
+        assert!(
+            phases
+                .windows(2)
+                .all(|pair| pair[0].percent() < pair[1].percent())
+        );
+
+        // Cancelling while the graph is built takes effect once DiskANN returns
+        let cancel = AtomicBool::new(false);
+        let result = adapter.build_with_progress(
+            &refs,
+            |report| {
+                if report.phase == BuildPhase::GraphConstruction {
+                    cancel.store(true, Ordering::Release);
+                }
+            },
+            &cancel,
+        );
+        assert!(matches!(
+            result,
+            Err(StorageError::VectorIndex(VectorIndexError::BuildCancelled))
+        ));
+        assert_eq!(adapter.mapping_count(), 0);
+        assert_eq!(adapter.stats().vector_count, 0);
+        assert!(matches!(
+            adapter.search(&vectors[0].1, 5, 20, None, false),
+            Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt))
+        ));
+        assert!(!adapter.build_gate().is_building());
+
+        // A build cancelled up front still leaves nothing behind
+        adapter.build(&refs)?;
+        let result = adapter.build_with_progress(&refs, |_| {}, &AtomicBool::new(true));
+        assert!(result.is_err());
+        assert_eq!(adapter.mapping_count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parallel_brute_force_matches_serial() -> StorageResult<()> {
+        // Second half duplicates the first, so equal distances must be broken consistently
+        let count = 2 * PARALLEL_BRUTE_FORCE_MIN_CANDIDATES;
+        let half = test_vectors(count / 2);
+        let vectors: Vec<(u64, Vec<f32>)> = half
+            .iter()
+            .cloned()
+            .chain(
+                half.iter()
+                    .map(|(id, vector)| (id + count as u64, vector.clone())),
+            )
+            .collect();
+        let adapter = build_adapter(&vectors)?;
+        let mask = FilterMask::new((0..count as u32).collect(), adapter.size());
+        let query = &vectors[777].1;
+
+        let parallel = adapter.brute_force_search(query, 50, &mask)?;
+        // Aligned like the search path's copy, so both take the same distance kernel
+        let aligned = InMemANNAdapter::ensure_query_aligned(query)?;
+        let mut serial = BinaryHeap::new();
+        adapter.scan_candidates(aligned.as_slice(), 50, mask.iter_candidates(), &mut serial)?;
+        let serial: Vec<(u64, f32)> = serial
+            .into_sorted_vec()
+            .into_iter()
+            .map(|(distance, node_id)| (node_id, distance.0))
+            .collect();
+        assert_eq!(parallel, serial);
+        assert_eq!(parallel[0].1, 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_deletion_accounting() -> StorageResult<()> {
+        let vectors = test_vectors(40);
+        let mut adapter = build_adapter(&vectors)?;
+        assert_eq!((adapter.num_deleted(), adapter.deletion_ratio()), (0, 0.0));
+
+        adapter.soft_delete(&[1000, 1001, 1002, 1003])?;
+        adapter.upsert(&[(1004, vectors[20].1.clone())])?;
+        assert_eq!(adapter.size(), 36);
+        assert_eq!(adapter.num_deleted(), 5);
+        assert_eq!(adapter.deletion_ratio(), 5.0 / 41.0);
+        let stats = adapter.stats();
+        assert_eq!((stats.deleted_count, stats.deletion_ratio), (5, 5.0 / 41.0));
+        assert_eq!(adapter.fragmentation_report().deleted_vectors, 5);
+
+        adapter.compact()?;
+        assert_eq!(adapter.num_deleted(), 0);
+        assert_eq!(adapter.stats().deleted_count, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_build_from_iter() -> StorageResult<()> {
+        let vectors = test_vectors(60);
+        let expected = build_adapter(&vectors)?;
+
+        let mut adapter = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 60))?;
+        adapter.build_from_iter(vectors.iter().rev().cloned())?;
+        for (node_id, _) in &vectors {
+            assert_eq!(
+                adapter.node_to_vector_id(*node_id),
+                expected.node_to_vector_id(*node_id)
+            );
+        }
+        assert_eq!(
+            adapter.search(&vectors[7].1, 5, 30, None, false)?,
+            expected.search(&vectors[7].1, 5, 30, None, false)?
+        );
+
+        // A bad vector fails the build before the rest of the source is read
+        let mut consumed = 0;
+        let source = vectors
+            .iter()
+            .cloned()
+            .enumerate()
+            .map(|(i, (id, vector))| {
+                consumed += 1;
+                if i == 10 {
+                    (id, vec![0.0; 3])
+                } else {
+                    (id, vector)
+                }
+            });
+        assert!(matches!(
+            adapter.build_from_iter(source),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension { .. }
+            ))
+        ));
+        assert_eq!(consumed, 11);
+
+        let duplicated = vectors
+            .iter()
+            .cloned()
+            .chain([(1005, vectors[1].1.clone())]);
+        assert!(matches!(
+            adapter.build_from_iter(duplicated),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::DuplicateNodeId { node_id: 1005 }
+            ))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_check_consistency() -> StorageResult<()> {
+        let vectors = test_vectors(30);
+        let mut adapter = build_adapter(&vectors)?;
+        adapter.check_consistency()?;
+        adapter.soft_delete(&[1003, 1004])?;
+        adapter.upsert(&[(1005, vectors[0].1.clone())])?;
+        adapter.check_consistency()?;
+
+        // Point node 1000 at node 1001's vector and add an orphan slot past the allocated range
+        adapter.node_to_vector.insert(1000, 1);
+        adapter.vector_to_node.set(99, 4242)?;
+        let Err(StorageError::VectorIndex(VectorIndexError::InconsistentMappings {
+            count,
+            examples,
+        })) = adapter.check_consistency()
+        else {
+            panic!("expected inconsistent mappings");
+        };
+        // node 1000 -> 1 -> 1001; vector 0 -> node 1000 -> 1; vector 99 -> unknown node; 4242's
+        // slot makes one more vector_to_node entry than node_to_vector
+        assert_eq!(count, 4);
+        assert!(
+            examples
+                .iter()
+                .any(|issue| issue == "node 1000 maps to vector 1, which maps to node 1001")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_natural_distance() -> StorageResult<()> {
+        let vectors = test_vectors(20);
+        let adapter = build_adapter(&vectors)?;
+        let query = &vectors[3].1;
+        for (node_id, distance) in adapter.search(query, 5, 32, None, false)? {
+            let stored = adapter.get_vector(node_id)?.unwrap();
+            let euclidean = DistanceMetric::L2.distance(query, &stored).sqrt();
+            assert!((adapter.natural_distance(distance) - euclidean).abs() < 1e-3);
+        }
 
+        let config = AdapterConfig::default().with_metric(IndexMetric::Cosine);
+        let mut cosine = InMemANNAdapter::with_config(
+            create_vector_index_config(TEST_DIM, vectors.len()),
+            config,
+        )?;
+        let refs: Vec<(u64, &[f32])> = vectors.iter().map(|(id, v)| (*id, v.as_slice())).collect();
+        cosine.build(&refs)?;
+        for (node_id, distance) in cosine.search(query, 5, 32, None, false)? {
+            let stored = &vectors.iter().find(|(id, _)| *id == node_id).unwrap().1;
+            let expected = DistanceMetric::Cosine.distance(query, stored);
+            assert!((cosine.natural_distance(distance) - expected).abs() < 1e-4);
+        }
         Ok(())
     }
 
     #[test]
-    fn test_sharding_and_distribution() -> StorageResult<()> {
-        let map = ShardedVectorMap::new(4)?; // 16 shards
-        for vector_id in 0..64u32 {
-            map.set(vector_id, vector_id as u64 + 1000)?;
-        }
-        let mut shard_counts = [0; 16];
-        for vector_id in 0..64u32 {
-            let (shard_idx, _) = map.get_shard_and_index(vector_id);
-            shard_counts[shard_idx] += 1;
+    fn test_insert_concurrent_alongside_searches() -> StorageResult<()> {
+        let vectors = test_vectors(200);
+        let mut adapter = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 200))?;
+        let refs: Vec<(u64, &[f32])> = vectors[..40]
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        adapter.build(&refs)?;
+
+        let adapter = &adapter;
+        std::thread::scope(|scope| {
+            for half in vectors[40..].chunks(80) {
+                scope.spawn(move || {
+                    for batch in half.chunks(10) {
+                        let refs: Vec<(u64, &[f32])> =
+                            batch.iter().map(|(id, v)| (*id, v.as_slice())).collect();
+                        adapter.insert_concurrent(&refs).unwrap();
+                        // A failing duplicate insert must not disturb the allocation of others
+                        assert!(adapter.insert_concurrent(&refs[..1]).is_err());
+                    }
+                });
+            }
+            scope.spawn(|| {
+                for _ in 0..20 {
+                    let results = adapter.search(&vectors[0].1, 5, 32, None, false).unwrap();
+                    assert_eq!(results[0].0, 1000);
+                }
+            });
+        });
+
+        assert_eq!(adapter.mapping_count(), 200);
+        assert_eq!(adapter.next_vector_id.load(Ordering::Acquire), 200);
+        adapter.check_consistency()?;
+        for (node_id, vector) in &vectors {
+            let results = adapter.search(vector, 1, 32, None, false)?;
+            assert_eq!(results[0].0, *node_id);
         }
 
-        // Verify bit-striping sharding spreads data evenly
-        let min_count = *shard_counts.iter().min().unwrap();
-        let max_count = *shard_counts.iter().max().unwrap();
-        let range = max_count - min_count;
+        // A node repeated within one batch is rejected before anything is inserted
+        let size = adapter.size();
+        let repeated = [
+            (9000, vectors[0].1.as_slice()),
+            (9000, vectors[1].1.as_slice()),
+        ];
+        assert!(matches!(
+            adapter.insert_concurrent(&repeated),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::DuplicateNodeId { node_id: 9000 }
+            ))
+        ));
+        assert_eq!(adapter.size(), size);
+        assert_eq!(adapter.node_to_vector_id(9000), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_evaluate_recall() -> StorageResult<()> {
+        let vectors = test_vectors(60);
+        let adapter = build_adapter(&vectors)?;
+        let queries: Vec<Vec<f32>> = vectors.iter().step_by(6).map(|(_, v)| v.clone()).collect();
+        let ground_truth = queries
+            .iter()
+            .map(|query| adapter.brute_force_ground_truth(query, 5))
+            .collect::<StorageResult<Vec<_>>>()?;
+        assert_eq!(ground_truth[0].len(), 5);
+        assert_eq!(ground_truth[0][0], 1000);
 
+        let sweep = [5, 10, 60]
+            .into_iter()
+            .map(|l_value| adapter.evaluate_recall(&queries, &ground_truth, 5, l_value))
+            .collect::<StorageResult<Vec<_>>>()?;
+        assert!(sweep.iter().all(|recall| (0.0..=1.0).contains(recall)));
+        // A search list covering the whole index finds the exact neighbors
+        assert_eq!(sweep[2], 1.0);
+        assert_eq!(adapter.stats().total_searches, 0);
+
+        assert_eq!(adapter.evaluate_recall(&[], &[], 5, 10)?, 1.0);
         assert!(
-            range <= 8,
-            "Bit-striping sharding should distribute evenly, range: {}",
-            range
+            adapter
+                .evaluate_recall(&queries, &ground_truth[1..], 5, 10)
+                .is_err()
         );
-        assert!(min_count > 0, "All shards should have data");
+        let mut malformed = queries.clone();
+        malformed[3].truncate(3);
+        assert!(matches!(
+            adapter.evaluate_recall(&malformed, &ground_truth, 5, 10),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension { .. }
+            ))
+        ));
+        assert!(matches!(
+            adapter.evaluate_recall(&queries, &ground_truth, 5, 0),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidSearchParams(_)
+            ))
+        ));
+        Ok(())
+    }
 
-        // Test shard boundary conditions with specific vector_ids
-        let boundary_map = ShardedVectorMap::new(2)?; // 4 shards, mask = 3
-        let boundary_ids = vec![0, 1, 2, 3, 4, 7, 8, 11, 12, 15, 16];
+    #[test]
+    fn test_warmup_leaves_index_unchanged() -> StorageResult<()> {
+        let unbuilt = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 10))?;
+        unbuilt.warmup(4)?;
+
+        let vectors = test_vectors(50);
+        let adapter = build_adapter(&vectors)?;
+        let stats_before = adapter.stats();
+        adapter.warmup(8)?;
+        adapter.warmup(1000)?;
+        assert_eq!(adapter.stats(), stats_before);
+        adapter.check_consistency()?;
+
+        let results = adapter.search(&vectors[7].1, 3, 32, None, false)?;
+        assert_eq!(results[0].0, 1007);
+        Ok(())
+    }
 
-        for &vector_id in &boundary_ids {
-            boundary_map.set(vector_id, vector_id as u64 + 2000)?;
-        }
+    #[test]
+    fn test_combined_filter_strategy() -> StorageResult<()> {
+        let vectors = test_vectors(100);
+        let adapter = build_adapter(&vectors)?;
+        let total = adapter.size();
+        let evens = FilterMask::new((0..100).step_by(2).collect(), total);
+        let low = FilterMask::new((0..20).collect(), total);
+        let thirds = FilterMask::new((0..100).step_by(3).collect(), total);
+        let in_mask = |mask: &dyn SearchFilter, results: &[(u64, f32)]| {
+            results.iter().all(|(node_id, _)| {
+                mask.contains_vector(adapter.node_to_vector_id(*node_id).unwrap())
+            })
+        };
 
-        // Verify shard calculation consistency
-        for &vector_id in &boundary_ids {
-            let (shard_idx, local_idx) = boundary_map.get_shard_and_index(vector_id);
-            assert!(shard_idx < boundary_map.shard_count());
+        // Intersecting a broad and a narrow mask: the 10 even IDs below 20, and the
+        // independence estimate agrees
+        let and = AndFilterMask::new(vec![&evens, &low]);
+        assert!((and.selectivity() - 0.1).abs() < 1e-6);
+        assert_eq!(and.to_filter_mask().candidate_count(), 10);
+        assert!(matches!(
+            adapter.filter_strategy(&and),
+            SearchPath::PreFilter
+        ));
+        let before = adapter.stats().brute_force_searches;
+        let results = adapter.search(&vectors[6].1, 3, 50, Some(&and), false)?;
+        assert_eq!(results[0].0, vectors[6].0);
+        assert!(in_mask(&and, &results));
+        assert_eq!(adapter.stats().brute_force_searches, before + 1);
+
+        // Multiples of 3 below 20 are sparse enough for brute force
+        let sparse = AndFilterMask::new(vec![&low, &thirds]);
+        assert_eq!(sparse.to_filter_mask().candidate_count(), 7);
+        assert!(matches!(
+            adapter.filter_strategy(&sparse),
+            SearchPath::BruteForce
+        ));
+        let before = adapter.stats().brute_force_searches;
+        let results = adapter.search(&vectors[6].1, 3, 50, Some(&sparse), false)?;
+        assert_eq!(results[0].0, vectors[6].0);
+        assert!(in_mask(&sparse, &results));
+        assert_eq!(adapter.stats().brute_force_searches, before + 1);
+
+        // A union of sparse masks can be broad enough for graph search
+        let or = OrFilterMask::new(vec![&evens, &thirds]);
+        assert_eq!(or.to_filter_mask().candidate_count(), 67);
+        assert!((or.selectivity() - 0.67).abs() < 0.01);
+        assert!(matches!(adapter.filter_strategy(&or), SearchPath::Ann));
+        let before = adapter.stats().ann_searches;
+        let results = adapter.search(&vectors[9].1, 5, 100, Some(&or), false)?;
+        assert_eq!(results[0].0, vectors[9].0);
+        assert!(in_mask(&or, &results));
+        assert_eq!(adapter.stats().ann_searches, before + 1);
+
+        // Identical masks break the independence assumption: both combinations select the same
+        // 30%, which alone would take the pre-filtered scan, but the strategy follows the
+        // estimates of 9% and 51%
+        let head = FilterMask::new((0..30).collect(), total);
+        let head_again = FilterMask::new((0..30).collect(), total);
+        assert!(matches!(
+            adapter.filter_strategy(&head),
+            SearchPath::PreFilter
+        ));
+        let overlap = AndFilterMask::new(vec![&head, &head_again]);
+        assert!(matches!(
+            adapter.filter_strategy(&overlap),
+            SearchPath::BruteForce
+        ));
+        let results = adapter.search(&vectors[3].1, 3, 50, Some(&overlap), false)?;
+        assert_eq!(results[0].0, vectors[3].0);
+        assert!(in_mask(&head, &results));
+        let overlap = OrFilterMask::new(vec![&head, &head_again]);
+        assert!(matches!(adapter.filter_strategy(&overlap), SearchPath::Ann));
+        let before = adapter.stats().ann_searches;
+        let results = adapter.search(&vectors[3].1, 3, 50, Some(&overlap), false)?;
+        assert_eq!(results[0].0, vectors[3].0);
+        assert!(in_mask(&head, &results));
+        assert_eq!(adapter.stats().ann_searches, before + 1);
+        Ok(())
+    }
 
-            // Verify reverse calculation matches
-            let reconstructed = (local_idx << 2) | shard_idx;
-            assert_eq!(reconstructed, vector_id as usize);
+    #[test]
+    fn test_search_arrow_boolean_mask() -> StorageResult<()> {
+        let vectors = test_vectors(60);
+        let adapter = build_adapter(&vectors)?;
+        // Every 20th vector, with the null at 40 excluded: brute force over 0 and 20
+        let sparse = ArrowBooleanFilterMask::new(BooleanArray::from(
+            (0..60)
+                .map(|i| (i != 40).then_some(i % 20 == 0))
+                .collect::<Vec<_>>(),
+        ));
+        assert!(matches!(
+            adapter.filter_strategy(&sparse),
+            SearchPath::BruteForce
+        ));
+        let results = adapter.search(&vectors[20].1, 5, 50, Some(&sparse), false)?;
+        let node_ids: Vec<u64> = results.iter().map(|(node_id, _)| *node_id).collect();
+        assert_eq!(node_ids, vec![vectors[20].0, vectors[0].0]);
+
+        // Half the vectors take the graph search, matching the copied mask
+        let broad = ArrowBooleanFilterMask::new(BooleanArray::from(
+            (0..60).map(|i| i % 2 == 0).collect::<Vec<_>>(),
+        ));
+        assert!(matches!(adapter.filter_strategy(&broad), SearchPath::Ann));
+        let copied = broad.to_filter_mask();
+        assert_eq!(
+            adapter.search(&vectors[8].1, 5, 50, Some(&broad), false)?,
+            adapter.search(&vectors[8].1, 5, 50, Some(&copied), false)?
+        );
+        Ok(())
+    }
 
-            assert_eq!(boundary_map.get(vector_id), Some(vector_id as u64 + 2000));
-            assert!(boundary_map.contains_key(vector_id));
-        }
+    #[test]
+    fn test_search_not_filter_mask() -> StorageResult<()> {
+        let vectors = test_vectors(60);
+        let adapter = build_adapter(&vectors)?;
+        let total = adapter.size();
+
+        // A short deny-list leaves a broad complement for the graph search
+        let denied = FilterMask::new(vec![0, 1, 2], total);
+        let allowed = NotFilterMask::new(&denied, total);
+        assert!(matches!(adapter.filter_strategy(&allowed), SearchPath::Ann));
+        let before = adapter.stats().ann_searches;
+        let results = adapter.search(&vectors[1].1, 5, 50, Some(&allowed), false)?;
+        assert_eq!(results.len(), 5);
+        assert!(results.iter().all(|(node_id, _)| {
+            !denied.contains_vector(adapter.node_to_vector_id(*node_id).unwrap())
+        }));
+        assert_eq!(adapter.stats().ann_searches, before + 1);
+        let copied = allowed.to_filter_mask();
+        assert_eq!(
+            results,
+            adapter.search(&vectors[1].1, 5, 50, Some(&copied), false)?
+        );
 
+        // Denying all but three vectors leaves a complement for brute force
+        let denied = FilterMask::new((3..total as u32).collect(), total);
+        let allowed = NotFilterMask::new(&denied, total);
+        assert!(matches!(
+            adapter.filter_strategy(&allowed),
+            SearchPath::BruteForce
+        ));
+        let results = adapter.search(&vectors[2].1, 5, 50, Some(&allowed), false)?;
+        let node_ids: Vec<u64> = results.iter().map(|(node_id, _)| *node_id).collect();
+        assert_eq!(node_ids[0], vectors[2].0);
+        let mut sorted = node_ids.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, vec![vectors[0].0, vectors[1].0, vectors[2].0]);
         Ok(())
     }
 
     #[test]
-    fn test_soft_delete_comprehensive() -> StorageResult<()> {
-        let map = ShardedVectorMap::new(3)?; // 8 shards
+    fn test_search_rejects_short_search_list() -> StorageResult<()> {
+        let vectors = test_vectors(30);
+        let adapter = build_adapter(&vectors)?;
+        let query = &vectors[0].1;
+        let mask = FilterMask::new((0..3).collect(), adapter.size());
+        let expect_invalid = |result: StorageResult<Vec<(u64, f32)>>| match result {
+            Err(StorageError::VectorIndex(VectorIndexError::InvalidSearchParams(message))) => {
+                message
+            }
+            other => panic!("expected invalid search params, got {other:?}"),
+        };
 
-        // Set up test data distributed across shards
-        let test_data = vec![
-            (0, 1000),
-            (1, 1001),
-            (2, 1002),
-            (3, 1003),
-            (10, 1010),
-            (20, 1020),
-            (30, 1030),
-            (40, 1040),
-            (100, 1100),
-            (200, 1200),
-            (300, 1300),
-            (400, 1400),
-        ];
+        let message = expect_invalid(adapter.ann_search(query, 10, 5, None, false));
+        assert!(message.contains("at least k"), "{message}");
+        expect_invalid(adapter.search(query, 10, 5, None, false));
+        // Rejected even when the filter would route to brute force, which ignores l_value
+        expect_invalid(adapter.search(query, 10, 5, Some(&mask), false));
+        expect_invalid(adapter.search(query, 0, 5, None, false));
+        expect_invalid(adapter.ann_search(query, 5, 0, None, false));
+        assert!(adapter.kth_distance(query, 10, 5, Some(&mask)).is_err());
+        assert_eq!(adapter.stats().total_searches, 0);
+
+        assert_eq!(adapter.search(query, 10, 10, None, false)?.len(), 10);
+        Ok(())
+    }
 
-        for (vector_id, node_id) in &test_data {
-            map.set(*vector_id, *node_id)?;
-        }
+    #[test]
+    fn test_delta_checkpoints() -> StorageResult<()> {
+        let vectors = test_vectors(60);
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        let mut adapter = build_adapter(&vectors[..30])?;
+        let dir = std::env::temp_dir();
+        let path = |name: &str| {
+            dir.join(format!("vector_index_{name}_{}.bin", std::process::id()))
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+        let (base, first, second) = (path("delta_base"), path("delta_1"), path("delta_2"));
+        let persistence_message = |result: StorageResult<()>| match result {
+            Err(StorageError::VectorIndex(VectorIndexError::Persistence(message))) => message,
+            other => panic!("expected a persistence error, got {other:?}"),
+        };
 
-        // Verify initial state
-        for (vector_id, node_id) in &test_data {
-            assert_eq!(map.get(*vector_id), Some(*node_id));
-            assert!(map.contains_key(*vector_id));
+        // A freshly built index has no base to diff against
+        assert!(persistence_message(adapter.save_delta(&first)).contains("base snapshot"));
+        adapter.save(&base)?;
+        adapter.insert(&refs[30..45])?;
+        adapter.soft_delete(&[vectors[2].0, vectors[40].0])?;
+        adapter.save_delta(&first)?;
+        adapter.insert(&refs[45..60])?;
+        adapter.soft_delete(&[vectors[5].0])?;
+        adapter.save_delta(&second)?;
+
+        let mut replayed = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 30))?;
+        replayed.load_with_deltas(&base, &[&first, &second])?;
+        assert_eq!(replayed.size(), 57);
+        for (node_id, _) in &vectors {
+            assert_eq!(
+                replayed.node_to_vector_id(*node_id),
+                adapter.node_to_vector_id(*node_id)
+            );
         }
+        let query = &vectors[50].1;
+        assert_eq!(
+            replayed.search(query, 5, 50, None, false)?,
+            adapter.search(query, 5, 50, None, false)?
+        );
+        assert_eq!(replayed.stats().total_inserts, 30);
+        // The chain continues from the replayed state
+        replayed.soft_delete(&[vectors[50].0])?;
+        replayed.save_delta(&path("delta_3"))?;
+        let mut continued = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 30))?;
+        continued.load_with_deltas(&base, &[&first, &second, &path("delta_3")])?;
+        assert_eq!(continued.size(), 56);
+        assert_eq!(continued.node_to_vector_id(vectors[50].0), None);
+
+        // Gaps, repeats and deltas of other bases are rejected without changing the index
+        let mut target = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 30))?;
+        target.load(&base)?;
+        assert!(persistence_message(target.load_delta(&second)).contains("order"));
+        assert_eq!(target.size(), 30);
+        target.load_delta(&first)?;
+        assert!(persistence_message(target.load_delta(&first)).contains("order"));
+        let mut other = build_adapter(&vectors[30..])?;
+        other.save(&path("delta_other"))?;
+        assert!(persistence_message(other.load_delta(&first)).contains("different base"));
+
+        for name in ["delta_base", "delta_1", "delta_2", "delta_3", "delta_other"] {
+            let _ = std::fs::remove_file(path(name));
+        }
+        Ok(())
+    }
 
-        // Test single soft delete
-        let deleted = map.soft_delete(20)?;
-        assert_eq!(deleted, Some(1020));
-        assert_eq!(map.get(20), None);
-        assert!(!map.contains_key(20));
-
-        // Test double deletion (should return None)
-        let deleted_again = map.soft_delete(20)?;
-        assert_eq!(deleted_again, None);
+    #[test]
+    fn test_delta_chain_after_compact_and_remap() -> StorageResult<()> {
+        let vectors = test_vectors(40);
+        let mut adapter = build_adapter(&vectors)?;
+        let dir = std::env::temp_dir();
+        let path = |name: &str| {
+            dir.join(format!("vector_index_{name}_{}.bin", std::process::id()))
+                .to_str()
+                .unwrap()
+                .to_string()
+        };
+        let (base, delta) = (path("reindex_base"), path("reindex_delta"));
+        let replay = || -> StorageResult<InMemANNAdapter> {
+            let mut replayed = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 40))?;
+            replayed.load_with_deltas(&base, &[&delta])?;
+            Ok(replayed)
+        };
+        let needs_base = |result: StorageResult<()>| {
+            matches!(
+                result,
+                Err(StorageError::VectorIndex(VectorIndexError::Persistence(message)))
+                    if message.contains("base snapshot")
+            )
+        };
 
-        // Test batch soft delete on mixed shards
-        let delete_ids = vec![1, 30, 200, 400];
-        let deleted_nodes = map.batch_soft_delete(&delete_ids)?;
+        // Compaction renumbers vector IDs, so the old base cannot take a delta
+        adapter.save(&base)?;
+        adapter.soft_delete(&[vectors[3].0])?;
+        adapter.compact()?;
+        assert!(needs_base(adapter.save_delta(&delta)));
+        adapter.save(&base)?;
+        adapter.soft_delete(&[vectors[8].0])?;
+        adapter.save_delta(&delta)?;
+        let replayed = replay()?;
+        assert_eq!(replayed.size(), 38);
+        for (node_id, _) in &vectors {
+            assert_eq!(
+                replayed.node_to_vector_id(*node_id),
+                adapter.node_to_vector_id(*node_id)
+            );
+        }
 
-        assert_eq!(deleted_nodes.len(), 4);
-        assert!(deleted_nodes.contains(&1001));
-        assert!(deleted_nodes.contains(&1030));
-        assert!(deleted_nodes.contains(&1200));
-        assert!(deleted_nodes.contains(&1400));
+        // Deltas do not carry remaps either
+        adapter.remap_node_ids(&[(vectors[0].0, 9999)])?;
+        assert!(needs_base(adapter.save_delta(&delta)));
+        adapter.save(&base)?;
+        adapter.save_delta(&delta)?;
+        let replayed = replay()?;
+        assert_eq!(replayed.node_to_vector_id(vectors[0].0), None);
+        assert_eq!(
+            replayed.node_to_vector_id(9999),
+            adapter.node_to_vector_id(9999)
+        );
 
-        // Verify batch deletion effects
-        for &id in &delete_ids {
-            assert_eq!(map.get(id), None);
-            assert!(!map.contains_key(id));
+        for name in ["reindex_base", "reindex_delta"] {
+            let _ = std::fs::remove_file(path(name));
         }
+        Ok(())
+    }
 
-        // Test batch delete edge cases
-        let empty_deleted = map.batch_soft_delete(&[])?;
-        assert!(empty_deleted.is_empty());
-
-        let nonexistent_deleted = map.batch_soft_delete(&[999, 888])?;
-        assert!(nonexistent_deleted.is_empty());
+    #[test]
+    fn test_cosine_distance_kernel() {
+        let cosine = |a: &[f32], b: &[f32]| cosine_distance(a, vector_norm(a), b, vector_norm(b));
+        let a: Vec<f32> = (0..37).map(|i| (i as f32 * 0.3).sin() + 0.5).collect();
+        let scaled: Vec<f32> = a.iter().map(|x| x * 7.5).collect();
+        let opposite: Vec<f32> = a.iter().map(|x| -x * 0.25).collect();
+        assert!(cosine(&a, &a).abs() < 1e-6);
+        assert!(cosine(&a, &scaled).abs() < 1e-6);
+        assert!((cosine(&a, &opposite) - 2.0).abs() < 1e-6);
+        assert_eq!(cosine(&[3.0, 0.0, 0.0], &[0.0, 0.0, 12.0]), 1.0);
+        assert_eq!(cosine(&a, &[0.0; 37]), 1.0);
+        let b: Vec<f32> = (0..37).map(|i| (i as f32 * 0.7).cos()).collect();
+        assert!((cosine(&a, &b) - DistanceMetric::Cosine.distance(&a, &b)).abs() < 1e-5);
+    }
 
-        // Verify remaining data intact
-        let remaining_ids = vec![0, 2, 3, 10, 40, 100, 300];
-        for &id in &remaining_ids {
-            assert!(map.contains_key(id));
-            assert!(map.get(id).is_some());
+    #[test]
+    fn test_cached_norms_for_cosine_rescoring() -> StorageResult<()> {
+        let vectors: Vec<(u64, Vec<f32>)> = test_vectors(40)
+            .into_iter()
+            .enumerate()
+            .map(|(i, (node_id, vector))| {
+                // Vary the lengths so cosine and L2 rankings differ
+                let scale = 1.0 + (i % 5) as f32 * 3.0;
+                (node_id, vector.into_iter().map(|x| x * scale).collect())
+            })
+            .collect();
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        let mut plain = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 40))?;
+        let mut cached = InMemANNAdapter::with_config(
+            create_vector_index_config(TEST_DIM, 40),
+            AdapterConfig::new().with_cache_vector_norms(true),
+        )?;
+        plain.build(&refs[..30])?;
+        cached.build(&refs[..30])?;
+        plain.insert(&refs[30..])?;
+        cached.insert(&refs[30..])?;
+        assert_eq!(cached.norms.read().as_ref().map(Vec::len), Some(40));
+        assert!(plain.norms.read().is_none());
+
+        let query = &vectors[33].1;
+        let expected = plain.search_rescored(query, 5, 40, None, DistanceMetric::Cosine)?;
+        let results = cached.search_rescored(query, 5, 40, None, DistanceMetric::Cosine)?;
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0].0, vectors[33].0);
+        for ((node_id, distance), (expected_id, expected_distance)) in results.iter().zip(&expected)
+        {
+            assert_eq!(node_id, expected_id);
+            assert!((distance - expected_distance).abs() < 1e-5);
         }
 
+        // Normalizing indexes have unit vectors and never cache norms
+        let normalized = InMemANNAdapter::with_config(
+            create_vector_index_config(TEST_DIM, 40),
+            AdapterConfig::new()
+                .with_cache_vector_norms(true)
+                .with_metric(IndexMetric::Cosine),
+        )?;
+        assert!(normalized.norms.read().is_none());
         Ok(())
     }
 
     #[test]
-    fn test_dynamic_expansion() -> StorageResult<()> {
-        let map = ShardedVectorMap::new(2)?; // 4 shards
-
-        // Test setting vector_ids that require expansion across different shards
-        // With 2 shard_bits: shard_idx = vector_id & 3, local_idx = vector_id >> 2
+    fn test_rebuild_with_config() -> StorageResult<()> {
+        let vectors = test_vectors(40);
+        let mut adapter = build_adapter(&vectors)?;
+        let deleted = vectors[3].0;
+        adapter.soft_delete(&[deleted])?;
+
+        let mut config = create_vector_index_config(TEST_DIM, 40);
+        config.index_write_parameter = IndexWriteParametersBuilder::new(50, 32)
+            .with_alpha(1.2)
+            .with_num_threads(1)
+            .build();
+        adapter.rebuild_with_config(config)?;
+        let provenance = adapter.provenance().unwrap();
+        assert_eq!(
+            (provenance.max_degree, provenance.build_list_size),
+            (32, 50)
+        );
+        assert_eq!(adapter.size(), 39);
+        assert!(!adapter.contains_all(&[deleted])[0]);
+        for (node_id, vector) in vectors.iter().filter(|(id, _)| *id != deleted) {
+            assert_eq!(
+                adapter.get_vectors(&[*node_id])?[0].as_deref(),
+                Some(vector.as_slice())
+            );
+        }
+        let results = adapter.search(&vectors[10].1, 3, 30, None, false)?;
+        assert_eq!(results[0].0, vectors[10].0);
+
+        // Rejected configurations leave the rebuilt index untouched
+        let wrong_dim = create_vector_index_config(256, 40);
+        assert!(matches!(
+            adapter.rebuild_with_config(wrong_dim),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidDimension { .. }
+            ))
+        ));
+        let mut cosine = create_vector_index_config(TEST_DIM, 40);
+        cosine.dist_metric = Metric::Cosine;
+        assert!(matches!(
+            adapter.rebuild_with_config(cosine),
+            Err(StorageError::VectorIndex(VectorIndexError::Configuration(
+                _
+            )))
+        ));
+        assert_eq!(adapter.provenance().unwrap().max_degree, 32);
+        assert_eq!(adapter.size(), 39);
+        let results = adapter.search(&vectors[10].1, 3, 30, None, false)?;
+        assert_eq!(results[0].0, vectors[10].0);
+
+        let mut unbuilt = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 10))?;
+        assert!(matches!(
+            unbuilt.rebuild_with_config(create_vector_index_config(TEST_DIM, 10)),
+            Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt))
+        ));
+        Ok(())
+    }
 
-        // Small initial value
-        map.set(10, 1010)?;
+    #[test]
+    fn test_search_with_cutoff() -> StorageResult<()> {
+        let vectors = test_vectors(40);
+        let adapter = build_adapter(&vectors)?;
+        let query = &vectors[12].1;
+        let full = adapter.search(query, 5, 30, None, false)?;
+        assert_eq!(full.len(), 5);
+
+        // A cutoff between the second and third neighbors keeps two
+        let cutoff = (full[1].1 + full[2].1) / 2.0;
+        let cut = adapter.search_with_cutoff(query, 5, 30, cutoff, None)?;
+        assert_eq!(cut, full[..2]);
+        // The threshold itself is inclusive; the query's own vector is at distance 0
+        assert_eq!(
+            adapter.search_with_cutoff(query, 5, 30, 0.0, None)?,
+            full[..1]
+        );
+        assert_eq!(
+            adapter.search_with_cutoff(query, 5, 30, f32::INFINITY, None)?,
+            full
+        );
+        assert!(
+            adapter
+                .search_with_cutoff(query, 5, 30, -1.0, None)?
+                .is_empty()
+        );
 
-        // Larger values that will expand different shards
-        map.set(1000, 2000)?; // shard 0, local_idx 250
-        map.set(10001, 20001)?; // shard 1, local_idx 2500  
-        map.set(100002, 200002)?; // shard 2, local_idx 25000
-        map.set(1000003, 2000003)?; // shard 3, local_idx 250000
+        assert!(matches!(
+            adapter.search_with_cutoff(query, 5, 30, f32::NAN, None),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::InvalidSearchParams(_)
+            ))
+        ));
+        Ok(())
+    }
 
-        // Verify all values are stored correctly
-        assert_eq!(map.get(10), Some(1010));
-        assert_eq!(map.get(1000), Some(2000));
-        assert_eq!(map.get(10001), Some(20001));
-        assert_eq!(map.get(100002), Some(200002));
-        assert_eq!(map.get(1000003), Some(2000003));
+    #[test]
+    fn test_seeded_build_is_deterministic() -> StorageResult<()> {
+        let vectors = test_vectors(60);
+        // Reverse the input: vector IDs follow the sorted node IDs regardless
+        let refs: Vec<(u64, &[f32])> = vectors
+            .iter()
+            .rev()
+            .map(|(id, vector)| (*id, vector.as_slice()))
+            .collect();
+        let seeded_build = || -> StorageResult<InMemANNAdapter> {
+            let mut config = create_vector_index_config(TEST_DIM, vectors.len());
+            config.index_write_parameter.num_threads = 4;
+            let mut adapter =
+                InMemANNAdapter::with_config(config, AdapterConfig::new().with_build_seed(7))?;
+            adapter.build(&refs)?;
+            Ok(adapter)
+        };
 
-        // Test intermediate values are None (sparse storage)
-        assert_eq!(map.get(500), None);
-        assert_eq!(map.get(5000), None);
-        assert_eq!(map.get(50000), None);
+        let first = seeded_build()?;
+        let second = seeded_build()?;
+        assert_eq!(first.rng_seed(), 7);
+        assert_eq!(first.index_config.index_write_parameter.num_threads, 1);
+        for (node_id, _) in &vectors {
+            assert_eq!(
+                first.node_to_vector_id(*node_id),
+                second.node_to_vector_id(*node_id)
+            );
+        }
+        for (_, query) in vectors.iter().step_by(5) {
+            assert_eq!(
+                first.ann_search(query, 10, 20, None, false)?,
+                second.ann_search(query, 10, 20, None, false)?
+            );
+        }
+        Ok(())
+    }
 
+    #[test]
+    fn test_search_rejects_query_of_wrong_length() -> StorageResult<()> {
+        let vectors = test_vectors(20);
+        let adapter = build_adapter(&vectors)?;
+        let mask = FilterMask::new(vec![1, 2], adapter.size());
+        let short = vec![0.5f32; TEST_DIM - 1];
+        for query in [&[][..], &short[..]] {
+            let expected_error = |result: StorageResult<Vec<(u64, f32)>>| {
+                matches!(
+                    result,
+                    Err(StorageError::VectorIndex(VectorIndexError::InvalidDimension {
+                        expected: TEST_DIM,
+                        actual,
+                    })) if actual == query.len()
+                )
+            };
+            assert!(expected_error(
+                adapter.ann_search(query, 5, 10, None, false)
+            ));
+            assert!(expected_error(adapter.search(query, 5, 10, None, false)));
+            // Filtered searches fail the same way, whichever strategy they would take
+            assert!(expected_error(adapter.search(
+                query,
+                2,
+                10,
+                Some(&mask),
+                false
+            )));
+            assert!(adapter.ann_search_vector_ids(query, 5, 10).is_err());
+        }
+        assert_eq!(adapter.stats().total_searches, 0);
         Ok(())
     }
 
     #[test]
-    fn test_parameter_validation() {
-        // Test boundary conditions for shard_bits parameter
-        assert!(ShardedVectorMap::new(17).is_err()); // > 16 should fail
-        assert!(ShardedVectorMap::new(0).is_ok()); // min valid value
-        assert!(ShardedVectorMap::new(16).is_ok()); // max valid value
+    fn test_insert_lenient_reports_failures() -> StorageResult<()> {
+        let vectors = test_vectors(20);
+        let mut adapter = build_adapter(&vectors)?;
+        let fresh = test_vectors(30);
+        let short = vec![0.5f32; TEST_DIM - 1];
+        let mut non_finite = fresh[21].1.clone();
+        non_finite[3] = f32::NAN;
+        let batch: Vec<(u64, &[f32])> = vec![
+            (2000, &fresh[20].1),
+            (1005, &fresh[22].1),
+            (2000, &fresh[23].1),
+            (2001, &short),
+            (2002, &non_finite),
+            (2003, &fresh[24].1),
+        ];
 
-        // Verify shard count calculation: 2^shard_bits
-        assert_eq!(ShardedVectorMap::new(2).unwrap().shard_count(), 4);
-        assert_eq!(ShardedVectorMap::new(0).unwrap().shard_count(), 1);
+        // The strict insert rejects the whole batch
+        assert!(adapter.insert(&batch).is_err());
+        assert_eq!(adapter.size(), 20);
+
+        let report = adapter.insert_lenient(&batch)?;
+        assert_eq!(report.inserted, vec![2000, 2003]);
+        let reasons: Vec<(u64, &InsertFailure)> = report
+            .failed
+            .iter()
+            .map(|(node_id, failure)| (*node_id, failure))
+            .collect();
+        assert!(matches!(
+            reasons.as_slice(),
+            [
+                (1005, InsertFailure::DuplicateNodeId),
+                (2000, InsertFailure::DuplicateNodeId),
+                (2001, InsertFailure::InvalidVector(_)),
+                (2002, InsertFailure::InvalidVector(_)),
+            ]
+        ));
+        assert_eq!(adapter.size(), 22);
+        let found = adapter.search(&fresh[24].1, 1, 32, None, false)?;
+        assert_eq!(found[0].0, 2003);
+        // The existing node keeps its vector
+        let found = adapter.search(&vectors[5].1, 1, 32, None, false)?;
+        assert_eq!(found[0].0, 1005);
+        Ok(())
     }
 
     #[test]
-    fn test_empty_map_behavior() -> StorageResult<()> {
-        let map = ShardedVectorMap::new(2)?;
+    fn test_reserve_keeps_mappings() -> StorageResult<()> {
+        let vectors = test_vectors(20);
+        let mut adapter = build_adapter(&vectors)?;
+        adapter.reserve(5000);
+        assert!(adapter.node_to_vector.capacity() >= 5020);
+        assert!(adapter.vector_to_node.capacity() >= 5020);
+        assert_eq!(adapter.size(), 20);
+        for (node_id, vector) in &vectors {
+            assert_eq!(adapter.search(vector, 1, 32, None, false)?[0].0, *node_id);
+        }
+        Ok(())
+    }
 
-        // Basic empty map properties
-        assert!(map.is_empty());
-        assert_eq!(map.get(0), None);
-        assert!(!map.contains_key(0));
+    #[test]
+    fn test_graph_diagnostics() -> StorageResult<()> {
+        let vectors = test_vectors(30);
+        let unbuilt = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 30))?;
+        assert_eq!(unbuilt.entry_vector_id(), None);
+        assert_eq!(unbuilt.entry_node_id(), None);
+
+        let mut adapter = build_adapter(&vectors)?;
+        assert_eq!(adapter.max_degree(), 64);
+        assert_eq!(adapter.build_list_size(), 100);
+        let entry_vector_id = adapter.entry_vector_id().unwrap();
+        let entry_node_id = adapter.entry_node_id().unwrap();
+        assert_eq!(
+            adapter.node_to_vector_id(entry_node_id),
+            Some(entry_vector_id)
+        );
 
-        // All operations on empty map should return None/empty
-        assert_eq!(map.soft_delete(0)?, None);
-        assert!(map.batch_soft_delete(&[1, 2, 3])?.is_empty());
-        assert_eq!(map.remove(0), None);
+        // A load keeps the saved graph; only new vectors link with the loading adapter's
+        // parameters
+        let mut path = std::env::temp_dir();
+        path.push(format!(
+            "vector_index_diagnostics_{}.bin",
+            std::process::id()
+        ));
+        let path_str = path.to_str().unwrap().to_string();
+        adapter.save(&path_str)?;
+        let mut config = create_vector_index_config(TEST_DIM, 30);
+        config.index_write_parameter.max_degree = 32;
+        config.index_write_parameter.search_list_size = 50;
+        let mut loaded = InMemANNAdapter::new(config)?;
+        loaded.load(&path_str)?;
+        std::fs::remove_file(&path).ok();
+        assert_eq!(loaded.max_degree(), 32);
+        assert_eq!(loaded.build_list_size(), 50);
+        assert_eq!(loaded.provenance().unwrap().max_degree, 64);
+        assert_eq!(loaded.entry_node_id(), Some(entry_node_id));
+
+        adapter.soft_delete(&[entry_node_id])?;
+        assert_eq!(adapter.entry_vector_id(), Some(entry_vector_id));
+        assert_eq!(adapter.entry_node_id(), None);
+        Ok(())
+    }
 
+    #[test]
+    fn test_soft_delete_matching() -> StorageResult<()> {
+        let vectors = test_vectors(30);
+        let mut adapter = build_adapter(&vectors)?;
+        let mut unbuilt = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 30))?;
+        assert!(matches!(
+            unbuilt.soft_delete_matching(&FilterMask::new(vec![0], 1)),
+            Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt))
+        ));
+
+        // Even vector IDs, plus one past the allocated slots
+        let even: Vec<u32> = (0..30).step_by(2).chain([40]).collect();
+        let mask = FilterMask::new(even.clone(), 41);
+        let doomed: Vec<u64> = even[..15]
+            .iter()
+            .map(|&vector_id| adapter.vector_to_node.get(vector_id).unwrap())
+            .collect();
+        assert_eq!(adapter.soft_delete_matching(&mask)?, 15);
+        assert_eq!(adapter.size(), 15);
+        for node_id in &doomed {
+            assert_eq!(adapter.node_to_vector_id(*node_id), None);
+        }
+        let found = adapter.search(&vectors[3].1, 30, 64, None, false)?;
+        assert_eq!(found.len(), 15);
+        assert!(found.iter().all(|(node_id, _)| !doomed.contains(node_id)));
+        adapter.check_consistency()?;
+
+        // The same mask now matches nothing
+        assert_eq!(adapter.soft_delete_matching(&mask)?, 0);
+        assert_eq!(adapter.num_deleted(), 15);
         Ok(())
     }
 
     #[test]
-    fn test_removal_operations() -> StorageResult<()> {
-        let map = ShardedVectorMap::new(2)?;
+    fn test_ensure_k_retries_crowded_searches() -> StorageResult<()> {
+        let vectors = test_vectors(200);
+        let mut adapter = build_adapter(&vectors)?;
+        // Tombstone the neighborhood of the query, so a short search list holds mostly deleted
+        // nodes
+        let crowd: Vec<u64> = (1040..1160).filter(|&node_id| node_id != 1100).collect();
+        adapter.soft_delete(&crowd)?;
+        let query = &vectors[100].1;
+
+        let fixed = adapter.ann_search(query, 10, 10, None, false)?;
+        assert!(fixed.len() < 10, "{} results", fixed.len());
+        assert_eq!(adapter.stats().ensure_k_retried_searches, 0);
+
+        adapter.set_tuning(SearchTuning {
+            ensure_k_max_l_value: 512,
+            ..Default::default()
+        });
+        let retried = adapter.ann_search(query, 10, 10, None, false)?;
+        assert_eq!(retried.len(), 10);
+        assert_eq!(retried[0].0, 1100);
+        assert!(retried.iter().all(|(node_id, _)| !crowd.contains(node_id)));
+        assert_eq!(adapter.search(query, 10, 10, None, false)?, retried);
+        let stats = adapter.stats();
+        assert_eq!(stats.ensure_k_retried_searches, 2);
+        assert!(stats.ensure_k_retries >= 2);
+
+        // With fewer than k live nodes the cap bounds the retries
+        let survivors: Vec<u64> = (1000..1200)
+            .filter(|node_id| !crowd.contains(node_id) && *node_id >= 1003)
+            .collect();
+        adapter.soft_delete(&survivors)?;
+        adapter.set_tuning(SearchTuning {
+            ensure_k_max_l_value: 40,
+            ..Default::default()
+        });
+        let results = adapter.ann_search(query, 10, 10, None, false)?;
+        assert!(results.len() <= 3);
+        let retries = adapter.stats().ensure_k_retries - stats.ensure_k_retries;
+        assert!(retries <= 2, "{retries} retries");
+        Ok(())
+    }
 
-        // Setup test data
-        map.set(10, 110)?;
-        map.set(20, 120)?;
+    #[test]
+    fn test_emptied_index_differs_from_unbuilt() -> StorageResult<()> {
+        let not_built = |result: StorageResult<Vec<(u64, f32)>>| {
+            matches!(
+                result,
+                Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt))
+            )
+        };
+        let vectors = test_vectors(10);
+        let query = &vectors[0].1;
+        let mask = FilterMask::new(vec![0, 1], 10);
+
+        let mut unbuilt = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 10))?;
+        assert!(not_built(unbuilt.ann_search(query, 3, 10, None, false)));
+        assert!(not_built(unbuilt.search(query, 3, 10, None, false)));
+        assert!(not_built(unbuilt.search(query, 3, 10, Some(&mask), false)));
+        assert!(unbuilt.insert(&[(1, query.as_slice())]).is_err());
+        assert!(unbuilt.soft_delete(&[1]).is_err());
+
+        // Deleting every vector leaves a built index that answers with no results
+        let mut adapter = build_adapter(&vectors)?;
+        let all: Vec<u64> = vectors.iter().map(|(node_id, _)| *node_id).collect();
+        adapter.soft_delete(&all)?;
+        assert_eq!(adapter.size(), 0);
+        assert!(adapter.ann_search(query, 3, 10, None, false)?.is_empty());
+        assert!(adapter.search(query, 3, 10, None, false)?.is_empty());
+        assert!(adapter.search(query, 3, 10, Some(&mask), false)?.is_empty());
+
+        // and stays built across a save and load
+        let mut path = std::env::temp_dir();
+        path.push(format!("vector_index_emptied_{}.bin", std::process::id()));
+        let path_str = path.to_str().unwrap().to_string();
+        adapter.save(&path_str)?;
+        let mut loaded = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 10))?;
+        loaded.load(&path_str)?;
+        std::fs::remove_file(&path).ok();
+        assert!(loaded.ann_search(query, 3, 10, None, false)?.is_empty());
+
+        adapter.insert(&[(all[0], query.as_slice())])?;
+        assert_eq!(adapter.ann_search(query, 3, 10, None, false)?[0].0, all[0]);
+        Ok(())
+    }
 
-        // Test remove (used for rollback)
-        assert_eq!(map.remove(10), Some(110));
-        assert_eq!(map.get(10), None);
-        assert_eq!(map.remove(10), None); // Already removed
+    #[test]
+    fn test_export_import_mappings() -> StorageResult<()> {
+        let vectors = test_vectors(20);
+        let mut adapter = build_adapter(&vectors)?;
+        adapter.soft_delete(&[1003, 1007])?;
+        let exported = adapter.export_mappings();
+        assert_eq!(exported.len(), 18);
+        assert!(exported.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(exported[0], (1000, 0));
+        // Sorted by node ID, so the export is reproducible
+        let bytes = postcard::to_allocvec(&exported).unwrap();
+        assert_eq!(
+            postcard::to_allocvec(&adapter.export_mappings()).unwrap(),
+            bytes
+        );
 
-        // Test clear
-        assert!(!map.is_empty());
-        map.clear();
-        assert!(map.is_empty());
-        assert_eq!(map.get(20), None);
+        // Rekey every node, and leave node 1010 out
+        let rekeyed: Vec<(u64, u32)> = exported
+            .iter()
+            .filter(|(node_id, _)| *node_id != 1010)
+            .map(|&(node_id, vector_id)| (node_id + 5000, vector_id))
+            .collect();
+        adapter.import_mappings(&rekeyed)?;
+        adapter.check_consistency()?;
+        assert_eq!(adapter.export_mappings(), rekeyed);
+        assert_eq!(adapter.size(), 17);
+        assert_eq!(adapter.num_deleted(), 3);
+        let found = adapter.search(&vectors[12].1, 1, 32, None, false)?;
+        assert_eq!(found[0].0, 6012);
+        let found = adapter.search(&vectors[10].1, 20, 32, None, false)?;
+        assert!(
+            found
+                .iter()
+                .all(|(node_id, _)| *node_id != 1010 && *node_id != 6010)
+        );
 
+        // Rejected imports change nothing
+        let invalid: [&[(u64, u32)]; 4] =
+            [&[(1, 0), (1, 1)], &[(1, 0), (2, 0)], &[(1, 3)], &[(1, 20)]];
+        for mappings in invalid {
+            assert!(adapter.import_mappings(mappings).is_err());
+            assert_eq!(adapter.export_mappings(), rekeyed);
+        }
+        let mut unbuilt = InMemANNAdapter::new(create_vector_index_config(TEST_DIM, 20))?;
+        assert!(matches!(
+            unbuilt.import_mappings(&[]),
+            Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt))
+        ));
+
+        // New inserts continue after the allocated slots
+        adapter.insert(&[(1010, vectors[10].1.as_slice())])?;
+        assert_eq!(adapter.node_to_vector_id(1010), Some(20));
         Ok(())
     }
 }
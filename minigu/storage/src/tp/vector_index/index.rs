@@ -1,17 +1,26 @@
 use diskann::common::FilterIndex as DiskANNFilterMask;
 
-use super::filter::FilterMask;
+use super::config::DistanceMetric;
+use super::filter::SearchFilter;
+use super::stats::IndexStats;
 use crate::error::StorageResult;
 
 /// Vector index trait for approximate nearest neighbor search
 pub trait VectorIndex: Send + Sync {
     /// Build the index from vectors with their associated node IDs
     /// Configuration is provided during adapter creation
+    /// Like `insert` and `soft_delete`, an empty input is not an error: it yields an empty but
+    /// built index that accepts subsequent inserts
     fn build(&mut self, vectors: &[(u64, &[f32])]) -> StorageResult<()>;
 
     /// Pure DiskANN search for k nearest neighbors without filtering
-    /// l_value corresponds to the search list size parameter
-    /// Returns a vector of (vertex, distance) tuples
+    /// l_value corresponds to the search list size parameter; both must be positive and
+    /// `l_value` at least `k`, otherwise `InvalidSearchParams` is returned
+    /// Returns a vector of (vertex, distance) tuples with distinct vertices, closest first.
+    /// Distances are squared L2 (no square root is taken); for cosine indexes that is the
+    /// squared L2 between unit vectors, `2 * (1 - cos)`
+    /// A never-built index fails with `IndexNotBuilt`; one built or loaded but since emptied by
+    /// deletions returns no results, as do `search` and its variants
     fn ann_search(
         &self,
         query: &[f32],
@@ -24,16 +33,31 @@ fn ann_search(
     /// Search for k nearest neighbors with optional filtering
     /// filter_mask: None for no filtering, Some(mask) for filtered search
     /// Automatically selects optimal strategy based on filter characteristics
-    /// Returns a vector of (vertex, distance) tuples
+    /// `k` and `l_value` are validated as for `ann_search`
+    /// Returns a vector of (vertex, distance) tuples with distinct vertices, in the same
+    /// distance units as `ann_search` for every strategy. Each vertex appears at most once, at
+    /// its closest distance, even if the index's vector-to-vertex mapping is inconsistent
     fn search(
         &self,
         query: &[f32],
         k: usize,
         l_value: u32,
-        filter_mask: Option<&FilterMask>,
+        filter_mask: Option<&dyn SearchFilter>,
         should_pre: bool,
     ) -> StorageResult<Vec<(u64, f32)>>;
 
+    /// Search like `search`, then re-score the candidates exactly under `metric` using the
+    /// stored vectors. The index fetches up to `l_value` candidates and returns the `k` closest
+    /// under `metric`, so this always pays for an exact distance per candidate.
+    fn search_rescored(
+        &self,
+        query: &[f32],
+        k: usize,
+        l_value: u32,
+        filter_mask: Option<&dyn SearchFilter>,
+        metric: DistanceMetric,
+    ) -> StorageResult<Vec<(u64, f32)>>;
+
     /// Insert vectors with their node IDs (for dynamic updates)
     fn insert(&mut self, vectors: &[(u64, &[f32])]) -> StorageResult<()>;
 
@@ -46,12 +70,25 @@ fn search(
     /// Load the index from a file
     fn load(&mut self, path: &str) -> StorageResult<()>;
 
+    /// Convert a distance returned by `ann_search` or `search` to
+    /// [`DistanceUnits::Natural`](super::config::DistanceUnits::Natural) of the index metric
+    fn natural_distance(&self, distance: f32) -> f32;
+
     /// Get the dimension of vectors in this index
     fn get_dimension(&self) -> usize;
 
+    /// Distance metric the index ranks by
+    fn metric(&self) -> DistanceMetric;
+
     /// Get the number of vectors in this index
     fn size(&self) -> usize;
 
     /// Convert node_id to vector_id, returns None if node_id not found in index
     fn node_to_vector_id(&self, node_id: u64) -> Option<u32>;
+
+    /// Convert vector_id to node_id, returns None for unallocated or deleted vector IDs
+    fn vector_to_node_id(&self, vector_id: u32) -> Option<u64>;
+
+    /// Get a snapshot of the index statistics
+    fn stats(&self) -> IndexStats;
 }
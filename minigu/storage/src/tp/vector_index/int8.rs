@@ -0,0 +1,160 @@
+//! Int8 scalar quantization of indexed vectors for brute-force scans.
+//!
+//! Each vector is scaled symmetrically by its own factor `max |x| / 127` and rounded to `i8`.
+//! Squared L2 distances are computed in the integer domain as
+//! `|q|^2 + |v|^2 - 2 * scale_q * scale_v * dot(q_i8, v_i8)`, where the norms are those of the
+//! dequantized vectors, so the hot loop is a single `i8` dot product.
+
+/// Lanes of the `i8` dot product accumulated side by side; wide enough to fill a 256-bit
+/// register once widened to `i16`, which lets LLVM vectorize the loop without intrinsics
+const DOT_LANES: usize = 16;
+
+/// A vector quantized to `i8` with its scale
+#[derive(Debug, Clone, PartialEq)]
+pub struct Int8Vector {
+    pub values: Vec<i8>,
+    /// Multiply the values by this to dequantize
+    pub scale: f32,
+    /// Squared L2 norm of the dequantized vector
+    pub squared_norm: f32,
+}
+
+impl Int8Vector {
+    pub fn quantize(vector: &[f32]) -> Self {
+        let mut values = Vec::with_capacity(vector.len());
+        let (scale, squared_norm) = quantize_into(vector, &mut values);
+        Self {
+            values,
+            scale,
+            squared_norm,
+        }
+    }
+}
+
+/// Int8 codes of every allocated vector slot of an index, indexed by vector ID
+#[derive(Debug, Clone)]
+pub(crate) struct Int8Vectors {
+    dimension: usize,
+    /// Row-major values, `dimension` per slot
+    values: Vec<i8>,
+    scales: Vec<f32>,
+    squared_norms: Vec<f32>,
+}
+
+impl Int8Vectors {
+    pub(crate) fn new(dimension: usize) -> Self {
+        Self {
+            dimension,
+            values: Vec::new(),
+            scales: Vec::new(),
+            squared_norms: Vec::new(),
+        }
+    }
+
+    /// Number of slots encoded so far; slot `i` has vector ID `i`
+    pub(crate) fn encoded_slots(&self) -> usize {
+        self.scales.len()
+    }
+
+    /// Quantize `vector` as the next slot
+    pub(crate) fn push(&mut self, vector: &[f32]) {
+        debug_assert_eq!(vector.len(), self.dimension);
+        let (scale, squared_norm) = quantize_into(vector, &mut self.values);
+        self.scales.push(scale);
+        self.squared_norms.push(squared_norm);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.values.clear();
+        self.scales.clear();
+        self.squared_norms.clear();
+    }
+
+    /// Approximate squared L2 distance from `query` to slot `vector_id`, or `None` if the slot
+    /// is not encoded
+    pub(crate) fn distance(&self, query: &Int8Vector, vector_id: u32) -> Option<f32> {
+        let slot = vector_id as usize;
+        let scale = *self.scales.get(slot)?;
+        let values = &self.values[slot * self.dimension..(slot + 1) * self.dimension];
+        let dot = dot_i8(&query.values, values) as f32;
+        // Rounding can push the expansion slightly below zero for near-identical vectors
+        Some(
+            (query.squared_norm + self.squared_norms[slot] - 2.0 * query.scale * scale * dot)
+                .max(0.0),
+        )
+    }
+
+    pub(crate) fn memory_bytes(&self) -> usize {
+        self.values.capacity()
+            + (self.scales.capacity() + self.squared_norms.capacity()) * std::mem::size_of::<f32>()
+    }
+}
+
+/// Append the `i8` values of `vector` to `values`, returning its scale and dequantized squared
+/// norm. An all-zero vector gets scale 0.0.
+fn quantize_into(vector: &[f32], values: &mut Vec<i8>) -> (f32, f32) {
+    let max_abs = vector.iter().fold(0.0f32, |max, x| max.max(x.abs()));
+    if max_abs == 0.0 || !max_abs.is_finite() {
+        values.extend(std::iter::repeat_n(0, vector.len()));
+        return (0.0, 0.0);
+    }
+    let scale = max_abs / i8::MAX as f32;
+    let mut squared_sum = 0i64;
+    values.extend(vector.iter().map(|x| {
+        let value = (x / scale).round().clamp(-127.0, 127.0) as i8;
+        squared_sum += value as i64 * value as i64;
+        value
+    }));
+    (scale, scale * scale * squared_sum as f32)
+}
+
+/// Integer dot product of equal-length `i8` slices
+fn dot_i8(a: &[i8], b: &[i8]) -> i32 {
+    let mut lanes = [0i32; DOT_LANES];
+    let mut a_chunks = a.chunks_exact(DOT_LANES);
+    let mut b_chunks = b.chunks_exact(DOT_LANES);
+    for (a_chunk, b_chunk) in (&mut a_chunks).zip(&mut b_chunks) {
+        for lane in 0..DOT_LANES {
+            lanes[lane] += a_chunk[lane] as i32 * b_chunk[lane] as i32;
+        }
+    }
+    let tail: i32 = a_chunks
+        .remainder()
+        .iter()
+        .zip(b_chunks.remainder())
+        .map(|(&x, &y)| x as i32 * y as i32)
+        .sum();
+    lanes.iter().sum::<i32>() + tail
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_distance_approximates_l2() {
+        let a: Vec<f32> = (0..37).map(|i| (i as f32 * 0.37).sin()).collect();
+        let b: Vec<f32> = (0..37).map(|i| (i as f32 * 0.21).cos() * 2.0).collect();
+        let exact: f32 = a.iter().zip(&b).map(|(x, y)| (x - y) * (x - y)).sum();
+
+        let mut store = Int8Vectors::new(37);
+        store.push(&b);
+        store.push(&[0.0; 37]);
+        assert_eq!(store.encoded_slots(), 2);
+
+        let query = Int8Vector::quantize(&a);
+        let approx = store.distance(&query, 0).unwrap();
+        assert!((approx - exact).abs() / exact < 0.01, "{approx} vs {exact}");
+        let to_zero = store.distance(&query, 1).unwrap();
+        assert!((to_zero - query.squared_norm).abs() < 1e-6);
+        assert!(store.distance(&query, 2).is_none());
+    }
+
+    #[test]
+    fn test_dot_i8_matches_scalar() {
+        let a: Vec<i8> = (0..45).map(|i| (i * 7 % 255 - 127) as i8).collect();
+        let b: Vec<i8> = (0..45).map(|i| (i * 13 % 255 - 127) as i8).collect();
+        let expected: i32 = a.iter().zip(&b).map(|(&x, &y)| x as i32 * y as i32).sum();
+        assert_eq!(dot_i8(&a, &b), expected);
+    }
+}
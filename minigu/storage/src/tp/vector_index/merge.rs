@@ -0,0 +1,134 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use ordered_float::OrderedFloat;
+
+/// Merge per-shard search results into the global top-`k`.
+///
+/// Each shard's list must be sorted by ascending distance, as returned by
+/// [`VectorIndex::search`]. A node ID reported by several shards is kept once, with its smallest
+/// distance. Equal distances are ordered by node ID so the merge is deterministic regardless of
+/// shard order. Runs in O(k log s) for `s` shards, plus the duplicates skipped.
+///
+/// [`VectorIndex::search`]: super::VectorIndex::search
+pub fn merge_shard_results(shard_results: Vec<Vec<(u64, f32)>>, k: usize) -> Vec<(u64, f32)> {
+    let mut merged = Vec::with_capacity(k);
+    if k == 0 {
+        return merged;
+    }
+    debug_assert!(shard_results.iter().all(|results| {
+        results
+            .windows(2)
+            .all(|pair| OrderedFloat(pair[0].1) <= OrderedFloat(pair[1].1))
+    }));
+
+    // Min-heap over the head of each shard: (distance, node_id, shard, position)
+    let mut heads = BinaryHeap::with_capacity(shard_results.len());
+    for (shard, results) in shard_results.iter().enumerate() {
+        if let Some(&(node_id, distance)) = results.first() {
+            heads.push(Reverse((OrderedFloat(distance), node_id, shard, 0usize)));
+        }
+    }
+
+    let mut seen = HashSet::with_capacity(k);
+    while let Some(Reverse((distance, node_id, shard, position))) = heads.pop() {
+        // Heads pop in ascending distance, so the first sighting of a node is its best
+        if seen.insert(node_id) {
+            merged.push((node_id, distance.0));
+            if merged.len() == k {
+                break;
+            }
+        }
+        if let Some(&(next_node, next_distance)) = shard_results[shard].get(position + 1) {
+            heads.push(Reverse((
+                OrderedFloat(next_distance),
+                next_node,
+                shard,
+                position + 1,
+            )));
+        }
+    }
+    merged
+}
+
+/// Fuse the results of searches over different vector properties into one ranking by
+/// weighted, normalized distance.
+///
+/// Each list's distances are min-max scaled to `[0, 1]` (all 0.0 when they are equal), so
+/// properties with different distance ranges are comparable. A node missing from a list scores
+/// 1.0 there, as far as that list's furthest result. A node's combined score is the sum over
+/// lists of `weight * normalized distance`; the `k` lowest are returned as `(node_id, score)`,
+/// ties ordered by node ID. Node IDs must be distinct within each list.
+pub fn merge_weighted_results(lists: &[(&[(u64, f32)], f32)], k: usize) -> Vec<(u64, f32)> {
+    let total_weight: f32 = lists.iter().map(|(_, weight)| weight).sum();
+    // Start every node at the full missing penalty and discount the lists it appears in
+    let mut scores: HashMap<u64, f32> = HashMap::new();
+    for &(results, weight) in lists {
+        let (min, max) = results.iter().fold(
+            (f32::INFINITY, f32::NEG_INFINITY),
+            |(min, max), &(_, distance)| (min.min(distance), max.max(distance)),
+        );
+        let range = max - min;
+        for &(node_id, distance) in results {
+            let normalized = if range > 0.0 {
+                (distance - min) / range
+            } else {
+                0.0
+            };
+            *scores.entry(node_id).or_insert(total_weight) -= weight * (1.0 - normalized);
+        }
+    }
+
+    let mut merged: Vec<(u64, f32)> = scores.into_iter().collect();
+    merged.sort_by_key(|&(node_id, score)| (OrderedFloat(score), node_id));
+    merged.truncate(k);
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_shard_results() {
+        let shards = vec![
+            vec![(1, 0.1), (2, 0.4), (3, 0.9)],
+            vec![(4, 0.2), (2, 0.3), (5, 0.4)],
+            vec![],
+            vec![(6, 0.4), (1, 0.5)],
+        ];
+
+        // Node 2 keeps its smaller distance; ties at 0.4 are ordered by node ID
+        assert_eq!(
+            merge_shard_results(shards.clone(), 5),
+            vec![(1, 0.1), (4, 0.2), (2, 0.3), (5, 0.4), (6, 0.4)]
+        );
+        // Fewer unique results than k
+        assert_eq!(merge_shard_results(shards.clone(), 100).len(), 6);
+        assert!(merge_shard_results(shards, 0).is_empty());
+        assert!(merge_shard_results(Vec::new(), 3).is_empty());
+    }
+
+    #[test]
+    fn test_merge_weighted_results() {
+        let text = [(1, 0.0), (2, 5.0), (3, 10.0)];
+        let image = [(3, 100.0), (4, 300.0)];
+
+        let merged = merge_weighted_results(&[(&text, 1.0), (&image, 1.0)], 10);
+        // 3: 1.0 + 0.0; 1: 0.0 + 1.0 (missing); 2: 0.5 + 1.0; 4: 1.0 + 1.0
+        assert_eq!(merged, vec![(1, 1.0), (3, 1.0), (2, 1.5), (4, 2.0)]);
+
+        // Weights shift the ranking towards the heavier property
+        let merged = merge_weighted_results(&[(&text, 0.2), (&image, 0.8)], 2);
+        assert_eq!(merged[0].0, 3);
+        assert!((merged[0].1 - 0.2).abs() < 1e-6);
+        assert_eq!(merged.len(), 2);
+
+        // A single result (or equal distances) normalizes to 0.0
+        assert_eq!(
+            merge_weighted_results(&[(&[(7, 42.0)], 1.0), (&[], 1.0)], 5),
+            vec![(7, 1.0)]
+        );
+        assert!(merge_weighted_results(&[(&text, 1.0)], 0).is_empty());
+    }
+}
@@ -1,6 +1,30 @@
+pub mod compare;
+pub mod config;
 pub mod filter;
+pub mod float16;
 pub mod in_mem_diskann;
 pub mod index;
+pub mod int8;
+pub mod merge;
+pub mod pq;
+pub mod provenance;
+#[cfg(feature = "sparse")]
+pub mod sparse;
+pub mod stats;
 
-pub use in_mem_diskann::InMemANNAdapter;
+pub use compare::{ComparisonReport, QueryComparison, compare_indexes};
+pub use config::{
+    AdapterConfig, DistanceMetric, DistanceUnits, DuplicateVectorPolicy, F64IngestPolicy,
+    IndexMetric, ProductQuantization, Quality, SearchTuning, VectorStorage, ZeroNormPolicy,
+};
+pub use in_mem_diskann::{
+    BuildGate, BuildPhase, BuildProgress, InMemANNAdapter, SearchDebugOutput,
+};
 pub use index::VectorIndex;
+pub use merge::{merge_shard_results, merge_weighted_results};
+pub use pq::ProductQuantizer;
+pub use provenance::IndexProvenance;
+pub use stats::{
+    BuildPlan, DimStat, DistanceStats, FragmentationReport, IndexBuildReport, IndexStats,
+    InsertFailure, InsertReport, SearchStats,
+};
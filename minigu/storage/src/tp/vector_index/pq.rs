@@ -0,0 +1,255 @@
+//! Product quantization (PQ) of indexed vectors for compressed brute-force scans.
+//!
+//! A vector is split into contiguous sub-vectors, one per subquantizer, and each sub-vector is
+//! replaced by the index of its nearest centroid in a per-subspace codebook trained by k-means.
+//! Distances are estimated by asymmetric distance computation (ADC): the query stays exact, and a
+//! per-query table of query-to-centroid distances turns each candidate into one table lookup per
+//! subquantizer.
+
+use std::ops::Range;
+
+use rayon::prelude::*;
+
+/// Lloyd iterations run per subspace codebook
+const TRAINING_ITERATIONS: usize = 10;
+
+/// Largest number of vectors a codebook is trained on; larger indexes are subsampled evenly
+pub const MAX_TRAINING_SAMPLES: usize = 16_384;
+
+/// Multiple of `k` scored by PQ before exact reranking, see
+/// [`ProductQuantization::rerank_exact`](super::config::ProductQuantization::rerank_exact)
+pub const PQ_RERANK_OVERFETCH: usize = 4;
+
+/// Trained PQ codebooks for vectors of one dimension
+#[derive(Debug, Clone)]
+pub struct ProductQuantizer {
+    subspaces: Vec<Range<usize>>,
+    /// Per subspace, `centroid_count` row-major centroids of the subspace's width
+    codebooks: Vec<Vec<f32>>,
+    centroid_count: usize,
+}
+
+impl ProductQuantizer {
+    /// Train `2^bits_per_code` centroids for each of `subquantizers` near-equal subspaces on every
+    /// vector of `samples`. With fewer samples than centroids the codebooks are the samples
+    /// themselves, so those samples encode exactly.
+    ///
+    /// # Panics
+    /// If `samples` is empty or of mixed dimension, `subquantizers` is not in `1..=dimension`,
+    /// or `bits_per_code` is not in `1..=8`.
+    pub fn train(subquantizers: usize, bits_per_code: u8, samples: &[&[f32]]) -> Self {
+        assert!(!samples.is_empty(), "PQ training needs at least one sample");
+        let dimension = samples[0].len();
+        assert!(
+            samples.iter().all(|sample| sample.len() == dimension),
+            "PQ training samples must share one dimension"
+        );
+        assert!((1..=dimension).contains(&subquantizers));
+        assert!((1..=8).contains(&bits_per_code));
+
+        let centroid_count = (1usize << bits_per_code).min(samples.len());
+        let subspaces: Vec<Range<usize>> = (0..subquantizers)
+            .map(|j| j * dimension / subquantizers..(j + 1) * dimension / subquantizers)
+            .collect();
+        let codebooks = subspaces
+            .par_iter()
+            .map(|range| train_codebook(samples, range.clone(), centroid_count))
+            .collect();
+        Self {
+            subspaces,
+            codebooks,
+            centroid_count,
+        }
+    }
+
+    /// Code length in bytes, one per subquantizer
+    pub fn subquantizers(&self) -> usize {
+        self.subspaces.len()
+    }
+
+    /// Bytes held by the codebooks
+    pub fn codebook_bytes(&self) -> usize {
+        self.codebooks.iter().map(Vec::len).sum::<usize>() * std::mem::size_of::<f32>()
+    }
+
+    /// Append the code of `vector` to `codes`
+    pub fn encode_into(&self, vector: &[f32], codes: &mut Vec<u8>) {
+        for (range, codebook) in self.subspaces.iter().zip(&self.codebooks) {
+            codes.push(nearest_centroid(codebook, range.len(), &vector[range.clone()]) as u8);
+        }
+    }
+
+    /// Lookup table estimating squared L2 distances from `query` to encoded vectors
+    pub fn distance_table(&self, query: &[f32]) -> DistanceTable {
+        let mut table = Vec::with_capacity(self.subspaces.len() * self.centroid_count);
+        for (range, codebook) in self.subspaces.iter().zip(&self.codebooks) {
+            let sub_query = &query[range.clone()];
+            table.extend(
+                codebook
+                    .chunks_exact(range.len())
+                    .map(|centroid| squared_l2(sub_query, centroid)),
+            );
+        }
+        DistanceTable {
+            centroid_count: self.centroid_count,
+            table,
+        }
+    }
+}
+
+/// Per-query ADC table of a [`ProductQuantizer`]
+#[derive(Debug, Clone)]
+pub struct DistanceTable {
+    centroid_count: usize,
+    /// Squared L2 distance from the query's sub-vector to each centroid, per subspace
+    table: Vec<f32>,
+}
+
+impl DistanceTable {
+    /// Estimated squared L2 distance from the query to the vector encoded as `code`
+    pub fn distance(&self, code: &[u8]) -> f32 {
+        code.iter()
+            .enumerate()
+            .map(|(subspace, &centroid)| {
+                self.table[subspace * self.centroid_count + centroid as usize]
+            })
+            .sum()
+    }
+}
+
+/// PQ codes of every allocated vector slot of an index, indexed by vector ID
+#[derive(Debug, Clone)]
+pub(crate) struct QuantizedVectors {
+    pub(crate) quantizer: ProductQuantizer,
+    codes: Vec<u8>,
+}
+
+impl QuantizedVectors {
+    pub(crate) fn new(quantizer: ProductQuantizer) -> Self {
+        Self {
+            quantizer,
+            codes: Vec::new(),
+        }
+    }
+
+    /// Number of slots encoded so far; slot `i` has vector ID `i`
+    pub(crate) fn encoded_slots(&self) -> usize {
+        self.codes.len() / self.quantizer.subquantizers()
+    }
+
+    /// Encode `vector` as the next slot
+    pub(crate) fn push(&mut self, vector: &[f32]) {
+        self.quantizer.encode_into(vector, &mut self.codes);
+    }
+
+    pub(crate) fn code(&self, vector_id: u32) -> Option<&[u8]> {
+        let width = self.quantizer.subquantizers();
+        let start = vector_id as usize * width;
+        self.codes.get(start..start + width)
+    }
+
+    pub(crate) fn memory_bytes(&self) -> usize {
+        self.codes.capacity() + self.quantizer.codebook_bytes()
+    }
+}
+
+/// k-means over the `range` sub-vectors of `samples`, seeded with evenly spaced samples
+fn train_codebook(samples: &[&[f32]], range: Range<usize>, centroid_count: usize) -> Vec<f32> {
+    let width = range.len();
+    let mut centroids = Vec::with_capacity(centroid_count * width);
+    for i in 0..centroid_count {
+        centroids.extend_from_slice(&samples[i * samples.len() / centroid_count][range.clone()]);
+    }
+
+    let mut sums = vec![0.0f64; centroid_count * width];
+    let mut counts = vec![0usize; centroid_count];
+    for _ in 0..TRAINING_ITERATIONS {
+        sums.fill(0.0);
+        counts.fill(0);
+        for sample in samples {
+            let sub_vector = &sample[range.clone()];
+            let centroid = nearest_centroid(&centroids, width, sub_vector);
+            counts[centroid] += 1;
+            for (sum, &x) in sums[centroid * width..(centroid + 1) * width]
+                .iter_mut()
+                .zip(sub_vector)
+            {
+                *sum += x as f64;
+            }
+        }
+        // Centroids left without samples keep their previous position
+        for (centroid, &count) in counts.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let offset = centroid * width;
+            for d in 0..width {
+                centroids[offset + d] = (sums[offset + d] / count as f64) as f32;
+            }
+        }
+    }
+    centroids
+}
+
+fn nearest_centroid(centroids: &[f32], width: usize, sub_vector: &[f32]) -> usize {
+    centroids
+        .chunks_exact(width)
+        .map(|centroid| squared_l2(sub_vector, centroid))
+        .enumerate()
+        .min_by(|(_, a), (_, b)| a.total_cmp(b))
+        .map_or(0, |(centroid, _)| centroid)
+}
+
+fn squared_l2(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_samples_within_codebook_size_encode_exactly() {
+        let samples: Vec<Vec<f32>> = (0..10)
+            .map(|i| (0..6).map(|d| (i * 6 + d) as f32).collect())
+            .collect();
+        let refs: Vec<&[f32]> = samples.iter().map(Vec::as_slice).collect();
+        let quantizer = ProductQuantizer::train(3, 4, &refs);
+        assert_eq!(quantizer.subquantizers(), 3);
+
+        let table = quantizer.distance_table(&samples[4]);
+        for sample in &samples {
+            let mut code = Vec::new();
+            quantizer.encode_into(sample, &mut code);
+            assert_eq!(code.len(), 3);
+            assert_eq!(table.distance(&code), squared_l2(&samples[4], sample));
+        }
+    }
+
+    #[test]
+    fn test_clustered_data_preserves_ranking() {
+        // Two well separated clusters; PQ must keep near points nearer than far ones
+        let samples: Vec<Vec<f32>> = (0..200)
+            .map(|i| {
+                let base = if i % 2 == 0 { 0.0 } else { 100.0 };
+                (0..8)
+                    .map(|d| base + ((i * 7 + d) % 5) as f32 * 0.1)
+                    .collect()
+            })
+            .collect();
+        let refs: Vec<&[f32]> = samples.iter().map(Vec::as_slice).collect();
+        let quantizer = ProductQuantizer::train(4, 2, &refs);
+
+        let mut store = QuantizedVectors::new(quantizer);
+        for sample in &samples {
+            store.push(sample);
+        }
+        assert_eq!(store.encoded_slots(), 200);
+        assert!(store.code(200).is_none());
+
+        let table = store.quantizer.distance_table(&samples[0]);
+        let near = table.distance(store.code(2).unwrap());
+        let far = table.distance(store.code(1).unwrap());
+        assert!(near < far);
+    }
+}
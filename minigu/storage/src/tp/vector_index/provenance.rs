@@ -0,0 +1,49 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use diskann::model::IndexConfiguration;
+use serde::{Deserialize, Serialize};
+
+use super::config::AdapterConfig;
+
+/// How and with what an index was built, captured at build time and kept across save/load
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexProvenance {
+    /// Version of `minigu-storage` that built the index
+    pub crate_version: String,
+    /// Version of the DiskANN implementation that built the graph
+    pub diskann_version: String,
+    /// Distance metric, as DiskANN names it
+    pub metric: String,
+    pub dimension: usize,
+    /// DiskANN max out-degree (R)
+    pub max_degree: u32,
+    /// DiskANN build search list size (L)
+    pub build_list_size: u32,
+    /// DiskANN pruning parameter
+    pub alpha: f32,
+    /// Whether vectors were normalized to unit length before indexing
+    pub normalized: bool,
+    /// Build time in seconds since the Unix epoch
+    pub built_at_unix_secs: u64,
+}
+
+impl IndexProvenance {
+    /// Provenance of a build happening now with the given configuration
+    pub(crate) fn capture(config: &IndexConfiguration, adapter_config: &AdapterConfig) -> Self {
+        let write_params = &config.index_write_parameter;
+        Self {
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            diskann_version: diskann::VERSION.to_string(),
+            metric: format!("{:?}", adapter_config.metric),
+            dimension: config.dim,
+            max_degree: write_params.max_degree,
+            build_list_size: write_params.search_list_size,
+            alpha: write_params.alpha,
+            normalized: adapter_config.normalizes(),
+            built_at_unix_secs: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+}
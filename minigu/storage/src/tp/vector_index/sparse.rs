@@ -0,0 +1,339 @@
+//! Sparse vector index for SPLADE-style embeddings.
+//!
+//! Vectors are stored as `(index, value)` pairs and scored by dot product over shared indices
+//! using an inverted index, so queries only touch postings of their non-zero dimensions. This is
+//! a distinct index type from the dense DiskANN adapter and does not implement [`VectorIndex`].
+//!
+//! [`VectorIndex`]: super::VectorIndex
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+
+use dashmap::DashMap;
+use ordered_float::OrderedFloat;
+use parking_lot::RwLock;
+
+use crate::error::{StorageError, StorageResult, VectorIndexError};
+
+/// Sparse vector with strictly increasing dimension indices
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseVector {
+    indices: Vec<u32>,
+    values: Vec<f32>,
+}
+
+impl SparseVector {
+    /// Create a sparse vector from `(index, value)` pairs in any order.
+    /// Duplicate indices, non-finite values and indices `>= dimension` are rejected; explicit
+    /// zeros are dropped.
+    pub fn new(pairs: &[(u32, f32)], dimension: u32) -> StorageResult<Self> {
+        let mut sorted: Vec<(u32, f32)> = Vec::with_capacity(pairs.len());
+        for &(index, value) in pairs {
+            if index >= dimension {
+                return Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(
+                    format!("Sparse index {index} out of range for dimension {dimension}"),
+                )));
+            }
+            if !value.is_finite() {
+                return Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(
+                    format!("Sparse value at index {index} is not finite"),
+                )));
+            }
+            if value != 0.0 {
+                sorted.push((index, value));
+            }
+        }
+        sorted.sort_unstable_by_key(|(index, _)| *index);
+        if let Some(pair) = sorted.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(StorageError::VectorIndex(VectorIndexError::InvalidInput(
+                format!("Duplicate sparse index {}", pair[0].0),
+            )));
+        }
+
+        let (indices, values) = sorted.into_iter().unzip();
+        Ok(Self { indices, values })
+    }
+
+    /// Number of non-zero entries
+    pub fn nnz(&self) -> usize {
+        self.indices.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (u32, f32)> + '_ {
+        self.indices
+            .iter()
+            .copied()
+            .zip(self.values.iter().copied())
+    }
+
+    /// Dot product over shared indices (merge join over the sorted index lists)
+    pub fn dot(&self, other: &SparseVector) -> f32 {
+        let (mut i, mut j) = (0, 0);
+        let mut sum = 0.0f32;
+        while i < self.indices.len() && j < other.indices.len() {
+            match self.indices[i].cmp(&other.indices[j]) {
+                std::cmp::Ordering::Less => i += 1,
+                std::cmp::Ordering::Greater => j += 1,
+                std::cmp::Ordering::Equal => {
+                    sum += self.values[i] * other.values[j];
+                    i += 1;
+                    j += 1;
+                }
+            }
+        }
+        sum
+    }
+}
+
+/// In-memory sparse vector index with an inverted index over dimensions
+pub struct SparseVectorIndex {
+    dimension: u32,
+    vectors: DashMap<u64, SparseVector>,
+    // dimension index -> (node_id, value) postings
+    postings: RwLock<HashMap<u32, Vec<(u64, f32)>>>,
+    built: bool,
+}
+
+impl SparseVectorIndex {
+    /// Create an empty index for sparse vectors over `dimension` dimensions (vocabulary size)
+    pub fn new(dimension: u32) -> StorageResult<Self> {
+        if dimension == 0 {
+            return Err(StorageError::VectorIndex(VectorIndexError::Configuration(
+                "Sparse index dimension must be greater than zero".to_string(),
+            )));
+        }
+        Ok(Self {
+            dimension,
+            vectors: DashMap::new(),
+            postings: RwLock::new(HashMap::new()),
+            built: false,
+        })
+    }
+
+    /// Build the index from sparse vectors, replacing any existing contents.
+    /// An empty input yields an empty but built index.
+    pub fn build(&mut self, vectors: &[(u64, &[(u32, f32)])]) -> StorageResult<()> {
+        let parsed = self.parse_batch(vectors, false)?;
+
+        self.vectors.clear();
+        self.postings.write().clear();
+        self.add_parsed(parsed);
+        self.built = true;
+        Ok(())
+    }
+
+    /// Insert sparse vectors into a built index
+    pub fn insert(&mut self, vectors: &[(u64, &[(u32, f32)])]) -> StorageResult<()> {
+        if vectors.is_empty() {
+            return Ok(());
+        }
+        if !self.built {
+            return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
+        }
+        let parsed = self.parse_batch(vectors, true)?;
+        self.add_parsed(parsed);
+        Ok(())
+    }
+
+    /// Remove vectors by node ID; all IDs must exist
+    pub fn delete(&mut self, node_ids: &[u64]) -> StorageResult<()> {
+        if node_ids.is_empty() {
+            return Ok(());
+        }
+        if !self.built {
+            return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
+        }
+        if let Some(&node_id) = node_ids.iter().find(|id| !self.vectors.contains_key(id)) {
+            return Err(StorageError::VectorIndex(
+                VectorIndexError::NodeIdNotFound { node_id },
+            ));
+        }
+
+        let mut postings = self.postings.write();
+        for node_id in node_ids {
+            let Some((_, vector)) = self.vectors.remove(node_id) else {
+                continue; // Repeated ID within the same call
+            };
+            for (index, _) in vector.iter() {
+                if let Some(list) = postings.get_mut(&index) {
+                    list.retain(|(id, _)| id != node_id);
+                    if list.is_empty() {
+                        postings.remove(&index);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Return up to `k` nodes with the highest dot product against `query`, best first.
+    /// Nodes sharing no non-zero dimension with the query are never returned.
+    pub fn search(&self, query: &[(u32, f32)], k: usize) -> StorageResult<Vec<(u64, f32)>> {
+        if !self.built {
+            return Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt));
+        }
+        let query = SparseVector::new(query, self.dimension)?;
+        if k == 0 || query.nnz() == 0 {
+            return Ok(Vec::new());
+        }
+
+        // Accumulate partial dot products by walking the postings of each query dimension
+        let mut scores: HashMap<u64, f32> = HashMap::new();
+        {
+            let postings = self.postings.read();
+            for (index, query_value) in query.iter() {
+                if let Some(list) = postings.get(&index) {
+                    for &(node_id, value) in list {
+                        *scores.entry(node_id).or_insert(0.0) += query_value * value;
+                    }
+                }
+            }
+        }
+
+        // Min-heap of size k keeps the best scores seen so far
+        let mut heap = BinaryHeap::<Reverse<(OrderedFloat<f32>, u64)>>::with_capacity(k);
+        for (node_id, score) in scores {
+            if heap.len() < k {
+                heap.push(Reverse((OrderedFloat(score), node_id)));
+            } else if let Some(Reverse((min_score, _))) = heap.peek()
+                && OrderedFloat(score) > *min_score
+            {
+                heap.pop();
+                heap.push(Reverse((OrderedFloat(score), node_id)));
+            }
+        }
+
+        Ok(heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((score, node_id))| (node_id, score.0))
+            .collect())
+    }
+
+    /// Get the stored sparse vector for a node
+    pub fn get_vector(&self, node_id: u64) -> Option<SparseVector> {
+        self.vectors.get(&node_id).map(|entry| entry.clone())
+    }
+
+    pub fn get_dimension(&self) -> u32 {
+        self.dimension
+    }
+
+    pub fn size(&self) -> usize {
+        self.vectors.len()
+    }
+
+    /// Validate a batch and reject duplicate node IDs (within the batch, and against existing
+    /// entries when `check_existing` is set)
+    fn parse_batch(
+        &self,
+        vectors: &[(u64, &[(u32, f32)])],
+        check_existing: bool,
+    ) -> StorageResult<Vec<(u64, SparseVector)>> {
+        let mut seen = std::collections::HashSet::with_capacity(vectors.len());
+        let mut parsed = Vec::with_capacity(vectors.len());
+        for &(node_id, pairs) in vectors {
+            if !seen.insert(node_id) || (check_existing && self.vectors.contains_key(&node_id)) {
+                return Err(StorageError::VectorIndex(
+                    VectorIndexError::DuplicateNodeId { node_id },
+                ));
+            }
+            parsed.push((node_id, SparseVector::new(pairs, self.dimension)?));
+        }
+        Ok(parsed)
+    }
+
+    fn add_parsed(&self, parsed: Vec<(u64, SparseVector)>) {
+        let mut postings = self.postings.write();
+        for (node_id, vector) in parsed {
+            for (index, value) in vector.iter() {
+                postings.entry(index).or_default().push((node_id, value));
+            }
+            self.vectors.insert(node_id, vector);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VOCAB: u32 = 30_000;
+
+    fn build_index() -> StorageResult<SparseVectorIndex> {
+        let mut index = SparseVectorIndex::new(VOCAB)?;
+        let a: &[(u32, f32)] = &[(1, 1.0), (500, 2.0), (29_999, 0.5)];
+        let b: &[(u32, f32)] = &[(500, 1.0), (7, 3.0)];
+        let c: &[(u32, f32)] = &[(12_000, 4.0)];
+        index.build(&[(10, a), (20, b), (30, c)])?;
+        Ok(index)
+    }
+
+    #[test]
+    fn test_sparse_vector_validation_and_dot() -> StorageResult<()> {
+        let a = SparseVector::new(&[(5, 2.0), (1, 1.0), (3, 0.0)], 10)?;
+        assert_eq!(a.nnz(), 2); // explicit zero dropped
+        assert_eq!(a.iter().collect::<Vec<_>>(), vec![(1, 1.0), (5, 2.0)]);
+
+        let b = SparseVector::new(&[(5, 3.0), (9, 1.0)], 10)?;
+        assert_eq!(a.dot(&b), 6.0);
+
+        assert!(SparseVector::new(&[(10, 1.0)], 10).is_err());
+        assert!(SparseVector::new(&[(1, f32::NAN)], 10).is_err());
+        assert!(SparseVector::new(&[(1, 1.0), (1, 2.0)], 10).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sparse_search_ranks_by_dot_product() -> StorageResult<()> {
+        let index = build_index()?;
+        assert_eq!(index.size(), 3);
+
+        let results = index.search(&[(500, 1.0), (7, 1.0)], 10)?;
+        // node 20: 1*1 + 3*1 = 4, node 10: 2*1 = 2, node 30 shares no dimension
+        assert_eq!(results, vec![(20, 4.0), (10, 2.0)]);
+
+        let top1 = index.search(&[(500, 1.0), (7, 1.0)], 1)?;
+        assert_eq!(top1, vec![(20, 4.0)]);
+
+        assert!(index.search(&[(42, 1.0)], 5)?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sparse_insert_and_delete() -> StorageResult<()> {
+        let mut index = build_index()?;
+        let d: &[(u32, f32)] = &[(12_000, 1.0)];
+        index.insert(&[(40, d)])?;
+        assert!(matches!(
+            index.insert(&[(40, d)]),
+            Err(StorageError::VectorIndex(
+                VectorIndexError::DuplicateNodeId { node_id: 40 }
+            ))
+        ));
+
+        let results = index.search(&[(12_000, 1.0)], 5)?;
+        assert_eq!(results, vec![(30, 4.0), (40, 1.0)]);
+
+        index.delete(&[30])?;
+        assert_eq!(index.search(&[(12_000, 1.0)], 5)?, vec![(40, 1.0)]);
+        assert!(index.get_vector(30).is_none());
+        assert!(index.delete(&[30]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_sparse_index_not_built() -> StorageResult<()> {
+        let index = SparseVectorIndex::new(VOCAB)?;
+        assert!(matches!(
+            index.search(&[(1, 1.0)], 1),
+            Err(StorageError::VectorIndex(VectorIndexError::IndexNotBuilt))
+        ));
+        assert!(SparseVectorIndex::new(0).is_err());
+
+        let mut index = SparseVectorIndex::new(VOCAB)?;
+        index.build(&[])?;
+        assert!(index.search(&[(1, 1.0)], 1)?.is_empty());
+        Ok(())
+    }
+}
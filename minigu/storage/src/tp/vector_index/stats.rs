@@ -0,0 +1,393 @@
+use std::sync::Arc;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+
+/// Snapshot of vector index statistics
+///
+/// Snapshots written by `save` persist the counters and timings and `load` restores them, so
+/// long-running counts survive a restart unless `reset_stats_on_load` is set. The timing fields
+/// are restored together: `min_search_time_us` reads 0 while `timed_searches` is 0 rather than
+/// holding a sentinel, so a restored minimum keeps being lowered correctly. The `#[serde(skip)]`
+/// size fields describe the loaded index and are recomputed instead.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IndexStats {
+    /// Number of active (non-deleted) vectors in the index
+    pub vector_count: usize,
+    /// Dimension of the indexed vectors
+    pub dimension: usize,
+    /// Total number of search calls, regardless of strategy
+    pub total_searches: u64,
+    /// Number of searches answered by brute force over filter candidates
+    pub brute_force_searches: u64,
+    /// Brute-force searches scored by product-quantized codes; included in
+    /// `brute_force_searches`, the remainder of which were exact
+    pub pq_searches: u64,
+    /// Number of searches answered by DiskANN graph traversal
+    pub ann_searches: u64,
+    /// Number of vectors inserted after the initial build
+    pub total_inserts: u64,
+    /// Wall-clock duration of the last successful build in milliseconds
+    pub build_time_ms: u64,
+    /// Number of searches whose latency was measured, see [`record_search_time`]
+    ///
+    /// [`record_search_time`]: Self::record_search_time
+    pub timed_searches: u64,
+    /// Summed latency of the timed searches in microseconds
+    pub total_search_time_us: u64,
+    /// `total_search_time_us / timed_searches`, 0.0 before the first search
+    pub avg_search_time_us: f64,
+    /// Fastest timed search in microseconds, 0 before the first search
+    pub min_search_time_us: u64,
+    /// Slowest timed search in microseconds
+    pub max_search_time_us: u64,
+    /// Candidates visited by all searches, see [`record_candidates_visited`]: vectors scored
+    /// by brute-force scans and results examined from DiskANN graph searches
+    ///
+    /// [`record_candidates_visited`]: Self::record_candidates_visited
+    pub total_candidates_visited: u64,
+    /// Candidates visited by the most recently counted search
+    pub last_candidates_visited: u64,
+    /// Unfiltered graph searches retried with a larger search list because they found fewer
+    /// than `k` live nodes, see `SearchTuning::ensure_k_max_l_value`. A high share of
+    /// `ann_searches` means deletions are crowding search lists and a compaction is due.
+    pub ensure_k_retried_searches: u64,
+    /// Retries of those searches in total, each doubling the search list
+    pub ensure_k_retries: u64,
+    /// Nodes of the last build whose vector duplicates an earlier node's; counted only under
+    /// `DuplicateVectorPolicy::Warn`
+    pub duplicate_vectors: usize,
+    /// Search results dropped because their node ID repeated an earlier result's. Nonzero
+    /// only when the vector ID -> node ID mapping is inconsistent.
+    pub duplicate_results_dropped: u64,
+    /// Estimated resident bytes of the index: DiskANN vector data and adjacency lists for
+    /// every allocated slot, plus the ID mappings. Recomputed on load rather than persisted.
+    #[serde(skip)]
+    pub memory_usage: usize,
+    /// Soft-deleted slots still held by DiskANN, as [`num_deleted`]; recomputed on load
+    ///
+    /// [`num_deleted`]: super::InMemANNAdapter::num_deleted
+    #[serde(skip)]
+    pub deleted_count: usize,
+    /// `deleted_count` over all allocated slots, as [`deletion_ratio`]; recomputed on load
+    ///
+    /// [`deletion_ratio`]: super::InMemANNAdapter::deletion_ratio
+    #[serde(skip)]
+    pub deletion_ratio: f64,
+}
+
+impl IndexStats {
+    /// Fold the latency of one search into the running average and extremes
+    pub fn record_search_time(&mut self, elapsed: Duration) {
+        let micros = elapsed.as_micros().min(u64::MAX as u128) as u64;
+        self.min_search_time_us = if self.timed_searches == 0 {
+            micros
+        } else {
+            self.min_search_time_us.min(micros)
+        };
+        self.max_search_time_us = self.max_search_time_us.max(micros);
+        self.timed_searches += 1;
+        self.total_search_time_us = self.total_search_time_us.saturating_add(micros);
+        self.avg_search_time_us = self.total_search_time_us as f64 / self.timed_searches as f64;
+    }
+
+    /// Record how many candidates one search visited. Brute-force scans visit every live
+    /// candidate of their filter; graph searches count the results DiskANN returned, since its
+    /// traversal does not report the nodes it compared; pre-filtered scans count both their
+    /// graph seeds and the candidates they scored before stopping.
+    pub fn record_candidates_visited(&mut self, visited: usize) {
+        let visited = visited as u64;
+        self.last_candidates_visited = visited;
+        self.total_candidates_visited = self.total_candidates_visited.saturating_add(visited);
+    }
+}
+
+/// Cost breakdown of a single search call
+///
+/// Returned to the caller rather than emitted as tracing spans: miniGU has no tracing
+/// instrumentation or subscriber to attach spans to, so callers that want spans record these
+/// fields on their own.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchStats {
+    /// Time spent building the filter: the vertex scan evaluating a filter predicate, when the
+    /// search was given one. Node-indexed filters are translated to vector IDs as the search
+    /// reads them, so that cost falls under `search_time`
+    pub filter_build_time: Duration,
+    /// Number of vertices checked against a filter predicate; 0 when the filter arrived built,
+    /// as a bitmap or mask
+    pub vertices_scanned: usize,
+    /// Number of candidates the filter selects; for a node-indexed filter, its candidate node
+    /// count, which includes nodes without an indexed vector
+    pub filter_candidates: usize,
+    /// Time spent in the index search itself, excluding filter construction
+    pub search_time: Duration,
+    /// Spread of the returned distances; `None` when nothing was returned
+    pub result_distance_stats: Option<DistanceStats>,
+}
+
+/// Summary of the distances in one result set. A small `std` relative to `mean` means the
+/// neighbors are tightly clustered around the query's best match.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DistanceStats {
+    pub min: f32,
+    pub max: f32,
+    pub mean: f64,
+    /// Population standard deviation
+    pub std: f64,
+}
+
+impl DistanceStats {
+    /// Statistics over the distances of `results`, or `None` if it is empty
+    pub fn from_results(results: &[(u64, f32)]) -> Option<Self> {
+        if results.is_empty() {
+            return None;
+        }
+        let count = results.len() as f64;
+        let (mut min, mut max, mut sum) = (f32::INFINITY, f32::NEG_INFINITY, 0.0f64);
+        for &(_, distance) in results {
+            min = min.min(distance);
+            max = max.max(distance);
+            sum += distance as f64;
+        }
+        let mean = sum / count;
+        let variance = results
+            .iter()
+            .map(|&(_, distance)| (distance as f64 - mean).powi(2))
+            .sum::<f64>()
+            / count;
+        Some(Self {
+            min,
+            max,
+            mean,
+            std: variance.sqrt(),
+        })
+    }
+}
+
+/// Outcome of building an index from a graph scan
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct IndexBuildReport {
+    /// Vertices whose vector was added to the index
+    pub indexed: usize,
+    /// Vertices with the index label but a null, missing or non-vector property
+    pub skipped: usize,
+    /// Vector dimension inferred from the data
+    pub dimension: usize,
+}
+
+/// What a `build` with the validated inputs would do, from [`validate_build_inputs`]
+///
+/// [`validate_build_inputs`]: super::InMemANNAdapter::validate_build_inputs
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BuildPlan {
+    /// Number of vectors the build would index
+    pub vector_count: usize,
+    pub dimension: usize,
+    /// Rough resident size of the built index: vector data, graph adjacency lists at full
+    /// degree and the two ID mappings
+    pub estimated_memory_bytes: usize,
+}
+
+/// Outcome of [`insert_lenient`]: which nodes were inserted and why the others were not
+///
+/// [`insert_lenient`]: super::InMemANNAdapter::insert_lenient
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct InsertReport {
+    /// Nodes now indexed, in input order
+    pub inserted: Vec<u64>,
+    /// Rejected nodes with the reason, in input order except that DiskANN failures come last
+    pub failed: Vec<(u64, InsertFailure)>,
+}
+
+/// Why [`insert_lenient`] left a node out
+///
+/// [`insert_lenient`]: super::InMemANNAdapter::insert_lenient
+#[derive(Debug, Clone, PartialEq)]
+#[allow(clippy::upper_case_acronyms)]
+pub enum InsertFailure {
+    /// The node is already indexed, or appeared earlier in the batch
+    DuplicateNodeId,
+    /// DiskANN's u32 vector ID space is exhausted
+    VectorIdOverflow,
+    /// Wrong dimension, a non-finite value, or a zero norm rejected by the zero-norm policy
+    InvalidVector(String),
+    /// DiskANN failed to insert the valid part of the batch
+    DiskANN(String),
+}
+
+/// Deletion and ID-space fragmentation of an index, with a compaction recommendation
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FragmentationReport {
+    /// Vector IDs handed out since the last build, live or deleted
+    pub allocated_slots: usize,
+    pub live_vectors: usize,
+    /// Slots whose vector was soft-deleted; DiskANN keeps their data and graph entries
+    pub deleted_vectors: usize,
+    /// `deleted_vectors / allocated_slots`, 0.0 for an empty index
+    pub deleted_ratio: f64,
+    /// Estimated memory held by deleted vectors and their adjacency lists, reclaimed by a
+    /// rebuild
+    pub reclaimable_bytes: usize,
+    /// Whether `deleted_ratio` reached the compaction threshold
+    pub recommend_compaction: bool,
+}
+
+/// Value distribution of one vector dimension over the live vectors of an index
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DimStat {
+    pub min: f32,
+    pub max: f32,
+    pub mean: f64,
+    /// Population variance; zero for a constant (possibly dead) dimension
+    pub variance: f64,
+}
+
+/// Callback receiving periodic stats snapshots
+pub type StatsSink = Box<dyn Fn(IndexStats) + Send>;
+
+/// Background thread pushing [`IndexStats`] snapshots to a sink at a fixed interval.
+///
+/// The thread is stopped and joined when the reporter is dropped.
+pub struct StatsReporter {
+    stop_tx: Option<Sender<()>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl StatsReporter {
+    /// Spawn a reporter emitting a snapshot of `stats` every `interval`
+    pub fn spawn(stats: Arc<RwLock<IndexStats>>, interval: Duration, sink: StatsSink) -> Self {
+        let (stop_tx, stop_rx) = mpsc::channel::<()>();
+        let handle = std::thread::spawn(move || {
+            // Exits once a stop is requested or the reporter handle is dropped
+            while let Err(RecvTimeoutError::Timeout) = stop_rx.recv_timeout(interval) {
+                let snapshot = stats.read().clone();
+                sink(snapshot);
+            }
+        });
+
+        Self {
+            stop_tx: Some(stop_tx),
+            handle: Some(handle),
+        }
+    }
+
+    /// Stop the reporter thread and wait for it to exit
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        if let Some(stop_tx) = self.stop_tx.take() {
+            // The thread may already have exited if the sink panicked
+            let _ = stop_tx.send(());
+        }
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for StatsReporter {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use super::*;
+
+    #[test]
+    fn test_reporter_emits_snapshots() {
+        let stats = Arc::new(RwLock::new(IndexStats {
+            vector_count: 42,
+            dimension: 104,
+            ..Default::default()
+        }));
+        let (tx, rx) = mpsc::channel();
+        let reporter = StatsReporter::spawn(
+            Arc::clone(&stats),
+            Duration::from_millis(5),
+            Box::new(move |snapshot| {
+                let _ = tx.send(snapshot);
+            }),
+        );
+
+        let first = rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(first.vector_count, 42);
+        assert_eq!(first.dimension, 104);
+
+        // Later snapshots observe updates made through the shared handle
+        stats.write().total_searches = 7;
+        let updated = (0..100)
+            .filter_map(|_| rx.recv_timeout(Duration::from_secs(2)).ok())
+            .find(|snapshot| snapshot.total_searches == 7);
+        assert!(updated.is_some());
+
+        reporter.stop();
+    }
+
+    #[test]
+    fn test_record_search_time() {
+        let mut stats = IndexStats::default();
+        stats.record_search_time(Duration::from_micros(30));
+        assert_eq!(
+            (stats.min_search_time_us, stats.max_search_time_us),
+            (30, 30)
+        );
+        stats.record_search_time(Duration::from_micros(10));
+        stats.record_search_time(Duration::from_micros(50));
+        assert_eq!(stats.timed_searches, 3);
+        assert_eq!(stats.total_search_time_us, 90);
+        assert_eq!(stats.avg_search_time_us, 30.0);
+        assert_eq!(
+            (stats.min_search_time_us, stats.max_search_time_us),
+            (10, 50)
+        );
+    }
+
+    #[test]
+    fn test_record_candidates_visited() {
+        let mut stats = IndexStats::default();
+        stats.record_candidates_visited(12);
+        stats.record_candidates_visited(3);
+        assert_eq!(stats.last_candidates_visited, 3);
+        assert_eq!(stats.total_candidates_visited, 15);
+    }
+
+    #[test]
+    fn test_distance_stats() {
+        assert_eq!(DistanceStats::from_results(&[]), None);
+        let stats = DistanceStats::from_results(&[(1, 1.0), (2, 3.0), (3, 5.0)]).unwrap();
+        assert_eq!((stats.min, stats.max), (1.0, 5.0));
+        assert_eq!(stats.mean, 3.0);
+        assert!((stats.std - (8.0f64 / 3.0).sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_reporter_stops_on_drop() {
+        let stats = Arc::new(RwLock::new(IndexStats::default()));
+        let (tx, rx) = mpsc::channel();
+        let reporter = StatsReporter::spawn(
+            stats,
+            Duration::from_millis(5),
+            Box::new(move |snapshot| {
+                let _ = tx.send(snapshot);
+            }),
+        );
+        assert!(rx.recv_timeout(Duration::from_secs(2)).is_ok());
+
+        drop(reporter);
+        // Once the thread is joined the sink (and its sender) is gone
+        while rx.try_recv().is_ok() {}
+        assert!(matches!(
+            rx.recv_timeout(Duration::from_millis(50)),
+            Err(mpsc::RecvTimeoutError::Disconnected)
+        ));
+    }
+}